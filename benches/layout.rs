@@ -0,0 +1,45 @@
+use divan::Bencher;
+use hmm_rs::app::AppState;
+use hmm_rs::config::AppConfig;
+use hmm_rs::layout::LayoutEngine;
+use hmm_rs::model::Node;
+
+fn main() {
+    divan::main();
+}
+
+/// A map with `branches` top-level topics, each holding `fan_out` nodes with
+/// `fan_out` leaves of their own
+fn create_map(branches: usize, fan_out: usize) -> AppState {
+    let mut app = AppState::new(AppConfig::default());
+    let root = app.tree.new_node(Node::new("Benchmark map".to_string()));
+    app.root_id = Some(root);
+    app.active_node_id = Some(root);
+
+    for i in 0..branches {
+        let branch = app.tree.new_node(Node::new(format!("Topic {i}")));
+        root.append(branch, &mut app.tree);
+        for j in 0..fan_out {
+            let title = format!("Idea {i}.{j} with a title long enough to wrap now and then");
+            let node = app.tree.new_node(Node::new(title));
+            branch.append(node, &mut app.tree);
+            for k in 0..fan_out {
+                let leaf = app.tree.new_node(Node::new(format!("Detail {k}")));
+                node.append(leaf, &mut app.tree);
+            }
+        }
+    }
+    app
+}
+
+#[divan::bench(args = [1_000, 10_000, 100_000])]
+fn sequential(bencher: Bencher, nodes: usize) {
+    let app = create_map(16, ((nodes / 16) as f64).sqrt() as usize);
+    bencher.bench_local(|| LayoutEngine::calculate_layout_with(&app, false));
+}
+
+#[divan::bench(args = [1_000, 10_000, 100_000])]
+fn parallel(bencher: Bencher, nodes: usize) {
+    let app = create_map(16, ((nodes / 16) as f64).sqrt() as usize);
+    bencher.bench_local(|| LayoutEngine::calculate_layout_with(&app, true));
+}
@@ -0,0 +1,129 @@
+use crate::app::{AppMode, AppState};
+use crate::model::{Node, NodeId};
+
+/// Ask for a line to add to the inbox
+pub fn start_capture(app: &mut AppState) {
+    if app.root_id.is_none() {
+        return;
+    }
+    app.mode = AppMode::Capture {
+        buffer: String::new(),
+    };
+}
+
+pub fn type_capture_char(app: &mut AppState, c: char) {
+    if let AppMode::Capture { buffer } = &mut app.mode {
+        buffer.push(c);
+    }
+}
+
+pub fn backspace_capture(app: &mut AppState) {
+    if let AppMode::Capture { buffer } = &mut app.mode {
+        buffer.pop();
+    }
+}
+
+/// Add the typed line as the last child of the inbox node below the root,
+/// creating the inbox when the map has none. The selection and viewport stay
+/// where they are.
+pub fn confirm_capture(app: &mut AppState) {
+    let AppMode::Capture { buffer } = std::mem::replace(&mut app.mode, AppMode::Normal) else {
+        return;
+    };
+    let line = buffer.trim();
+    let Some(root_id) = app.root_id.filter(|_| !line.is_empty()) else {
+        return;
+    };
+
+    app.begin_transaction();
+    let inbox = inbox_node(app, root_id);
+    inbox.append_value(Node::new(line.to_string()), &mut app.tree);
+    app.commit_transaction();
+    let inbox_title = app.config.inbox_title.clone();
+    app.notify_with("status.captured", &[("inbox", &inbox_title)]);
+}
+
+pub fn cancel_capture(app: &mut AppState) {
+    app.mode = AppMode::Normal;
+}
+
+/// The child of the root titled like the configured inbox, appended to the
+/// root when there is none
+fn inbox_node(app: &mut AppState, root_id: NodeId) -> NodeId {
+    let title = app.config.inbox_title.trim();
+    let inbox = root_id
+        .children(&app.tree)
+        .find(|&child| app.tree[child].get().title.trim() == title);
+    match inbox {
+        Some(inbox) => inbox,
+        None => {
+            let inbox = Node::new(title.to_string());
+            root_id.append_value(inbox, &mut app.tree)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    fn create_test_app() -> AppState {
+        let mut app = AppState::new(AppConfig::default());
+        let root = app.tree.new_node(Node::new("Root".to_string()));
+        let deep = root.append_value(Node::new("Project".to_string()), &mut app.tree);
+        let task = deep.append_value(Node::new("Task".to_string()), &mut app.tree);
+        app.root_id = Some(root);
+        app.active_node_id = Some(task);
+        app.viewport_top = 12.0;
+        app
+    }
+
+    fn capture(app: &mut AppState, line: &str) {
+        start_capture(app);
+        for c in line.chars() {
+            type_capture_char(app, c);
+        }
+        confirm_capture(app);
+    }
+
+    #[test]
+    fn test_capture_creates_inbox_and_keeps_the_view() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let task = app.active_node_id;
+
+        capture(&mut app, "Call the dentist");
+        assert!(matches!(app.mode, AppMode::Normal));
+        let inbox = root.children(&app.tree).next_back().unwrap();
+        assert_eq!(app.tree[inbox].get().title, "Inbox");
+        let titles: Vec<_> = inbox
+            .children(&app.tree)
+            .map(|id| app.tree[id].get().title.clone())
+            .collect();
+        assert_eq!(titles, ["Call the dentist"]);
+        assert_eq!(app.active_node_id, task);
+        assert_eq!(app.viewport_top, 12.0);
+        assert!(app.is_dirty);
+        assert_eq!(app.message.as_deref(), Some("Added to Inbox"));
+
+        // The next line joins the same inbox, and one undo takes it back
+        capture(&mut app, "Buy milk");
+        assert_eq!(root.children(&app.tree).count(), 2);
+        assert_eq!(inbox.children(&app.tree).count(), 2);
+        crate::actions::undo(&mut app);
+        assert_eq!(inbox.children(&app.tree).count(), 1);
+    }
+
+    #[test]
+    fn test_empty_or_cancelled_capture_adds_nothing() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        capture(&mut app, "   ");
+        start_capture(&mut app);
+        type_capture_char(&mut app, 'x');
+        cancel_capture(&mut app);
+        assert_eq!(root.children(&app.tree).count(), 1);
+        assert!(!app.is_dirty);
+    }
+}
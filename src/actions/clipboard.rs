@@ -1,21 +1,43 @@
-use crate::app::AppState;
+use crate::app::{AppMode, AppState};
+use crate::casing;
 use crate::model::{Node, NodeId};
 use crate::parser;
 use anyhow::Result;
+#[cfg(feature = "clipboard")]
 use clipboard::{ClipboardContext, ClipboardProvider};
 use indextree::Arena;
 
+/// Copy text to the system clipboard; failures (e.g. no display) are ignored
+pub(crate) fn set_system_clipboard(text: String) {
+    #[cfg(feature = "clipboard")]
+    if let Ok(mut ctx) = ClipboardContext::new() {
+        let _ = ctx.set_contents(text);
+    }
+    #[cfg(not(feature = "clipboard"))]
+    let _ = text;
+}
+
+/// Read text from the system clipboard, if available
+pub(crate) fn get_system_clipboard() -> Option<String> {
+    #[cfg(feature = "clipboard")]
+    {
+        ClipboardContext::new().ok()?.get_contents().ok()
+    }
+    #[cfg(not(feature = "clipboard"))]
+    {
+        None
+    }
+}
+
 pub fn yank_node(app: &mut AppState) -> Result<()> {
     if let Some(active_id) = app.active_node_id {
         let text = parser::map_to_list(&app.tree, active_id, false, 0);
         app.clipboard = Some(text.clone());
 
         // Try to copy to system clipboard
-        if let Ok(mut ctx) = ClipboardContext::new() {
-            let _ = ctx.set_contents(text);
-        }
+        set_system_clipboard(text);
 
-        app.set_message("Node yanked");
+        app.notify("status.node_yanked");
     }
     Ok(())
 }
@@ -26,97 +48,285 @@ pub fn yank_children(app: &mut AppState) -> Result<()> {
         app.clipboard = Some(text.clone());
 
         // Try to copy to system clipboard
-        if let Ok(mut ctx) = ClipboardContext::new() {
-            let _ = ctx.set_contents(text);
-        }
+        set_system_clipboard(text);
 
-        app.set_message("Children yanked");
+        app.notify("status.children_yanked");
     }
     Ok(())
 }
 
 pub fn paste_as_children(app: &mut AppState) -> Result<()> {
-    if let Some(clipboard_text) = app.clipboard.clone() {
-        if let Some(active_id) = app.active_node_id {
-            app.push_history();
-
-            // Parse the clipboard text into a tree
-            match parser::parse_hmm_content(&clipboard_text) {
-                Ok((parsed_tree, parsed_root)) => {
-                    // Add all nodes from the parsed tree as children of the active node
-                    add_subtree_to_parent(&mut app.tree, &parsed_tree, parsed_root, active_id);
-                    app.set_message("Pasted as children");
-                }
-                Err(_) => {
-                    app.set_message("Failed to parse clipboard content");
-                }
-            }
+    paste(app, false, true)
+}
+
+pub fn paste_as_siblings(app: &mut AppState) -> Result<()> {
+    paste(app, true, true)
+}
+
+/// Paste the clipboard under the active node, or after it as siblings. When
+/// `ask_to_merge` is set and pasted nodes are titled like nodes already
+/// there, ask whether to merge them instead, showing what merging would do.
+fn paste(app: &mut AppState, as_siblings: bool, ask_to_merge: bool) -> Result<()> {
+    let Some(clipboard_text) = app.clipboard.clone() else {
+        app.notify("status.clipboard_empty");
+        return Ok(());
+    };
+    let Some(active_id) = app.active_node_id else {
+        return Ok(());
+    };
+    let Some(parent_id) = paste_parent(app, active_id, as_siblings) else {
+        app.notify("status.cannot_paste_siblings_at_root");
+        return Ok(());
+    };
+    let Ok((parsed_tree, parsed_root)) = parse_clipboard(app, &clipboard_text) else {
+        app.notify("status.clipboard_parse_failed");
+        return Ok(());
+    };
+
+    let roots = pasted_roots(&parsed_tree, parsed_root);
+    if ask_to_merge {
+        let duplicates = roots
+            .iter()
+            .filter(|&&root| {
+                child_titled(&app.tree, parent_id, &parsed_tree[root].get().title).is_some()
+            })
+            .count();
+        if duplicates > 0 {
+            let preview = merge_preview(&app.tree, &parsed_tree, &roots, parent_id);
+            app.mode = AppMode::PasteMerge {
+                as_siblings,
+                duplicates,
+                preview,
+            };
+            return Ok(());
         }
+    }
+
+    app.begin_transaction();
+    if as_siblings {
+        // Add all nodes from the parsed tree as siblings after the active node
+        add_subtree_as_sibling(
+            &mut app.tree,
+            &parsed_tree,
+            parsed_root,
+            active_id,
+            parent_id,
+        );
+        app.notify("status.pasted_as_siblings");
     } else {
-        app.set_message("Clipboard is empty");
+        // Add all nodes from the parsed tree as children of the active node
+        add_subtree_to_parent(&mut app.tree, &parsed_tree, parsed_root, parent_id);
+        app.notify("status.pasted_as_children");
     }
+    app.commit_transaction();
     Ok(())
 }
 
-pub fn paste_as_siblings(app: &mut AppState) -> Result<()> {
-    if let Some(clipboard_text) = app.clipboard.clone() {
-        if let Some(active_id) = app.active_node_id {
-            app.push_history();
-
-            // Get the parent of the active node
-            if let Some(parent_id) = app.tree.get(active_id).and_then(|n| n.parent()) {
-                // Parse the clipboard text into a tree
-                match parser::parse_hmm_content(&clipboard_text) {
-                    Ok((parsed_tree, parsed_root)) => {
-                        // Add all nodes from the parsed tree as siblings after the active node
-                        add_subtree_as_sibling(
-                            &mut app.tree,
-                            &parsed_tree,
-                            parsed_root,
-                            active_id,
-                            parent_id,
-                        );
-                        app.set_message("Pasted as siblings");
-                    }
-                    Err(_) => {
-                        app.set_message("Failed to parse clipboard content");
-                    }
-                }
-            } else {
-                app.set_message("Cannot paste siblings at root level");
-            }
-        }
+/// Paste the clipboard the merge prompt was opened for, merging pasted nodes
+/// into the same-titled nodes already there
+pub fn merge_paste(app: &mut AppState) -> Result<()> {
+    let AppMode::PasteMerge { as_siblings, .. } = app.mode else {
+        return Ok(());
+    };
+    app.mode = AppMode::Normal;
+
+    let (Some(clipboard_text), Some(active_id)) = (app.clipboard.clone(), app.active_node_id)
+    else {
+        return Ok(());
+    };
+    let Some(parent_id) = paste_parent(app, active_id, as_siblings) else {
+        return Ok(());
+    };
+    let (parsed_tree, parsed_root) = parse_clipboard(app, &clipboard_text)?;
+
+    app.begin_transaction();
+    let roots = pasted_roots(&parsed_tree, parsed_root);
+    let after = as_siblings.then_some(active_id);
+    let merge = merge_subtrees(&mut app.tree, &parsed_tree, &roots, parent_id, after);
+    app.commit_transaction();
+    app.notify_with("status.paste_merged", &[("count", &merge.added)]);
+    Ok(())
+}
+
+/// Paste the clipboard the merge prompt was opened for as it is, duplicates included
+pub fn duplicate_paste(app: &mut AppState) -> Result<()> {
+    let AppMode::PasteMerge { as_siblings, .. } = app.mode else {
+        return Ok(());
+    };
+    app.mode = AppMode::Normal;
+    paste(app, as_siblings, false)
+}
+
+pub fn cancel_paste(app: &mut AppState) {
+    app.mode = AppMode::Normal;
+}
+
+/// The outline in the clipboard, in sentence case if it is shouting and
+/// `fix_shouting_case` is on
+fn parse_clipboard(app: &AppState, text: &str) -> Result<(Arena<Node>, NodeId)> {
+    let (mut tree, root_id) = parser::parse_hmm_content(text)?;
+    if app.config.fix_shouting_case && casing::is_shouting_outline(&tree, root_id) {
+        casing::fix_shouting_case(&mut tree, root_id, &app.config.acronyms);
+    }
+    Ok((tree, root_id))
+}
+
+/// The node pasted nodes go under: the active node, or its parent when
+/// pasting siblings
+fn paste_parent(app: &AppState, active_id: NodeId, as_siblings: bool) -> Option<NodeId> {
+    if as_siblings {
+        app.tree.get(active_id).and_then(|n| n.parent())
     } else {
-        app.set_message("Clipboard is empty");
+        Some(active_id)
     }
-    Ok(())
 }
 
-// Helper functions for paste operations
-pub fn add_subtree_to_parent(
+/// The top-level pasted nodes, below the synthetic root a multi-root paste
+/// is parsed under
+fn pasted_roots(source_tree: &Arena<Node>, source_root: NodeId) -> Vec<NodeId> {
+    let source_node = source_tree.get(source_root).unwrap().get();
+    if source_node.title == "root" && source_root.children(source_tree).count() > 0 {
+        source_root.children(source_tree).collect()
+    } else {
+        vec![source_root]
+    }
+}
+
+fn child_titled(tree: &Arena<Node>, parent_id: NodeId, title: &str) -> Option<NodeId> {
+    parent_id
+        .children(tree)
+        .find(|&child_id| tree[child_id].get().title == title)
+}
+
+/// What merging a paste did
+struct Merge {
+    /// Number of nodes copied in
+    added: usize,
+    /// Nodes already there that pasted nodes were merged into
+    merged: Vec<NodeId>,
+}
+
+/// Merge the pasted `roots` into `parent_id`: a node titled like one of the
+/// parent's children merges its own children into that child the same way,
+/// and the rest are copied in, after `after` if given.
+fn merge_subtrees(
     target_tree: &mut Arena<Node>,
     source_tree: &Arena<Node>,
-    source_root: NodeId,
+    roots: &[NodeId],
     parent_id: NodeId,
-) {
-    // Recursively copy nodes from source tree to target tree
-    fn copy_subtree(
-        target_tree: &mut Arena<Node>,
-        source_tree: &Arena<Node>,
-        source_id: NodeId,
-        target_parent_id: NodeId,
-    ) {
-        // Copy the node
-        let source_node = source_tree.get(source_id).unwrap().get();
-        let new_node_id = target_tree.new_node(source_node.clone());
-        target_parent_id.append(new_node_id, target_tree);
+    mut after: Option<NodeId>,
+) -> Merge {
+    let mut merge = Merge {
+        added: 0,
+        merged: Vec::new(),
+    };
+    let mut stack: Vec<(NodeId, NodeId, bool)> = roots
+        .iter()
+        .rev()
+        .map(|&root| (root, parent_id, true))
+        .collect();
+
+    while let Some((source_id, target_parent_id, top_level)) = stack.pop() {
+        let title = &source_tree[source_id].get().title;
+        if let Some(existing) = child_titled(target_tree, target_parent_id, title) {
+            merge.merged.push(existing);
+            stack.extend(
+                source_id
+                    .children(source_tree)
+                    .rev()
+                    .map(|child| (child, existing, false)),
+            );
+            continue;
+        }
 
-        // Recursively copy children
-        for child in source_id.children(source_tree) {
-            copy_subtree(target_tree, source_tree, child, new_node_id);
+        let copied = copy_subtree(target_tree, source_tree, source_id, target_parent_id);
+        merge.added += copied.descendants(target_tree).count();
+        if let Some(previous) = after.filter(|_| top_level) {
+            copied.detach(target_tree);
+            previous.insert_after(copied, target_tree);
+            after = Some(copied);
         }
     }
+    merge
+}
+
+/// Outline of what merging the paste would do under `parent_id`, one line per
+/// node: `+` for a node copied in, `=` for one merged into an existing node
+fn merge_preview(
+    tree: &Arena<Node>,
+    source_tree: &Arena<Node>,
+    roots: &[NodeId],
+    parent_id: NodeId,
+) -> Vec<String> {
+    let mut merged_tree = tree.clone();
+    let merge = merge_subtrees(&mut merged_tree, source_tree, roots, parent_id, None);
+
+    let mut lines = Vec::new();
+    let mut stack: Vec<(NodeId, usize)> = parent_id
+        .children(&merged_tree)
+        .rev()
+        .map(|child| (child, 0))
+        .collect();
+    while let Some((node_id, depth)) = stack.pop() {
+        let mark = if tree.get(node_id).is_none_or(|n| n.is_removed()) {
+            '+'
+        } else if merge.merged.contains(&node_id) {
+            '='
+        } else {
+            continue;
+        };
+        lines.push(format!(
+            "{}{} {}",
+            "  ".repeat(depth),
+            mark,
+            merged_tree[node_id].get().title
+        ));
+        stack.extend(
+            node_id
+                .children(&merged_tree)
+                .rev()
+                .map(|child| (child, depth + 1)),
+        );
+    }
+    lines
+}
+
+/// Copy a subtree of `source_tree` under `target_parent_id` and return the
+/// copy of `source_id`.
+pub(crate) fn copy_subtree(
+    target_tree: &mut Arena<Node>,
+    source_tree: &Arena<Node>,
+    source_id: NodeId,
+    target_parent_id: NodeId,
+) -> NodeId {
+    let mut stack = vec![(source_id, target_parent_id)];
+    let mut copied_root = None;
+
+    while let Some((source_id, target_parent_id)) = stack.pop() {
+        // Copy the node
+        let source_node = source_tree.get(source_id).unwrap().get();
+        let new_node_id = target_parent_id.append_value(source_node.clone(), target_tree);
+        copied_root.get_or_insert(new_node_id);
+
+        // Children are appended in order, so push them last-first
+        stack.extend(
+            source_id
+                .children(source_tree)
+                .rev()
+                .map(|child| (child, new_node_id)),
+        );
+    }
+
+    copied_root.unwrap_or(target_parent_id)
+}
 
+// Helper functions for paste operations
+pub fn add_subtree_to_parent(
+    target_tree: &mut Arena<Node>,
+    source_tree: &Arena<Node>,
+    source_root: NodeId,
+    parent_id: NodeId,
+) {
     // If the parsed root is a synthetic root, add its children
     // Otherwise, add the root itself
     let source_node = source_tree.get(source_root).unwrap().get();
@@ -138,26 +348,6 @@ pub fn add_subtree_as_sibling(
     after_node: NodeId,
     parent_id: NodeId,
 ) {
-    // Recursively copy nodes from source tree to target tree
-    fn copy_subtree(
-        target_tree: &mut Arena<Node>,
-        source_tree: &Arena<Node>,
-        source_id: NodeId,
-        target_parent_id: NodeId,
-    ) -> NodeId {
-        // Copy the node
-        let source_node = source_tree.get(source_id).unwrap().get();
-        let new_node_id = target_tree.new_node(source_node.clone());
-        target_parent_id.append(new_node_id, target_tree);
-
-        // Recursively copy children
-        for child in source_id.children(source_tree) {
-            copy_subtree(target_tree, source_tree, child, new_node_id);
-        }
-
-        new_node_id
-    }
-
     // Collect all nodes to add
     let mut nodes_to_add = Vec::new();
 
@@ -298,4 +488,54 @@ mod tests {
         assert!(found_sibling1);
         assert!(found_sibling2);
     }
+
+    #[test]
+    fn test_paste_offers_to_merge_same_titled_nodes() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let titles = |app: &AppState, id: NodeId| -> Vec<String> {
+            id.children(&app.tree)
+                .map(|c| app.tree[c].get().title.clone())
+                .collect()
+        };
+        app.clipboard = Some("Child 2\n\tGrandchild\n\tNew grandchild\nChild 3".to_string());
+
+        // Nothing is pasted until the prompt is answered
+        paste_as_children(&mut app).unwrap();
+        let AppMode::PasteMerge {
+            duplicates,
+            ref preview,
+            ..
+        } = app.mode
+        else {
+            panic!("expected the merge prompt, got {:?}", app.mode);
+        };
+        assert_eq!(duplicates, 1);
+        assert_eq!(
+            preview,
+            &vec![
+                "= Child 2",
+                "  = Grandchild",
+                "  + New grandchild",
+                "+ Child 3"
+            ]
+        );
+        assert_eq!(titles(&app, root), vec!["Child 1", "Child 2"]);
+
+        merge_paste(&mut app).unwrap();
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app.is_dirty);
+        assert_eq!(titles(&app, root), vec!["Child 1", "Child 2", "Child 3"]);
+        let child2 = root.children(&app.tree).nth(1).unwrap();
+        assert_eq!(titles(&app, child2), vec!["Grandchild", "New grandchild"]);
+
+        // Pasting duplicates keeps both copies
+        app.undo();
+        paste_as_children(&mut app).unwrap();
+        duplicate_paste(&mut app).unwrap();
+        assert_eq!(
+            titles(&app, root),
+            vec!["Child 1", "Child 2", "Child 2", "Child 3"]
+        );
+    }
 }
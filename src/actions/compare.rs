@@ -0,0 +1,133 @@
+use crate::app::{AppMode, AppState, Damage};
+use crate::diff::diff_trees;
+use crate::git;
+use crate::model::{Node, NodeId};
+use crate::parser::{self, MapFormat};
+use anyhow::Result;
+use indextree::Arena;
+use std::io::Write;
+use std::path::Path;
+
+/// Compare the map with the snapshot selected in the snapshot browser
+pub fn compare_snapshot(app: &mut AppState) {
+    let AppMode::Snapshots {
+        ref snapshots,
+        selected,
+        ref preview,
+    } = app.mode
+    else {
+        return;
+    };
+    let label = snapshots[selected]
+        .time
+        .format("%Y-%m-%d %H:%M")
+        .to_string();
+    match parser::parse_hmm_content(preview) {
+        Ok((tree, root_id)) => start_compare(app, label, &tree, root_id),
+        Err(_) => app.notify("status.snapshot_unreadable"),
+    }
+}
+
+/// Compare the map with the version of its file in the last git commit
+pub fn compare_head(app: &mut AppState) {
+    let Some(path) = app.filename.clone() else {
+        app.notify("status.no_file_to_compare");
+        return;
+    };
+    match head_version(&path) {
+        Ok((tree, root_id)) => start_compare(app, "HEAD".to_string(), &tree, root_id),
+        Err(e) => app.notify_with("status.compare_failed", &[("error", &e)]),
+    }
+}
+
+/// Compare the map with the map in another file
+pub fn compare_file(app: &mut AppState, path: &Path) -> Result<()> {
+    let (tree, root_id) = parser::load_file(path)?;
+    let label = path.file_name().map_or_else(
+        || path.display().to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    );
+    start_compare(app, label, &tree, root_id);
+    Ok(())
+}
+
+fn start_compare(app: &mut AppState, label: String, tree: &Arena<Node>, root_id: NodeId) {
+    let Some(current_root) = app.root_id else {
+        return;
+    };
+    let counts = diff_trees(tree, root_id, &app.tree, current_root).counts();
+    app.mode = AppMode::Compare {
+        label,
+        other: parser::map_to_hmm(tree, root_id),
+        counts,
+    };
+    app.mark_damage(Damage::View);
+}
+
+pub fn close_compare(app: &mut AppState) {
+    app.mode = AppMode::Normal;
+    app.mark_damage(Damage::View);
+}
+
+/// The map at `path` as committed in git's `HEAD`
+fn head_version(path: &Path) -> Result<(Arena<Node>, NodeId)> {
+    let committed = git::show_head(path)?;
+    let format = MapFormat::from_path(path).unwrap_or_else(|| MapFormat::from_contents(&committed));
+    if format == MapFormat::Hmm {
+        return parser::parse_hmm_content(&String::from_utf8_lossy(&committed));
+    }
+    // The other formats are read from files, here a private one that goes
+    // away when dropped
+    let extension = path.extension().unwrap_or_default().to_string_lossy();
+    let mut temp = tempfile::Builder::new()
+        .prefix("hmm-rs-head-")
+        .suffix(&format!(".{extension}"))
+        .tempfile()?;
+    temp.write_all(&committed)?;
+    temp.flush()?;
+    parser::load_file_as(temp.path(), format)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_compare_file() {
+        let dir = TempDir::new().unwrap();
+        let map = dir.path().join("plan.hmm");
+        let other = dir.path().join("draft.hmm");
+        fs::write(&map, "Plan\n\tKeep\n\tNew idea\n").unwrap();
+        fs::write(&other, "Plan\n\tKeep\n").unwrap();
+
+        let mut app = AppState::new(AppConfig::default());
+        super::super::open_file(&mut app, &map).unwrap();
+        compare_file(&mut app, &other).unwrap();
+        assert_eq!(
+            app.mode,
+            AppMode::Compare {
+                label: "draft.hmm".to_string(),
+                other: "Plan\n\tKeep\n".to_string(),
+                counts: (1, 0, 0),
+            }
+        );
+
+        close_compare(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(compare_file(&mut app, &dir.path().join("missing.hmm")).is_err());
+    }
+
+    #[test]
+    fn test_compare_head_needs_a_file() {
+        let mut app = AppState::new(AppConfig::default());
+        compare_head(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(
+            app.message.as_deref(),
+            Some("This map has no file to compare")
+        );
+    }
+}
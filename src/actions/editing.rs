@@ -1,5 +1,7 @@
 use crate::app::{AppMode, AppState};
-use clipboard::{ClipboardContext, ClipboardProvider};
+
+use super::clipboard::get_system_clipboard;
+use super::link::offer_link_updates;
 
 pub fn start_editing(app: &mut AppState, replace: bool) {
     if let Some(active_id) = app.active_node_id {
@@ -168,18 +170,16 @@ pub fn delete_to_start(app: &mut AppState) {
 pub fn paste_at_cursor(app: &mut AppState) {
     if let AppMode::Editing { buffer, cursor_pos } = &mut app.mode {
         // Try to get content from system clipboard
-        if let Ok(mut ctx) = ClipboardContext::new() {
-            if let Ok(content) = ctx.get_contents() {
-                // Clean the content: replace newlines and tabs with spaces
-                let cleaned = content
-                    .replace('\n', " ")
-                    .replace('\r', "")
-                    .replace('\t', "  ");
-
-                // Insert at cursor position
-                buffer.insert_str(*cursor_pos, &cleaned);
-                *cursor_pos += cleaned.len();
-            }
+        if let Some(content) = get_system_clipboard() {
+            // Clean the content: replace newlines and tabs with spaces
+            let cleaned = content
+                .replace('\n', " ")
+                .replace('\r', "")
+                .replace('\t', "  ");
+
+            // Insert at cursor position
+            buffer.insert_str(*cursor_pos, &cleaned);
+            *cursor_pos += cleaned.len();
         }
     }
 }
@@ -191,16 +191,30 @@ pub fn confirm_edit(app: &mut AppState) {
         return;
     };
 
+    let mut old_title = None;
     if let Some(active_id) = app.active_node_id {
+        let index_current = app.text_index.is_current();
         app.push_history();
 
         if let Some(node) = app.tree.get_mut(active_id) {
-            node.get_mut().title = new_title;
+            old_title = Some(std::mem::replace(
+                &mut node.get_mut().title,
+                new_title.clone(),
+            ));
+            if index_current {
+                app.text_index.follow_edit(active_id, &new_title);
+            }
+            app.last_edit_text = Some(new_title.clone());
             app.is_dirty = true;
             app.last_modify_time = Some(std::time::Instant::now());
         }
     }
     app.mode = AppMode::Normal;
+    if let (Some(old_title), Some(map)) = (old_title, app.filename.clone()) {
+        if old_title != new_title {
+            offer_link_updates(app, map, old_title, new_title);
+        }
+    }
 }
 
 pub fn cancel_edit(app: &mut AppState) {
@@ -321,6 +335,43 @@ mod tests {
         assert!(matches!(app.mode, AppMode::Normal));
     }
 
+    #[test]
+    fn test_viewport_follows_wrapping_edit() {
+        use crate::actions::{ensure_node_visible, execute_action, Action};
+
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        for i in 0..10 {
+            let child = app.tree.new_node(Node::new(format!("Item {i}")));
+            root.append(child, &mut app.tree);
+        }
+        let last = root.children(&app.tree).next_back().unwrap();
+        app.terminal_height = 12;
+        app.active_node_id = Some(last);
+        ensure_node_visible(&mut app);
+
+        // The last row belongs to the status line, not the map
+        let map_bottom = |app: &mut AppState| app.viewport_top + 11.0;
+        let node_bottom = |app: &mut AppState| {
+            let layout = app.layout();
+            let node = &layout.nodes[&last];
+            node.y + node.yo + node.lh
+        };
+        assert!(node_bottom(&mut app) <= map_bottom(&mut app));
+
+        execute_action(Action::EditNodeAppend, &mut app).unwrap();
+        for c in " that keeps going on and on until it wraps"
+            .repeat(3)
+            .chars()
+        {
+            execute_action(Action::TypeChar(c), &mut app).unwrap();
+        }
+
+        // The map shows the text being typed, wrapped, and scrolls to keep it in view
+        assert!(app.layout().nodes[&last].lh > 1.0);
+        assert!(node_bottom(&mut app) <= map_bottom(&mut app));
+    }
+
     #[test]
     fn test_cursor_movement() {
         let mut app = create_test_app();
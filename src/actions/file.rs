@@ -1,87 +1,531 @@
-use crate::app::AppState;
+use crate::app::{AppMode, AppState, PickerKind};
+use crate::casing;
+use crate::config::GitOnSave;
+use crate::export;
+use crate::git;
+use crate::hooks::{self, map_dir, node_env, HookEvent};
+use crate::link::LinkRename;
+use crate::lock;
 use crate::model::{Node, NodeId};
-use crate::parser;
+use crate::parser::{self, MapFormat};
 use anyhow::Result;
-use clipboard::{ClipboardContext, ClipboardProvider};
 use indextree::Arena;
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use super::clipboard::set_system_clipboard;
+use super::link::update_workspace_links;
+use super::picker::open_picker;
 
 pub fn save(app: &mut AppState) -> Result<()> {
-    if let Some(ref path) = app.filename {
+    // Maps in a read-only format are saved under a new name instead
+    if app.filename.is_some() && !app.format.is_writable() {
+        start_save_as(app, false);
+        return Ok(());
+    }
+
+    if let Some(path) = app.filename.clone() {
         if let Some(root_id) = app.root_id {
-            match parser::save_file(&app.tree, root_id, path) {
+            match parser::save_file_as(&mut app.tree, root_id, &path, app.format) {
                 Ok(_) => {
-                    app.set_message(format!("Saved to {}", path.display()));
+                    app.notify_with("status.saved_to", &[("path", &path.display())]);
                     app.is_dirty = false;
+                    // Pins follow renamed nodes; failing to record them is not a failed save
+                    let _ = app.store_pins();
+                    git_after_save(app, &path);
+                    hooks::run_hook(app, HookEvent::Save, None);
                 }
                 Err(e) => {
-                    app.set_message(format!("Failed to save: {}", e));
+                    app.notify_with("status.save_failed", &[("error", &e)]);
                     return Err(e);
                 }
             }
         } else {
-            app.set_message("No content to save");
+            app.notify("status.nothing_to_save");
         }
     } else {
-        app.set_message("No filename set - use Shift+S for Save As");
+        app.notify("status.no_filename");
+    }
+    Ok(())
+}
+
+/// Commit the map just saved to `path` or show its uncommitted changes, as
+/// `git_on_save` says, when it is inside a git repository
+fn git_after_save(app: &mut AppState, path: &Path) {
+    let mode = app.config.git_on_save;
+    if mode == GitOnSave::Off || !git::in_repository(path) {
+        return;
+    }
+    let file = path.display();
+
+    match mode {
+        GitOnSave::Commit => {
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_default();
+            let time = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+            let message = app
+                .config
+                .git_commit_message
+                .replace("{file}", &name)
+                .replace("{time}", &time);
+            match git::commit_file(path, &message) {
+                Ok(true) => app.notify_with("status.saved_and_committed", &[("path", &file)]),
+                Ok(false) => {}
+                Err(e) => app.notify_with("status.git_failed", &[("error", &e)]),
+            }
+        }
+        GitOnSave::Stat => match git::diff_stat(path) {
+            Ok(Some(stat)) => app.notify_with(
+                "status.saved_with_stat",
+                &[("path", &file), ("stat", &stat)],
+            ),
+            Ok(None) => {}
+            Err(e) => app.notify_with("status.git_failed", &[("error", &e)]),
+        },
+        GitOnSave::Off => {}
     }
+}
+
+/// Whether saving has to ask for a file name first
+pub fn needs_save_as(app: &AppState) -> bool {
+    app.filename.is_none() || !app.format.is_writable()
+}
+
+/// Replace the current map with the one in `path` and add it to the recent list
+pub fn open_file(app: &mut AppState, path: &Path) -> Result<()> {
+    let format = parser::detect_format(path)?;
+    let (tree, root_id) = parser::load_file_as(path, format)?;
+    app.tree = tree;
+    app.root_id = Some(root_id);
+    app.active_node_id = Some(root_id);
+    app.filename = Some(path.to_path_buf());
+    app.format = format;
+    app.read_only = lock::read_only_reason(path);
+    app.viewport_top = 0.0;
+    app.viewport_left = 0.0;
+    app.search_results.clear();
+    app.search_fold = None;
+    app.edit_times.clear();
+    app.load_pins();
+    app.mark_map_changed();
+
+    app.history.clear();
+    app.history_index = 0;
+    app.push_history();
+    app.is_dirty = false;
+    app.last_modify_time = None;
+    fix_imported_case(app);
+
+    // The map is open either way; a stale recent list is not worth failing over
+    let _ = app.recent.record(path);
     Ok(())
 }
 
+/// Put a map imported from another outliner in sentence case when it came
+/// in capitals only and `fix_shouting_case` is on
+pub fn fix_imported_case(app: &mut AppState) {
+    let Some(root_id) = app.root_id else {
+        return;
+    };
+    if !app.config.fix_shouting_case
+        || matches!(app.format, MapFormat::Hmm | MapFormat::Sqlite)
+        || !casing::is_shouting_outline(&app.tree, root_id)
+    {
+        return;
+    }
+    let count = casing::fix_shouting_case(&mut app.tree, root_id, &app.config.acronyms);
+    if count > 0 {
+        app.is_dirty = true;
+        app.notify_with("status.case_fixed", &[("count", &count)]);
+    }
+}
+
+/// File name the save-as prompt offers for a map that has none yet
+const DEFAULT_SAVE_AS_PATH: &str = "mindmap.hmm";
+
+/// Save under the default file name without asking
 pub fn save_as(app: &mut AppState) -> Result<()> {
-    // For now, we'll save with a default name
-    // In a real implementation, this would open a file dialog
-    let default_path = PathBuf::from("mindmap.hmm");
+    save_to(app, PathBuf::from(DEFAULT_SAVE_AS_PATH), MapFormat::Hmm)
+}
 
+/// Save the map to `path` in `format` and keep editing that file from now on
+pub fn save_to(app: &mut AppState, path: PathBuf, format: MapFormat) -> Result<()> {
     if let Some(root_id) = app.root_id {
-        match parser::save_file(&app.tree, root_id, &default_path) {
+        match parser::save_file_as(&mut app.tree, root_id, &path, format) {
             Ok(_) => {
-                app.filename = Some(default_path.clone());
+                app.notify_with("status.saved_as", &[("path", &path.display())]);
+                git_after_save(app, &path);
+                app.filename = Some(path);
+                app.format = format;
+                // The copy is the user's own to edit, and no longer scratch
+                app.read_only = None;
+                app.scratch = false;
                 app.is_dirty = false;
-                app.set_message(format!("Saved as {}", default_path.display()));
+                let _ = app.store_pins();
+                hooks::run_hook(app, HookEvent::Save, None);
             }
             Err(e) => {
-                app.set_message(format!("Failed to save: {}", e));
+                app.notify_with("status.save_failed", &[("error", &e)]);
                 return Err(e);
             }
         }
     } else {
-        app.set_message("No content to save");
+        app.notify("status.nothing_to_save");
     }
     Ok(())
 }
 
+/// Ask for the path to save the map under, quitting afterwards if `quit_after`
+pub fn start_save_as(app: &mut AppState, quit_after: bool) {
+    // Maps in a read-only format are offered a .hmm name next to the original
+    let buffer = match &app.filename {
+        Some(path) if !app.format.is_writable() => path
+            .with_extension(MapFormat::Hmm.extension())
+            .display()
+            .to_string(),
+        Some(path) => path.display().to_string(),
+        None => DEFAULT_SAVE_AS_PATH.to_string(),
+    };
+    app.mode = AppMode::SaveAs {
+        buffer,
+        quit_after,
+        format: None,
+    };
+}
+
+pub fn type_save_as_char(app: &mut AppState, c: char) {
+    if let AppMode::SaveAs { buffer, .. } = &mut app.mode {
+        buffer.push(c);
+    }
+}
+
+pub fn backspace_save_as(app: &mut AppState) {
+    if let AppMode::SaveAs { buffer, .. } = &mut app.mode {
+        buffer.pop();
+    }
+}
+
+/// Save to the typed path; errors stay in the status line so a failed save
+/// never quits
+pub fn confirm_save_as(app: &mut AppState) {
+    let AppMode::SaveAs {
+        buffer,
+        quit_after,
+        format,
+    } = &app.mode
+    else {
+        return;
+    };
+    let path = PathBuf::from(buffer.trim());
+    let quit_after = *quit_after;
+    // Without a chosen format the extension decides, and a path without a
+    // known extension keeps the current format if it can be written
+    let format = format
+        .or_else(|| MapFormat::from_path(&path))
+        .or(Some(app.format).filter(|format| format.is_writable()))
+        .unwrap_or_default();
+    app.mode = AppMode::Normal;
+
+    if path.as_os_str().is_empty() {
+        app.notify("status.no_filename");
+    } else if save_to(app, path, format).is_ok() && quit_after && !app.is_dirty {
+        app.running = false;
+    }
+}
+
+pub fn cancel_save_as(app: &mut AppState) {
+    app.mode = AppMode::Normal;
+}
+
+/// Ask for the new path of the map's file, offering the current one
+pub fn start_rename_file(app: &mut AppState) {
+    let Some(path) = &app.filename else {
+        app.notify("status.no_filename");
+        return;
+    };
+    app.mode = AppMode::RenameFile {
+        buffer: path.display().to_string(),
+    };
+}
+
+pub fn type_rename_file_char(app: &mut AppState, c: char) {
+    if let AppMode::RenameFile { buffer } = &mut app.mode {
+        buffer.push(c);
+    }
+}
+
+pub fn backspace_rename_file(app: &mut AppState) {
+    if let AppMode::RenameFile { buffer } = &mut app.mode {
+        buffer.pop();
+    }
+}
+
+/// Move the map's file to the typed path and save the map there, in the
+/// format its extension names or else the current one
+pub fn confirm_rename_file(app: &mut AppState) {
+    let AppMode::RenameFile { buffer } = std::mem::replace(&mut app.mode, AppMode::Normal) else {
+        return;
+    };
+    let Some(old_path) = app.filename.clone() else {
+        return;
+    };
+    let path = PathBuf::from(buffer.trim());
+    if path.as_os_str().is_empty() || path == old_path {
+        return;
+    }
+    if path.exists() {
+        app.notify_with("status.rename_target_exists", &[("path", &path.display())]);
+        return;
+    }
+
+    if let Err(e) = std::fs::rename(&old_path, &path) {
+        app.notify_with("status.rename_failed", &[("error", &e)]);
+        return;
+    }
+    // The old file is gone, so the map belongs to the new one even if saving fails
+    app.filename = Some(path.clone());
+    let format = MapFormat::from_path(&path)
+        .or(Some(app.format))
+        .filter(|format| format.is_writable())
+        .unwrap_or_default();
+    if save_to(app, path.clone(), format).is_ok() {
+        app.notify_with("status.file_renamed", &[("path", &path.display())]);
+        // The map is open either way; a stale recent list is not worth failing over
+        let _ = app.recent.record(&path);
+    }
+    let rename = LinkRename::File {
+        from: &old_path,
+        to: &path,
+    };
+    update_workspace_links(app, rename);
+}
+
+pub fn cancel_rename_file(app: &mut AppState) {
+    app.mode = AppMode::Normal;
+}
+
+/// Ask which format to save the map in, starting from the current one
+pub fn start_save_format(app: &mut AppState) {
+    let selected = if app.format.is_writable() {
+        app.format
+    } else {
+        MapFormat::Hmm
+    };
+    app.mode = AppMode::FormatPrompt { selected };
+}
+
+pub fn format_prompt_next(app: &mut AppState) {
+    move_format_selection(app, false);
+}
+
+pub fn format_prompt_previous(app: &mut AppState) {
+    move_format_selection(app, true);
+}
+
+fn move_format_selection(app: &mut AppState, backwards: bool) {
+    if let AppMode::FormatPrompt { selected } = &mut app.mode {
+        let formats: Vec<_> = MapFormat::writable().collect();
+        let index = formats.iter().position(|f| f == selected).unwrap_or(0);
+        let step = if backwards { formats.len() - 1 } else { 1 };
+        *selected = formats[(index + step) % formats.len()];
+    }
+}
+
+/// Go on to the file name, offering the current one with the format's extension
+pub fn choose_save_format(app: &mut AppState, format: MapFormat) {
+    let path = app
+        .filename
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SAVE_AS_PATH));
+    app.mode = AppMode::SaveAs {
+        buffer: path
+            .with_extension(format.extension())
+            .display()
+            .to_string(),
+        quit_after: false,
+        format: Some(format),
+    };
+}
+
 pub fn export_text(app: &mut AppState) -> Result<()> {
     if let Some(root_id) = app.root_id {
         // Export the entire visible tree to text format
-        let mut output = String::new();
-        export_text_node(&app.tree, root_id, &mut output, 0);
+        let output = export::to_text(&app.tree, root_id, &app.export_options());
 
         // Copy to clipboard
-        if let Ok(mut ctx) = ClipboardContext::new() {
-            let _ = ctx.set_contents(output.clone());
+        set_system_clipboard(output.clone());
+        app.clipboard = Some(output);
+
+        app.notify("status.exported_to_clipboard");
+    }
+
+    Ok(())
+}
+
+/// The active node's subtree to export, below its ancestors when
+/// `export_breadcrumbs` is on
+fn active_subtree(app: &AppState) -> Option<(Cow<'_, Arena<Node>>, NodeId)> {
+    let active_id = app.active_node_id?;
+    match app.root_id {
+        Some(root_id) if app.config.export_breadcrumbs => {
+            let (tree, root) = export::with_breadcrumbs(&app.tree, root_id, active_id);
+            Some((Cow::Owned(tree), root))
         }
+        _ => Some((Cow::Borrowed(&app.tree), active_id)),
+    }
+}
+
+/// Export options for a tree from `active_subtree`, reading folded node counts
+/// from the aggregates only when it is the map itself, not a copy
+fn subtree_options(app: &AppState, tree: &Arena<Node>) -> export::ExportOptions {
+    if std::ptr::eq(tree, &app.tree) {
+        app.export_options()
+    } else {
+        export::ExportOptions::from_config(&app.config)
+    }
+}
+
+/// Copy the active node's subtree to the clipboard as tab-indented text
+pub fn export_subtree(app: &mut AppState) -> Result<()> {
+    if let Some((tree, root)) = active_subtree(app) {
+        let output = export::to_text(&tree, root, &subtree_options(app, &tree));
+
+        set_system_clipboard(output.clone());
+        app.clipboard = Some(output);
+
+        app.notify("status.exported_subtree");
+    }
+
+    Ok(())
+}
+
+/// Copy the active node's subtree to the clipboard as a Mermaid mindmap
+pub fn export_mermaid(app: &mut AppState) -> Result<()> {
+    if let Some((tree, root)) = active_subtree(app) {
+        let output = export::to_mermaid(
+            &tree,
+            root,
+            export::MermaidStyle::Mindmap,
+            &subtree_options(app, &tree),
+        );
+
+        set_system_clipboard(output.clone());
         app.clipboard = Some(output);
 
-        app.set_message("Exported the map to clipboard.");
+        app.notify("status.exported_mermaid");
     }
 
     Ok(())
 }
 
-pub fn export_text_node(tree: &Arena<Node>, node_id: NodeId, output: &mut String, depth: usize) {
-    let node = tree.get(node_id).unwrap().get();
+/// Write the visible map as an HTML page next to the map file, named after it
+pub fn export_html(app: &mut AppState) {
+    let Some(root_id) = app.root_id else {
+        return;
+    };
+    let path = app
+        .filename
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_SAVE_AS_PATH))
+        .with_extension(export::ExportFormat::Html.extension());
+    let output = export::to_html(&app.tree, root_id, &app.export_options());
+    match std::fs::write(&path, output) {
+        Ok(()) => app.notify_with("status.exported_html", &[("path", &path.display())]),
+        Err(e) => app.notify_with("status.export_html_failed", &[("error", &e)]),
+    }
+}
+
+/// Pipe an export of the map through a command configured under
+/// `[export.pipes]`, asking which one when there are several
+pub fn export_pipe(app: &mut AppState) {
+    let mut names: Vec<_> = app.config.export.pipes.keys().cloned().collect();
+    names.sort_unstable();
+    match names.as_slice() {
+        [] => app.notify("status.no_export_pipes"),
+        [name] => {
+            let name = name.clone();
+            export_pipe_named(app, &name);
+        }
+        _ => {
+            let names = names.into_iter().map(PathBuf::from).collect();
+            open_picker(app, PickerKind::ExportPipe, names);
+        }
+    }
+}
+
+/// Pipe an export of the map through the command configured as `name`,
+/// reporting how it exited
+pub(crate) fn export_pipe_named(app: &mut AppState, name: &str) {
+    let (Some(root_id), Some(pipe)) = (app.root_id, app.config.export.pipes.get(name)) else {
+        return;
+    };
+    let (command, format) = (pipe.command.clone(), pipe.format);
+    let output = export::export(&app.tree, root_id, format, &app.export_options());
+    let error = match hooks::pipe_to_shell(&command, output, node_env(app), map_dir(app).as_deref())
+    {
+        Ok(result) if result.status.success() => {
+            let (format, status) = (format.name(), result.status);
+            app.notify_with(
+                "status.exported_to_pipe",
+                &[("format", &format), ("name", &name), ("status", &status)],
+            );
+            return;
+        }
+        Ok(result) => {
+            // The first line of what the command said is usually the reason
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            match stderr.lines().map(str::trim).find(|line| !line.is_empty()) {
+                Some(line) => format!("{}: {line}", result.status),
+                None => result.status.to_string(),
+            }
+        }
+        Err(e) => e.to_string(),
+    };
+    app.notify_with(
+        "status.export_pipe_failed",
+        &[("name", &name), ("error", &error)],
+    );
+}
 
-    // Add the current node with proper indentation
-    output.push_str(&"\t".repeat(depth));
-    output.push_str(&node.title);
-    output.push('\n');
+/// Copy the map rendered through a configured export template to the
+/// clipboard, asking which template when there are several
+pub fn export_template(app: &mut AppState) {
+    let mut templates: Vec<_> = app.config.export.templates.iter().collect();
+    templates.sort_unstable();
+    match templates.as_slice() {
+        [] => app.notify("status.no_export_templates"),
+        [(name, _)] => {
+            let name = name.to_string();
+            export_template_named(app, &name);
+        }
+        _ => {
+            let paths = templates
+                .iter()
+                .map(|(_, path)| path.to_path_buf())
+                .collect();
+            open_picker(app, PickerKind::ExportTemplate, paths);
+        }
+    }
+}
 
-    // Process children if node is not collapsed
-    if !node.is_collapsed {
-        for child_id in node_id.children(tree) {
-            export_text_node(tree, child_id, output, depth + 1);
+/// Copy the map rendered through the export template `name` to the clipboard
+pub(crate) fn export_template_named(app: &mut AppState, name: &str) {
+    let Some(root_id) = app.root_id else {
+        return;
+    };
+    let output = app.config.export.template(name).and_then(|template| {
+        let options = app.export_options();
+        export::to_template(&app.tree, root_id, &template, &options)
+    });
+    match output {
+        Ok(output) => {
+            set_system_clipboard(output.clone());
+            app.clipboard = Some(output);
+            app.notify_with("status.exported_template", &[("name", &name)]);
         }
+        Err(e) => app.notify_with("status.export_template_failed", &[("error", &e)]),
     }
 }
 
@@ -89,6 +533,7 @@ pub fn export_text_node(tree: &Arena<Node>, node_id: NodeId, output: &mut String
 mod tests {
     use super::*;
     use crate::config::AppConfig;
+    use crate::model::Node;
 
     fn create_test_app() -> AppState {
         let config = AppConfig::default();
@@ -134,4 +579,279 @@ mod tests {
         // Should not contain grandchild of collapsed Child 2
         assert!(!exported.contains("Grandchild"));
     }
+
+    #[test]
+    fn test_export_mermaid_uses_active_subtree() {
+        let mut app = create_test_app();
+        let child2 = app.root_id.unwrap().children(&app.tree).nth(1).unwrap();
+        app.active_node_id = Some(child2);
+
+        export_mermaid(&mut app).unwrap();
+
+        assert_eq!(
+            app.clipboard.as_deref(),
+            Some("mindmap\n  root((\"Child 2\"))\n    Grandchild\n")
+        );
+    }
+
+    #[test]
+    fn test_export_subtree_with_breadcrumbs() {
+        let mut app = create_test_app();
+        let child2 = app.root_id.unwrap().children(&app.tree).nth(1).unwrap();
+        app.active_node_id = Some(child2);
+
+        export_subtree(&mut app).unwrap();
+        assert_eq!(app.clipboard.as_deref(), Some("Child 2\n\tGrandchild\n"));
+
+        app.config.export_breadcrumbs = true;
+        export_subtree(&mut app).unwrap();
+        assert_eq!(
+            app.clipboard.as_deref(),
+            Some("Root\n\tChild 2\n\t\tGrandchild\n")
+        );
+        export_mermaid(&mut app).unwrap();
+        assert_eq!(
+            app.clipboard.as_deref(),
+            Some("mindmap\n  root((\"Root\"))\n    Child 2\n      Grandchild\n")
+        );
+    }
+
+    #[test]
+    fn test_export_template() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut app = create_test_app();
+        export_template(&mut app);
+        assert_eq!(
+            app.message.as_deref(),
+            Some("No export templates configured under [export.templates]")
+        );
+
+        let template = temp_dir.path().join("list.j2");
+        std::fs::write(
+            &template,
+            "{% for node in nodes %}- {{ node.title }}\n{% endfor %}",
+        )
+        .unwrap();
+        app.config
+            .export
+            .templates
+            .insert("list".to_string(), template.clone());
+        export_template(&mut app);
+        assert!(app
+            .clipboard
+            .as_deref()
+            .unwrap()
+            .starts_with("- Root\n- Child 1\n"));
+
+        // With several templates the picker asks which one, by name
+        app.config
+            .export
+            .templates
+            .insert("other".to_string(), temp_dir.path().join("other.j2"));
+        export_template(&mut app);
+        assert!(matches!(
+            app.mode,
+            AppMode::Picker {
+                kind: PickerKind::ExportTemplate,
+                ..
+            }
+        ));
+        assert_eq!(super::super::picker_label(&app, &template), "list");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_export_pipe_reports_exit_status() {
+        use crate::config::ExportPipe;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut app = create_test_app();
+        app.filename = Some(temp_dir.path().join("plan.hmm"));
+        export_pipe(&mut app);
+        assert_eq!(
+            app.message.as_deref(),
+            Some("No export commands configured under [export.pipes]")
+        );
+
+        // The command runs from the map's directory
+        let pipe = ExportPipe {
+            command: "cat > out.md".to_string(),
+            format: export::ExportFormat::Markdown,
+        };
+        app.config.export.pipes.insert("save".to_string(), pipe);
+        export_pipe(&mut app);
+        assert_eq!(
+            app.message.as_deref(),
+            Some("Piped the map as markdown through save: exit status: 0")
+        );
+        let written = std::fs::read_to_string(temp_dir.path().join("out.md")).unwrap();
+        assert!(written.starts_with("# Root\n"));
+
+        let pipe = ExportPipe {
+            command: "echo 'no such printer' >&2; exit 3".to_string(),
+            format: export::ExportFormat::Text,
+        };
+        app.config.export.pipes.insert("print".to_string(), pipe);
+        export_pipe(&mut app);
+        assert!(matches!(
+            app.mode,
+            AppMode::Picker {
+                kind: PickerKind::ExportPipe,
+                ..
+            }
+        ));
+        app.mode = AppMode::Normal;
+        export_pipe_named(&mut app, "print");
+        assert_eq!(
+            app.message.as_deref(),
+            Some("Export through print failed: exit status: 3: no such printer")
+        );
+    }
+
+    #[test]
+    fn test_save_in_chosen_format() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut app = create_test_app();
+        app.filename = Some(temp_dir.path().join("plan.hmm"));
+
+        start_save_format(&mut app);
+        assert_eq!(
+            app.mode,
+            AppMode::FormatPrompt {
+                selected: MapFormat::Hmm
+            }
+        );
+        format_prompt_previous(&mut app);
+        format_prompt_next(&mut app);
+        format_prompt_next(&mut app);
+        let AppMode::FormatPrompt { selected } = app.mode else {
+            panic!("format prompt closed");
+        };
+        assert_ne!(selected, MapFormat::Hmm);
+
+        choose_save_format(&mut app, MapFormat::Csv);
+        let path = temp_dir.path().join("plan.csv");
+        assert_eq!(
+            app.mode,
+            AppMode::SaveAs {
+                buffer: path.display().to_string(),
+                quit_after: false,
+                format: Some(MapFormat::Csv),
+            }
+        );
+
+        confirm_save_as(&mut app);
+        assert_eq!(app.filename, Some(path.clone()));
+        assert_eq!(app.format, MapFormat::Csv);
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .starts_with("level,title\n1,Root\n"));
+
+        // Later saves keep the chosen format
+        app.is_dirty = true;
+        save(&mut app).unwrap();
+        assert!(!app.is_dirty);
+    }
+
+    #[test]
+    fn test_rename_file_moves_and_saves_the_map() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let old_path = temp_dir.path().join("draft.hmm");
+        let new_path = temp_dir.path().join("plan.hmm");
+        let mut app = create_test_app();
+        app.config.root_title = "{file}: {title}".to_string();
+        save_to(&mut app, old_path.clone(), MapFormat::Hmm).unwrap();
+        let root = app.root_id.unwrap();
+        assert_eq!(app.display_title(root, app.tree[root].get()), "draft: Root");
+
+        app.tree[root].get_mut().title = "Plan".to_string();
+        start_rename_file(&mut app);
+        for _ in "draft.hmm".chars() {
+            backspace_rename_file(&mut app);
+        }
+        for c in "plan.hmm".chars() {
+            type_rename_file_char(&mut app, c);
+        }
+        confirm_rename_file(&mut app);
+
+        assert!(!old_path.exists());
+        assert!(std::fs::read_to_string(&new_path)
+            .unwrap()
+            .starts_with("Plan\n"));
+        assert_eq!(app.filename.as_deref(), Some(new_path.as_path()));
+        assert!(!app.is_dirty);
+        assert_eq!(app.display_title(root, app.tree[root].get()), "plan: Plan");
+    }
+
+    #[test]
+    fn test_save_keeps_detected_format() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("backup");
+        std::fs::write(&path, r#"[{"nm": "Outline", "ch": [{"nm": "Item"}]}]"#).unwrap();
+        let mut app = create_test_app();
+
+        open_file(&mut app, &path).unwrap();
+        assert_eq!(app.format, MapFormat::Outliner);
+
+        save(&mut app).unwrap();
+        assert!(std::fs::read_to_string(&path)
+            .unwrap()
+            .contains(r#""nm": "Item""#));
+    }
+
+    #[test]
+    fn test_saving_read_only_format_asks_for_a_name() {
+        let mut app = create_test_app();
+        app.filename = Some(PathBuf::from("plan.xmind"));
+        app.format = MapFormat::XMind;
+
+        save(&mut app).unwrap();
+        assert_eq!(
+            app.mode,
+            AppMode::SaveAs {
+                buffer: "plan.hmm".to_string(),
+                quit_after: false,
+                format: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_save_commits_in_git_repository() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .arg("-C")
+                .arg(temp_dir.path())
+                .args(args)
+                .status()
+                .unwrap();
+            assert!(status.success());
+        };
+        git(&["init", "--quiet"]);
+        git(&["config", "user.name", "Test"]);
+        git(&["config", "user.email", "test@example.com"]);
+
+        let mut app = create_test_app();
+        app.config.git_on_save = GitOnSave::Commit;
+        app.config.git_commit_message = "Notes: {file}".to_string();
+        app.filename = Some(temp_dir.path().join("notes.hmm"));
+        save(&mut app).unwrap();
+        assert!(app.message.as_deref().unwrap().ends_with("and committed"));
+        let path = app.filename.clone().unwrap();
+        assert_eq!(
+            git::show_head(&path).unwrap(),
+            std::fs::read(&path).unwrap()
+        );
+
+        app.config.git_on_save = GitOnSave::Stat;
+        let root = app.root_id.unwrap();
+        app.tree[root].get_mut().title = "Root v2".to_string();
+        save(&mut app).unwrap();
+        assert!(app
+            .message
+            .as_deref()
+            .unwrap()
+            .ends_with(": 1 insertion(+), 1 deletion(-)"));
+    }
 }
@@ -1,9 +1,22 @@
 use crate::app::AppState;
+use crate::casing::{is_shouting, sentence_case};
+use crate::config::{HeatmapMode, SortCollation};
+use crate::hooks::{self, HookEvent};
+use crate::kind::{with_kind, NodeKind};
+use crate::model::NodeId;
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::cmp::Ordering;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use super::on_off;
 
 pub fn toggle_symbol(app: &mut AppState) {
     if let Some(active_id) = app.active_node_id {
         app.push_history();
 
+        let mut done = false;
         if let Some(node) = app.tree.get_mut(active_id) {
             let title = &mut node.get_mut().title;
             let sym1 = format!("{} ", app.config.symbol1);
@@ -15,19 +28,211 @@ pub fn toggle_symbol(app: &mut AppState) {
                 *title = title[sym2.len()..].to_string();
             } else {
                 *title = format!("{}{}", sym1, title);
+                done = true;
             }
         }
+        if done {
+            hooks::run_hook(app, HookEvent::TaskDone, None);
+        }
+    }
+}
+
+/// Step the kind of the active node from none through heading, item, note
+/// and quote back to none
+pub fn cycle_kind(app: &mut AppState) {
+    let Some(active_id) = app.active_node_id else {
+        return;
+    };
+    app.push_history();
+
+    if let Some(node) = app.tree.get_mut(active_id) {
+        let node = node.get_mut();
+        let kind = NodeKind::next(node.kind());
+        node.title = with_kind(&node.title, kind);
+        match kind {
+            Some(kind) => app.notify_with("status.kind_set", &[("kind", &kind.name())]),
+            None => app.notify("status.kind_cleared"),
+        }
     }
+    app.is_dirty = true;
+    app.last_modify_time = Some(std::time::Instant::now());
+}
+
+/// Put the titles in the active subtree written in capitals only in sentence case
+pub fn fix_case(app: &mut AppState) {
+    let Some(active_id) = app.active_node_id else {
+        return;
+    };
+    let fixed: Vec<(NodeId, String)> = active_id
+        .descendants(&app.tree)
+        .filter_map(|id| {
+            let title = &app.tree.get(id)?.get().title;
+            let fixed = sentence_case(title, &app.config.acronyms);
+            (is_shouting(title) && fixed != *title).then_some((id, fixed))
+        })
+        .collect();
+    if fixed.is_empty() {
+        app.notify("status.nothing_to_fix_case");
+        return;
+    }
+
+    app.push_history();
+    let count = fixed.len();
+    for (id, title) in fixed {
+        if let Some(node) = app.tree.get_mut(id) {
+            node.get_mut().title = title;
+        }
+    }
+    app.is_dirty = true;
+    app.last_modify_time = Some(std::time::Instant::now());
+    app.notify_with("status.case_fixed", &[("count", &count)]);
 }
 
 pub fn sort_siblings(app: &mut AppState) {
-    // TODO: Implement sibling sorting
-    app.set_message("Sorting not yet implemented");
+    let Some(active_id) = app.active_node_id else {
+        return;
+    };
+
+    // Sort the active node among its siblings; the root sorts its own children
+    let parent_id = active_id.ancestors(&app.tree).nth(1).unwrap_or(active_id);
+    let mut siblings: Vec<NodeId> = parent_id.children(&app.tree).collect();
+    if siblings.len() < 2 {
+        app.notify("status.nothing_to_sort");
+        return;
+    }
+
+    let prefixes = match IgnoredPrefixes::new(&app.config.sort_ignore_prefixes) {
+        Ok(prefixes) => prefixes,
+        Err(e) => {
+            app.notify_with("status.invalid_sort_prefix", &[("error", &e)]);
+            return;
+        }
+    };
+    let collation = app.config.sort_collation;
+    siblings.sort_by(|a, b| {
+        let title_a = &app.tree.get(*a).unwrap().get().title;
+        let title_b = &app.tree.get(*b).unwrap().get().title;
+        compare_titles(prefixes.strip(title_a), prefixes.strip(title_b), collation)
+            .then_with(|| title_a.cmp(title_b))
+    });
+
+    let already_sorted = siblings.iter().copied().eq(parent_id.children(&app.tree));
+    if already_sorted {
+        app.notify("status.siblings_already_sorted");
+        return;
+    }
+
+    app.begin_transaction();
+    for sibling_id in siblings {
+        sibling_id.detach(&mut app.tree);
+        parent_id.append(sibling_id, &mut app.tree);
+    }
+    app.commit_transaction();
+    app.notify("status.siblings_sorted");
+}
+
+/// Title prefixes left out when sorting, from `sort_ignore_prefixes`
+pub struct IgnoredPrefixes(Vec<Regex>);
+
+impl IgnoredPrefixes {
+    /// Anchor each pattern at the start of a title, refusing invalid ones
+    pub fn new(patterns: &[String]) -> Result<Self> {
+        let prefixes = patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(&format!("^(?:{pattern})"))
+                    .with_context(|| format!("invalid sort prefix '{pattern}'"))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self(prefixes))
+    }
+
+    /// The sort key of `title`: the title without the prefixes at its start,
+    /// however many of them there are
+    pub fn strip<'a>(&self, mut title: &'a str) -> &'a str {
+        // A pattern that matches nothing could otherwise match forever
+        while let Some(end) = self
+            .0
+            .iter()
+            .filter_map(|prefix| prefix.find(title))
+            .map(|found| found.end())
+            .find(|&end| end > 0)
+        {
+            title = &title[end..];
+        }
+        title
+    }
+}
+
+/// Compare two titles according to the configured collation.
+///
+/// Ties under the case/accent-insensitive collations fall back to a byte-wise
+/// comparison so the resulting order is deterministic.
+pub fn compare_titles(a: &str, b: &str, collation: SortCollation) -> Ordering {
+    let primary = match collation {
+        SortCollation::Natural => natural_cmp(&fold_title(a), &fold_title(b)),
+        SortCollation::CaseInsensitive => fold_title(a).cmp(&fold_title(b)),
+        SortCollation::Bytewise => Ordering::Equal,
+    };
+    primary.then_with(|| a.cmp(b))
+}
+
+/// Fold a title for comparison: strip accents (NFD minus combining marks) and lowercase
+fn fold_title(title: &str) -> String {
+    title
+        .nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Compare strings treating runs of ASCII digits as numbers
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().peekable();
+    let mut b_chars = b.chars().peekable();
+
+    loop {
+        match (a_chars.peek().copied(), b_chars.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(ca), Some(cb)) if ca.is_ascii_digit() && cb.is_ascii_digit() => {
+                let num_a = take_digits(&mut a_chars);
+                let num_b = take_digits(&mut b_chars);
+                let ordering = num_a
+                    .len()
+                    .cmp(&num_b.len())
+                    .then_with(|| num_a.cmp(&num_b));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(ca), Some(cb)) => {
+                if ca != cb {
+                    return ca.cmp(&cb);
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// Consume a run of digits, returning it without leading zeros
+fn take_digits(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.peek().copied().filter(char::is_ascii_digit) {
+        if !(digits.is_empty() && c == '0') {
+            digits.push(c);
+        }
+        chars.next();
+    }
+    digits
 }
 
 pub fn toggle_numbers(app: &mut AppState) {
     // TODO: Implement numbering
-    app.set_message("Numbering not yet implemented");
+    app.notify("status.numbering_not_implemented");
 }
 
 pub fn toggle_hide(app: &mut AppState) {
@@ -38,10 +243,10 @@ pub fn toggle_hide(app: &mut AppState) {
             let title = &mut node.get_mut().title;
             if title.starts_with("[HIDDEN] ") {
                 *title = title[9..].to_string();
-                app.set_message("Node unhidden");
+                app.notify("status.node_unhidden");
             } else {
                 *title = format!("[HIDDEN] {}", title);
-                app.set_message("Node hidden");
+                app.notify("status.node_hidden");
             }
         }
     }
@@ -49,19 +254,34 @@ pub fn toggle_hide(app: &mut AppState) {
 
 pub fn toggle_show_hidden(app: &mut AppState) {
     app.config.show_hidden = !app.config.show_hidden;
-    app.set_message(format!(
-        "Show hidden: {}",
-        if app.config.show_hidden { "ON" } else { "OFF" }
-    ));
+    let state = on_off(app, app.config.show_hidden);
+    app.notify_with("status.show_hidden", &[("state", &state)]);
+}
+
+pub fn toggle_depth_guides(app: &mut AppState) {
+    app.config.depth_guides = !app.config.depth_guides;
+    let state = on_off(app, app.config.depth_guides);
+    app.notify_with("status.depth_guides", &[("state", &state)]);
+}
+
+/// Switch the heatmap between off, subtree size and recent activity
+pub fn cycle_heatmap(app: &mut AppState) {
+    app.config.heatmap = app.config.heatmap.next();
+    app.notify(match app.config.heatmap {
+        HeatmapMode::Off => "status.heatmap_off",
+        HeatmapMode::Size => "status.heatmap_size",
+        HeatmapMode::Activity => "status.heatmap_activity",
+    });
 }
 
 pub fn increase_text_width(app: &mut AppState) {
     app.config.max_parent_node_width = (app.config.max_parent_node_width as f32 * 1.2) as usize;
     app.config.max_leaf_node_width = (app.config.max_leaf_node_width as f32 * 1.2) as usize;
-    app.set_message(format!(
-        "Width: {} / {}",
-        app.config.max_parent_node_width, app.config.max_leaf_node_width
-    ));
+    let (parent, leaf) = (
+        app.config.max_parent_node_width,
+        app.config.max_leaf_node_width,
+    );
+    app.notify_with("status.text_width", &[("parent", &parent), ("leaf", &leaf)]);
 }
 
 pub fn decrease_text_width(app: &mut AppState) {
@@ -69,22 +289,25 @@ pub fn decrease_text_width(app: &mut AppState) {
         ((app.config.max_parent_node_width as f32 / 1.2).max(15.0)) as usize;
     app.config.max_leaf_node_width =
         ((app.config.max_leaf_node_width as f32 / 1.2).max(15.0)) as usize;
-    app.set_message(format!(
-        "Width: {} / {}",
-        app.config.max_parent_node_width, app.config.max_leaf_node_width
-    ));
+    let (parent, leaf) = (
+        app.config.max_parent_node_width,
+        app.config.max_leaf_node_width,
+    );
+    app.notify_with("status.text_width", &[("parent", &parent), ("leaf", &leaf)]);
 }
 
 pub fn increase_line_spacing(app: &mut AppState) {
     app.config.line_spacing += 1;
-    app.set_message(format!("Line spacing: {}", app.config.line_spacing));
+    let spacing = app.config.line_spacing;
+    app.notify_with("status.line_spacing", &[("spacing", &spacing)]);
 }
 
 pub fn decrease_line_spacing(app: &mut AppState) {
     if app.config.line_spacing > 0 {
         app.config.line_spacing -= 1;
     }
-    app.set_message(format!("Line spacing: {}", app.config.line_spacing));
+    let spacing = app.config.line_spacing;
+    app.notify_with("status.line_spacing", &[("spacing", &spacing)]);
 }
 
 #[cfg(test)]
@@ -147,6 +370,114 @@ mod tests {
         assert_eq!(title_without_sym, original_title);
     }
 
+    fn child_titles(app: &AppState) -> Vec<String> {
+        let root = app.root_id.unwrap();
+        root.children(&app.tree)
+            .map(|id| app.tree.get(id).unwrap().get().title.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_siblings_natural() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        for title in ["item 10", "Banana", "item 9", "apple", "Éclair", "egg"] {
+            let child = app.tree.new_node(Node::new(title.to_string()));
+            root.append(child, &mut app.tree);
+        }
+        app.active_node_id = root.children(&app.tree).next();
+
+        sort_siblings(&mut app);
+
+        assert_eq!(
+            child_titles(&app),
+            vec!["apple", "Banana", "Éclair", "egg", "item 9", "item 10"]
+        );
+        assert!(app.is_dirty);
+    }
+
+    #[test]
+    fn test_sort_siblings_bytewise() {
+        let mut app = create_test_app();
+        app.config.sort_collation = SortCollation::Bytewise;
+        let root = app.root_id.unwrap();
+        for title in ["item 10", "banana", "item 9", "Apple"] {
+            let child = app.tree.new_node(Node::new(title.to_string()));
+            root.append(child, &mut app.tree);
+        }
+
+        // Sorting from the root sorts its children
+        sort_siblings(&mut app);
+
+        assert_eq!(
+            child_titles(&app),
+            vec!["Apple", "banana", "item 10", "item 9"]
+        );
+    }
+
+    #[test]
+    fn test_sort_siblings_ignores_prefixes() {
+        let mut app = create_test_app();
+        app.config.sort_ignore_prefixes = vec![
+            "[✓✗] ".to_string(),
+            r"\d+\. ".to_string(),
+            "(?i)the ".to_string(),
+        ];
+        let root = app.root_id.unwrap();
+        for title in [
+            "Banana",
+            "✓ Apple",
+            "The Cherry",
+            "2. Apricot",
+            "✗ 1. the Avocado",
+        ] {
+            let child = app.tree.new_node(Node::new(title.to_string()));
+            root.append(child, &mut app.tree);
+        }
+
+        sort_siblings(&mut app);
+        assert_eq!(
+            child_titles(&app),
+            vec![
+                "✓ Apple",
+                "2. Apricot",
+                "✗ 1. the Avocado",
+                "Banana",
+                "The Cherry"
+            ]
+        );
+
+        app.config.sort_ignore_prefixes = vec!["(".to_string()];
+        app.is_dirty = false;
+        sort_siblings(&mut app);
+        assert!(!app.is_dirty);
+        assert!(app
+            .message
+            .as_deref()
+            .unwrap()
+            .starts_with("Cannot sort: invalid sort prefix '('"));
+    }
+
+    #[test]
+    fn test_compare_titles_collations() {
+        use std::cmp::Ordering::*;
+
+        assert_eq!(compare_titles("a2", "a10", SortCollation::Natural), Less);
+        assert_eq!(compare_titles("a02", "a2", SortCollation::Natural), Less);
+        assert_eq!(
+            compare_titles("a2", "a10", SortCollation::CaseInsensitive),
+            Greater
+        );
+        assert_eq!(
+            compare_titles("Zebra", "apple", SortCollation::CaseInsensitive),
+            Greater
+        );
+        assert_eq!(
+            compare_titles("Zebra", "apple", SortCollation::Bytewise),
+            Less
+        );
+    }
+
     #[test]
     fn test_toggle_show_hidden() {
         let mut app = create_test_app();
@@ -156,6 +487,28 @@ mod tests {
         assert_ne!(app.config.show_hidden, initial_show_hidden);
     }
 
+    #[test]
+    fn test_toggle_depth_guides() {
+        let mut app = create_test_app();
+
+        toggle_depth_guides(&mut app);
+        assert!(app.config.depth_guides);
+        toggle_depth_guides(&mut app);
+        assert!(!app.config.depth_guides);
+    }
+
+    #[test]
+    fn test_cycle_heatmap() {
+        let mut app = create_test_app();
+
+        cycle_heatmap(&mut app);
+        assert_eq!(app.config.heatmap, HeatmapMode::Size);
+        cycle_heatmap(&mut app);
+        assert_eq!(app.config.heatmap, HeatmapMode::Activity);
+        cycle_heatmap(&mut app);
+        assert_eq!(app.config.heatmap, HeatmapMode::Off);
+    }
+
     #[test]
     fn test_layout_adjustments() {
         let mut app = create_test_app();
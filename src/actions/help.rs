@@ -8,10 +8,47 @@ pub fn close_help(app: &mut AppState) {
     app.mode = AppMode::Normal;
 }
 
+/// Report the map size and how much memory the undo history holds
+pub fn show_stats(app: &mut AppState) {
+    let aggregates = app.aggregates().unwrap_or_default();
+    let (nodes, words, duplicates) = (aggregates.nodes(), aggregates.words, aggregates.duplicates);
+    let steps = app.history.len();
+    let compressed = app.history.compressed_count();
+    let memory = format_bytes(app.history.memory_usage());
+    app.notify_with(
+        "status.stats",
+        &[
+            ("nodes", &nodes),
+            ("steps", &steps),
+            ("compressed", &compressed),
+            ("memory", &memory),
+            ("words", &words),
+            ("duplicates", &duplicates),
+        ],
+    );
+}
+
+/// Human-readable size such as `512 B`, `3.2 KiB` or `1.5 MiB`
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 3] = ["KiB", "MiB", "GiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::app::Damage;
     use crate::config::AppConfig;
+    use crate::model::Node;
 
     fn create_test_app() -> AppState {
         let config = AppConfig::default();
@@ -28,4 +65,40 @@ mod tests {
         close_help(&mut app);
         assert!(matches!(app.mode, AppMode::Normal));
     }
+
+    #[test]
+    fn test_show_stats() {
+        let mut app = create_test_app();
+        let root = app.tree.new_node(Node::new("Root".to_string()));
+        let child = app.tree.new_node(Node::new("Child".to_string()));
+        root.append(child, &mut app.tree);
+        app.root_id = Some(root);
+        app.push_history();
+
+        show_stats(&mut app);
+        let message = app.message.clone().unwrap();
+        assert!(message.starts_with("2 nodes, undo: 1 steps (0 compressed) in "));
+        assert!(message.ends_with(", 2 words, 0 repeated titles"));
+
+        // Figures worked out on an idle tick last until the map changes
+        app.precompute();
+        let child2 = app.tree.new_node(Node::new("Child".to_string()));
+        root.append(child2, &mut app.tree);
+        // Layout damage alone, as from typing in the edit buffer, keeps them
+        app.mark_damage(Damage::Layout);
+        show_stats(&mut app);
+        assert!(app.message.as_deref().unwrap().starts_with("2 nodes"));
+        app.push_history();
+        show_stats(&mut app);
+        let message = app.message.unwrap();
+        assert!(message.starts_with("3 nodes"));
+        assert!(message.ends_with(", 3 words, 1 repeated titles"));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(3 * 1024 + 200), "3.2 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 / 2), "1.5 MiB");
+    }
 }
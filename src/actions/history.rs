@@ -2,17 +2,17 @@ use crate::app::AppState;
 
 pub fn undo(app: &mut AppState) {
     if app.undo() {
-        app.set_message("Undone");
+        app.notify("status.undone");
     } else {
-        app.set_message("Nothing to undo");
+        app.notify("status.nothing_to_undo");
     }
 }
 
 pub fn redo(app: &mut AppState) {
     if app.redo() {
-        app.set_message("Redone");
+        app.notify("status.redone");
     } else {
-        app.set_message("Nothing to redo");
+        app.notify("status.nothing_to_redo");
     }
 }
 
@@ -93,4 +93,82 @@ mod tests {
             "Modified"
         );
     }
+
+    #[test]
+    fn test_undo_restores_selection_viewport_and_collapse_state() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let branch = root.append_value(Node::new("Branch".to_string()), &mut app.tree);
+        let leaf = branch.append_value(Node::new("Leaf".to_string()), &mut app.tree);
+        let other = root.append_value(Node::new("Other".to_string()), &mut app.tree);
+        app.tree[other].get_mut().is_collapsed = true;
+        app.push_history();
+
+        // Select the leaf, scroll, then delete it
+        app.active_node_id = Some(leaf);
+        app.viewport_top = 7.0;
+        app.viewport_left = 30.0;
+        crate::actions::node::delete_node(&mut app);
+        assert_eq!(app.active_node_id, Some(branch));
+        app.viewport_top = 0.0;
+        app.viewport_left = 0.0;
+
+        undo(&mut app);
+        assert!(!app.tree[leaf].is_removed());
+        assert_eq!(app.active_node_id, Some(leaf));
+        assert_eq!(app.viewport_top, 7.0);
+        assert_eq!(app.viewport_left, 30.0);
+        assert!(app.tree[other].get().is_collapsed);
+    }
+
+    #[test]
+    fn test_transaction_is_undone_in_one_step() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        app.push_history();
+        let entries = app.history.len();
+
+        app.begin_transaction();
+        app.tree[root].get_mut().title = "First".to_string();
+        // Nested transactions and the edits inside them join the outer one
+        app.begin_transaction();
+        app.push_history();
+        root.append_value(Node::new("Child".to_string()), &mut app.tree);
+        app.commit_transaction();
+        assert!(!app.is_dirty);
+        app.commit_transaction();
+        assert!(app.is_dirty);
+        assert!(app.last_modify_time.is_some());
+        assert_eq!(app.history.len(), entries + 1);
+
+        undo(&mut app);
+        assert_eq!(app.tree[root].get().title, "Root");
+        assert_eq!(root.children(&app.tree).count(), 0);
+    }
+
+    #[test]
+    fn test_failed_transaction_is_rolled_back() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        app.push_history();
+        let entries = app.history.len();
+
+        let result: anyhow::Result<()> = app.transaction(|app| {
+            app.tree[root].get_mut().title = "Half done".to_string();
+            anyhow::bail!("import failed")
+        });
+        assert!(result.is_err());
+        assert_eq!(app.tree[root].get().title, "Root");
+        assert_eq!(app.history.len(), entries);
+        assert_eq!(app.transaction_depth, 0);
+        assert!(!app.is_dirty);
+
+        app.transaction(|app| {
+            app.tree[root].get_mut().title = "Done".to_string();
+            Ok(())
+        })
+        .unwrap();
+        assert!(app.is_dirty);
+        assert_eq!(app.tree[root].get().title, "Done");
+    }
 }
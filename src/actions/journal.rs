@@ -0,0 +1,127 @@
+use crate::app::AppState;
+use crate::journal::{ensure_journal, TODAY_TITLE};
+use crate::model::Node;
+use crate::workspace::expand_home;
+use anyhow::{anyhow, Result};
+use chrono::{Local, NaiveDate};
+use std::path::PathBuf;
+use std::time::Instant;
+
+use super::file::open_file;
+use super::movement::reveal_and_select;
+
+/// Open today's journal map, reporting problems in the status line
+pub fn open_journal(app: &mut AppState) {
+    if app.is_dirty {
+        app.notify("status.unsaved_before_open");
+        return;
+    }
+    if let Err(e) = open_journal_for(app, Local::now().date_naive()) {
+        app.notify_with("status.open_failed", &[("error", &e)]);
+    }
+}
+
+/// Open the journal map for `date`, creating it from the template if needed,
+/// and select its "Today" node
+pub fn open_journal_for(app: &mut AppState, date: NaiveDate) -> Result<()> {
+    let dir = journal_dir(app).ok_or_else(|| anyhow!(app.catalog.get("status.no_journal_dir")))?;
+    let template = app.config.journal_template.as_deref().map(expand_home);
+    let path = ensure_journal(&dir, template.as_deref(), date)?;
+    open_file(app, &path)?;
+
+    let root = app
+        .root_id
+        .ok_or_else(|| anyhow!("journal map has no root"))?;
+    let today = root
+        .descendants(&app.tree)
+        .find(|&id| app.tree[id].get().title == TODAY_TITLE);
+    let today = match today {
+        Some(today) => today,
+        None => {
+            // Templates without a "Today" node get one appended to the root
+            app.push_history();
+            let today = app.tree.new_node(Node::new(TODAY_TITLE.to_string()));
+            root.append(today, &mut app.tree);
+            app.is_dirty = true;
+            app.last_modify_time = Some(Instant::now());
+            today
+        }
+    };
+    reveal_and_select(app, today);
+    Ok(())
+}
+
+/// The configured journal directory, or `journal` inside the workspace
+fn journal_dir(app: &AppState) -> Option<PathBuf> {
+    match (&app.config.journal_dir, &app.config.workspace) {
+        (Some(dir), _) => Some(expand_home(dir)),
+        (None, Some(workspace)) => Some(expand_home(workspace).join("journal")),
+        (None, None) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_app() -> AppState {
+        let config = AppConfig::default();
+        let mut app = AppState::new(config);
+        let root = app.tree.new_node(Node::new("Scratch".to_string()));
+        app.root_id = Some(root);
+        app.active_node_id = Some(root);
+        app
+    }
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+    }
+
+    #[test]
+    fn test_open_journal_selects_today() {
+        let dir = TempDir::new().unwrap();
+        let mut app = create_test_app();
+        app.config.workspace = Some(dir.path().to_path_buf());
+
+        open_journal_for(&mut app, date()).unwrap();
+        assert!(dir.path().join("journal/2024-03-01.hmm").exists());
+        assert_eq!(
+            app.tree[app.root_id.unwrap()].get().title,
+            "2024-03-01 Friday"
+        );
+        assert_eq!(app.tree[app.active_node_id.unwrap()].get().title, "Today");
+        assert!(!app.is_dirty);
+    }
+
+    #[test]
+    fn test_open_journal_adds_missing_today_node() {
+        let dir = TempDir::new().unwrap();
+        let template = dir.path().join("template.hmm");
+        fs::write(&template, "{date}\n\tHabits\n").unwrap();
+
+        let mut app = create_test_app();
+        app.config.journal_dir = Some(dir.path().join("days"));
+        app.config.journal_template = Some(template);
+
+        open_journal_for(&mut app, date()).unwrap();
+        let root = app.root_id.unwrap();
+        assert_eq!(root.children(&app.tree).count(), 2);
+        assert_eq!(app.tree[app.active_node_id.unwrap()].get().title, "Today");
+        assert!(app.is_dirty);
+
+        app.undo();
+        let root = app.root_id.unwrap();
+        assert_eq!(root.children(&app.tree).count(), 1);
+    }
+
+    #[test]
+    fn test_open_journal_requires_directory() {
+        let mut app = create_test_app();
+        assert!(open_journal_for(&mut app, date()).is_err());
+        open_journal(&mut app);
+        assert!(app.message.unwrap().contains("No journal directory"));
+    }
+}
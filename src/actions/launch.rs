@@ -0,0 +1,89 @@
+use crate::app::{AppMode, AppState};
+use crate::hooks::{map_dir, node_env, spawn_shell};
+use crate::launch::node_command;
+use crate::link::parse_link;
+
+use super::link::open_link;
+
+/// Ask whether to run the command of the active node, or open its map link
+/// when it has no command
+pub fn activate(app: &mut AppState) {
+    let Some(title) = app
+        .active_node_id
+        .and_then(|id| app.tree.get(id))
+        .map(|node| node.get().title.clone())
+    else {
+        return;
+    };
+
+    match node_command(&title) {
+        Some(command) => {
+            app.mode = AppMode::ConfirmRun {
+                command: command.to_string(),
+            };
+        }
+        None if parse_link(&title).is_some() => open_link(app),
+        None => app.notify("status.nothing_to_activate"),
+    }
+}
+
+/// Run the command asked about, from the directory of the map
+pub fn confirm_run(app: &mut AppState) {
+    let AppMode::ConfirmRun { command } = std::mem::replace(&mut app.mode, AppMode::Normal) else {
+        return;
+    };
+    match spawn_shell(&command, node_env(app), map_dir(app).as_deref()) {
+        Ok(()) => app.notify_with("status.command_started", &[("command", &command)]),
+        Err(e) => app.notify_with("status.command_failed", &[("error", &e)]),
+    }
+}
+
+pub fn cancel_run(app: &mut AppState) {
+    app.mode = AppMode::Normal;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::model::Node;
+
+    fn create_test_app(title: &str) -> AppState {
+        let mut app = AppState::new(AppConfig::default());
+        let root = app.tree.new_node(Node::new("Launcher".to_string()));
+        let node = root.append_value(Node::new(title.to_string()), &mut app.tree);
+        app.root_id = Some(root);
+        app.active_node_id = Some(node);
+        app
+    }
+
+    #[test]
+    fn test_activate_asks_before_running() {
+        let mut app = create_test_app("Greet run: true");
+        activate(&mut app);
+        assert!(matches!(
+            &app.mode,
+            AppMode::ConfirmRun { command } if command == "true"
+        ));
+
+        cancel_run(&mut app);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(app.message, None);
+
+        activate(&mut app);
+        confirm_run(&mut app);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert!(app.message.as_deref().unwrap().contains("true"));
+    }
+
+    #[test]
+    fn test_activate_without_command() {
+        let mut app = create_test_app("Plain node");
+        activate(&mut app);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(
+            app.message.as_deref(),
+            Some(app.catalog.get("status.nothing_to_activate").as_str())
+        );
+    }
+}
@@ -0,0 +1,391 @@
+use crate::app::{AppMode, AppState};
+use crate::import::{
+    add_feed_entries, append_headings, fetch_feed, fetch_outline, new_feed_entries, page_url,
+};
+use crate::link::{
+    find_link_updates, parse_link, resolve_link, rewrite_links, update_links, LinkRename,
+    LinkUpdate,
+};
+use crate::reference::{parse_reference, resolve_reference};
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use super::file::open_file;
+use super::movement::reveal_and_select;
+use super::search::find_node_by_title;
+
+/// Open the map linked from the active node and select the linked node
+pub fn open_link(app: &mut AppState) {
+    let Some(link) = app
+        .active_node_id
+        .and_then(|id| app.tree.get(id))
+        .and_then(|node| parse_link(&node.get().title))
+    else {
+        app.notify("status.no_link");
+        return;
+    };
+
+    let target = resolve_link(
+        &link.path,
+        app.filename.as_deref(),
+        app.config.workspace.as_deref(),
+    );
+    let same_map = app.filename.as_ref().is_some_and(|current| {
+        match (current.canonicalize(), target.canonicalize()) {
+            (Ok(current), Ok(target)) => current == target,
+            _ => current == &target,
+        }
+    });
+
+    // Jumping within the current map needs no reload, so unsaved edits are fine
+    if !same_map {
+        if app.is_dirty {
+            app.notify("status.unsaved_before_open");
+            return;
+        }
+        if let Err(e) = open_file(app, &target) {
+            app.notify_with("status.open_failed", &[("error", &e)]);
+            return;
+        }
+        let path = target.display().to_string();
+        app.notify_with("status.opened", &[("path", &path)]);
+    }
+
+    if let Some(anchor) = link.anchor {
+        match find_node_by_title(app, &anchor) {
+            Some(node_id) => reveal_and_select(app, node_id),
+            None => app.notify_with("status.no_node_matching", &[("title", &anchor)]),
+        }
+    }
+}
+
+/// Most changed lines named in the report of updated links
+const MAX_REPORTED_LINKS: usize = 5;
+
+/// Follow a renamed map or node in the links of the other maps of the
+/// workspace, when `update_links` is on, and report the lines changed
+pub fn update_workspace_links(app: &mut AppState, rename: LinkRename) {
+    let Some(workspace) = links_workspace(app) else {
+        return;
+    };
+    let result = update_links(&workspace, app.filename.as_deref(), rename);
+    report_link_updates(app, &workspace, result);
+}
+
+/// After a node of `map` is retitled, ask whether to rewrite the links to its
+/// old title in the other maps of the workspace, if `update_links` is on and
+/// there are any. Undo does not reach into other maps, so they are only
+/// changed once confirmed
+pub fn offer_link_updates(app: &mut AppState, map: PathBuf, from: String, to: String) {
+    let Some(workspace) = links_workspace(app) else {
+        return;
+    };
+    let maps = app
+        .link_index
+        .maps_linking_to(&workspace, Some(&map), &from);
+    if maps.is_empty() {
+        return;
+    }
+    let rename = LinkRename::Node {
+        map: &map,
+        from: &from,
+        to: &to,
+    };
+    let count = find_link_updates(&maps, &workspace, rename).len();
+    if count > 0 {
+        app.mode = AppMode::ConfirmLinks {
+            map,
+            from,
+            to,
+            maps,
+            count,
+        };
+    }
+}
+
+/// Rewrite the links offered by [`offer_link_updates`]
+pub fn confirm_link_updates(app: &mut AppState) {
+    let AppMode::ConfirmLinks {
+        map,
+        from,
+        to,
+        maps,
+        ..
+    } = std::mem::replace(&mut app.mode, AppMode::Normal)
+    else {
+        return;
+    };
+    let Some(workspace) = links_workspace(app) else {
+        return;
+    };
+    let rename = LinkRename::Node {
+        map: &map,
+        from: &from,
+        to: &to,
+    };
+    let result = rewrite_links(&maps, &workspace, rename);
+    report_link_updates(app, &workspace, result);
+}
+
+pub fn cancel_link_updates(app: &mut AppState) {
+    app.mode = AppMode::Normal;
+    app.notify("status.links_kept");
+}
+
+/// The workspace whose links follow renames, if `update_links` is on
+fn links_workspace(app: &AppState) -> Option<PathBuf> {
+    app.config
+        .workspace
+        .clone()
+        .filter(|_| app.config.update_links)
+}
+
+/// Report the lines whose links were rewritten, or why they could not be
+fn report_link_updates(app: &mut AppState, workspace: &Path, result: Result<Vec<LinkUpdate>>) {
+    match result {
+        Ok(updates) if updates.is_empty() => {}
+        Ok(updates) => {
+            let workspace = crate::workspace::expand_home(workspace);
+            let mut locations: Vec<String> = updates
+                .iter()
+                .take(MAX_REPORTED_LINKS)
+                .map(|update| {
+                    let path = update.path.strip_prefix(&workspace).unwrap_or(&update.path);
+                    format!("{}:{}", path.display(), update.line)
+                })
+                .collect();
+            if updates.len() > MAX_REPORTED_LINKS {
+                locations.push("…".to_string());
+            }
+            let locations = locations.join(", ");
+            app.notify_with(
+                "status.links_updated",
+                &[("count", &updates.len()), ("locations", &locations)],
+            );
+        }
+        Err(e) => app.notify_with("status.links_update_failed", &[("error", &e)]),
+    }
+}
+
+/// Select the node the active node refers to with `->1.2.3` or `->#id`
+pub fn follow_reference(app: &mut AppState) {
+    let (Some(root_id), Some(active_id)) = (app.root_id, app.active_node_id) else {
+        return;
+    };
+    let Some(reference) = app
+        .tree
+        .get(active_id)
+        .and_then(|node| parse_reference(&node.get().title))
+    else {
+        app.notify("status.no_reference");
+        return;
+    };
+
+    match resolve_reference(&app.tree, root_id, &reference) {
+        Some(node_id) => reveal_and_select(app, node_id),
+        None => {
+            let reference = reference.to_string();
+            app.notify_with("status.reference_not_found", &[("reference", &reference)]);
+        }
+    }
+}
+
+/// Fetch the web page the active node links to and add its heading outline
+/// below the node
+pub fn import_url(app: &mut AppState) {
+    let Some((active_id, url)) = app.active_node_id.and_then(|id| {
+        let url = page_url(&app.tree.get(id)?.get().title)?;
+        Some((id, url.to_string()))
+    }) else {
+        app.notify("status.no_url");
+        return;
+    };
+
+    let headings = match fetch_outline(&url) {
+        Ok(headings) if headings.is_empty() => {
+            app.notify_with("status.no_headings", &[("url", &url)]);
+            return;
+        }
+        Ok(headings) => headings,
+        Err(e) => {
+            app.notify_with("status.import_url_failed", &[("error", &format!("{e:#}"))]);
+            return;
+        }
+    };
+
+    app.begin_transaction();
+    let count = append_headings(&mut app.tree, active_id, &headings);
+    app.tree[active_id].get_mut().is_collapsed = false;
+    app.commit_transaction();
+    app.notify_with("status.imported_headings", &[("count", &count)]);
+}
+
+/// Fetch the feed the active node links to and add its new entries below
+/// the node
+pub fn import_feed(app: &mut AppState) {
+    let Some((active_id, url)) = app.active_node_id.and_then(|id| {
+        let url = page_url(&app.tree.get(id)?.get().title)?;
+        Some((id, url.to_string()))
+    }) else {
+        app.notify("status.no_url");
+        return;
+    };
+
+    let entries = match fetch_feed(&url) {
+        Ok(entries) => new_feed_entries(&app.tree, active_id, &entries),
+        Err(e) => {
+            app.notify_with("status.import_feed_failed", &[("error", &format!("{e:#}"))]);
+            return;
+        }
+    };
+
+    if entries.is_empty() {
+        app.notify("status.feed_up_to_date");
+        return;
+    }
+
+    app.begin_transaction();
+    add_feed_entries(&mut app.tree, active_id, &entries);
+    app.tree[active_id].get_mut().is_collapsed = false;
+    app.commit_transaction();
+    app.notify_with("status.imported_entries", &[("count", &entries.len())]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::model::Node;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_app(title: &str) -> AppState {
+        let config = AppConfig::default();
+        let mut app = AppState::new(config);
+        let root = app.tree.new_node(Node::new("Index".to_string()));
+        let link = app.tree.new_node(Node::new(title.to_string()));
+        root.append(link, &mut app.tree);
+        app.root_id = Some(root);
+        app.active_node_id = Some(link);
+        app
+    }
+
+    #[test]
+    fn test_open_link_jumps_to_anchor() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("index.hmm"), "Index").unwrap();
+        fs::write(
+            dir.path().join("other.hmm"),
+            "Other\n\tTopics\n\t\tNode Title\n",
+        )
+        .unwrap();
+
+        let mut app = create_test_app("other.hmm#Node Title");
+        app.filename = Some(dir.path().join("index.hmm"));
+        open_link(&mut app);
+
+        assert!(app.filename.as_ref().unwrap().ends_with("other.hmm"));
+        let active = app.active_node_id.unwrap();
+        assert_eq!(app.tree[active].get().title, "Node Title");
+    }
+
+    #[test]
+    fn test_retitled_node_asks_before_updating_other_maps() {
+        let dir = TempDir::new().unwrap();
+        let plan = dir.path().join("plan.hmm");
+        let index = dir.path().join("index.hmm");
+        fs::write(&plan, "Plan\n\tGoals\n").unwrap();
+        fs::write(&index, "Index\n\tplan.hmm#Goals\n").unwrap();
+
+        let mut app = create_test_app("Goals");
+        app.filename = Some(plan.clone());
+        app.config.workspace = Some(dir.path().to_path_buf());
+        app.config.update_links = true;
+        let retitle = |app: &mut AppState, from: &str, to: &str| {
+            let active = app.active_node_id.unwrap();
+            app.tree[active].get_mut().title = to.to_string();
+            offer_link_updates(app, plan.clone(), from.to_string(), to.to_string());
+        };
+
+        // Nothing links to a title that is no anchor
+        retitle(&mut app, "Plans", "Goals");
+        assert!(matches!(app.mode, AppMode::Normal));
+
+        retitle(&mut app, "Goals", "Aims");
+        assert!(matches!(app.mode, AppMode::ConfirmLinks { count: 1, .. }));
+        cancel_link_updates(&mut app);
+        assert_eq!(
+            fs::read_to_string(&index).unwrap(),
+            "Index\n\tplan.hmm#Goals\n"
+        );
+
+        retitle(&mut app, "Goals", "Aims");
+        confirm_link_updates(&mut app);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(
+            fs::read_to_string(&index).unwrap(),
+            "Index\n\tplan.hmm#Aims\n"
+        );
+        assert!(app.message.as_deref().unwrap().contains("index.hmm:2"));
+    }
+
+    #[test]
+    fn test_follow_reference() {
+        let mut app = create_test_app("See ->#goals");
+        let root = app.root_id.unwrap();
+        let goals = app.tree.new_node(Node::new("Goals #goals".to_string()));
+        let detail = app.tree.new_node(Node::new("Detail ->2".to_string()));
+        root.append(goals, &mut app.tree);
+        goals.append(detail, &mut app.tree);
+        app.tree[goals].get_mut().is_collapsed = true;
+
+        follow_reference(&mut app);
+        assert_eq!(app.active_node_id, Some(goals));
+
+        // Targets inside folded branches are revealed
+        app.tree[goals].get_mut().title = "Goals #goals ->2.1".to_string();
+        follow_reference(&mut app);
+        assert_eq!(app.active_node_id, Some(detail));
+        assert!(!app.tree[goals].get().is_collapsed);
+
+        follow_reference(&mut app);
+        assert_eq!(app.active_node_id, Some(goals));
+
+        app.tree[goals].get_mut().title = "Goals ->3.1".to_string();
+        follow_reference(&mut app);
+        assert_eq!(app.active_node_id, Some(goals));
+        assert_eq!(app.message.as_deref(), Some("Nothing at ->3.1"));
+    }
+
+    #[test]
+    fn test_open_link_without_link() {
+        let mut app = create_test_app("Plain node");
+        open_link(&mut app);
+        assert_eq!(app.message.as_deref(), Some("No map link in this node"));
+    }
+
+    #[test]
+    fn test_open_link_refuses_unsaved_changes() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("other.hmm"), "Other").unwrap();
+
+        let mut app = create_test_app("@other.hmm");
+        app.filename = Some(dir.path().join("index.hmm"));
+        app.is_dirty = true;
+        open_link(&mut app);
+
+        assert!(app.filename.as_ref().unwrap().ends_with("index.hmm"));
+        assert_eq!(app.tree[app.root_id.unwrap()].get().title, "Index");
+    }
+
+    #[test]
+    fn test_import_url_without_url() {
+        let mut app = create_test_app("No page here");
+        import_url(&mut app);
+        assert_eq!(
+            app.message.as_deref(),
+            Some("No web page link in this node")
+        );
+        assert!(!app.is_dirty);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::app::AppState;
+use crate::lock::{is_locked, subtree_has_lock, LOCK_PREFIX};
+
+/// The nodes an action edits, which all have to be editable for it to run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditScope {
+    /// The active node
+    Node,
+    /// The children of the active node's parent, or of the root
+    Parent,
+    /// The active node and everything below it
+    Subtree,
+    /// The map as a whole, editable unless it is read-only
+    Map,
+}
+
+/// Catalog key of the reason an edit of `scope` is turned down, if it is
+pub fn edit_denial(app: &AppState, scope: EditScope) -> Option<&'static str> {
+    if let Some(read_only) = app.read_only {
+        return Some(read_only.message_key());
+    }
+    let active_id = app.active_node_id?;
+    let locked = match scope {
+        EditScope::Node => is_locked(&app.tree, active_id),
+        EditScope::Parent => {
+            let parent_id = active_id.ancestors(&app.tree).nth(1).unwrap_or(active_id);
+            is_locked(&app.tree, parent_id)
+        }
+        EditScope::Subtree => subtree_has_lock(&app.tree, active_id),
+        EditScope::Map => false,
+    };
+    locked.then_some("status.node_locked")
+}
+
+/// Whether an edit of `scope` may go ahead, telling why when it may not
+pub fn edit_allowed(app: &mut AppState, scope: EditScope) -> bool {
+    match edit_denial(app, scope) {
+        Some(key) => {
+            app.notify(key);
+            false
+        }
+        None => true,
+    }
+}
+
+/// Lock the active node against edits, or unlock it
+pub fn toggle_lock(app: &mut AppState) {
+    let Some(active_id) = app.active_node_id else {
+        return;
+    };
+    app.push_history();
+
+    if let Some(node) = app.tree.get_mut(active_id) {
+        let title = &mut node.get_mut().title;
+        if let Some(unlocked) = title.strip_prefix(LOCK_PREFIX) {
+            *title = unlocked.to_string();
+            app.notify("status.node_unlocked");
+        } else {
+            *title = format!("{LOCK_PREFIX}{title}");
+            app.notify("status.node_locked_now");
+        }
+    }
+    app.is_dirty = true;
+    app.last_modify_time = Some(std::time::Instant::now());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{execute_action, Action};
+    use crate::app::AppMode;
+    use crate::config::AppConfig;
+    use crate::lock::ReadOnly;
+    use crate::model::Node;
+
+    fn create_test_app() -> AppState {
+        let mut app = AppState::new(AppConfig::default());
+        let root = app.tree.new_node(Node::new("Root".to_string()));
+        let shared = root.append_value(Node::new("Shared".to_string()), &mut app.tree);
+        shared.append_value(Node::new("Detail".to_string()), &mut app.tree);
+        app.root_id = Some(root);
+        app.active_node_id = Some(shared);
+        app
+    }
+
+    fn titles(app: &AppState) -> String {
+        crate::parser::map_to_list(&app.tree, app.root_id.unwrap(), false, 0)
+    }
+
+    #[test]
+    fn test_locked_nodes_turn_down_edits() {
+        let mut app = create_test_app();
+        execute_action(Action::ToggleLock, &mut app).unwrap();
+        let locked = titles(&app);
+        assert_eq!(locked, "Root\n\t[LOCKED] Shared\n\t\tDetail\n");
+
+        for action in [
+            Action::EditNodeReplace,
+            Action::InsertChild,
+            Action::DeleteNode,
+            Action::ToggleSymbol,
+        ] {
+            execute_action(action, &mut app).unwrap();
+            assert!(matches!(app.mode, AppMode::Normal));
+            assert_eq!(titles(&app), locked);
+        }
+        assert_eq!(
+            app.message.as_deref(),
+            Some(app.catalog.get("status.node_locked").as_str())
+        );
+
+        // Siblings belong to the unlocked root, but the child is locked too
+        assert_eq!(edit_denial(&app, EditScope::Parent), None);
+        let shared = app.active_node_id.unwrap();
+        app.active_node_id = shared.children(&app.tree).next();
+        assert_eq!(
+            edit_denial(&app, EditScope::Node),
+            Some("status.node_locked")
+        );
+
+        app.active_node_id = app.root_id;
+        assert_eq!(edit_denial(&app, EditScope::Node), None);
+        assert_eq!(
+            edit_denial(&app, EditScope::Subtree),
+            Some("status.node_locked")
+        );
+    }
+
+    #[test]
+    fn test_locked_link_is_not_imported_into() {
+        let mut app = create_test_app();
+        let shared = app.active_node_id.unwrap();
+        app.tree[shared].get_mut().title = "[LOCKED] https://example.com/feed".to_string();
+        let before = titles(&app);
+
+        // Turned down before anything is fetched
+        for action in [Action::ImportUrl, Action::ImportFeed] {
+            app.message = None;
+            execute_action(action, &mut app).unwrap();
+            assert_eq!(titles(&app), before);
+            assert_eq!(
+                app.message.as_deref(),
+                Some(app.catalog.get("status.node_locked").as_str())
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_only_map_turns_down_every_edit() {
+        let mut app = create_test_app();
+        app.read_only = Some(ReadOnly::Conflict);
+        execute_action(Action::ToggleLock, &mut app).unwrap();
+        execute_action(Action::InsertSibling, &mut app).unwrap();
+        assert_eq!(titles(&app), "Root\n\tShared\n\t\tDetail\n");
+        assert!(!app.is_dirty);
+        assert_eq!(
+            app.message.as_deref(),
+            Some(app.catalog.get("status.read_only_conflict").as_str())
+        );
+    }
+}
@@ -1,34 +1,71 @@
+mod capture;
 mod clipboard;
+mod compare;
 mod editing;
 mod file;
 mod formatting;
 mod help;
 mod history;
+mod journal;
+mod launch;
+mod link;
+mod locking;
 mod movement;
 mod node;
+mod pick;
+mod picker;
+mod pins;
+mod quit;
+mod recent;
+mod rename;
 mod search;
+mod settings;
+mod snapshots;
 mod view;
+mod workspace;
 
-use crate::app::AppState;
+use crate::app::{AppState, Damage, QuitChoice};
+use crate::parser::MapFormat;
 use anyhow::Result;
 
 // Re-export all public functions from submodules
+pub use capture::*;
 pub use clipboard::*;
+pub use compare::*;
 pub use editing::*;
 pub use file::*;
 pub use formatting::*;
 pub use help::*;
 pub use history::*;
+pub use journal::*;
+pub use launch::*;
+pub use link::*;
+pub use locking::*;
 pub use movement::*;
 pub use node::*;
+pub use pick::*;
+pub use picker::*;
+pub use pins::*;
+pub use quit::*;
+pub use recent::*;
+pub use rename::*;
 pub use search::*;
+pub use settings::*;
+pub use snapshots::*;
 pub use view::*;
+pub use workspace::*;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Action {
     // Application control
     Quit,
     ForceQuit,
+    QuitPromptNext,
+    QuitPromptPrevious,
+    ChooseQuit(QuitChoice),
+    PickNode,
+    TogglePickSelection,
+    CancelPick,
 
     // Movement
     GoUp,
@@ -41,7 +78,16 @@ pub enum Action {
 
     // Node manipulation
     InsertSibling,
+    InsertSiblingAbove,
     InsertChild,
+    InsertChildFirst,
+    RepeatInsert,
+    RepeatInsertIncrement,
+    BatchRename,
+    TypeRenameChar(char),
+    BackspaceRename,
+    ConfirmRename,
+    CancelRename,
     DeleteNode,
     DeleteChildren,
     MoveNodeUp,
@@ -75,20 +121,89 @@ pub enum Action {
     CollapseOtherBranches,
     CollapseToLevel(usize),
     CenterActiveNode,
+    ScrollActiveToTop,
+    ScrollActiveToCenter,
+    ScrollActiveToBottom,
+    /// Move the map by this many columns and rows, as a mouse drag does
+    PanView(i32, i32),
     ToggleCenterLock,
     Focus,
     ToggleFocusLock,
+    ToggleAccordion,
 
     // File operations
+    OpenRecent,
+    OpenWorkspace,
+    OpenLink,
+    Activate,
+    ConfirmRun,
+    CancelRun,
+    ConfirmLinkUpdates,
+    CancelLinkUpdates,
+    ImportUrl,
+    ImportFeed,
+    FollowReference,
+    TogglePin,
+    JumpToPin(usize),
+    OpenJournal,
+    TypePickerChar(char),
+    BackspacePicker,
+    PickerUp,
+    PickerDown,
+    ConfirmPicker,
+    CancelPicker,
     Save,
     SaveAs,
+    TypeSaveAsChar(char),
+    BackspaceSaveAs,
+    ConfirmSaveAs,
+    CancelSaveAs,
+    RenameFile,
+    TypeRenameFileChar(char),
+    BackspaceRenameFile,
+    ConfirmRenameFile,
+    CancelRenameFile,
+    Capture,
+    TypeCaptureChar(char),
+    BackspaceCapture,
+    ConfirmCapture,
+    CancelCapture,
+    SaveAsFormat,
+    FormatPromptNext,
+    FormatPromptPrevious,
+    ChooseSaveFormat(MapFormat),
     ExportText,
+    ExportSubtree,
+    ExportMermaid,
+    ExportHtml,
+    ExportTemplate,
+    ExportPipe,
+    OpenSettings,
+    CloseSettings,
+    SettingsUp,
+    SettingsDown,
+    ActivateSetting,
+    TypeSettingChar(char),
+    BackspaceSetting,
+    CancelSetting,
+    WriteSettings,
 
     // Clipboard
     YankNode,
     YankChildren,
     PasteAsChildren,
     PasteAsSiblings,
+    MergePaste,
+    DuplicatePaste,
+    CancelPaste,
+    OpenSnapshots,
+    SnapshotOlder,
+    SnapshotNewer,
+    RestoreSnapshot,
+    CloseSnapshots,
+    CompareSnapshot,
+    CompareHead,
+    CloseCompare,
 
     // Undo/Redo
     Undo,
@@ -102,13 +217,21 @@ pub enum Action {
     CancelSearch,
     NextSearchResult,
     PreviousSearchResult,
+    FoldToSearchResults,
+    ClearSearch,
 
     // Symbols and formatting
     ToggleSymbol,
     SortSiblings,
+    FixCase,
+    CycleKind,
+    UpdateGlossary,
     ToggleNumbers,
     ToggleHide,
+    ToggleLock,
     ToggleShowHidden,
+    CycleHeatmap,
+    ToggleDepthGuides,
 
     // Layout
     IncreaseTextWidth,
@@ -119,20 +242,211 @@ pub enum Action {
     // Help
     ShowHelp,
     CloseHelp,
+    ShowStats,
 }
 
+/// Localized "ON"/"OFF" for toggle status messages
+pub(crate) fn on_off(app: &AppState, flag: bool) -> String {
+    app.catalog
+        .get(if flag { "status.on" } else { "status.off" })
+}
+
+impl Action {
+    /// The nodes the action edits, if it edits any
+    pub fn edits(&self) -> Option<EditScope> {
+        match self {
+            Action::InsertChild
+            | Action::InsertChildFirst
+            | Action::EditNodeAppend
+            | Action::EditNodeReplace
+            | Action::PasteAsChildren
+            | Action::ToggleSymbol
+            | Action::CycleKind
+            | Action::ToggleHide
+            | Action::ImportUrl
+            | Action::ImportFeed => Some(EditScope::Node),
+            Action::InsertSibling
+            | Action::InsertSiblingAbove
+            | Action::PasteAsSiblings
+            | Action::RepeatInsert
+            | Action::RepeatInsertIncrement
+            | Action::SortSiblings => Some(EditScope::Parent),
+            Action::DeleteNode
+            | Action::DeleteChildren
+            | Action::BatchRename
+            | Action::ConfirmRename
+            | Action::FixCase
+            | Action::MoveNodeUp
+            | Action::MoveNodeDown => Some(EditScope::Subtree),
+            Action::RestoreSnapshot
+            | Action::UpdateGlossary
+            | Action::ToggleLock
+            | Action::RenameFile
+            | Action::ConfirmRenameFile
+            | Action::Capture
+            | Action::ConfirmCapture
+            | Action::Save => Some(EditScope::Map),
+            _ => None,
+        }
+    }
+
+    /// What running this action can invalidate. Anything that may touch
+    /// titles, structure, folding or layout settings counts as layout damage.
+    pub fn damage(&self) -> Damage {
+        match self {
+            Action::Quit
+            | Action::ForceQuit
+            | Action::QuitPromptNext
+            | Action::QuitPromptPrevious
+            | Action::ChooseQuit(_)
+            | Action::PickNode
+            | Action::TogglePickSelection
+            | Action::CancelPick
+            | Action::CancelPaste
+            | Action::CancelRun
+            | Action::ConfirmLinkUpdates
+            | Action::CancelLinkUpdates
+            | Action::BatchRename
+            | Action::TypeRenameChar(_)
+            | Action::BackspaceRename
+            | Action::CancelRename
+            | Action::OpenSnapshots
+            | Action::SnapshotOlder
+            | Action::SnapshotNewer
+            | Action::CloseSnapshots
+            | Action::CompareSnapshot
+            | Action::CompareHead
+            | Action::CloseCompare
+            | Action::GoUp
+            | Action::GoDown
+            | Action::GoLeft
+            | Action::GoToTop
+            | Action::GoToBottom
+            | Action::GoToRoot
+            | Action::TogglePin
+            | Action::JumpToPin(_)
+            | Action::EditNodeAppend
+            | Action::MoveCursorLeft
+            | Action::MoveCursorRight
+            | Action::MoveCursorHome
+            | Action::MoveCursorEnd
+            | Action::MoveCursorWordLeft
+            | Action::MoveCursorWordRight
+            | Action::CenterActiveNode
+            | Action::ScrollActiveToTop
+            | Action::ScrollActiveToCenter
+            | Action::ScrollActiveToBottom
+            | Action::PanView(..)
+            | Action::ToggleCenterLock
+            | Action::OpenRecent
+            | Action::OpenWorkspace
+            | Action::TypePickerChar(_)
+            | Action::BackspacePicker
+            | Action::PickerUp
+            | Action::PickerDown
+            | Action::CancelPicker
+            | Action::Save
+            | Action::SaveAs
+            | Action::TypeSaveAsChar(_)
+            | Action::BackspaceSaveAs
+            | Action::ConfirmSaveAs
+            | Action::CancelSaveAs
+            | Action::RenameFile
+            | Action::TypeRenameFileChar(_)
+            | Action::BackspaceRenameFile
+            | Action::CancelRenameFile
+            | Action::Capture
+            | Action::TypeCaptureChar(_)
+            | Action::BackspaceCapture
+            | Action::CancelCapture
+            | Action::SaveAsFormat
+            | Action::FormatPromptNext
+            | Action::FormatPromptPrevious
+            | Action::ChooseSaveFormat(_)
+            | Action::ExportText
+            | Action::ExportSubtree
+            | Action::ExportMermaid
+            | Action::ExportHtml
+            | Action::ExportTemplate
+            | Action::ExportPipe
+            | Action::OpenSettings
+            | Action::CloseSettings
+            | Action::SettingsUp
+            | Action::SettingsDown
+            | Action::TypeSettingChar(_)
+            | Action::BackspaceSetting
+            | Action::CancelSetting
+            | Action::WriteSettings
+            | Action::YankNode
+            | Action::YankChildren
+            | Action::Search
+            | Action::TypeSearchChar(_)
+            | Action::BackspaceSearch
+            | Action::CancelSearch
+            | Action::NextSearchResult
+            | Action::PreviousSearchResult
+            | Action::ShowHelp
+            | Action::CloseHelp
+            | Action::ShowStats => Damage::View,
+            _ => Damage::Layout,
+        }
+    }
+}
+
+/// Run an action and record the damage it leaves for the next frame
 pub fn execute_action(action: Action, app: &mut AppState) -> Result<()> {
-    match action {
-        Action::Quit => {
-            if app.is_dirty {
-                app.set_message("Unsaved changes! Press Shift+Q to force quit or 's' to save");
-            } else {
-                app.running = false;
-            }
+    let damage = action.damage();
+    // Drop the cached layout up front so the action never sees a stale one
+    app.mark_damage(damage);
+    if app.pending_confirm.as_ref() != Some(&action) {
+        app.pending_confirm = None;
+    }
+    // Only back-to-back repeats of an edit share an undo step
+    if app
+        .history_group
+        .as_ref()
+        .is_some_and(|(group, _)| *group != action)
+    {
+        app.history_group = None;
+    }
+    // Actions assume a valid root and selection, whatever left them broken
+    app.repair_selection();
+    // Edits of read-only maps and locked nodes are turned down up front
+    if let Some(scope) = action.edits() {
+        if !locking::edit_allowed(app, scope) {
+            return Ok(());
+        }
+    }
+    let last_modify_time = app.last_modify_time;
+    let result = dispatch(action, app);
+    app.repair_selection();
+    app.check_map_size();
+    // Edits relayout the node being edited, which may move it out of view
+    if damage == Damage::Layout {
+        movement::follow_edited_node(app);
+    }
+    // Edits leave the edited node selected; remember it for the activity heatmap
+    if let (Some(time), Some(active_id)) = (app.last_modify_time, app.active_node_id) {
+        if Some(time) != last_modify_time {
+            app.edit_times.insert(active_id, time);
         }
+    }
+    app.mark_damage(damage);
+    result
+}
+
+fn dispatch(action: Action, app: &mut AppState) -> Result<()> {
+    match action {
+        Action::Quit => quit::quit(app),
         Action::ForceQuit => {
             app.running = false;
         }
+        Action::QuitPromptNext => quit::quit_prompt_next(app),
+        Action::QuitPromptPrevious => quit::quit_prompt_previous(app),
+        Action::ChooseQuit(choice) => quit::choose_quit(app, choice),
+        Action::PickNode => pick::pick_node(app),
+        Action::TogglePickSelection => pick::toggle_pick_selection(app),
+        Action::CancelPick => pick::cancel_pick(app),
 
         // Movement actions
         Action::GoUp => movement::go_up(app),
@@ -145,7 +459,16 @@ pub fn execute_action(action: Action, app: &mut AppState) -> Result<()> {
 
         // Node manipulation
         Action::InsertSibling => node::insert_sibling(app),
+        Action::InsertSiblingAbove => node::insert_sibling_above(app),
         Action::InsertChild => node::insert_child(app),
+        Action::InsertChildFirst => node::insert_child_first(app),
+        Action::RepeatInsert => node::repeat_insert(app),
+        Action::RepeatInsertIncrement => node::repeat_insert_increment(app),
+        Action::BatchRename => rename::start_rename(app),
+        Action::TypeRenameChar(c) => rename::type_rename_char(app, c),
+        Action::BackspaceRename => rename::backspace_rename(app),
+        Action::ConfirmRename => rename::confirm_rename(app),
+        Action::CancelRename => rename::cancel_rename(app),
         Action::DeleteNode => node::delete_node(app),
         Action::DeleteChildren => node::delete_children(app),
         Action::MoveNodeUp => node::move_node_up(app),
@@ -179,20 +502,88 @@ pub fn execute_action(action: Action, app: &mut AppState) -> Result<()> {
         Action::CollapseOtherBranches => view::collapse_other_branches(app),
         Action::CollapseToLevel(level) => view::collapse_to_level(app, level),
         Action::CenterActiveNode => view::center_active_node(app),
+        Action::ScrollActiveToTop => view::scroll_active_to_top(app),
+        Action::ScrollActiveToCenter => view::scroll_active_to_center(app),
+        Action::ScrollActiveToBottom => view::scroll_active_to_bottom(app),
+        Action::PanView(columns, rows) => view::pan_view(app, columns, rows),
         Action::ToggleCenterLock => view::toggle_center_lock(app),
         Action::Focus => view::focus(app),
         Action::ToggleFocusLock => view::toggle_focus_lock(app),
+        Action::ToggleAccordion => view::toggle_accordion(app),
 
         // File operations
+        Action::OpenRecent => recent::open_recent(app),
+        Action::OpenWorkspace => workspace::open_workspace(app),
+        Action::OpenLink => link::open_link(app),
+        Action::Activate => launch::activate(app),
+        Action::ConfirmRun => launch::confirm_run(app),
+        Action::CancelRun => launch::cancel_run(app),
+        Action::ConfirmLinkUpdates => link::confirm_link_updates(app),
+        Action::CancelLinkUpdates => link::cancel_link_updates(app),
+        Action::ImportUrl => link::import_url(app),
+        Action::ImportFeed => link::import_feed(app),
+        Action::FollowReference => link::follow_reference(app),
+        Action::TogglePin => pins::toggle_pin(app),
+        Action::JumpToPin(index) => pins::jump_to_pin(app, index),
+        Action::OpenJournal => journal::open_journal(app),
+        Action::TypePickerChar(c) => picker::type_picker_char(app, c),
+        Action::BackspacePicker => picker::backspace_picker(app),
+        Action::PickerUp => picker::picker_up(app),
+        Action::PickerDown => picker::picker_down(app),
+        Action::ConfirmPicker => picker::confirm_picker(app),
+        Action::CancelPicker => picker::cancel_picker(app),
         Action::Save => file::save(app)?,
-        Action::SaveAs => file::save_as(app)?,
+        Action::SaveAs => file::start_save_as(app, false),
+        Action::TypeSaveAsChar(c) => file::type_save_as_char(app, c),
+        Action::BackspaceSaveAs => file::backspace_save_as(app),
+        Action::ConfirmSaveAs => file::confirm_save_as(app),
+        Action::CancelSaveAs => file::cancel_save_as(app),
+        Action::RenameFile => file::start_rename_file(app),
+        Action::TypeRenameFileChar(c) => file::type_rename_file_char(app, c),
+        Action::BackspaceRenameFile => file::backspace_rename_file(app),
+        Action::ConfirmRenameFile => file::confirm_rename_file(app),
+        Action::CancelRenameFile => file::cancel_rename_file(app),
+        Action::Capture => capture::start_capture(app),
+        Action::TypeCaptureChar(c) => capture::type_capture_char(app, c),
+        Action::BackspaceCapture => capture::backspace_capture(app),
+        Action::ConfirmCapture => capture::confirm_capture(app),
+        Action::CancelCapture => capture::cancel_capture(app),
+        Action::SaveAsFormat => file::start_save_format(app),
+        Action::FormatPromptNext => file::format_prompt_next(app),
+        Action::FormatPromptPrevious => file::format_prompt_previous(app),
+        Action::ChooseSaveFormat(format) => file::choose_save_format(app, format),
         Action::ExportText => file::export_text(app)?,
+        Action::ExportSubtree => file::export_subtree(app)?,
+        Action::ExportMermaid => file::export_mermaid(app)?,
+        Action::ExportHtml => file::export_html(app),
+        Action::ExportTemplate => file::export_template(app),
+        Action::ExportPipe => file::export_pipe(app),
+        Action::OpenSettings => settings::open_settings(app),
+        Action::CloseSettings => settings::close_settings(app),
+        Action::SettingsUp => settings::settings_up(app),
+        Action::SettingsDown => settings::settings_down(app),
+        Action::ActivateSetting => settings::activate_setting(app),
+        Action::TypeSettingChar(c) => settings::type_setting_char(app, c),
+        Action::BackspaceSetting => settings::backspace_setting(app),
+        Action::CancelSetting => settings::cancel_setting(app),
+        Action::WriteSettings => settings::write_changed_settings(app),
 
         // Clipboard
         Action::YankNode => clipboard::yank_node(app)?,
         Action::YankChildren => clipboard::yank_children(app)?,
         Action::PasteAsChildren => clipboard::paste_as_children(app)?,
         Action::PasteAsSiblings => clipboard::paste_as_siblings(app)?,
+        Action::MergePaste => clipboard::merge_paste(app)?,
+        Action::DuplicatePaste => clipboard::duplicate_paste(app)?,
+        Action::CancelPaste => clipboard::cancel_paste(app),
+        Action::OpenSnapshots => snapshots::open_snapshots(app),
+        Action::SnapshotOlder => snapshots::snapshot_older(app),
+        Action::SnapshotNewer => snapshots::snapshot_newer(app),
+        Action::RestoreSnapshot => snapshots::restore_snapshot(app),
+        Action::CloseSnapshots => snapshots::close_snapshots(app),
+        Action::CompareSnapshot => compare::compare_snapshot(app),
+        Action::CompareHead => compare::compare_head(app),
+        Action::CloseCompare => compare::close_compare(app),
 
         // Undo/Redo
         Action::Undo => history::undo(app),
@@ -206,13 +597,21 @@ pub fn execute_action(action: Action, app: &mut AppState) -> Result<()> {
         Action::CancelSearch => search::cancel_search(app),
         Action::NextSearchResult => search::next_search_result(app),
         Action::PreviousSearchResult => search::previous_search_result(app),
+        Action::FoldToSearchResults => search::fold_to_search_results(app),
+        Action::ClearSearch => search::clear_search(app),
 
         // Symbols
         Action::ToggleSymbol => formatting::toggle_symbol(app),
         Action::SortSiblings => formatting::sort_siblings(app),
+        Action::FixCase => formatting::fix_case(app),
+        Action::CycleKind => formatting::cycle_kind(app),
+        Action::UpdateGlossary => node::update_glossary(app),
         Action::ToggleNumbers => formatting::toggle_numbers(app),
         Action::ToggleHide => formatting::toggle_hide(app),
+        Action::ToggleLock => locking::toggle_lock(app),
         Action::ToggleShowHidden => formatting::toggle_show_hidden(app),
+        Action::CycleHeatmap => formatting::cycle_heatmap(app),
+        Action::ToggleDepthGuides => formatting::toggle_depth_guides(app),
 
         // Layout
         Action::IncreaseTextWidth => formatting::increase_text_width(app),
@@ -223,6 +622,7 @@ pub fn execute_action(action: Action, app: &mut AppState) -> Result<()> {
         // Help
         Action::ShowHelp => help::show_help(app),
         Action::CloseHelp => help::close_help(app),
+        Action::ShowStats => help::show_stats(app),
     }
     Ok(())
 }
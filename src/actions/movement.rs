@@ -1,4 +1,4 @@
-use crate::app::AppState;
+use crate::app::{AppState, Damage};
 use crate::layout::LayoutEngine;
 use crate::model::NodeId;
 
@@ -12,11 +12,27 @@ pub fn ensure_node_visible(app: &mut AppState) {
     if app.config.focus_lock {
         focus(app);
     }
+    apply_accordion(app);
 
+    scroll_to_active_node(app);
+}
+
+/// While editing, keep the edited node in view as its text grows or shrinks
+pub fn follow_edited_node(app: &mut AppState) {
+    if app.config.follow_edits && app.edit_preview().is_some() {
+        scroll_to_active_node(app);
+    }
+}
+
+// Scroll just enough to bring the active node into the map area, or center it
+// when center lock is on
+fn scroll_to_active_node(app: &mut AppState) {
     if app.config.center_lock {
         center_active_node(app);
     } else if let Some(active_id) = app.active_node_id {
-        let layout = LayoutEngine::calculate_layout(app);
+        let layout = app.layout();
+        // The status line and pin bar cover the rows below and above the map
+        let map_height = map_area_height(app);
 
         if let Some(node_layout) = layout.nodes.get(&active_id) {
             let node_x = node_layout.x;
@@ -37,8 +53,8 @@ pub fn ensure_node_visible(app: &mut AppState) {
             // Vertical adjustment
             if node_y < app.viewport_top + margin {
                 app.viewport_top = (node_y - margin).max(0.0);
-            } else if node_bottom > app.viewport_top + app.terminal_height as f64 - margin {
-                app.viewport_top = node_bottom - app.terminal_height as f64 + margin;
+            } else if node_bottom > app.viewport_top + map_height - margin {
+                app.viewport_top = node_bottom - map_height + margin;
             }
         }
     }
@@ -103,9 +119,41 @@ fn find_nearest_node_in_direction(
     best_node
 }
 
+/// A node among siblings packed into columns
+struct GridCell {
+    /// The packed siblings in order, column by column
+    siblings: Vec<NodeId>,
+    index: usize,
+    rows: usize,
+}
+
+fn grid_cell(app: &AppState, layout: &LayoutEngine, node_id: NodeId) -> Option<GridCell> {
+    let parent_id = node_id.ancestors(&app.tree).nth(1)?;
+    let rows = *layout.columns.get(&parent_id)?;
+    let siblings: Vec<NodeId> = parent_id
+        .children(&app.tree)
+        .filter(|id| layout.nodes.contains_key(id))
+        .collect();
+    let index = siblings.iter().position(|&id| id == node_id)?;
+    Some(GridCell {
+        siblings,
+        index,
+        rows,
+    })
+}
+
 pub fn go_up(app: &mut AppState) {
     if let Some(active_id) = app.active_node_id {
-        let layout = LayoutEngine::calculate_layout(app);
+        let layout = app.layout();
+
+        // Packed siblings are stepped through in order, column by column
+        if let Some(cell) = grid_cell(app, &layout, active_id) {
+            if let Some(index) = cell.index.checked_sub(1) {
+                app.active_node_id = Some(cell.siblings[index]);
+                ensure_node_visible(app);
+                return;
+            }
+        }
 
         // First try to move to previous sibling based on position
         if let Some(parent_id) = active_id.ancestors(&app.tree).nth(1) {
@@ -148,7 +196,23 @@ pub fn go_up(app: &mut AppState) {
 
 pub fn go_down(app: &mut AppState) {
     if let Some(active_id) = app.active_node_id {
-        let layout = LayoutEngine::calculate_layout(app);
+        let layout = app.layout();
+
+        if let Some(cell) = grid_cell(app, &layout, active_id) {
+            let next = cell.siblings.get(cell.index + 1).copied().or_else(|| {
+                // Past the last one, leave the grid below its lowest node
+                let lowest = cell.siblings.iter().copied().max_by(|&a, &b| {
+                    let y = |id| get_node_center(&layout, id).map_or(0.0, |(_, y)| y);
+                    y(a).total_cmp(&y(b))
+                })?;
+                find_nearest_node_in_direction(app, &layout, lowest, 0.0, 1.0)
+            });
+            if let Some(next) = next {
+                app.active_node_id = Some(next);
+                ensure_node_visible(app);
+            }
+            return;
+        }
 
         // First try to move to next sibling based on position
         if let Some(parent_id) = active_id.ancestors(&app.tree).nth(1) {
@@ -191,6 +255,16 @@ pub fn go_down(app: &mut AppState) {
 
 pub fn go_left(app: &mut AppState) {
     if let Some(active_id) = app.active_node_id {
+        // Packed siblings move to the previous column before the parent
+        let layout = app.layout();
+        if let Some(cell) = grid_cell(app, &layout, active_id) {
+            if cell.index >= cell.rows {
+                app.active_node_id = Some(cell.siblings[cell.index - cell.rows]);
+                ensure_node_visible(app);
+                return;
+            }
+        }
+
         if let Some(parent_id) = active_id.ancestors(&app.tree).nth(1) {
             // Allow moving to parent even if it's the root
             app.active_node_id = Some(parent_id);
@@ -201,6 +275,19 @@ pub fn go_left(app: &mut AppState) {
 
 pub fn go_right(app: &mut AppState) {
     if let Some(active_id) = app.active_node_id {
+        // Packed siblings move to the next column, if there is one, before
+        // their own children; a shorter column is entered at its end
+        let layout = app.layout();
+        if let Some(cell) = grid_cell(app, &layout, active_id) {
+            let next_column = (cell.index / cell.rows + 1) * cell.rows;
+            if next_column < cell.siblings.len() {
+                let index = (cell.index + cell.rows).min(cell.siblings.len() - 1);
+                app.active_node_id = Some(cell.siblings[index]);
+                ensure_node_visible(app);
+                return;
+            }
+        }
+
         let has_children = active_id.children(&app.tree).next().is_some();
         let is_collapsed = app.tree.get(active_id).unwrap().get().is_collapsed;
 
@@ -208,10 +295,11 @@ pub fn go_right(app: &mut AppState) {
         if is_collapsed && has_children {
             // Toggle the collapsed state
             app.tree.get_mut(active_id).unwrap().get_mut().is_collapsed = false;
+            app.mark_damage(Damage::Layout);
         }
 
         // Get layout after potential expansion
-        let layout = LayoutEngine::calculate_layout(app);
+        let layout = app.layout();
 
         if let Some(current_layout) = layout.nodes.get(&active_id) {
             let current_y = current_layout.y + current_layout.yo + current_layout.lh / 2.0;
@@ -240,13 +328,28 @@ pub fn go_right(app: &mut AppState) {
     }
 }
 
+/// Make a node active, expanding its ancestors, and center it in the view
+pub fn reveal_and_select(app: &mut AppState, node_id: NodeId) {
+    let ancestors: Vec<NodeId> = node_id.ancestors(&app.tree).skip(1).collect();
+    for ancestor_id in ancestors {
+        if let Some(node) = app.tree.get_mut(ancestor_id) {
+            node.get_mut().is_collapsed = false;
+        }
+    }
+    app.mark_damage(Damage::Layout);
+
+    app.active_node_id = Some(node_id);
+    apply_accordion(app);
+    center_active_node(app);
+}
+
 pub fn go_to_root(app: &mut AppState) {
     app.active_node_id = app.root_id;
     ensure_node_visible(app);
 }
 
 pub fn go_to_top(app: &mut AppState) {
-    let layout = LayoutEngine::calculate_layout(app);
+    let layout = app.layout();
 
     // Find the node with the smallest y position (topmost)
     let mut top_node = None;
@@ -273,7 +376,7 @@ pub fn go_to_top(app: &mut AppState) {
 }
 
 pub fn go_to_bottom(app: &mut AppState) {
-    let layout = LayoutEngine::calculate_layout(app);
+    let layout = app.layout();
 
     // Find the node with the largest y position (bottommost)
     let mut bottom_node = None;
@@ -299,7 +402,7 @@ pub fn go_to_bottom(app: &mut AppState) {
 }
 
 // Import from view module to avoid circular dependency
-use super::view::{center_active_node, focus};
+use super::view::{apply_accordion, center_active_node, focus, map_area_height};
 
 #[cfg(test)]
 mod tests {
@@ -439,6 +542,22 @@ mod tests {
         assert_eq!(app.active_node_id, Some(root));
     }
 
+    #[test]
+    fn test_reveal_and_select() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let child2 = root.children(&app.tree).nth(1).unwrap();
+        let grandchild = child2.children(&app.tree).next().unwrap();
+        app.tree.get_mut(child2).unwrap().get_mut().is_collapsed = true;
+
+        reveal_and_select(&mut app, grandchild);
+
+        assert_eq!(app.active_node_id, Some(grandchild));
+        assert!(!app.tree.get(child2).unwrap().get().is_collapsed);
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert!(layout.nodes.contains_key(&grandchild));
+    }
+
     #[test]
     fn test_go_to_top() {
         let mut app = create_test_app();
@@ -471,4 +590,41 @@ mod tests {
         // This is the node with the largest y coordinate
         assert!(app.active_node_id.is_some());
     }
+
+    #[test]
+    fn test_movement_in_packed_columns() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let child1 = root.children(&app.tree).next().unwrap();
+        for i in 0..5 {
+            let leaf = app.tree.new_node(Node::new(format!("Leaf {}", i)));
+            child1.append(leaf, &mut app.tree);
+        }
+        let leaves: Vec<_> = child1.children(&app.tree).collect();
+        app.config.pack_leaves_after = 3;
+
+        // j and k step through the leaves in order, across columns
+        app.active_node_id = Some(leaves[2]);
+        go_down(&mut app);
+        assert_eq!(app.active_node_id, Some(leaves[3]));
+        go_up(&mut app);
+        assert_eq!(app.active_node_id, Some(leaves[2]));
+
+        // l and h move along a row, into the end of a shorter column
+        go_right(&mut app);
+        assert_eq!(app.active_node_id, Some(leaves[4]));
+        go_right(&mut app);
+        assert_eq!(app.active_node_id, Some(leaves[4]));
+        go_left(&mut app);
+        assert_eq!(app.active_node_id, Some(leaves[1]));
+        go_left(&mut app);
+        assert_eq!(app.active_node_id, Some(child1));
+
+        // Past the last leaf, j leaves the grid downwards
+        app.active_node_id = Some(leaves[4]);
+        go_down(&mut app);
+        let below = app.active_node_id.unwrap();
+        assert!(!leaves.contains(&below));
+        assert!(!below.ancestors(&app.tree).any(|id| id == child1));
+    }
 }
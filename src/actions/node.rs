@@ -1,51 +1,116 @@
+use crate::actions::Action;
 use crate::app::AppState;
+use crate::glossary;
 use crate::model::{Node, NodeId};
 use crate::parser;
 
 use super::editing::start_editing;
 
 pub fn insert_sibling(app: &mut AppState) {
+    insert_sibling_beside(app, false);
+}
+
+/// Insert a new sibling before the active node, like vim's `O`
+pub fn insert_sibling_above(app: &mut AppState) {
+    insert_sibling_beside(app, true);
+}
+
+fn insert_sibling_beside(app: &mut AppState, above: bool) {
     if let Some(active_id) = app.active_node_id {
+        let index_current = app.text_index.is_current();
         app.push_history();
 
         let new_node = app.tree.new_node(Node::new("NEW".to_string()));
 
         if let Some(_parent_id) = active_id.ancestors(&app.tree).nth(1) {
-            active_id.insert_after(new_node, &mut app.tree);
+            if above {
+                active_id.insert_before(new_node, &mut app.tree);
+            } else {
+                active_id.insert_after(new_node, &mut app.tree);
+            }
         }
 
+        // A new node is all that changed, so the index need not be walked
+        if index_current {
+            app.text_index
+                .follow_edit(new_node, &app.tree[new_node].get().title);
+        }
         app.active_node_id = Some(new_node);
         app.is_dirty = true;
         app.last_modify_time = Some(std::time::Instant::now());
-        app.last_modify_time = Some(std::time::Instant::now());
         start_editing(app, true);
     }
 }
 
 pub fn insert_child(app: &mut AppState) {
+    insert_child_at(app, false);
+}
+
+/// Insert a new child at the head of the active node's children
+pub fn insert_child_first(app: &mut AppState) {
+    insert_child_at(app, true);
+}
+
+fn insert_child_at(app: &mut AppState, first: bool) {
     if let Some(active_id) = app.active_node_id {
+        let index_current = app.text_index.is_current();
         app.push_history();
 
         let new_node = app.tree.new_node(Node::new("NEW".to_string()));
-        active_id.append(new_node, &mut app.tree);
+        if first {
+            active_id.prepend(new_node, &mut app.tree);
+        } else {
+            active_id.append(new_node, &mut app.tree);
+        }
 
         // Expand parent node
         if let Some(node) = app.tree.get_mut(active_id) {
             node.get_mut().is_collapsed = false;
         }
 
+        // A new node is all that changed, so the index need not be walked
+        if index_current {
+            app.text_index
+                .follow_edit(new_node, &app.tree[new_node].get().title);
+        }
         app.active_node_id = Some(new_node);
         app.is_dirty = true;
         app.last_modify_time = Some(std::time::Instant::now());
-        app.last_modify_time = Some(std::time::Instant::now());
         start_editing(app, true);
     }
 }
 
+/// Create or refresh the "Glossary" branch listing the map's acronyms
+pub fn update_glossary(app: &mut AppState) {
+    let Some(root_id) = app.root_id else {
+        return;
+    };
+
+    // Undo needs the tree from before the update, so work on a copy
+    let mut tree = app.tree.clone();
+    let update = glossary::update_glossary(&mut tree, root_id);
+    let Some(glossary_id) = update.glossary_id else {
+        app.notify("status.no_acronyms");
+        return;
+    };
+    if !update.changed {
+        app.notify("status.glossary_up_to_date");
+        return;
+    }
+
+    app.push_history();
+    app.tree = tree;
+    app.active_node_id = Some(glossary_id);
+    app.is_dirty = true;
+    app.last_modify_time = Some(std::time::Instant::now());
+    let added = update.added;
+    app.notify_with("status.glossary_updated", &[("added", &added)]);
+}
+
 pub fn delete_node(app: &mut AppState) {
     if let Some(active_id) = app.active_node_id {
-        if active_id == app.root_id.unwrap() {
-            app.set_message("Cannot delete root node");
+        if Some(active_id) == app.root_id {
+            app.notify("status.cannot_delete_root");
             return;
         }
 
@@ -79,6 +144,15 @@ pub fn delete_node(app: &mut AppState) {
 
 pub fn delete_children(app: &mut AppState) {
     if let Some(active_id) = app.active_node_id {
+        // Emptying the root clears the whole map, so it takes a second press
+        let clears_map =
+            Some(active_id) == app.root_id && active_id.children(&app.tree).next().is_some();
+        if clears_map && app.pending_confirm.take() != Some(Action::DeleteChildren) {
+            app.pending_confirm = Some(Action::DeleteChildren);
+            app.notify("status.confirm_clear_map");
+            return;
+        }
+
         app.push_history();
 
         let children: Vec<NodeId> = active_id.children(&app.tree).collect();
@@ -90,10 +164,59 @@ pub fn delete_children(app: &mut AppState) {
     }
 }
 
+/// Insert the text of the last confirmed edit again as a sibling below the
+/// active node, or as a child of the root
+pub fn repeat_insert(app: &mut AppState) {
+    insert_again(app, false);
+}
+
+/// Like [`repeat_insert`] with the number ending the text one higher, like
+/// vim's Ctrl+A, so pressing it again makes "Week 2", "Week 3" and so on
+pub fn repeat_insert_increment(app: &mut AppState) {
+    insert_again(app, true);
+}
+
+fn insert_again(app: &mut AppState, increment: bool) {
+    let (Some(active_id), Some(text)) = (app.active_node_id, app.last_edit_text.clone()) else {
+        app.notify("status.nothing_to_repeat");
+        return;
+    };
+    let text = if increment {
+        increment_trailing_number(&text).unwrap_or(text)
+    } else {
+        text
+    };
+
+    app.push_history();
+    let new_node = app.tree.new_node(Node::new(text.clone()));
+    if Some(active_id) == app.root_id {
+        active_id.append(new_node, &mut app.tree);
+        if let Some(node) = app.tree.get_mut(active_id) {
+            node.get_mut().is_collapsed = false;
+        }
+    } else {
+        active_id.insert_after(new_node, &mut app.tree);
+    }
+
+    app.active_node_id = Some(new_node);
+    app.last_edit_text = Some(text);
+    app.is_dirty = true;
+    app.last_modify_time = Some(std::time::Instant::now());
+}
+
+/// `text` with the number at its end one higher, keeping any zero padding
+fn increment_trailing_number(text: &str) -> Option<String> {
+    let prefix = text.trim_end_matches(|c: char| c.is_ascii_digit());
+    let digits = &text[prefix.len()..];
+    let number: u64 = digits.parse().ok()?;
+    let next = number.checked_add(1)?;
+    Some(format!("{prefix}{next:0width$}", width = digits.len()))
+}
+
 pub fn move_node_up(app: &mut AppState) {
     if let Some(active_id) = app.active_node_id {
         if let Some(prev_sibling) = active_id.preceding_siblings(&app.tree).nth(1) {
-            app.push_history();
+            app.push_history_grouped(Action::MoveNodeUp, active_id);
             prev_sibling.insert_before(active_id, &mut app.tree);
             app.is_dirty = true;
             app.last_modify_time = Some(std::time::Instant::now());
@@ -104,7 +227,7 @@ pub fn move_node_up(app: &mut AppState) {
 pub fn move_node_down(app: &mut AppState) {
     if let Some(active_id) = app.active_node_id {
         if let Some(next_sibling) = active_id.following_siblings(&app.tree).nth(1) {
-            app.push_history();
+            app.push_history_grouped(Action::MoveNodeDown, active_id);
             next_sibling.insert_after(active_id, &mut app.tree);
             app.is_dirty = true;
             app.last_modify_time = Some(std::time::Instant::now());
@@ -115,6 +238,7 @@ pub fn move_node_down(app: &mut AppState) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::actions::execute_action;
     use crate::app::AppMode;
     use crate::config::AppConfig;
 
@@ -171,6 +295,50 @@ mod tests {
         assert!(matches!(app.mode, AppMode::Editing { .. }));
     }
 
+    #[test]
+    fn test_insert_above_and_first() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let child1 = root.children(&app.tree).next().unwrap();
+
+        app.active_node_id = Some(child1);
+        insert_sibling_above(&mut app);
+        let above = app.active_node_id.unwrap();
+        assert_eq!(root.children(&app.tree).next(), Some(above));
+        assert_eq!(above.following_siblings(&app.tree).nth(1), Some(child1));
+        assert!(matches!(app.mode, AppMode::Editing { .. }));
+
+        app.mode = AppMode::Normal;
+        app.active_node_id = Some(root);
+        insert_child_first(&mut app);
+        let first = app.active_node_id.unwrap();
+        assert_eq!(root.children(&app.tree).next(), Some(first));
+        assert_eq!(root.children(&app.tree).nth(1), Some(above));
+    }
+
+    #[test]
+    fn test_repeat_insert_increments_trailing_number() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let child2 = root.children(&app.tree).nth(1).unwrap();
+
+        app.active_node_id = Some(child2);
+        repeat_insert(&mut app);
+        assert_eq!(root.children(&app.tree).count(), 2);
+
+        app.last_edit_text = Some("Week 09".to_string());
+        repeat_insert_increment(&mut app);
+        repeat_insert_increment(&mut app);
+        repeat_insert(&mut app);
+        let titles: Vec<_> = root
+            .children(&app.tree)
+            .skip(2)
+            .map(|id| app.tree[id].get().title.clone())
+            .collect();
+        assert_eq!(titles, ["Week 10", "Week 11", "Week 11"]);
+        assert!(matches!(app.mode, AppMode::Normal));
+    }
+
     #[test]
     fn test_delete_node() {
         let mut app = create_test_app();
@@ -239,7 +407,12 @@ mod tests {
         // Ensure root is the active node
         app.active_node_id = Some(root);
 
-        // Call delete_children
+        // Emptying the root asks for confirmation first
+        delete_children(&mut app);
+        assert_eq!(root.children(&app.tree).count(), 2);
+        assert_eq!(app.pending_confirm, Some(Action::DeleteChildren));
+
+        // Pressing again confirms
         delete_children(&mut app);
 
         // Children should be marked as removed
@@ -259,6 +432,32 @@ mod tests {
         assert_eq!(app.active_node_id, Some(root));
     }
 
+    #[test]
+    fn test_clearing_map_confirmation_is_cancelled_by_other_keys() {
+        let mut app = create_test_app();
+        let children: Vec<NodeId> = app.root_id.unwrap().children(&app.tree).collect();
+        let removed = |app: &AppState| children.iter().all(|&id| app.tree[id].is_removed());
+
+        execute_action(Action::DeleteChildren, &mut app).unwrap();
+        execute_action(Action::GoDown, &mut app).unwrap();
+        execute_action(Action::GoToRoot, &mut app).unwrap();
+        execute_action(Action::DeleteChildren, &mut app).unwrap();
+        assert!(!removed(&app));
+
+        execute_action(Action::DeleteChildren, &mut app).unwrap();
+        assert!(removed(&app));
+    }
+
+    #[test]
+    fn test_delete_children_of_inner_node_needs_no_confirmation() {
+        let mut app = create_test_app();
+        let child2 = app.root_id.unwrap().children(&app.tree).nth(1).unwrap();
+        app.active_node_id = Some(child2);
+
+        delete_children(&mut app);
+        assert_eq!(child2.children(&app.tree).count(), 0);
+    }
+
     #[test]
     fn test_move_node_up() {
         let mut app = create_test_app();
@@ -292,4 +491,49 @@ mod tests {
         assert_eq!(new_children[0], children[1]);
         assert_eq!(new_children[1], child1);
     }
+
+    #[test]
+    fn test_repeated_moves_undo_in_one_step() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        for title in ["Child 3", "Child 4"] {
+            root.append_value(Node::new(title.to_string()), &mut app.tree);
+        }
+        let original: Vec<_> = root.children(&app.tree).collect();
+        app.active_node_id = Some(original[0]);
+
+        for _ in 0..3 {
+            execute_action(Action::MoveNodeDown, &mut app).unwrap();
+        }
+        assert_eq!(root.children(&app.tree).next_back(), Some(original[0]));
+        assert_eq!(app.history_index, 1);
+
+        // Moving back up is a new step, undone on its own
+        execute_action(Action::MoveNodeUp, &mut app).unwrap();
+        assert_eq!(app.history_index, 2);
+        execute_action(Action::Undo, &mut app).unwrap();
+        execute_action(Action::Undo, &mut app).unwrap();
+        assert_eq!(root.children(&app.tree).collect::<Vec<_>>(), original);
+    }
+
+    #[test]
+    fn test_update_glossary_is_undoable() {
+        let mut app = create_test_app();
+        update_glossary(&mut app);
+        assert_eq!(app.message.as_deref(), Some("No acronyms in this map"));
+        assert!(!app.is_dirty);
+
+        let root = app.root_id.unwrap();
+        app.tree[root].get_mut().title = "Root of the API".to_string();
+        execute_action(Action::UpdateGlossary, &mut app).unwrap();
+        let glossary = app.active_node_id.unwrap();
+        assert_eq!(app.tree[glossary].get().title, "Glossary");
+        assert_eq!(root.children(&app.tree).count(), 3);
+
+        update_glossary(&mut app);
+        assert_eq!(app.message.as_deref(), Some("Glossary is up to date"));
+
+        execute_action(Action::Undo, &mut app).unwrap();
+        assert_eq!(root.children(&app.tree).count(), 2);
+    }
 }
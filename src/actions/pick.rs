@@ -0,0 +1,105 @@
+use crate::app::AppState;
+use crate::config::PickOutput;
+use crate::model::NodeId;
+use crate::pins::title_path;
+
+/// Separator of the titles in a picked path
+const PATH_SEPARATOR: &str = "/";
+
+/// Choose the nodes ticked with [`toggle_pick_selection`], or the active node
+/// if none are, and quit, for `--pick` to print them one per line in map order
+pub fn pick_node(app: &mut AppState) {
+    let (Some(output), Some(root_id), Some(active_id)) =
+        (app.pick, app.root_id, app.active_node_id)
+    else {
+        return;
+    };
+    let nodes: Vec<NodeId> = if app.pick_selection.is_empty() {
+        vec![active_id]
+    } else {
+        root_id
+            .descendants(&app.tree)
+            .filter(|id| app.pick_selection.contains(id))
+            .collect()
+    };
+    let lines: Vec<String> = nodes
+        .into_iter()
+        .map(|node_id| match output {
+            PickOutput::Title => app.tree[node_id].get().title.clone(),
+            PickOutput::Path => title_path(&app.tree, root_id, node_id).join(PATH_SEPARATOR),
+        })
+        .collect();
+    app.picked = Some(lines.join("\n"));
+    app.running = false;
+}
+
+/// Tick the active node for [`pick_node`], or untick it
+pub fn toggle_pick_selection(app: &mut AppState) {
+    let Some(active_id) = app.active_node_id else {
+        return;
+    };
+    match app.pick_selection.iter().position(|&id| id == active_id) {
+        Some(index) => {
+            app.pick_selection.remove(index);
+        }
+        None => app.pick_selection.push(active_id),
+    }
+    let count = app.pick_selection.len();
+    app.notify_with("status.pick_selected", &[("count", &count)]);
+}
+
+/// Quit without choosing a node
+pub fn cancel_pick(app: &mut AppState) {
+    app.picked = None;
+    app.running = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::model::Node;
+
+    #[test]
+    fn test_pick_node_prints_title_or_path() {
+        let mut app = AppState::new(AppConfig::default());
+        let root = app.tree.new_node(Node::new("Menu".to_string()));
+        let drinks = root.append_value(Node::new("Drinks".to_string()), &mut app.tree);
+        let tea = drinks.append_value(Node::new("Tea".to_string()), &mut app.tree);
+        app.root_id = Some(root);
+        app.active_node_id = Some(tea);
+
+        app.pick = Some(PickOutput::Title);
+        pick_node(&mut app);
+        assert_eq!(app.picked.as_deref(), Some("Tea"));
+        assert!(!app.running);
+
+        app.pick = Some(PickOutput::Path);
+        pick_node(&mut app);
+        assert_eq!(app.picked.as_deref(), Some("Menu/Drinks/Tea"));
+
+        cancel_pick(&mut app);
+        assert_eq!(app.picked, None);
+    }
+
+    #[test]
+    fn test_pick_prints_ticked_nodes_in_map_order() {
+        let mut app = AppState::new(AppConfig::default());
+        let root = app.tree.new_node(Node::new("Groceries".to_string()));
+        let milk = root.append_value(Node::new("Milk".to_string()), &mut app.tree);
+        let bread = root.append_value(Node::new("Bread".to_string()), &mut app.tree);
+        let eggs = root.append_value(Node::new("Eggs".to_string()), &mut app.tree);
+        app.root_id = Some(root);
+        app.pick = Some(PickOutput::Title);
+
+        for node in [eggs, bread, milk] {
+            app.active_node_id = Some(node);
+            toggle_pick_selection(&mut app);
+        }
+        toggle_pick_selection(&mut app);
+        assert_eq!(app.pick_selection, [eggs, bread]);
+
+        pick_node(&mut app);
+        assert_eq!(app.picked.as_deref(), Some("Bread\nEggs"));
+    }
+}
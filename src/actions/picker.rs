@@ -0,0 +1,132 @@
+use crate::app::{AppMode, AppState, PickerKind};
+use crate::recent::fuzzy_filter;
+use std::path::{Path, PathBuf};
+
+use super::file::{export_pipe_named, export_template_named, open_file};
+
+/// Show the picker over `items`
+pub(crate) fn open_picker(app: &mut AppState, kind: PickerKind, items: Vec<PathBuf>) {
+    app.picker_items = items;
+    app.mode = AppMode::Picker {
+        kind,
+        query: String::new(),
+        selected: 0,
+    };
+}
+
+pub fn type_picker_char(app: &mut AppState, c: char) {
+    if let AppMode::Picker {
+        query, selected, ..
+    } = &mut app.mode
+    {
+        query.push(c);
+        *selected = 0;
+    }
+}
+
+pub fn backspace_picker(app: &mut AppState) {
+    if let AppMode::Picker {
+        query, selected, ..
+    } = &mut app.mode
+    {
+        query.pop();
+        *selected = 0;
+    }
+}
+
+pub fn picker_up(app: &mut AppState) {
+    if let AppMode::Picker { selected, .. } = &mut app.mode {
+        *selected = selected.saturating_sub(1);
+    }
+}
+
+pub fn picker_down(app: &mut AppState) {
+    let count = picker_matches(app).len();
+    if let AppMode::Picker { selected, .. } = &mut app.mode {
+        if *selected + 1 < count {
+            *selected += 1;
+        }
+    }
+}
+
+pub fn confirm_picker(app: &mut AppState) {
+    let AppMode::Picker { kind, selected, .. } = app.mode else {
+        return;
+    };
+    let choice = picker_matches(app).get(selected).cloned();
+    let label = choice.as_deref().map(|path| picker_label(app, path));
+    cancel_picker(app);
+
+    if kind == PickerKind::ExportTemplate {
+        if let Some(name) = label {
+            export_template_named(app, &name);
+        }
+        return;
+    }
+    if kind == PickerKind::ExportPipe {
+        if let Some(name) = label {
+            export_pipe_named(app, &name);
+        }
+        return;
+    }
+    if let Some(path) = choice {
+        match open_file(app, &path) {
+            Ok(()) => {
+                let path = path.display().to_string();
+                app.notify_with("status.opened", &[("path", &path)]);
+            }
+            Err(e) => app.notify_with("status.open_failed", &[("error", &e)]),
+        }
+    }
+}
+
+pub fn cancel_picker(app: &mut AppState) {
+    app.mode = AppMode::Normal;
+    app.picker_items.clear();
+}
+
+/// Text shown and matched for a picker entry: workspace maps are relative to
+/// the workspace directory, templates and pipes go by their name and recent
+/// maps are shown in full
+pub fn picker_label(app: &AppState, path: &Path) -> String {
+    if let AppMode::Picker {
+        kind: PickerKind::ExportTemplate,
+        ..
+    } = app.mode
+    {
+        let name = app
+            .config
+            .export
+            .templates
+            .iter()
+            .find(|(_, template)| template.as_path() == path)
+            .map(|(name, _)| name.clone());
+        if let Some(name) = name {
+            return name;
+        }
+    }
+    let relative = match (&app.mode, &app.config.workspace) {
+        (
+            AppMode::Picker {
+                kind: PickerKind::Workspace,
+                ..
+            },
+            Some(dir),
+        ) => path.strip_prefix(crate::workspace::expand_home(dir)).ok(),
+        _ => None,
+    };
+    relative.unwrap_or(path).display().to_string()
+}
+
+/// Picker entries matching the query, best match first
+pub fn picker_matches(app: &AppState) -> Vec<PathBuf> {
+    match &app.mode {
+        AppMode::Picker { query, .. } => {
+            fuzzy_filter(&app.picker_items, query, |path| picker_label(app, path))
+                .into_iter()
+                .cloned()
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
@@ -0,0 +1,98 @@
+use crate::app::AppState;
+use crate::pins::MAX_PINS;
+
+use super::movement::reveal_and_select;
+
+/// Pin the active node to the bar above the map, or unpin it
+pub fn toggle_pin(app: &mut AppState) {
+    let Some(active_id) = app.active_node_id else {
+        return;
+    };
+
+    let mut pins = app.pinned_nodes();
+    if let Some(position) = pins.iter().position(|&id| id == active_id) {
+        pins.remove(position);
+        app.notify("status.unpinned");
+    } else {
+        let max = app.config.max_pins.min(MAX_PINS);
+        if pins.len() >= max {
+            app.notify_with("status.pins_full", &[("max", &max)]);
+            return;
+        }
+        pins.push(active_id);
+        let index = pins.len();
+        app.notify_with("status.pinned", &[("index", &index)]);
+    }
+
+    app.pins = pins;
+    if let Err(e) = app.store_pins() {
+        app.notify_with("status.pins_not_saved", &[("error", &e)]);
+    }
+}
+
+/// Select the pin numbered `index`, counting from 1
+pub fn jump_to_pin(app: &mut AppState, index: usize) {
+    match index
+        .checked_sub(1)
+        .and_then(|i| app.pinned_nodes().get(i).copied())
+    {
+        Some(node_id) => reveal_and_select(app, node_id),
+        None => app.notify_with("status.no_pin", &[("index", &index)]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::model::Node;
+
+    fn create_test_app() -> AppState {
+        let config = AppConfig {
+            max_pins: 2,
+            ..Default::default()
+        };
+        let mut app = AppState::new(config);
+        let root = app.tree.new_node(Node::new("Root".to_string()));
+        for title in ["A", "B", "C"] {
+            root.append_value(Node::new(title.to_string()), &mut app.tree);
+        }
+        app.root_id = Some(root);
+        app.active_node_id = Some(root);
+        app
+    }
+
+    #[test]
+    fn test_pin_and_jump() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let children: Vec<_> = root.children(&app.tree).collect();
+
+        for &child in &children {
+            app.active_node_id = Some(child);
+            toggle_pin(&mut app);
+        }
+        assert_eq!(app.pins, children[..2]);
+        assert_eq!(app.message.as_deref(), Some("Only 2 nodes can be pinned"));
+
+        app.active_node_id = Some(root);
+        jump_to_pin(&mut app, 2);
+        assert_eq!(app.active_node_id, Some(children[1]));
+
+        toggle_pin(&mut app);
+        assert_eq!(app.pins, [children[0]]);
+        jump_to_pin(&mut app, 2);
+        assert_eq!(app.message.as_deref(), Some("No pin 2"));
+    }
+
+    #[test]
+    fn test_deleted_nodes_drop_out() {
+        let mut app = create_test_app();
+        let first = app.root_id.unwrap().children(&app.tree).next().unwrap();
+        app.active_node_id = Some(first);
+        toggle_pin(&mut app);
+
+        first.remove_subtree(&mut app.tree);
+        assert!(app.pinned_nodes().is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::app::{AppMode, AppState, QuitChoice};
+use crate::config::PrintOnExit;
+use crate::model::NodeId;
+use crate::parser::map_to_list;
+
+use super::file::{needs_save_as, save, start_save_as};
+
+/// Quit right away if everything is saved or the map is a scratch map,
+/// otherwise ask what to do first
+pub fn quit(app: &mut AppState) {
+    if app.is_dirty && !app.scratch {
+        app.mode = AppMode::QuitPrompt {
+            selected: QuitChoice::Save,
+        };
+    } else {
+        app.running = false;
+    }
+}
+
+pub fn quit_prompt_next(app: &mut AppState) {
+    move_quit_selection(app, 1);
+}
+
+pub fn quit_prompt_previous(app: &mut AppState) {
+    move_quit_selection(app, QuitChoice::ALL.len() - 1);
+}
+
+fn move_quit_selection(app: &mut AppState, step: usize) {
+    if let AppMode::QuitPrompt { selected } = &mut app.mode {
+        let index = QuitChoice::ALL
+            .iter()
+            .position(|c| c == selected)
+            .unwrap_or(0);
+        *selected = QuitChoice::ALL[(index + step) % QuitChoice::ALL.len()];
+    }
+}
+
+/// Act on an answer to the quit prompt. A map without a writable file goes
+/// through the save-as prompt first; a failed save keeps the app open.
+pub fn choose_quit(app: &mut AppState, choice: QuitChoice) {
+    app.mode = AppMode::Normal;
+    match choice {
+        QuitChoice::Save if needs_save_as(app) => start_save_as(app, true),
+        QuitChoice::Save => {
+            // save() reports its own errors in the status line
+            if save(app).is_ok() && !app.is_dirty {
+                app.running = false;
+            }
+        }
+        QuitChoice::Discard => app.running = false,
+        QuitChoice::Cancel => {}
+    }
+}
+
+/// The outline to print once the editor has quit, as `print_on_exit` asks
+pub fn exit_outline(app: &AppState) -> Option<String> {
+    let root_id = app.root_id?;
+    match app.config.print_on_exit {
+        PrintOnExit::Off => None,
+        PrintOnExit::Visible => Some(visible_outline(app, root_id)),
+        PrintOnExit::Full => Some(map_to_list(&app.tree, root_id, false, 0)),
+    }
+}
+
+/// Tab-indented titles of the nodes the map shows
+fn visible_outline(app: &AppState, root_id: NodeId) -> String {
+    let mut output = String::new();
+    let mut stack = vec![(root_id, 0)];
+    while let Some((node_id, depth)) = stack.pop() {
+        let node = app.tree[node_id].get();
+        output.push_str(&"\t".repeat(depth));
+        output.push_str(&node.title);
+        output.push('\n');
+
+        if !node.is_collapsed {
+            let children = node_id
+                .children(&app.tree)
+                .rev()
+                .filter(|&child| app.config.show_hidden || !app.tree[child].get().is_hidden());
+            stack.extend(children.map(|child| (child, depth + 1)));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::confirm_save_as;
+    use crate::config::AppConfig;
+    use crate::model::Node;
+    use tempfile::TempDir;
+
+    fn create_test_app() -> AppState {
+        let config = AppConfig::default();
+        let mut app = AppState::new(config);
+
+        let root = app.tree.new_node(Node::new("Root".to_string()));
+        app.root_id = Some(root);
+        app.active_node_id = Some(root);
+        app.is_dirty = true;
+
+        app
+    }
+
+    #[test]
+    fn test_quit_without_changes_exits() {
+        let mut app = create_test_app();
+        app.is_dirty = false;
+
+        quit(&mut app);
+        assert!(!app.running);
+    }
+
+    #[test]
+    fn test_quit_scratch_map_with_changes_exits() {
+        let mut app = create_test_app();
+        app.scratch = true;
+
+        quit(&mut app);
+        assert!(!app.running);
+    }
+
+    #[test]
+    fn test_quit_with_changes_asks_first() {
+        let mut app = create_test_app();
+
+        quit(&mut app);
+        assert!(app.running);
+        assert_eq!(
+            app.mode,
+            AppMode::QuitPrompt {
+                selected: QuitChoice::Save
+            }
+        );
+
+        quit_prompt_previous(&mut app);
+        assert_eq!(
+            app.mode,
+            AppMode::QuitPrompt {
+                selected: QuitChoice::Cancel
+            }
+        );
+        quit_prompt_next(&mut app);
+        quit_prompt_next(&mut app);
+        assert_eq!(
+            app.mode,
+            AppMode::QuitPrompt {
+                selected: QuitChoice::Discard
+            }
+        );
+    }
+
+    #[test]
+    fn test_discard_and_cancel() {
+        let mut app = create_test_app();
+        quit(&mut app);
+        choose_quit(&mut app, QuitChoice::Cancel);
+        assert!(app.running);
+        assert_eq!(app.mode, AppMode::Normal);
+
+        quit(&mut app);
+        choose_quit(&mut app, QuitChoice::Discard);
+        assert!(!app.running);
+        assert!(app.is_dirty);
+    }
+
+    #[test]
+    fn test_save_and_quit() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("map.hmm");
+        let mut app = create_test_app();
+        app.filename = Some(path.clone());
+
+        quit(&mut app);
+        choose_quit(&mut app, QuitChoice::Save);
+        assert!(!app.running);
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_save_and_quit_without_filename_asks_for_one() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("new.hmm");
+        let mut app = create_test_app();
+
+        quit(&mut app);
+        choose_quit(&mut app, QuitChoice::Save);
+        assert!(app.running);
+        assert!(matches!(
+            app.mode,
+            AppMode::SaveAs {
+                quit_after: true,
+                ..
+            }
+        ));
+
+        app.mode = AppMode::SaveAs {
+            buffer: path.display().to_string(),
+            quit_after: true,
+            format: None,
+        };
+        confirm_save_as(&mut app);
+        assert!(!app.running);
+        assert_eq!(app.filename, Some(path.clone()));
+        assert!(path.exists());
+    }
+
+    #[test]
+    fn test_exit_outline() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let open = root.append_value(Node::new("Open".to_string()), &mut app.tree);
+        open.append_value(Node::new("Shown".to_string()), &mut app.tree);
+        let closed = root.append_value(Node::new("Closed".to_string()), &mut app.tree);
+        closed.append_value(Node::new("Folded".to_string()), &mut app.tree);
+        app.tree[closed].get_mut().is_collapsed = true;
+        root.append_value(Node::new("[HIDDEN] Secret".to_string()), &mut app.tree);
+
+        assert_eq!(exit_outline(&app), None);
+        app.config.print_on_exit = PrintOnExit::Visible;
+        assert_eq!(
+            exit_outline(&app).as_deref(),
+            Some("Root\n\tOpen\n\t\tShown\n\tClosed\n")
+        );
+        app.config.print_on_exit = PrintOnExit::Full;
+        assert_eq!(
+            exit_outline(&app).as_deref(),
+            Some("Root\n\tOpen\n\t\tShown\n\tClosed\n\t\tFolded\n\t[HIDDEN] Secret\n")
+        );
+    }
+}
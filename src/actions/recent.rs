@@ -0,0 +1,109 @@
+use crate::app::{AppState, PickerKind};
+
+use super::picker::open_picker;
+
+pub fn open_recent(app: &mut AppState) {
+    if app.is_dirty {
+        app.notify("status.unsaved_before_open");
+        return;
+    }
+
+    app.recent.prune_missing();
+    if app.recent.is_empty() {
+        app.notify("status.no_recent_files");
+        return;
+    }
+
+    let items = app.recent.entries().to_vec();
+    open_picker(app, PickerKind::Recent, items);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{
+        cancel_picker, confirm_picker, picker_down, picker_matches, type_picker_char,
+    };
+    use crate::app::AppMode;
+    use crate::config::AppConfig;
+    use crate::model::Node;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_app() -> AppState {
+        let config = AppConfig::default();
+        let mut app = AppState::new(config);
+        let root = app.tree.new_node(Node::new("Scratch".to_string()));
+        app.root_id = Some(root);
+        app.active_node_id = Some(root);
+        app
+    }
+
+    #[test]
+    fn test_open_recent_requires_entries() {
+        let mut app = create_test_app();
+        open_recent(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(app.message.as_deref(), Some("No recent maps"));
+    }
+
+    #[test]
+    fn test_open_recent_refuses_unsaved_changes() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.hmm");
+        fs::write(&path, "Notes").unwrap();
+
+        let mut app = create_test_app();
+        app.recent.record(&path).unwrap();
+        app.is_dirty = true;
+
+        open_recent(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+    }
+
+    #[test]
+    fn test_filter_and_open() {
+        let dir = TempDir::new().unwrap();
+        let notes = dir.path().join("notes.hmm");
+        let plan = dir.path().join("plan.hmm");
+        fs::write(&notes, "Notes\n\tIdea").unwrap();
+        fs::write(&plan, "Plan").unwrap();
+
+        let mut app = create_test_app();
+        app.recent.record(&notes).unwrap();
+        app.recent.record(&plan).unwrap();
+
+        open_recent(&mut app);
+        assert_eq!(picker_matches(&app).len(), 2);
+
+        for c in "notes.hmm".chars() {
+            type_picker_char(&mut app, c);
+        }
+        picker_down(&mut app);
+        assert!(matches!(app.mode, AppMode::Picker { selected: 0, .. }));
+        assert!(picker_matches(&app)[0].ends_with("notes.hmm"));
+
+        confirm_picker(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        let root = app.root_id.unwrap();
+        assert_eq!(app.tree[root].get().title, "Notes");
+        assert!(app.filename.as_ref().unwrap().ends_with("notes.hmm"));
+        assert!(app.recent.entries()[0].ends_with("notes.hmm"));
+        assert!(!app.is_dirty);
+    }
+
+    #[test]
+    fn test_cancel_picker_keeps_map() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("notes.hmm");
+        fs::write(&path, "Notes").unwrap();
+
+        let mut app = create_test_app();
+        app.recent.record(&path).unwrap();
+
+        open_recent(&mut app);
+        cancel_picker(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(app.tree[app.root_id.unwrap()].get().title, "Scratch");
+    }
+}
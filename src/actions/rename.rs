@@ -0,0 +1,179 @@
+use crate::app::{AppMode, AppState};
+use crate::model::NodeId;
+use regex::Regex;
+
+/// Ask for a `pattern/replacement` substitution to apply to every title in
+/// the active subtree
+pub fn start_rename(app: &mut AppState) {
+    app.mode = AppMode::Rename {
+        buffer: String::new(),
+        matches: None,
+    };
+}
+
+pub fn type_rename_char(app: &mut AppState, c: char) {
+    if let AppMode::Rename { buffer, .. } = &mut app.mode {
+        buffer.push(c);
+    }
+    count_renames(app);
+}
+
+pub fn backspace_rename(app: &mut AppState) {
+    if let AppMode::Rename { buffer, .. } = &mut app.mode {
+        buffer.pop();
+    }
+    count_renames(app);
+}
+
+pub fn cancel_rename(app: &mut AppState) {
+    app.mode = AppMode::Normal;
+}
+
+/// Rename the matching titles, all in one undo step
+pub fn confirm_rename(app: &mut AppState) {
+    let AppMode::Rename { buffer, .. } = std::mem::replace(&mut app.mode, AppMode::Normal) else {
+        return;
+    };
+    let (Some(active_id), Some((pattern, replacement))) =
+        (app.active_node_id, parse_substitution(&buffer))
+    else {
+        app.notify("status.invalid_rename");
+        return;
+    };
+
+    let renamed = renamed_titles(app, active_id, &pattern, &replacement);
+    if renamed.is_empty() {
+        app.notify("status.nothing_to_rename");
+        return;
+    }
+
+    app.push_history();
+    let count = renamed.len();
+    for (id, title) in renamed {
+        if let Some(node) = app.tree.get_mut(id) {
+            node.get_mut().title = title;
+        }
+    }
+    app.is_dirty = true;
+    app.last_modify_time = Some(std::time::Instant::now());
+    app.notify_with("status.renamed", &[("count", &count)]);
+}
+
+/// Keep the count of titles the substitution typed so far would change
+/// up to date for the prompt
+fn count_renames(app: &mut AppState) {
+    let count = match (&app.mode, app.active_node_id) {
+        (AppMode::Rename { buffer, .. }, Some(active_id)) => {
+            parse_substitution(buffer).map(|(pattern, replacement)| {
+                renamed_titles(app, active_id, &pattern, &replacement).len()
+            })
+        }
+        _ => return,
+    };
+    if let AppMode::Rename { matches, .. } = &mut app.mode {
+        *matches = count;
+    }
+}
+
+/// Split `pattern/replacement` at the first `/` not escaped with a backslash.
+/// The replacement may refer to groups as `$1` or `${name}`.
+fn parse_substitution(text: &str) -> Option<(Regex, String)> {
+    let mut escaped = false;
+    let split = text.char_indices().find(|&(_, c)| {
+        let found = c == '/' && !escaped;
+        escaped = c == '\\' && !escaped;
+        found
+    })?;
+    let (pattern, replacement) = (&text[..split.0], &text[split.0 + 1..]);
+    if pattern.is_empty() {
+        return None;
+    }
+    let pattern = Regex::new(pattern).ok()?;
+    Some((pattern, replacement.to_string()))
+}
+
+/// The nodes in the subtree of `root` whose title the substitution changes,
+/// with their new titles
+fn renamed_titles(
+    app: &AppState,
+    root: NodeId,
+    pattern: &Regex,
+    replacement: &str,
+) -> Vec<(NodeId, String)> {
+    root.descendants(&app.tree)
+        .filter_map(|id| {
+            let title = &app.tree.get(id)?.get().title;
+            let renamed = pattern.replace_all(title, replacement);
+            (renamed != *title).then(|| (id, renamed.into_owned()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::undo;
+    use crate::config::AppConfig;
+    use crate::model::Node;
+
+    fn create_test_app() -> AppState {
+        let mut app = AppState::new(AppConfig::default());
+        let root = app.tree.new_node(Node::new("Projects".to_string()));
+        let apollo = root.append_value(Node::new("APO-1 Plan".to_string()), &mut app.tree);
+        apollo.append_value(Node::new("APO-2 Build".to_string()), &mut app.tree);
+        apollo.append_value(Node::new("Notes".to_string()), &mut app.tree);
+        root.append_value(Node::new("APO-3 Elsewhere".to_string()), &mut app.tree);
+        app.root_id = Some(root);
+        app.active_node_id = Some(apollo);
+        app
+    }
+
+    fn titles(app: &AppState) -> Vec<String> {
+        let root = app.root_id.unwrap();
+        root.descendants(&app.tree)
+            .map(|id| app.tree[id].get().title.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_rename_is_scoped_to_the_subtree() {
+        let mut app = create_test_app();
+        start_rename(&mut app);
+        for c in r"APO-(\d)/ARES-$1".chars() {
+            type_rename_char(&mut app, c);
+        }
+        assert!(matches!(
+            app.mode,
+            AppMode::Rename {
+                matches: Some(2),
+                ..
+            }
+        ));
+
+        confirm_rename(&mut app);
+        assert!(matches!(app.mode, AppMode::Normal));
+        assert_eq!(
+            titles(&app),
+            [
+                "Projects",
+                "ARES-1 Plan",
+                "ARES-2 Build",
+                "Notes",
+                "APO-3 Elsewhere"
+            ]
+        );
+
+        undo(&mut app);
+        assert_eq!(titles(&app)[1..3], ["APO-1 Plan", "APO-2 Build"]);
+    }
+
+    #[test]
+    fn test_parse_substitution() {
+        let (pattern, replacement) = parse_substitution(r"a\/b/c/d").unwrap();
+        assert_eq!(pattern.as_str(), r"a\/b");
+        assert_eq!(replacement, "c/d");
+        assert!(parse_substitution("no separator").is_none());
+        assert!(parse_substitution("/empty pattern").is_none());
+        assert!(parse_substitution("(/unclosed").is_none());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::app::{AppMode, AppState};
+use crate::model::NodeId;
 
 pub fn start_search(app: &mut AppState) {
     app.mode = AppMode::Search {
@@ -20,16 +21,17 @@ pub fn backspace_search(app: &mut AppState) {
 
 pub fn confirm_search(app: &mut AppState) {
     if let AppMode::Search { query } = &app.mode {
-        // Perform search
-        let mut results = Vec::new();
-        for node_ref in app.tree.iter() {
-            if node_ref
-                .get()
-                .title
-                .to_lowercase()
-                .contains(&query.to_lowercase())
-            {
-                results.push(app.tree.get_node_id(node_ref).unwrap());
+        // Ranked word-prefix matches from the index, caught up with the edits
+        // the idle ticks have not got to yet, falling back to a substring
+        // scan so matches inside words are still found
+        app.text_index.refresh_some(&app.tree, usize::MAX);
+        let mut results = app.text_index.query(query);
+        if results.is_empty() {
+            let needle = query.to_lowercase();
+            for node_ref in app.tree.iter().filter(|n| !n.is_removed()) {
+                if node_ref.get().title.to_lowercase().contains(&needle) {
+                    results.push(app.tree.get_node_id(node_ref).unwrap());
+                }
             }
         }
 
@@ -38,15 +40,27 @@ pub fn confirm_search(app: &mut AppState) {
 
         if !app.search_results.is_empty() {
             app.active_node_id = Some(app.search_results[0]);
-            app.set_message(format!("Found {} results", app.search_results.len()));
+            let count = app.search_results.len();
+            app.notify_with("status.found_results", &[("count", &count)]);
         } else {
-            app.set_message("No results found");
+            app.notify("status.no_results");
         }
     }
 
     app.mode = AppMode::Normal;
 }
 
+/// Find a node by title: an exact match first, then a case-insensitive substring match
+pub fn find_node_by_title(app: &AppState, title: &str) -> Option<NodeId> {
+    let live_nodes = || app.tree.iter().filter(|n| !n.is_removed());
+    let needle = title.to_lowercase();
+
+    live_nodes()
+        .find(|n| n.get().title == title)
+        .or_else(|| live_nodes().find(|n| n.get().title.to_lowercase().contains(&needle)))
+        .and_then(|n| app.tree.get_node_id(n))
+}
+
 pub fn cancel_search(app: &mut AppState) {
     app.mode = AppMode::Normal;
 }
@@ -55,11 +69,11 @@ pub fn next_search_result(app: &mut AppState) {
     if !app.search_results.is_empty() {
         app.search_index = (app.search_index + 1) % app.search_results.len();
         app.active_node_id = Some(app.search_results[app.search_index]);
-        app.set_message(format!(
-            "Result {}/{}",
-            app.search_index + 1,
-            app.search_results.len()
-        ));
+        let (index, count) = (app.search_index + 1, app.search_results.len());
+        app.notify_with(
+            "status.search_result",
+            &[("index", &index), ("count", &count)],
+        );
     }
 }
 
@@ -71,14 +85,65 @@ pub fn previous_search_result(app: &mut AppState) {
             app.search_index - 1
         };
         app.active_node_id = Some(app.search_results[app.search_index]);
-        app.set_message(format!(
-            "Result {}/{}",
-            app.search_index + 1,
-            app.search_results.len()
-        ));
+        let (index, count) = (app.search_index + 1, app.search_results.len());
+        app.notify_with(
+            "status.search_result",
+            &[("index", &index), ("count", &count)],
+        );
     }
 }
 
+/// Collapse the whole map but the ancestors of the search results, so that
+/// they are all that is left to see. Clearing the search unfolds it again.
+pub fn fold_to_search_results(app: &mut AppState) {
+    if app.search_results.is_empty() {
+        app.notify("status.no_search_results");
+        return;
+    }
+    app.push_view_history();
+    // Folding again keeps the state from before the first fold
+    if app.search_fold.is_none() {
+        let state = app
+            .tree
+            .iter()
+            .filter(|n| !n.is_removed())
+            .filter_map(|n| Some((app.tree.get_node_id(n)?, n.get().is_collapsed)))
+            .collect();
+        app.search_fold = Some(state);
+    }
+
+    for node in app.tree.iter_mut() {
+        node.get_mut().is_collapsed = true;
+    }
+    for &result in &app.search_results {
+        let ancestors: Vec<NodeId> = result.ancestors(&app.tree).skip(1).collect();
+        for ancestor_id in ancestors {
+            app.tree[ancestor_id].get_mut().is_collapsed = false;
+        }
+    }
+    if let Some(&result) = app.search_results.get(app.search_index) {
+        app.active_node_id = Some(result);
+    }
+    let count = app.search_results.len();
+    app.notify_with("status.folded_to_results", &[("count", &count)]);
+}
+
+/// Forget the search results, unfolding the map if it was folded to them
+pub fn clear_search(app: &mut AppState) {
+    app.search_results.clear();
+    app.search_index = 0;
+    if let Some(state) = app.search_fold.take() {
+        app.push_view_history();
+        for (node_id, collapsed) in state {
+            // Nodes deleted since are gone, and new ones stay as they are
+            if let Some(node) = app.tree.get_mut(node_id).filter(|n| !n.is_removed()) {
+                node.get_mut().is_collapsed = collapsed;
+            }
+        }
+    }
+    app.notify("status.search_cleared");
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -103,6 +168,17 @@ mod tests {
         app
     }
 
+    #[test]
+    fn test_find_node_by_title() {
+        let app = create_test_app();
+        let root = app.root_id.unwrap();
+        let child2 = root.children(&app.tree).nth(1).unwrap();
+
+        assert_eq!(find_node_by_title(&app, "Child 2"), Some(child2));
+        assert_eq!(find_node_by_title(&app, "child 2"), Some(child2));
+        assert_eq!(find_node_by_title(&app, "Missing"), None);
+    }
+
     #[test]
     fn test_search_mode() {
         let mut app = create_test_app();
@@ -122,4 +198,87 @@ mod tests {
         assert!(matches!(app.mode, AppMode::Normal));
         assert!(!app.search_results.is_empty());
     }
+
+    #[test]
+    fn test_search_multi_word_and_substring_fallback() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let child2 = root.children(&app.tree).nth(1).unwrap();
+
+        app.mode = AppMode::Search {
+            query: "child 2 | nothing".to_string(),
+        };
+        confirm_search(&mut app);
+        assert_eq!(app.search_results, vec![child2]);
+        assert_eq!(app.active_node_id, Some(child2));
+
+        // No word starts with "hild", so the substring scan kicks in
+        app.mode = AppMode::Search {
+            query: "hild".to_string(),
+        };
+        confirm_search(&mut app);
+        assert_eq!(app.search_results.len(), 2);
+    }
+
+    #[test]
+    fn test_search_follows_edits_and_punctuation() {
+        let mut app = create_test_app();
+        app.mode = AppMode::Search {
+            query: "child".to_string(),
+        };
+        confirm_search(&mut app);
+        assert!(app.text_index.is_current());
+
+        // A new node typed in keeps the index up to date without a walk
+        crate::actions::insert_child(&mut app);
+        "C++ tips"
+            .chars()
+            .for_each(|c| crate::actions::type_char(&mut app, c));
+        crate::actions::confirm_edit(&mut app);
+        assert!(app.text_index.is_current());
+        let tips = app.active_node_id.unwrap();
+
+        // "c" starts the word "Child" too, but only one title has "c++"
+        app.mode = AppMode::Search {
+            query: "c++".to_string(),
+        };
+        confirm_search(&mut app);
+        assert_eq!(app.search_results, vec![tips]);
+    }
+
+    #[test]
+    fn test_fold_to_search_results_and_clear() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let child1 = root.children(&app.tree).next().unwrap();
+        let child2 = root.children(&app.tree).nth(1).unwrap();
+        let needle = child1.append_value(Node::new("Needle".to_string()), &mut app.tree);
+        let other = child2.append_value(Node::new("Hay".to_string()), &mut app.tree);
+        other.append_value(Node::new("Needle too".to_string()), &mut app.tree);
+        app.tree[child1].get_mut().is_collapsed = true;
+
+        fold_to_search_results(&mut app);
+        assert!(app.search_fold.is_none());
+
+        app.mode = AppMode::Search {
+            query: "needle".to_string(),
+        };
+        confirm_search(&mut app);
+        fold_to_search_results(&mut app);
+        let collapsed = |app: &AppState, id: NodeId| app.tree[id].get().is_collapsed;
+        assert!(!collapsed(&app, root));
+        assert!(!collapsed(&app, child1));
+        assert!(!collapsed(&app, child2));
+        assert!(!collapsed(&app, other));
+        assert!(collapsed(&app, needle));
+
+        // A second fold still restores the state from before the first
+        fold_to_search_results(&mut app);
+        clear_search(&mut app);
+        assert!(app.search_results.is_empty());
+        assert!(app.search_fold.is_none());
+        assert!(collapsed(&app, child1));
+        assert!(!collapsed(&app, child2));
+        assert!(!collapsed(&app, needle));
+    }
 }
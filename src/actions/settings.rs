@@ -0,0 +1,241 @@
+use crate::app::{AppMode, AppState, Damage};
+use crate::config::AppConfig;
+use crate::i18n::Catalog;
+use crate::keymap::Keymap;
+use crate::settings::{edit_text, settings, with_setting, write_settings};
+use anyhow::anyhow;
+use toml::Value;
+
+pub fn open_settings(app: &mut AppState) {
+    app.clear_message();
+    app.mode = AppMode::Settings {
+        selected: 0,
+        buffer: None,
+        changed: Vec::new(),
+    };
+}
+
+/// Leave the overlay; changes stay applied, but only for this session unless written
+pub fn close_settings(app: &mut AppState) {
+    if let AppMode::Settings { changed, .. } = &app.mode {
+        if !changed.is_empty() {
+            app.notify("status.settings_not_written");
+        }
+    }
+    app.mode = AppMode::Normal;
+}
+
+pub fn settings_up(app: &mut AppState) {
+    app.clear_message();
+    if let AppMode::Settings { selected, .. } = &mut app.mode {
+        *selected = selected.saturating_sub(1);
+    }
+}
+
+pub fn settings_down(app: &mut AppState) {
+    app.clear_message();
+    let count = settings(&app.config).len();
+    if let AppMode::Settings { selected, .. } = &mut app.mode {
+        if *selected + 1 < count {
+            *selected += 1;
+        }
+    }
+}
+
+/// Toggle the selected switch, start editing any other setting, or apply the
+/// edit in progress
+pub fn activate_setting(app: &mut AppState) {
+    let AppMode::Settings {
+        selected,
+        ref buffer,
+        ..
+    } = app.mode
+    else {
+        return;
+    };
+    let Some((name, value)) = settings(&app.config).into_iter().nth(selected) else {
+        return;
+    };
+
+    let text = match (buffer, &value) {
+        (Some(text), _) => text.clone(),
+        (None, Value::Boolean(on)) => (!on).to_string(),
+        (None, value) => {
+            let text = edit_text(value);
+            if let AppMode::Settings { buffer, .. } = &mut app.mode {
+                *buffer = Some(text);
+            }
+            return;
+        }
+    };
+
+    match with_setting(&app.config, &name, &text).and_then(|config| apply_config(app, config)) {
+        Ok(()) => {
+            app.clear_message();
+            if let AppMode::Settings {
+                buffer, changed, ..
+            } = &mut app.mode
+            {
+                *buffer = None;
+                if !changed.contains(&name) {
+                    changed.push(name);
+                }
+            }
+        }
+        Err(e) => app.notify_with("status.invalid_setting", &[("name", &name), ("error", &e)]),
+    }
+}
+
+pub fn type_setting_char(app: &mut AppState, c: char) {
+    if let AppMode::Settings {
+        buffer: Some(buffer),
+        ..
+    } = &mut app.mode
+    {
+        buffer.push(c);
+    }
+}
+
+pub fn backspace_setting(app: &mut AppState) {
+    if let AppMode::Settings {
+        buffer: Some(buffer),
+        ..
+    } = &mut app.mode
+    {
+        buffer.pop();
+    }
+}
+
+/// Drop the edit in progress, leaving the setting as it was
+pub fn cancel_setting(app: &mut AppState) {
+    if let AppMode::Settings { buffer, .. } = &mut app.mode {
+        *buffer = None;
+    }
+}
+
+/// Write the settings changed in the overlay to the config file
+pub fn write_changed_settings(app: &mut AppState) {
+    let AppMode::Settings { changed, .. } = &app.mode else {
+        return;
+    };
+    let result = match &app.config_path {
+        Some(path) => write_settings(path, &app.config, changed).map(|()| path.clone()),
+        None => Err(anyhow!("no config file")),
+    };
+
+    match result {
+        Ok(path) => {
+            if let AppMode::Settings { changed, .. } = &mut app.mode {
+                changed.clear();
+            }
+            let path = path.display();
+            app.notify_with("status.settings_written", &[("path", &path)]);
+        }
+        Err(e) => app.notify_with("status.settings_write_failed", &[("error", &e)]),
+    }
+}
+
+/// Switch to `config`, rebuilding what is derived from it
+fn apply_config(app: &mut AppState, config: AppConfig) -> anyhow::Result<()> {
+    if config.leader != app.config.leader {
+        app.keymap = Keymap::new(&config.keys, &config.leader)?;
+    }
+    if config.language != app.config.language {
+        app.catalog = config
+            .language
+            .as_deref()
+            .map(Catalog::new)
+            .unwrap_or_default();
+    }
+    app.config = config;
+    app.mark_damage(Damage::Layout);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{execute_action, Action};
+    use crate::model::Node;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_app() -> AppState {
+        let config = AppConfig::default();
+        let mut app = AppState::new(config);
+
+        let root = app.tree.new_node(Node::new("Root".to_string()));
+        app.root_id = Some(root);
+        app.active_node_id = Some(root);
+
+        app
+    }
+
+    fn select(app: &mut AppState, name: &str) {
+        let index = settings(&app.config)
+            .iter()
+            .position(|(setting, _)| setting == name)
+            .unwrap();
+        if let AppMode::Settings { selected, .. } = &mut app.mode {
+            *selected = index;
+        }
+    }
+
+    #[test]
+    fn test_toggle_and_edit_settings() {
+        let mut app = create_test_app();
+        execute_action(Action::OpenSettings, &mut app).unwrap();
+
+        select(&mut app, "center_lock");
+        execute_action(Action::ActivateSetting, &mut app).unwrap();
+        assert!(app.config.center_lock);
+
+        // Numbers are edited as text and applied on Enter
+        select(&mut app, "line_spacing");
+        execute_action(Action::ActivateSetting, &mut app).unwrap();
+        execute_action(Action::BackspaceSetting, &mut app).unwrap();
+        execute_action(Action::TypeSettingChar('4'), &mut app).unwrap();
+        assert_eq!(app.config.line_spacing, 1);
+        execute_action(Action::ActivateSetting, &mut app).unwrap();
+        assert_eq!(app.config.line_spacing, 4);
+
+        // A bad value is reported and the edit stays open to fix it
+        execute_action(Action::ActivateSetting, &mut app).unwrap();
+        execute_action(Action::TypeSettingChar('x'), &mut app).unwrap();
+        execute_action(Action::ActivateSetting, &mut app).unwrap();
+        assert_eq!(app.config.line_spacing, 4);
+        assert!(app.message.as_deref().unwrap().contains("line_spacing"));
+        execute_action(Action::CancelSetting, &mut app).unwrap();
+
+        let AppMode::Settings {
+            buffer, changed, ..
+        } = &app.mode
+        else {
+            panic!("settings should stay open");
+        };
+        assert_eq!(buffer, &None);
+        assert_eq!(changed, &["center_lock", "line_spacing"]);
+    }
+
+    #[test]
+    fn test_write_changed_settings() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("h-m-m.conf");
+        let mut app = create_test_app();
+        app.config_path = Some(path.clone());
+
+        open_settings(&mut app);
+        select(&mut app, "focus_lock");
+        activate_setting(&mut app);
+        write_changed_settings(&mut app);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "focus_lock = true\n");
+
+        close_settings(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app
+            .message
+            .as_deref()
+            .unwrap()
+            .contains(&path.display().to_string()));
+    }
+}
@@ -0,0 +1,170 @@
+use crate::app::{AppMode, AppState, Damage};
+use crate::parser;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use super::clipboard::copy_subtree;
+
+/// Snapshot the open map if the configured interval has passed since the
+/// last one was due. Unchanged maps are not stored again.
+pub fn take_snapshot_if_due(app: &mut AppState) {
+    let interval = Duration::from_secs(app.config.snapshot_interval as u64 * 60);
+    if interval.is_zero()
+        || app
+            .last_snapshot_time
+            .is_some_and(|last| last.elapsed() < interval)
+    {
+        return;
+    }
+    let (Some(path), Some(root_id)) = (app.filename.as_deref(), app.root_id) else {
+        return;
+    };
+
+    let content = parser::map_to_hmm(&app.tree, root_id);
+    let now = chrono::Local::now().naive_local();
+    // A missing snapshot is not worth interrupting the session for
+    let _ = app
+        .snapshot_store
+        .take(path, &content, now, app.config.snapshot_keep);
+    app.last_snapshot_time = Some(Instant::now());
+}
+
+/// Browse the snapshots of the open map
+pub fn open_snapshots(app: &mut AppState) {
+    let snapshots = app
+        .filename
+        .as_deref()
+        .map(|path| app.snapshot_store.list(path))
+        .unwrap_or_default();
+    if snapshots.is_empty() {
+        app.notify("status.no_snapshots");
+        return;
+    }
+
+    let preview = fs::read_to_string(&snapshots[0].path).unwrap_or_default();
+    app.mode = AppMode::Snapshots {
+        snapshots,
+        selected: 0,
+        preview,
+    };
+}
+
+pub fn snapshot_older(app: &mut AppState) {
+    select_snapshot(app, 1);
+}
+
+pub fn snapshot_newer(app: &mut AppState) {
+    select_snapshot(app, -1);
+}
+
+fn select_snapshot(app: &mut AppState, step: isize) {
+    if let AppMode::Snapshots {
+        snapshots,
+        selected,
+        preview,
+    } = &mut app.mode
+    {
+        let index = selected
+            .saturating_add_signed(step)
+            .min(snapshots.len() - 1);
+        if index != *selected {
+            *selected = index;
+            *preview = fs::read_to_string(&snapshots[index].path).unwrap_or_default();
+        }
+    }
+}
+
+/// Replace the map with the selected snapshot; undo brings the current map back
+pub fn restore_snapshot(app: &mut AppState) {
+    let AppMode::Snapshots {
+        ref snapshots,
+        selected,
+        ref preview,
+    } = app.mode
+    else {
+        return;
+    };
+    let time = snapshots[selected]
+        .time
+        .format("%Y-%m-%d %H:%M")
+        .to_string();
+    let (Ok((snapshot, snapshot_root)), Some(root_id)) =
+        (parser::parse_hmm_content(preview), app.root_id)
+    else {
+        app.notify("status.snapshot_unreadable");
+        return;
+    };
+
+    // The root stays the same node, so undo and the view keep working
+    app.push_history();
+    let children: Vec<_> = root_id.children(&app.tree).collect();
+    for child in children {
+        child.remove_subtree(&mut app.tree);
+    }
+    app.tree[root_id].get_mut().title = snapshot[snapshot_root].get().title.clone();
+    for child in snapshot_root.children(&snapshot).collect::<Vec<_>>() {
+        copy_subtree(&mut app.tree, &snapshot, child, root_id);
+    }
+
+    app.active_node_id = Some(root_id);
+    app.search_results.clear();
+    app.search_fold = None;
+    app.edit_times.clear();
+    app.load_pins();
+    app.mode = AppMode::Normal;
+    app.is_dirty = true;
+    app.last_modify_time = Some(Instant::now());
+    app.notify_with("status.snapshot_restored", &[("time", &time)]);
+}
+
+pub fn close_snapshots(app: &mut AppState) {
+    app.mode = AppMode::Normal;
+    app.mark_damage(Damage::View);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::snapshots::SnapshotStore;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_restore_snapshot_is_undoable() {
+        let dir = TempDir::new().unwrap();
+        let map = dir.path().join("plan.hmm");
+        fs::write(&map, "Plan\n\tOld idea\n").unwrap();
+
+        let mut app = AppState::new(AppConfig::default());
+        app.snapshot_store = SnapshotStore::new(dir.path().join("snapshots"));
+        super::super::open_file(&mut app, &map).unwrap();
+        take_snapshot_if_due(&mut app);
+
+        // Nothing is due again until the interval passed
+        let root = app.root_id.unwrap();
+        app.tree[root].get_mut().title = "Plan v2".to_string();
+        take_snapshot_if_due(&mut app);
+        assert_eq!(app.snapshot_store.list(&map).len(), 1);
+
+        open_snapshots(&mut app);
+        assert!(matches!(app.mode, AppMode::Snapshots { selected: 0, .. }));
+        snapshot_older(&mut app);
+        assert!(matches!(app.mode, AppMode::Snapshots { selected: 0, .. }));
+
+        restore_snapshot(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(parser::map_to_hmm(&app.tree, root), "Plan\n\tOld idea\n");
+        assert!(app.is_dirty);
+
+        app.undo();
+        assert_eq!(app.tree[root].get().title, "Plan v2");
+    }
+
+    #[test]
+    fn test_no_snapshots_without_a_file() {
+        let mut app = AppState::new(AppConfig::default());
+        open_snapshots(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(app.message.as_deref(), Some("No snapshots of this map yet"));
+    }
+}
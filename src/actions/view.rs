@@ -1,29 +1,35 @@
-use crate::app::AppState;
-use crate::layout::LayoutEngine;
+use crate::app::{AppState, Damage};
 use crate::model::{Node, NodeId};
 use indextree::Arena;
 
+use super::on_off;
+
 pub fn toggle_collapse(app: &mut AppState) {
+    app.push_view_history();
     if let Some(active_id) = app.active_node_id {
         if let Some(node) = app.tree.get_mut(active_id) {
             node.get_mut().is_collapsed = !node.get().is_collapsed;
         }
+        apply_accordion(app);
     }
 }
 
 pub fn collapse_all(app: &mut AppState) {
+    app.push_view_history();
     for node in app.tree.iter_mut() {
         node.get_mut().is_collapsed = true;
     }
 }
 
 pub fn expand_all(app: &mut AppState) {
+    app.push_view_history();
     for node in app.tree.iter_mut() {
         node.get_mut().is_collapsed = false;
     }
 }
 
 pub fn collapse_children(app: &mut AppState) {
+    app.push_view_history();
     if let Some(active_id) = app.active_node_id {
         let children: Vec<NodeId> = active_id.children(&app.tree).collect();
         for child_id in children {
@@ -35,6 +41,7 @@ pub fn collapse_children(app: &mut AppState) {
 }
 
 pub fn collapse_other_branches(app: &mut AppState) {
+    app.push_view_history();
     if let Some(active_id) = app.active_node_id {
         // Collapse all nodes
         for node in app.tree.iter_mut() {
@@ -52,31 +59,24 @@ pub fn collapse_other_branches(app: &mut AppState) {
 }
 
 pub fn collapse_to_level(app: &mut AppState, target_level: usize) {
-    fn set_collapse_at_depth(
-        tree: &mut Arena<Node>,
-        node_id: NodeId,
-        current_level: usize,
-        target_level: usize,
-    ) {
-        if let Some(node) = tree.get_mut(node_id) {
-            node.get_mut().is_collapsed = current_level >= target_level;
-        }
+    app.push_view_history();
+    let Some(root_id) = app.root_id else {
+        return;
+    };
 
-        let children: Vec<NodeId> = node_id.children(tree).collect();
-        for child_id in children {
-            set_collapse_at_depth(tree, child_id, current_level + 1, target_level);
+    let mut stack = vec![(root_id, 0)];
+    while let Some((node_id, level)) = stack.pop() {
+        if let Some(node) = app.tree.get_mut(node_id) {
+            node.get_mut().is_collapsed = level >= target_level;
         }
-    }
-
-    if let Some(root_id) = app.root_id {
-        set_collapse_at_depth(&mut app.tree, root_id, 0, target_level);
+        stack.extend(node_id.children(&app.tree).map(|child| (child, level + 1)));
     }
 }
 
 pub fn center_active_node(app: &mut AppState) {
     if let Some(active_id) = app.active_node_id {
         // Get the layout to find the active node's position
-        let layout = LayoutEngine::calculate_layout(app);
+        let layout = app.layout();
 
         if let Some(node_layout) = layout.nodes.get(&active_id) {
             // Calculate center position
@@ -91,73 +91,125 @@ pub fn center_active_node(app: &mut AppState) {
     }
 }
 
+/// Scroll vertically so the active node sits on the first row of the map (vim's `zt`)
+pub fn scroll_active_to_top(app: &mut AppState) {
+    if let Some((top, _)) = active_node_rows(app) {
+        app.viewport_top = top;
+    }
+}
+
+/// Scroll vertically so the active node sits in the middle of the map (vim's `zz`)
+pub fn scroll_active_to_center(app: &mut AppState) {
+    if let Some((top, bottom)) = active_node_rows(app) {
+        let map_height = map_area_height(app);
+        app.viewport_top = ((top + bottom) / 2.0 - map_height / 2.0).round();
+    }
+}
+
+/// Scroll vertically so the active node sits on the last row of the map (vim's `zb`)
+pub fn scroll_active_to_bottom(app: &mut AppState) {
+    if let Some((_, bottom)) = active_node_rows(app) {
+        app.viewport_top = bottom - map_area_height(app);
+    }
+}
+
+/// Move the map along with the mouse dragging it. Only the viewport moves,
+/// so the cached layout is drawn again without being recomputed.
+pub fn pan_view(app: &mut AppState, columns: i32, rows: i32) {
+    app.viewport_left -= columns as f64;
+    app.viewport_top -= rows as f64;
+}
+
+/// Top and bottom rows of the active node's text in map coordinates
+fn active_node_rows(app: &mut AppState) -> Option<(f64, f64)> {
+    let active_id = app.active_node_id?;
+    let layout = app.layout();
+    let node_layout = layout.nodes.get(&active_id)?;
+    let top = node_layout.y + node_layout.yo;
+    Some((top, top + node_layout.lh))
+}
+
+/// Rows available to the map, excluding the status line and the pin bar
+pub(super) fn map_area_height(app: &AppState) -> f64 {
+    let pin_bar = u16::from(!app.pinned_nodes().is_empty());
+    app.terminal_height.saturating_sub(1 + pin_bar) as f64
+}
+
 pub fn toggle_center_lock(app: &mut AppState) {
     app.config.center_lock = !app.config.center_lock;
-    app.set_message(format!(
-        "Center lock: {}",
-        if app.config.center_lock { "ON" } else { "OFF" }
-    ));
+    let state = on_off(app, app.config.center_lock);
+    app.notify_with("status.center_lock", &[("state", &state)]);
 }
 
 pub fn focus(app: &mut AppState) {
+    app.push_view_history();
     if let Some(active_id) = app.active_node_id {
         // Focus mode: collapse all except ancestors and descendants of active node
         // This matches the PHP implementation's focus_vh function
 
         // Collapse siblings recursively up the tree
-        collapse_siblings_recursive(&mut app.tree, active_id);
+        collapse_siblings_up(&mut app.tree, active_id);
 
         // Expand all descendants of the active node
         expand_descendants(&mut app.tree, active_id);
+        app.mark_damage(Damage::Layout);
 
-        app.set_message("Focus mode applied");
+        app.notify("status.focus_applied");
     }
 }
 
 pub fn toggle_focus_lock(app: &mut AppState) {
     app.config.focus_lock = !app.config.focus_lock;
-    app.set_message(format!(
-        "Focus lock: {}",
-        if app.config.focus_lock { "ON" } else { "OFF" }
-    ));
+    let state = on_off(app, app.config.focus_lock);
+    app.notify_with("status.focus_lock", &[("state", &state)]);
 }
 
-/// Helper function to recursively collapse all siblings of a node up the tree
-fn collapse_siblings_recursive(tree: &mut Arena<Node>, node_id: NodeId) {
-    // Get the parent of the current node
-    let parent_id = match tree.get(node_id) {
-        Some(node_ref) => match node_ref.parent() {
-            Some(parent) => parent,
-            None => return, // Root node, no parent
-        },
-        None => return,
-    };
+pub fn toggle_accordion(app: &mut AppState) {
+    app.config.accordion = !app.config.accordion;
+    let state = on_off(app, app.config.accordion);
+    app.notify_with("status.accordion", &[("state", &state)]);
+    apply_accordion(app);
+}
 
-    // Collapse all siblings (children of parent except current node)
-    let children: Vec<NodeId> = parent_id.children(tree).collect();
-    for child_id in children {
-        if child_id != node_id {
-            if let Some(child_node) = tree.get_mut(child_id) {
-                child_node.get_mut().is_collapsed = true;
-            }
+/// In accordion mode, fold every branch beside the path to the active node so
+/// only one branch per level stays open
+pub fn apply_accordion(app: &mut AppState) {
+    if !app.config.accordion {
+        return;
+    }
+    if let Some(active_id) = app.active_node_id {
+        if collapse_siblings_up(&mut app.tree, active_id) {
+            app.mark_damage(Damage::Layout);
         }
     }
-
-    // Recursively apply to parent
-    collapse_siblings_recursive(tree, parent_id);
 }
 
-/// Helper function to recursively expand all descendants of a node
-fn expand_descendants(tree: &mut Arena<Node>, node_id: NodeId) {
-    // Expand the current node
-    if let Some(node) = tree.get_mut(node_id) {
-        node.get_mut().is_collapsed = false;
+/// Collapse the siblings of a node and of each of its ancestors, returning
+/// whether any of them was expanded
+fn collapse_siblings_up(tree: &mut Arena<Node>, node_id: NodeId) -> bool {
+    let mut changed = false;
+    let path: Vec<NodeId> = node_id.ancestors(tree).collect();
+    for (&node_id, &parent_id) in path.iter().zip(path.iter().skip(1)) {
+        let children: Vec<NodeId> = parent_id.children(tree).collect();
+        for child_id in children {
+            if child_id != node_id {
+                if let Some(child_node) = tree.get_mut(child_id) {
+                    changed |= !child_node.get().is_collapsed;
+                    child_node.get_mut().is_collapsed = true;
+                }
+            }
+        }
     }
+    changed
+}
 
-    // Recursively expand all children
-    let children: Vec<NodeId> = node_id.children(tree).collect();
-    for child_id in children {
-        expand_descendants(tree, child_id);
+/// Expand a node and all of its descendants
+fn expand_descendants(tree: &mut Arena<Node>, node_id: NodeId) {
+    let descendants: Vec<NodeId> = node_id.descendants(tree).collect();
+    for descendant_id in descendants {
+        if let Some(node) = tree.get_mut(descendant_id) {
+            node.get_mut().is_collapsed = false;
+        }
     }
 }
 
@@ -165,6 +217,7 @@ fn expand_descendants(tree: &mut Arena<Node>, node_id: NodeId) {
 mod tests {
     use super::*;
     use crate::config::AppConfig;
+    use crate::layout::LayoutEngine;
     use crate::model::Node;
 
     fn create_test_app() -> AppState {
@@ -270,6 +323,37 @@ mod tests {
         assert!(app.tree.get(child2).unwrap().get().is_collapsed);
     }
 
+    #[test]
+    fn test_view_changes_undo_as_one_step() {
+        let collapsed = |app: &AppState| -> Vec<bool> {
+            app.tree
+                .iter()
+                .map(|node| node.get().is_collapsed)
+                .collect()
+        };
+        let mut app = create_test_app();
+        app.push_history();
+
+        // Not recorded by default
+        collapse_all(&mut app);
+        assert_eq!(app.history.len(), 1);
+
+        app.config.undo_view_changes = true;
+        let before = collapsed(&app);
+        expand_all(&mut app);
+        collapse_to_level(&mut app, 1);
+        toggle_collapse(&mut app);
+        assert_eq!(app.history.len(), 2);
+
+        assert!(app.undo());
+        assert_eq!(collapsed(&app), before);
+
+        // An edit starts a new step
+        app.push_history();
+        collapse_all(&mut app);
+        assert_eq!(app.history.len(), 3);
+    }
+
     #[test]
     fn test_toggle_settings() {
         let mut app = create_test_app();
@@ -330,6 +414,33 @@ mod tests {
         assert!(app.viewport_top < 0.0 || app.viewport_left < 0.0);
     }
 
+    #[test]
+    fn test_scroll_active_to_top_center_bottom() {
+        let mut app = create_test_app();
+        app.terminal_width = 80;
+        app.terminal_height = 21;
+
+        let root = app.root_id.unwrap();
+        let child2 = root.children(&app.tree).nth(1).unwrap();
+        app.active_node_id = Some(child2);
+
+        let layout = LayoutEngine::calculate_layout(&app);
+        let node = layout.nodes.get(&child2).unwrap();
+        let top = node.y + node.yo;
+
+        scroll_active_to_top(&mut app);
+        assert_eq!(app.viewport_top, top);
+
+        scroll_active_to_bottom(&mut app);
+        assert_eq!(app.viewport_top, top + node.lh - 20.0);
+
+        scroll_active_to_center(&mut app);
+        assert_eq!(app.viewport_top, (top + node.lh / 2.0 - 10.0).round());
+
+        // Horizontal scroll is left untouched
+        assert_eq!(app.viewport_left, 0.0);
+    }
+
     #[test]
     fn test_focus_mode() {
         let mut app = create_test_app();
@@ -388,7 +499,32 @@ mod tests {
     }
 
     #[test]
-    fn test_helper_collapse_siblings_recursive() {
+    fn test_accordion_mode() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let children: Vec<_> = root.children(&app.tree).collect();
+        let (child1, child2) = (children[0], children[1]);
+
+        // Off by default: entering a branch leaves its siblings alone
+        app.active_node_id = Some(child1);
+        crate::actions::ensure_node_visible(&mut app);
+        assert!(!app.tree[child2].get().is_collapsed);
+
+        toggle_accordion(&mut app);
+        assert!(app.config.accordion);
+        assert!(app.tree[child2].get().is_collapsed);
+
+        // Expanding a branch folds the one that was open beside it
+        app.tree[child1].get_mut().is_collapsed = false;
+        app.active_node_id = Some(child2);
+        toggle_collapse(&mut app);
+        assert!(!app.tree[child2].get().is_collapsed);
+        assert!(app.tree[child1].get().is_collapsed);
+        assert!(!app.tree[root].get().is_collapsed);
+    }
+
+    #[test]
+    fn test_helper_collapse_siblings_up() {
         let mut app = create_test_app();
         let root = app.root_id.unwrap();
 
@@ -401,7 +537,7 @@ mod tests {
         expand_all(&mut app);
 
         // Call helper on child2
-        collapse_siblings_recursive(&mut app.tree, child2);
+        collapse_siblings_up(&mut app.tree, child2);
 
         // Child1 should be collapsed (sibling)
         assert!(app.tree.get(child1).unwrap().get().is_collapsed);
@@ -0,0 +1,86 @@
+use crate::app::{AppState, PickerKind};
+use crate::workspace::list_maps;
+
+use super::picker::open_picker;
+
+pub fn open_workspace(app: &mut AppState) {
+    if app.is_dirty {
+        app.notify("status.unsaved_before_open");
+        return;
+    }
+
+    let Some(dir) = app.config.workspace.clone() else {
+        app.notify("status.no_workspace");
+        return;
+    };
+
+    let maps = list_maps(&dir);
+    if maps.is_empty() {
+        let dir = dir.display();
+        app.notify_with("status.workspace_empty", &[("dir", &dir)]);
+        return;
+    }
+
+    open_picker(app, PickerKind::Workspace, maps);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::{confirm_picker, picker_matches, type_picker_char};
+    use crate::app::AppMode;
+    use crate::config::AppConfig;
+    use crate::model::Node;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn create_test_app() -> AppState {
+        let config = AppConfig::default();
+        let mut app = AppState::new(config);
+        let root = app.tree.new_node(Node::new("Scratch".to_string()));
+        app.root_id = Some(root);
+        app.active_node_id = Some(root);
+        app
+    }
+
+    #[test]
+    fn test_open_workspace_requires_directory() {
+        let mut app = create_test_app();
+        open_workspace(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert!(app.message.unwrap().starts_with("No workspace directory"));
+    }
+
+    #[test]
+    fn test_workspace_search_matches_relative_paths() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("work")).unwrap();
+        fs::write(dir.path().join("work/roadmap.hmm"), "Roadmap").unwrap();
+        fs::write(dir.path().join("recipes.hmm"), "Recipes").unwrap();
+
+        let mut app = create_test_app();
+        app.config.workspace = Some(dir.path().to_path_buf());
+        open_workspace(&mut app);
+        assert!(matches!(
+            app.mode,
+            AppMode::Picker {
+                kind: PickerKind::Workspace,
+                ..
+            }
+        ));
+        assert_eq!(picker_matches(&app).len(), 2);
+
+        // The workspace directory itself is not part of what is matched
+        for c in "wrm".chars() {
+            type_picker_char(&mut app, c);
+        }
+        let matches = picker_matches(&app);
+        assert_eq!(matches.len(), 1);
+        assert!(matches[0].ends_with("work/roadmap.hmm"));
+
+        confirm_picker(&mut app);
+        assert_eq!(app.mode, AppMode::Normal);
+        assert_eq!(app.tree[app.root_id.unwrap()].get().title, "Roadmap");
+        assert!(app.picker_items.is_empty());
+    }
+}
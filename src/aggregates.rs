@@ -0,0 +1,163 @@
+//! Figures about the whole map that take a walk over every node: how many
+//! nodes sit below each one, how many words the titles hold and how many
+//! titles repeat a sibling's.
+//!
+//! The editor keeps them from one change of the map to the next. The event
+//! loop works them out over its idle ticks after a change, a slice of the map
+//! at a time, so the stats overlay, the size heatmap and the placeholders of
+//! collapsed branches in exports need not stall on a huge map.
+
+use crate::model::{Node, NodeId};
+use indextree::Arena;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aggregates {
+    /// Nodes below each node, not counting the node itself
+    pub descendants: HashMap<NodeId, usize>,
+    /// Nodes below each node that an export shows when it is unfolded,
+    /// leaving out hidden nodes and comments with everything under them
+    pub exported: HashMap<NodeId, usize>,
+    /// Title prefix of the comments left out of `exported`
+    pub comment_prefix: Option<String>,
+    /// Words in the titles of all nodes
+    pub words: usize,
+    /// Nodes titled like an earlier sibling
+    pub duplicates: usize,
+}
+
+impl Aggregates {
+    /// Walk the map below `root_id` at once
+    pub fn compute(tree: &Arena<Node>, root_id: NodeId, comment_prefix: Option<&str>) -> Self {
+        let mut builder = AggregatesBuilder::new(root_id, comment_prefix.map(str::to_string));
+        loop {
+            if let Some(aggregates) = builder.step(tree, usize::MAX) {
+                return aggregates;
+            }
+        }
+    }
+
+    /// Nodes in the map, the root included
+    pub fn nodes(&self) -> usize {
+        self.descendants.len()
+    }
+}
+
+/// A walk working out [`Aggregates`] that stops after a number of nodes and
+/// picks up where it left off. The tree must not change in between.
+#[derive(Debug, Clone)]
+pub struct AggregatesBuilder {
+    aggregates: Aggregates,
+    /// Nodes walked in pre-order, each with its parent's position and
+    /// whether an export of the parent shows it
+    order: Vec<(NodeId, Option<usize>, bool)>,
+    /// Nodes still to walk, each with its parent's position
+    stack: Vec<(NodeId, Option<usize>)>,
+}
+
+impl AggregatesBuilder {
+    pub fn new(root_id: NodeId, comment_prefix: Option<String>) -> Self {
+        Self {
+            aggregates: Aggregates {
+                comment_prefix,
+                ..Aggregates::default()
+            },
+            order: Vec::new(),
+            stack: vec![(root_id, None)],
+        }
+    }
+
+    /// Walk up to `budget` more nodes, returning the aggregates once the
+    /// whole map has been walked
+    pub fn step(&mut self, tree: &Arena<Node>, budget: usize) -> Option<Aggregates> {
+        let aggregates = &mut self.aggregates;
+        for _ in 0..budget {
+            let Some((node_id, parent)) = self.stack.pop() else {
+                break;
+            };
+            let node = tree[node_id].get();
+            let excluded = aggregates
+                .comment_prefix
+                .as_deref()
+                .is_some_and(|prefix| node.is_comment(prefix));
+            let index = self.order.len();
+            self.order
+                .push((node_id, parent, !node.is_hidden() && !excluded));
+            aggregates.words += node.title.split_whitespace().count();
+
+            let mut titles = HashSet::new();
+            for child in node_id.children(tree) {
+                if !titles.insert(tree[child].get().title.as_str()) {
+                    aggregates.duplicates += 1;
+                }
+                self.stack.push((child, Some(index)));
+            }
+        }
+        if !self.stack.is_empty() {
+            return None;
+        }
+
+        // Counts summed bottom-up, children coming after their parent
+        let mut counts = vec![0; self.order.len()];
+        let mut exported = vec![0; self.order.len()];
+        for index in (1..self.order.len()).rev() {
+            let (_, parent, shown) = self.order[index];
+            if let Some(parent) = parent {
+                counts[parent] += counts[index] + 1;
+                if shown {
+                    exported[parent] += exported[index] + 1;
+                }
+            }
+        }
+        let ids = || self.order.iter().map(|&(id, _, _)| id);
+        aggregates.descendants = ids().zip(counts).collect();
+        aggregates.exported = ids().zip(exported).collect();
+        self.order.clear();
+        Some(std::mem::take(aggregates))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_hmm_content;
+
+    #[test]
+    fn test_compute_aggregates() {
+        let (tree, root) =
+            parse_hmm_content("Plan the trip\n\tBook\n\t\tTrain\n\t\tHotel\n\tBook\n\tPack bags\n")
+                .unwrap();
+        let aggregates = Aggregates::compute(&tree, root, None);
+        let book = root.children(&tree).next().unwrap();
+
+        assert_eq!(aggregates.nodes(), 6);
+        assert_eq!(aggregates.descendants[&root], 5);
+        assert_eq!(aggregates.descendants[&book], 2);
+        assert_eq!(aggregates.words, 9);
+        assert_eq!(aggregates.duplicates, 1);
+    }
+
+    #[test]
+    fn test_build_in_steps() {
+        let (tree, root) = parse_hmm_content(
+            "Plan the trip\n\tBook\n\t\t[HIDDEN] Train\n\t\tHotel\n\t// Ask Ann\n\t\tBy phone\n\tPack bags\n",
+        )
+        .unwrap();
+        let mut builder = AggregatesBuilder::new(root, Some("//".to_string()));
+        let mut steps = 1;
+        let aggregates = loop {
+            match builder.step(&tree, 2) {
+                Some(aggregates) => break aggregates,
+                None => steps += 1,
+            }
+        };
+        let book = root.children(&tree).next().unwrap();
+
+        assert_eq!(steps, 4);
+        assert_eq!(aggregates, Aggregates::compute(&tree, root, Some("//")));
+        assert_eq!(aggregates.descendants[&root], 6);
+        // The hidden train, the comment and the node under it are not exported
+        assert_eq!(aggregates.exported[&root], 3);
+        assert_eq!(aggregates.exported[&book], 1);
+    }
+}
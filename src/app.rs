@@ -1,16 +1,161 @@
-use crate::config::AppConfig;
-use crate::model::{Node, NodeId};
+use crate::actions::Action;
+use crate::aggregates::{Aggregates, AggregatesBuilder};
+use crate::config::{AppConfig, HeatmapMode, PickOutput};
+use crate::export::ExportOptions;
+use crate::history::{History, ViewState};
+use crate::i18n::Catalog;
+use crate::keymap::{Key, Keymap};
+use crate::kind::shown_title;
+use crate::layout::LayoutEngine;
+use crate::link::{same_file, LinkIndex};
+use crate::lock::ReadOnly;
+use crate::model::{Node, NodeId, NEW_MAP_TITLE};
+use crate::parser::MapFormat;
+use crate::pins::PinStore;
+use crate::recent::RecentFiles;
+use crate::search_index::SearchIndex;
+use crate::snapshots::{Snapshot, SnapshotStore};
+use crate::workspace::expand_home;
 use indextree::Arena;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
 use std::path::PathBuf;
+use std::rc::Rc;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AppMode {
     Normal,
-    Editing { buffer: String, cursor_pos: usize },
-    Search { query: String },
+    Editing {
+        buffer: String,
+        cursor_pos: usize,
+    },
+    Search {
+        query: String,
+    },
     Help,
+    Picker {
+        kind: PickerKind,
+        query: String,
+        selected: usize,
+    },
+    /// Asking what to do with unsaved changes before quitting
+    QuitPrompt {
+        selected: QuitChoice,
+    },
+    /// Typing the path to save the map under, in `format` or else the one
+    /// its extension names
+    SaveAs {
+        buffer: String,
+        quit_after: bool,
+        format: Option<MapFormat>,
+    },
+    /// Typing the path to move the map's file to
+    RenameFile {
+        buffer: String,
+    },
+    /// Typing a line to add to the inbox node
+    Capture {
+        buffer: String,
+    },
+    /// Choosing the format to save the map in
+    FormatPrompt {
+        selected: MapFormat,
+    },
+    /// The settings overlay, with the text of the setting being edited and
+    /// the settings changed since it opened
+    Settings {
+        selected: usize,
+        buffer: Option<String>,
+        changed: Vec<String>,
+    },
+    /// Asking whether pasted nodes titled like nodes already there should be
+    /// merged into them, with an outline of the merge result
+    PasteMerge {
+        as_siblings: bool,
+        duplicates: usize,
+        preview: Vec<String>,
+    },
+    /// Browsing the snapshots of the map, newest first, with the `.hmm` text
+    /// of the selected one shown beside the current map
+    Snapshots {
+        snapshots: Vec<Snapshot>,
+        selected: usize,
+        preview: String,
+    },
+    /// The map beside another version of it in `.hmm` text, a snapshot, file
+    /// or git commit named by `label`, with the differences highlighted
+    Compare {
+        label: String,
+        other: String,
+        /// Added, removed and changed nodes
+        counts: (usize, usize, usize),
+    },
+    /// Asking whether to run the command of the active node
+    ConfirmRun {
+        command: String,
+    },
+    /// Asking whether to rewrite the `count` links to a retitled node of
+    /// `map` found in the other maps `maps`
+    ConfirmLinks {
+        map: PathBuf,
+        from: String,
+        to: String,
+        maps: Vec<PathBuf>,
+        count: usize,
+    },
+    /// Typing a `pattern/replacement` substitution for the titles of the
+    /// active subtree, with how many it would change once it parses
+    Rename {
+        buffer: String,
+        matches: Option<usize>,
+    },
 }
 
+/// Answers offered when quitting with unsaved changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuitChoice {
+    Save,
+    Discard,
+    Cancel,
+}
+
+impl QuitChoice {
+    /// Choices in the order the prompt shows them
+    pub const ALL: [QuitChoice; 3] = [QuitChoice::Save, QuitChoice::Discard, QuitChoice::Cancel];
+}
+
+/// Which list of maps the file picker offers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PickerKind {
+    Recent,
+    Workspace,
+    /// The configured export templates, listed by name
+    ExportTemplate,
+    /// The configured export pipes, listed by name
+    ExportPipe,
+}
+
+/// How much of the screen a change invalidates, from least to most
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum Damage {
+    /// Nothing changed; the last frame is still correct
+    #[default]
+    None,
+    /// Selection, viewport, mode or status line changed; node positions did not
+    View,
+    /// Titles, structure, folding or layout settings changed
+    Layout,
+}
+
+/// Undo steps kept at most once the map has grown past `large_map_nodes`,
+/// as each one is a copy of the whole map
+const LARGE_MAP_UNDO_STEPS: usize = 8;
+
+/// Nodes walked per idle tick to bring the aggregates and the search index
+/// up to date, a few milliseconds' work
+const PRECOMPUTE_BUDGET: usize = 10_000;
+
 pub struct AppState {
     pub running: bool,
     pub mode: AppMode,
@@ -18,7 +163,14 @@ pub struct AppState {
     pub root_id: Option<NodeId>,
     pub active_node_id: Option<NodeId>,
     pub config: AppConfig,
+    /// Config file the settings overlay writes to
+    pub config_path: Option<PathBuf>,
+    pub keymap: Keymap,
+    pub catalog: Catalog,
     pub filename: Option<PathBuf>,
+    pub format: MapFormat,
+    /// Why the map cannot be edited, if it cannot
+    pub read_only: Option<ReadOnly>,
 
     // Viewport state
     pub viewport_top: f64,
@@ -27,15 +179,70 @@ pub struct AppState {
     pub terminal_height: u16,
 
     // Undo/Redo history
-    pub history: Vec<Arena<Node>>,
+    pub history: History,
     pub history_index: usize,
+    /// Repeatable edit the newest history entry was taken for, and the node it
+    /// edited; repeating it straight away shares that entry
+    pub history_group: Option<(Action, NodeId)>,
+    /// Whether the newest history entry was taken for view changes, which
+    /// further ones join until the next edit
+    pub view_history_group: bool,
+    /// Transactions begun and not yet committed; edits inside them share the
+    /// history entry taken when the outermost one began
+    pub transaction_depth: usize,
+    /// Title of the last confirmed edit, which repeat_insert inserts again
+    pub last_edit_text: Option<String>,
+    /// Screen cell the mouse was last dragged over while the left button is
+    /// held, or none when no drag is in progress
+    pub drag_from: Option<(u16, u16)>,
+    /// A throwaway map from `--scratch`: never auto-saved, and quitting
+    /// doesn't ask to save it
+    pub scratch: bool,
+    /// Set by `--pick`: Enter chooses the active node and quits
+    pub pick: Option<PickOutput>,
+    /// Nodes ticked with space while picking, to be picked together
+    pub pick_selection: Vec<NodeId>,
+    /// Text of the nodes chosen with `--pick`, printed once the editor quits
+    pub picked: Option<String>,
+    /// Whether the map has grown past `large_map_nodes` and the lighter
+    /// settings were switched to
+    pub large_map: bool,
 
     // Message for status line
     pub message: Option<String>,
 
+    // Destructive action waiting for its key to be pressed again
+    pub pending_confirm: Option<Action>,
+
+    // Start of a key sequence typed so far, and when its last key came in
+    pub pending_keys: Vec<Key>,
+    pub pending_keys_at: Option<std::time::Instant>,
+
     // Search state
     pub search_results: Vec<NodeId>,
     pub search_index: usize,
+    pub text_index: SearchIndex,
+    // Whether each node was collapsed before the map was folded to the
+    // search results, to put back when the search is cleared
+    pub search_fold: Option<Vec<(NodeId, bool)>>,
+
+    // Recently opened maps
+    pub recent: RecentFiles,
+
+    // Pinned nodes of the open map, and of every map for the next session
+    pub pins: Vec<NodeId>,
+    pub pin_store: PinStore,
+
+    // Periodic snapshots of the open map, and when the last one was due
+    pub snapshot_store: SnapshotStore,
+    pub last_snapshot_time: Option<std::time::Instant>,
+
+    // Maps offered by the open file picker
+    pub picker_items: Vec<PathBuf>,
+
+    // Anchors of the links in the workspace maps, to find links to a
+    // retitled node
+    pub link_index: LinkIndex,
 
     // Clipboard
     pub clipboard: Option<String>,
@@ -43,6 +250,15 @@ pub struct AppState {
     // Track unsaved changes
     pub is_dirty: bool,
 
+    // When nodes were last edited this session, for the activity heatmap
+    pub edit_times: HashMap<NodeId, std::time::Instant>,
+
+    // Pending redraw, and the layout and aggregates it can reuse
+    pub damage: Damage,
+    layout_cache: Option<Rc<LayoutEngine>>,
+    aggregates_cache: Option<Rc<Aggregates>>,
+    aggregates_builder: Option<AggregatesBuilder>,
+
     // Auto-save tracking
     pub last_save_time: Option<std::time::Instant>,
     pub last_modify_time: Option<std::time::Instant>,
@@ -51,6 +267,12 @@ pub struct AppState {
 impl AppState {
     pub fn new(config: AppConfig) -> Self {
         let tree = Arena::new();
+        let keymap = Keymap::new(&config.keys, &config.leader).unwrap_or_default();
+        let catalog = config
+            .language
+            .as_deref()
+            .map(Catalog::new)
+            .unwrap_or_default();
 
         Self {
             running: true,
@@ -59,42 +281,274 @@ impl AppState {
             root_id: None,
             active_node_id: None,
             config,
+            config_path: None,
+            keymap,
+            catalog,
             filename: None,
+            format: MapFormat::default(),
+            read_only: None,
             viewport_top: 0.0,
             viewport_left: 0.0,
             terminal_width: 80,
             terminal_height: 24,
-            history: Vec::new(),
+            history: History::new(),
             history_index: 0,
             message: None,
+            pending_confirm: None,
+            pending_keys: Vec::new(),
+            pending_keys_at: None,
+            history_group: None,
+            view_history_group: false,
+            transaction_depth: 0,
+            large_map: false,
+            last_edit_text: None,
+            drag_from: None,
+            scratch: false,
+            pick: None,
+            pick_selection: Vec::new(),
+            picked: None,
             search_results: Vec::new(),
             search_index: 0,
+            text_index: SearchIndex::new(),
+            search_fold: None,
+            recent: RecentFiles::default(),
+            pins: Vec::new(),
+            pin_store: PinStore::default(),
+            snapshot_store: SnapshotStore::default(),
+            last_snapshot_time: None,
+            picker_items: Vec::new(),
+            link_index: LinkIndex::default(),
             clipboard: None,
             is_dirty: false,
+            edit_times: HashMap::new(),
+            damage: Damage::Layout,
+            layout_cache: None,
+            aggregates_cache: None,
+            aggregates_builder: None,
             last_save_time: None,
             last_modify_time: None,
         }
     }
 
+    /// Record that the next frame must be redrawn; layout damage also drops
+    /// the cached layout
+    pub fn mark_damage(&mut self, damage: Damage) {
+        self.damage = self.damage.max(damage);
+        if damage == Damage::Layout {
+            self.layout_cache = None;
+        }
+    }
+
+    /// Record that the tree itself changed, dropping the aggregates and
+    /// putting the search index out of date besides the layout. Layout
+    /// damage alone, such as typing in the edit buffer, keeps them.
+    pub fn mark_map_changed(&mut self) {
+        self.aggregates_cache = None;
+        self.aggregates_builder = None;
+        self.text_index.invalidate();
+        self.mark_damage(Damage::Layout);
+    }
+
+    /// Damage accumulated since the last call, resetting it to `Damage::None`
+    pub fn take_damage(&mut self) -> Damage {
+        std::mem::take(&mut self.damage)
+    }
+
+    /// Layout of the current map, recomputed only after layout damage
+    pub fn layout(&mut self) -> Rc<LayoutEngine> {
+        if let Some(ref layout) = self.layout_cache {
+            return Rc::clone(layout);
+        }
+        let layout = Rc::new(LayoutEngine::calculate_layout(self));
+        self.layout_cache = Some(Rc::clone(&layout));
+        layout
+    }
+
+    /// Figures about the whole map, worked out again only after it changed
+    pub fn aggregates(&mut self) -> Option<Rc<Aggregates>> {
+        if let Some(ref aggregates) = self.aggregates_cache {
+            return Some(Rc::clone(aggregates));
+        }
+        let mut builder = self
+            .aggregates_builder
+            .take()
+            .or_else(|| self.new_aggregates_builder())?;
+        let aggregates = Rc::new(builder.step(&self.tree, usize::MAX)?);
+        self.aggregates_cache = Some(Rc::clone(&aggregates));
+        Some(aggregates)
+    }
+
+    /// The aggregates if they are already worked out for the current map
+    pub fn cached_aggregates(&self) -> Option<&Aggregates> {
+        self.aggregates_cache.as_deref()
+    }
+
+    /// Export options from the config, counting what collapsed branches fold
+    /// away with the aggregates when they are at hand
+    pub fn export_options(&self) -> ExportOptions {
+        ExportOptions {
+            folded_counts: self.aggregates_cache.clone(),
+            ..ExportOptions::from_config(&self.config)
+        }
+    }
+
+    fn new_aggregates_builder(&self) -> Option<AggregatesBuilder> {
+        let comment_prefix = ExportOptions::from_config(&self.config).comment_prefix;
+        Some(AggregatesBuilder::new(self.root_id?, comment_prefix))
+    }
+
+    /// Bring what the map changed since the last idle tick up to date, the
+    /// aggregates and then the search index, `PRECOMPUTE_BUDGET` nodes per
+    /// tick so that keys pressed meanwhile are not held up
+    pub fn precompute(&mut self) {
+        if self.aggregates_cache.is_none() {
+            let Some(mut builder) = self
+                .aggregates_builder
+                .take()
+                .or_else(|| self.new_aggregates_builder())
+            else {
+                return;
+            };
+            match builder.step(&self.tree, PRECOMPUTE_BUDGET) {
+                Some(aggregates) => self.aggregates_cache = Some(Rc::new(aggregates)),
+                None => self.aggregates_builder = Some(builder),
+            }
+            return;
+        }
+        self.text_index.refresh_some(&self.tree, PRECOMPUTE_BUDGET);
+    }
+
     pub fn push_history(&mut self) {
+        // History is pushed right before the tree is edited
+        self.mark_map_changed();
+        if self.transaction_depth > 0 {
+            return;
+        }
+        self.history_group = None;
+        self.view_history_group = false;
+
         // Remove any redo history
         self.history.truncate(self.history_index);
 
         // Add current state to history
-        self.history.push(self.tree.clone());
+        let view = self.view_state();
+        self.history.push(self.tree.clone(), view);
         self.history_index += 1;
 
-        // Limit history size
-        if self.history.len() > self.config.max_undo_steps {
-            self.history.remove(0);
+        // Limit history size by steps and by memory, always keeping the newest
+        let max_memory = self.config.max_undo_memory.saturating_mul(1024 * 1024);
+        while self.history.len() > 1
+            && (self.history.len() > self.config.max_undo_steps
+                || self.history.memory_usage() > max_memory)
+        {
+            self.history.remove_oldest();
             self.history_index -= 1;
         }
     }
 
+    /// Start grouping edits into one: the tree is recorded for undo once, and
+    /// the map is marked edited and laid out again once, when the outermost
+    /// transaction is committed. Transactions nest.
+    pub fn begin_transaction(&mut self) {
+        if self.transaction_depth == 0 {
+            self.push_history();
+        }
+        self.transaction_depth += 1;
+    }
+
+    /// End the innermost transaction; ending the outermost one marks the map
+    /// edited
+    pub fn commit_transaction(&mut self) {
+        if self.transaction_depth == 0 {
+            return;
+        }
+        self.transaction_depth -= 1;
+        if self.transaction_depth == 0 {
+            self.is_dirty = true;
+            self.last_modify_time = Some(std::time::Instant::now());
+            self.mark_map_changed();
+        }
+    }
+
+    /// Put the tree back as it was when the outermost transaction began,
+    /// ending every open transaction and dropping their history entry
+    pub fn rollback_transaction(&mut self) {
+        if self.transaction_depth == 0 {
+            return;
+        }
+        self.transaction_depth = 0;
+        self.history_index -= 1;
+        self.restore_history();
+        self.history.truncate(self.history_index);
+    }
+
+    /// Run `edit` in a transaction, committed when it succeeds and rolled
+    /// back when it fails, so that it is undone and saved as a whole
+    pub fn transaction<T>(
+        &mut self,
+        edit: impl FnOnce(&mut Self) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        self.begin_transaction();
+        let result = edit(self);
+        match result {
+            Ok(_) => self.commit_transaction(),
+            Err(_) => self.rollback_transaction(),
+        }
+        result
+    }
+
+    /// Push history before a repeatable edit of `node_id`, unless the previous
+    /// action was the same edit of the same node, so that holding a key down
+    /// undoes in one step
+    pub fn push_history_grouped(&mut self, action: Action, node_id: NodeId) {
+        let group = Some((action, node_id));
+        if self.history_group == group && self.history_index == self.history.len() {
+            self.mark_map_changed();
+        } else {
+            self.push_history();
+        }
+        self.history_group = group;
+    }
+
+    /// Switch to lighter settings once the map grows past `large_map_nodes`,
+    /// telling why, so that huge maps stay responsive instead of freezing.
+    /// The config file is left alone, and it only happens once per session.
+    pub fn check_map_size(&mut self) {
+        let limit = self.config.large_map_nodes;
+        let count = self.tree.count();
+        if self.large_map || limit == 0 || count <= limit {
+            return;
+        }
+        self.large_map = true;
+
+        let config = &mut self.config;
+        config.heatmap = HeatmapMode::Off;
+        config.sticky_headers = false;
+        config.depth_guides = false;
+        config.render_math = false;
+        config.undo_view_changes = false;
+        config.max_undo_steps = config.max_undo_steps.min(LARGE_MAP_UNDO_STEPS);
+        self.mark_damage(Damage::Layout);
+        self.notify_with("status.large_map", &[("count", &count)]);
+    }
+
+    /// Push history before collapsing or expanding nodes, when the config
+    /// makes those undoable, unless it joins a run of view changes that
+    /// already has its entry
+    pub fn push_view_history(&mut self) {
+        if !self.config.undo_view_changes
+            || (self.view_history_group && self.history_index == self.history.len())
+        {
+            return;
+        }
+        self.push_history();
+        self.view_history_group = true;
+    }
+
     pub fn undo(&mut self) -> bool {
         if self.history_index > 0 {
             self.history_index -= 1;
-            self.tree = self.history[self.history_index].clone();
+            self.restore_history();
             true
         } else {
             false
@@ -104,18 +558,185 @@ impl AppState {
     pub fn redo(&mut self) -> bool {
         if self.history_index < self.history.len() - 1 {
             self.history_index += 1;
-            self.tree = self.history[self.history_index].clone();
+            self.restore_history();
             true
         } else {
             false
         }
     }
 
+    /// Selection and scroll position to record with a history snapshot
+    pub fn view_state(&self) -> ViewState {
+        ViewState {
+            active_node_id: self.active_node_id,
+            viewport_top: self.viewport_top,
+            viewport_left: self.viewport_left,
+        }
+    }
+
+    /// Load the tree at `history_index` with the selection and viewport it
+    /// was recorded with. If that node is gone, fall back to the nearest
+    /// surviving ancestor of the current selection.
+    fn restore_history(&mut self) {
+        self.history_group = None;
+        self.view_history_group = false;
+        let Some((tree, view)) = self.history.get(self.history_index) else {
+            return;
+        };
+        let path: Vec<NodeId> = self
+            .active_node_id
+            .filter(|&id| self.tree.get(id).is_some())
+            .map(|id| id.ancestors(&self.tree).collect())
+            .unwrap_or_default();
+
+        self.tree = tree;
+        self.active_node_id = view
+            .active_node_id
+            .into_iter()
+            .chain(path)
+            .find(|&id| self.is_in_map(id));
+        self.viewport_top = view.viewport_top;
+        self.viewport_left = view.viewport_left;
+        self.repair_selection();
+        self.mark_map_changed();
+    }
+
+    /// Pinned nodes still in the map, in pin order
+    pub fn pinned_nodes(&self) -> Vec<NodeId> {
+        let pins = self.pins.iter().copied();
+        pins.filter(|&id| self.is_in_map(id)).collect()
+    }
+
+    /// Pick up the pins remembered for the open map
+    pub fn load_pins(&mut self) {
+        self.pins = match (&self.filename, self.root_id) {
+            (Some(path), Some(root_id)) => self.pin_store.pins_for(path, &self.tree, root_id),
+            _ => Vec::new(),
+        };
+    }
+
+    /// Remember the pins of the open map under its current titles
+    pub fn store_pins(&mut self) -> anyhow::Result<()> {
+        let (Some(path), Some(root_id)) = (self.filename.clone(), self.root_id) else {
+            return Ok(());
+        };
+        let pins = self.pinned_nodes();
+        self.pin_store.set(&path, &self.tree, root_id, &pins)
+    }
+
+    /// The node being edited and the text typed so far, while editing
+    pub fn edit_preview(&self) -> Option<(NodeId, &str)> {
+        match (&self.mode, self.active_node_id) {
+            (AppMode::Editing { buffer, .. }, Some(active_id)) => Some((active_id, buffer)),
+            _ => None,
+        }
+    }
+
+    /// The title to show for a node: the edit buffer for the node being
+    /// edited, so the map follows the text as it is typed, the configured
+    /// `root_title` for the root, else the title without its kind tag and
+    /// with its math rendered
+    pub fn display_title<'a>(&'a self, node_id: NodeId, node: &'a Node) -> Cow<'a, str> {
+        match self.edit_preview() {
+            Some((edited_id, buffer)) if edited_id == node_id => Cow::Borrowed(buffer),
+            _ if Some(node_id) == self.root_id => match self.root_title() {
+                Some((_, title)) => Cow::Owned(title),
+                None => shown_title(&node.title, self.config.render_math),
+            },
+            _ => shown_title(&node.title, self.config.render_math),
+        }
+    }
+
+    /// The root and the title `root_title` shows it with, if one is set
+    pub fn root_title(&self) -> Option<(NodeId, String)> {
+        let root_id = self.root_id?;
+        if self.config.root_title.is_empty() {
+            return None;
+        }
+        let title = &self.tree.get(root_id)?.get().title;
+        let file = self
+            .filename
+            .as_deref()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy())
+            .unwrap_or_default();
+        let shown = self
+            .config
+            .root_title
+            .replace("{title}", title)
+            .replace("{file}", &file);
+        Some((root_id, shown))
+    }
+
+    /// Whether changes to the map are saved automatically: `auto_save` is on
+    /// and the map has a writable file that `no_auto_save` doesn't list
+    pub fn auto_saves(&self) -> bool {
+        let Some(path) = self.filename.as_deref().filter(|_| self.config.auto_save) else {
+            return false;
+        };
+        let excluded = (self.config.no_auto_save.iter())
+            .any(|excluded| same_file(&expand_home(excluded), path));
+        !self.scratch && self.format.is_writable() && !excluded
+    }
+
+    /// Whether `node_id` is a live node under the map root
+    pub fn is_in_map(&self, node_id: NodeId) -> bool {
+        let Some(root_id) = self.root_id else {
+            return false;
+        };
+        self.tree
+            .get(node_id)
+            .is_some_and(|node| !node.is_removed())
+            && node_id.ancestors(&self.tree).any(|id| id == root_id)
+    }
+
+    /// Make sure the map has a root and the selection points into it. A map
+    /// that lost its root adopts a remaining top-level node, or gets a new
+    /// empty root if nothing is left.
+    pub fn repair_selection(&mut self) {
+        let root_is_live = self
+            .root_id
+            .and_then(|id| self.tree.get(id))
+            .is_some_and(|root| !root.is_removed());
+        if !root_is_live && (self.root_id.is_some() || !self.tree.is_empty()) {
+            let orphan = self
+                .tree
+                .iter()
+                .find(|node| !node.is_removed() && node.parent().is_none())
+                .and_then(|node| self.tree.get_node_id(node));
+            let root_id =
+                orphan.unwrap_or_else(|| self.tree.new_node(Node::new(NEW_MAP_TITLE.to_string())));
+            self.root_id = Some(root_id);
+            self.active_node_id = Some(root_id);
+            self.mark_damage(Damage::Layout);
+            self.notify("status.root_recreated");
+        }
+
+        if let Some(root_id) = self.root_id {
+            if !self.active_node_id.is_some_and(|id| self.is_in_map(id)) {
+                self.active_node_id = Some(root_id);
+                self.mark_damage(Damage::View);
+            }
+        }
+    }
+
     pub fn set_message(&mut self, msg: impl Into<String>) {
         self.message = Some(msg.into());
+        self.mark_damage(Damage::View);
+    }
+
+    /// Show the localized status message for a catalog key
+    pub fn notify(&mut self, key: &str) {
+        self.set_message(self.catalog.get(key));
+    }
+
+    /// Show a localized status message with its `{name}` placeholders filled in
+    pub fn notify_with(&mut self, key: &str, args: &[(&str, &dyn fmt::Display)]) {
+        self.set_message(self.catalog.format(key, args));
     }
 
     pub fn clear_message(&mut self) {
         self.message = None;
+        self.mark_damage(Damage::View);
     }
 }
@@ -0,0 +1,132 @@
+//! Sentence case for outlines arriving in ALL CAPS.
+//!
+//! Some tools export every title upper case. A title counts as shouting
+//! when it has at least two letters and none of them is lower case; an
+//! outline does when most of its titles with letters do. Converting keeps
+//! the configured acronyms, words with digits such as `Q3`, and bracketed
+//! markers such as `[HIDDEN]` as they are.
+
+use crate::model::{Node, NodeId};
+use indextree::Arena;
+
+/// Share of titles that have to be shouting for an outline to be
+const SHOUTING_SHARE: f64 = 0.75;
+
+/// Whether the title is written in capitals only
+pub fn is_shouting(title: &str) -> bool {
+    let mut letters = title.chars().filter(|c| c.is_alphabetic());
+    let mut count = 0;
+    letters.all(|c| {
+        count += 1;
+        !c.is_lowercase()
+    }) && count >= 2
+}
+
+/// Whether most titles with letters in the subtree of `root_id` are shouting
+pub fn is_shouting_outline(tree: &Arena<Node>, root_id: NodeId) -> bool {
+    let (mut titles, mut shouting) = (0, 0);
+    for node_id in root_id.descendants(tree) {
+        let title = &tree[node_id].get().title;
+        if title.chars().any(char::is_alphabetic) {
+            titles += 1;
+            shouting += usize::from(is_shouting(title));
+        }
+    }
+    titles > 0 && shouting as f64 >= titles as f64 * SHOUTING_SHARE
+}
+
+/// The title in sentence case: lower case apart from the first letter of
+/// each sentence, the word "I" and the words kept as they are
+pub fn sentence_case(title: &str, acronyms: &[String]) -> String {
+    let mut result = String::with_capacity(title.len());
+    let mut sentence_start = true;
+    for (i, word) in title.split(' ').enumerate() {
+        if i > 0 {
+            result.push(' ');
+        }
+        let letters = word.trim_matches(|c: char| !c.is_alphanumeric());
+        // Markers and symbols neither change nor start the sentence
+        if word.starts_with('[') || !letters.chars().any(char::is_alphabetic) {
+            result.push_str(word);
+            continue;
+        }
+        let keep = letters == "I"
+            || letters.chars().any(|c| c.is_ascii_digit())
+            || acronyms.iter().any(|acronym| acronym == letters);
+        if keep {
+            result.push_str(word);
+        } else if sentence_start {
+            result.push_str(&capitalize_first_letter(&word.to_lowercase()));
+        } else {
+            result.push_str(&word.to_lowercase());
+        }
+        sentence_start = word.ends_with(['.', '!', '?', ':']);
+    }
+    result
+}
+
+fn capitalize_first_letter(word: &str) -> String {
+    match word.find(char::is_alphabetic) {
+        Some(start) => {
+            let (before, rest) = word.split_at(start);
+            let mut chars = rest.chars();
+            let first = chars.next().into_iter().flat_map(char::to_uppercase);
+            before.chars().chain(first).chain(chars).collect()
+        }
+        None => word.to_string(),
+    }
+}
+
+/// Put the shouting titles in the subtree of `root_id` in sentence case,
+/// returning how many changed
+pub fn fix_shouting_case(tree: &mut Arena<Node>, root_id: NodeId, acronyms: &[String]) -> usize {
+    let nodes: Vec<NodeId> = root_id.descendants(tree).collect();
+    let mut changed = 0;
+    for node_id in nodes {
+        let title = &mut tree[node_id].get_mut().title;
+        if is_shouting(title) {
+            let fixed = sentence_case(title, acronyms);
+            if fixed != *title {
+                *title = fixed;
+                changed += 1;
+            }
+        }
+    }
+    changed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentence_case_keeps_acronyms() {
+        let acronyms = vec!["API".to_string(), "CEO".to_string()];
+        assert_eq!(
+            sentence_case("REVIEW API LIMITS WITH THE CEO. THEN SHIP Q3", &acronyms),
+            "Review API limits with the CEO. Then ship Q3"
+        );
+        assert_eq!(
+            sentence_case("[HIDDEN] ✓ \"SECRET\" PLANS", &acronyms),
+            "[HIDDEN] ✓ \"Secret\" plans"
+        );
+        assert_eq!(sentence_case("WHAT I KNOW", &[]), "What I know");
+    }
+
+    #[test]
+    fn test_shouting_outline() {
+        let mut tree = Arena::new();
+        let root = tree.new_node(Node::new("MEETING NOTES".to_string()));
+        root.append_value(Node::new("AGENDA".to_string()), &mut tree);
+        root.append_value(Node::new("2024-05-01".to_string()), &mut tree);
+        let mixed = root.append_value(Node::new("Follow-ups".to_string()), &mut tree);
+        assert!(!is_shouting_outline(&tree, root));
+
+        mixed.append_value(Node::new("ACTION ITEMS".to_string()), &mut tree);
+        tree[mixed].get_mut().title = "FOLLOW-UPS".to_string();
+        assert!(is_shouting_outline(&tree, root));
+
+        assert_eq!(fix_shouting_case(&mut tree, root, &[]), 4);
+        assert_eq!(tree[mixed].get().title, "Follow-ups");
+    }
+}
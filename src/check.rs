@@ -0,0 +1,179 @@
+//! Integrity check of `.hmm` files, for `hmm-rs check`.
+//!
+//! The parser accepts almost any text, guessing at what a badly indented or
+//! garbled line means. The check reports the lines it had to guess about, and
+//! the ones that load fine but make a map hard to work with, so that maps kept
+//! in git can be checked in a pre-commit hook.
+
+use crate::parser::{clean_line, indent_width, strip_line_ending};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Longest line, in characters, `hmm-rs check` accepts by default
+pub const DEFAULT_MAX_LINE_LENGTH: usize = 1000;
+
+/// Something wrong with a line of a map
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// A list marker with no title after it
+    EmptyTitle,
+    /// The same title as an earlier sibling, which links to it can't tell apart
+    DuplicateTitle { first_line: usize },
+    /// Indented with tabs where the file uses spaces, or the other way round
+    MixedIndentation,
+    /// Outdented to a column no enclosing line starts at
+    MisalignedIndentation,
+    /// A control character other than a tab
+    ControlCharacter(char),
+    /// Longer than the limit, in characters
+    LongLine { length: usize },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::EmptyTitle => write!(f, "empty title"),
+            Problem::DuplicateTitle { first_line } => {
+                write!(f, "duplicate title, first on line {first_line}")
+            }
+            Problem::MixedIndentation => write!(f, "mixed tabs and spaces in indentation"),
+            Problem::MisalignedIndentation => {
+                write!(f, "indentation matches no enclosing line")
+            }
+            Problem::ControlCharacter(c) => write!(f, "control character U+{:04X}", *c as u32),
+            Problem::LongLine { length } => write!(f, "line of {length} characters"),
+        }
+    }
+}
+
+/// A problem and the line it is on, from 1
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub line: usize,
+    pub problem: Problem,
+}
+
+/// An indentation level open while reading the file: the column of a line and
+/// the titles of its children so far, with their line numbers
+struct Level {
+    indent: usize,
+    children: HashMap<String, usize>,
+}
+
+/// Every problem in the `.hmm` text `content`, in line order
+pub fn check_map(content: &str, max_line_length: usize) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    // Whether the file indents with tabs, as its first indented line does
+    let mut uses_tabs = None;
+    let mut levels = vec![Level {
+        indent: 0,
+        children: HashMap::new(),
+    }];
+
+    for (index, raw) in content.split_inclusive('\n').enumerate() {
+        let line = strip_line_ending(raw);
+        let mut report = |problem| {
+            issues.push(Issue {
+                line: index + 1,
+                problem,
+            })
+        };
+
+        if let Some(c) = line.chars().find(|&c| c.is_control() && c != '\t') {
+            report(Problem::ControlCharacter(c));
+        }
+        let length = line.chars().count();
+        if length > max_line_length {
+            report(Problem::LongLine { length });
+        }
+
+        let leading = &line[..indent_width(line)];
+        let (tabs, spaces) = (leading.contains('\t'), leading.contains(' '));
+        if (tabs && spaces) || uses_tabs.is_some_and(|uses_tabs| uses_tabs != tabs) {
+            report(Problem::MixedIndentation);
+        } else if tabs || spaces {
+            uses_tabs.get_or_insert(tabs);
+        }
+
+        let clean = clean_line(line);
+        let title = clean.trim();
+        if title.is_empty() || matches!(title, "-" | "*") {
+            if !line.trim().is_empty() {
+                report(Problem::EmptyTitle);
+            }
+            continue;
+        }
+
+        let indent = indent_width(&clean);
+        let mut sibling_indent = None;
+        while levels.len() > 1 && levels.last().is_some_and(|level| level.indent >= indent) {
+            sibling_indent = levels.pop().map(|level| level.indent);
+        }
+        if sibling_indent.is_some_and(|sibling| sibling != indent) {
+            report(Problem::MisalignedIndentation);
+        }
+
+        let siblings = &mut levels.last_mut().expect("the top level stays").children;
+        match siblings.get(title) {
+            Some(&first_line) => report(Problem::DuplicateTitle { first_line }),
+            None => {
+                siblings.insert(title.to_string(), index + 1);
+            }
+        }
+        levels.push(Level {
+            indent,
+            children: HashMap::new(),
+        });
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(content: &str) -> Vec<(usize, Problem)> {
+        check_map(content, 40)
+            .into_iter()
+            .map(|issue| (issue.line, issue.problem))
+            .collect()
+    }
+
+    #[test]
+    fn test_clean_map_has_no_issues() {
+        assert!(problems("Root\n\tGoals\n\t\tShip\n\tNotes\n\t\tShip\n").is_empty());
+        assert!(problems("Root\r\n  - One\r\n  - Two\r\n").is_empty());
+    }
+
+    #[test]
+    fn test_check_reports_line_numbers() {
+        let content = "Root\n\tGoals\n\tGoals\n\t-\n    Spaces\n\t\tDeep\n\t Both\n";
+        assert_eq!(
+            problems(content),
+            [
+                (3, Problem::DuplicateTitle { first_line: 2 }),
+                (4, Problem::EmptyTitle),
+                (5, Problem::MixedIndentation),
+                (7, Problem::MixedIndentation),
+                (7, Problem::MisalignedIndentation),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_check_reports_control_characters_and_long_lines() {
+        let long = "x".repeat(41);
+        let content = format!("Root\n\tBell\u{7}\n\t{long}\n");
+        assert_eq!(
+            problems(&content),
+            [
+                (2, Problem::ControlCharacter('\u{7}')),
+                (3, Problem::LongLine { length: 42 }),
+            ]
+        );
+        assert_eq!(
+            Problem::ControlCharacter('\u{7}').to_string(),
+            "control character U+0007"
+        );
+    }
+}
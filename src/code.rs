@@ -0,0 +1,122 @@
+//! Nodes holding a code snippet.
+//!
+//! A title wrapped in backticks, such as ``` ```rust let x = 1;``` ``` or
+//! `` `make test` ``, or a command line flag such as `--no-verify`, is code:
+//! it is shown without its fences, on one line truncated with an ellipsis
+//! rather than wrapped, and highlighted when the `highlight` feature is on.
+
+use unicode_width::UnicodeWidthChar;
+
+/// Marks the end of code cut short to fit its node
+pub const ELLIPSIS: char = '…';
+
+/// The code in a node title
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Code<'a> {
+    /// Language named after the opening fence, e.g. `rust`
+    pub lang: Option<&'a str>,
+    pub code: &'a str,
+}
+
+/// The code `title` holds, if it is a code node
+pub fn code_block(title: &str) -> Option<Code<'_>> {
+    let title = title.trim();
+
+    if let Some(inner) = title
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+    {
+        // The first word is the language unless the fence holds nothing else
+        return Some(match inner.split_once(char::is_whitespace) {
+            Some((lang, code)) if !lang.is_empty() => Code {
+                lang: Some(lang),
+                code: code.trim(),
+            },
+            _ => Code {
+                lang: None,
+                code: inner.trim(),
+            },
+        });
+    }
+
+    if let Some(code) = title
+        .strip_prefix('`')
+        .and_then(|rest| rest.strip_suffix('`'))
+        .filter(|code| !code.is_empty() && !code.contains('`'))
+    {
+        return Some(Code { lang: None, code });
+    }
+
+    // A flag like `--dry-run` or `--jobs=4`
+    let is_flag = title.len() > 2
+        && title.starts_with("--")
+        && !title.contains(char::is_whitespace)
+        && title[2..].starts_with(|c: char| c.is_ascii_alphanumeric());
+    is_flag.then_some(Code {
+        lang: Some("sh"),
+        code: title,
+    })
+}
+
+/// `code` cut to `max_width` columns, ending in [`ELLIPSIS`] if anything was cut
+pub fn truncate(code: &str, max_width: usize) -> String {
+    let width = unicode_width::UnicodeWidthStr::width(code);
+    if width <= max_width {
+        return code.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut used = 0;
+    for c in code.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > max_width {
+            break;
+        }
+        truncated.push(c);
+        used += w;
+    }
+    if max_width > 0 {
+        truncated.push(ELLIPSIS);
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_block_detection() {
+        assert_eq!(
+            code_block("```rust let x = 1;```"),
+            Some(Code {
+                lang: Some("rust"),
+                code: "let x = 1;"
+            })
+        );
+        assert_eq!(
+            code_block("`make test`"),
+            Some(Code {
+                lang: None,
+                code: "make test"
+            })
+        );
+        assert_eq!(
+            code_block("--dry-run"),
+            Some(Code {
+                lang: Some("sh"),
+                code: "--dry-run"
+            })
+        );
+        assert_eq!(code_block("Use `ls` here"), None);
+        assert_eq!(code_block("-- a note"), None);
+        assert_eq!(code_block("Plain title"), None);
+    }
+
+    #[test]
+    fn test_truncate_with_ellipsis() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("fn main() {}", 8), "fn main…");
+        assert_eq!(truncate("let x = 1;", 1), "…");
+    }
+}
@@ -1,9 +1,21 @@
-use anyhow::Result;
-use clap::Parser;
+#[cfg(feature = "cli")]
+use crate::check::DEFAULT_MAX_LINE_LENGTH;
+use crate::export::ExportFormat;
+#[cfg(feature = "cli")]
+use crate::import::TableHierarchy;
+#[cfg(feature = "cli")]
+use crate::keymap::CheatSheetFormat;
+use crate::theme::Palette;
+use anyhow::{bail, Context, Result};
+#[cfg(feature = "cli")]
+use clap::{Parser, Subcommand};
+#[cfg(feature = "cli")]
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+#[cfg(feature = "cli")]
 #[derive(Parser, Debug)]
 #[command(name = "h-m-m")]
 #[command(version, about = "A simple, fast, keyboard-centric terminal-based tool for working with mind maps", long_about = None)]
@@ -30,6 +42,174 @@ pub struct CliArgs {
     /// Auto-save mode
     #[arg(long)]
     pub auto_save: Option<bool>,
+
+    /// Pick nodes of the map: space ticks several, Enter prints them, or
+    /// the selected node, to stdout one per line and exits, Esc exits with
+    /// status 1, e.g. `choice=$(hmm-rs --pick=path menu.hmm)`
+    #[arg(
+        long,
+        value_enum,
+        value_name = "OUTPUT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "title",
+        requires = "filename"
+    )]
+    pub pick: Option<PickOutput>,
+
+    /// Print the map's outline to stdout on exit, e.g. to pick notes with
+    /// `notes=$(hmm-rs --print-on-exit visible pick.hmm)`
+    #[arg(long, value_enum, value_name = "MODE")]
+    pub print_on_exit: Option<PrintOnExit>,
+
+    /// Open the interactive tutorial map
+    #[arg(long, conflicts_with = "filename")]
+    pub tutorial: bool,
+
+    /// Start a throwaway map that is never auto-saved and quits without
+    /// asking to save
+    #[arg(long, conflicts_with_all = ["filename", "tutorial"])]
+    pub scratch: bool,
+
+    /// Create the map file if it does not exist yet instead of failing
+    #[arg(long, requires = "filename")]
+    pub create: bool,
+
+    /// Open the map without allowing edits
+    #[arg(long, requires = "filename")]
+    pub read_only: bool,
+
+    /// Import a CSV/TSV file using this column of `A/B/C` paths as the hierarchy
+    #[arg(
+        long,
+        value_name = "COLUMN",
+        requires = "filename",
+        conflicts_with = "csv_level"
+    )]
+    pub csv_path: Option<String>,
+
+    /// Import a CSV/TSV file using this column of nesting levels as the hierarchy
+    #[arg(long, value_name = "COLUMN", requires = "filename")]
+    pub csv_level: Option<String>,
+
+    /// Column holding the node titles for --csv-level [default: title]
+    #[arg(long, value_name = "COLUMN", requires = "csv_level")]
+    pub csv_title: Option<String>,
+
+    /// Open with the first node matching this title selected and centered
+    #[arg(long, value_name = "TITLE", conflicts_with = "select_line")]
+    pub select: Option<String>,
+
+    /// Open with the node on this line of the file selected and centered; .hmm maps only
+    #[arg(long, value_name = "LINE")]
+    pub select_line: Option<usize>,
+
+    /// Open showing the map side by side with this other map, differences highlighted
+    #[arg(long, value_name = "FILE", requires = "filename")]
+    pub compare: Option<PathBuf>,
+
+    /// Print the effective key bindings as a cheat sheet and exit
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "text")]
+    pub print_keys: Option<CheatSheetFormat>,
+
+    /// Browse the maps under this directory; without a file, start in the workspace picker
+    #[arg(long, value_name = "DIR")]
+    pub workspace: Option<PathBuf>,
+
+    /// Keep this file updated with the open map, selected node and dirty state as JSON
+    #[arg(long, value_name = "PATH")]
+    pub status_file: Option<PathBuf>,
+
+    /// Take commands such as `add-child "text"` from other programs on this Unix socket
+    #[cfg(feature = "ipc")]
+    #[arg(long, value_name = "PATH")]
+    pub ipc_socket: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[cfg(feature = "cli")]
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Open today's journal map, creating it from the template if needed
+    Journal,
+    /// Print a map in another format to stdout
+    Export {
+        /// The mind map file to export
+        file: PathBuf,
+        /// Output format
+        #[arg(long, value_enum, default_value = "text")]
+        format: ExportFormat,
+        /// Render this template from `[export.templates]` instead
+        #[arg(long, value_name = "NAME", conflicts_with = "format")]
+        template: Option<String>,
+        /// Export only the branch reached by following these titles down
+        /// from the root; `.hmmdb` maps load just that branch
+        #[arg(long, value_name = "TITLE")]
+        branch: Vec<String>,
+    },
+    /// List the formats maps can be opened from, saved in and exported to
+    Formats,
+    /// Report structural and formatting problems in `.hmm` files with their
+    /// line numbers, failing if there are any
+    Check {
+        /// The mind map files to check
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Longest line accepted, in characters
+        #[arg(long, value_name = "CHARS", default_value_t = DEFAULT_MAX_LINE_LENGTH)]
+        max_line_length: usize,
+    },
+    /// Add a node to a `.hmmdb` map, even while the editor has it open
+    #[cfg(feature = "sqlite")]
+    Capture {
+        /// The `.hmmdb` map to add to
+        file: PathBuf,
+        /// Title of the new node
+        title: String,
+        /// Titles leading from the root down to the parent of the new node;
+        /// the root if left out
+        #[arg(long, value_name = "TITLE")]
+        under: Vec<String>,
+    },
+    /// Search the titles of a `.hmmdb` map without loading it, printing the
+    /// path to each match, best matches first
+    #[cfg(feature = "sqlite")]
+    Search {
+        /// The `.hmmdb` map to search
+        file: PathBuf,
+        /// SQLite full-text query, such as `rust*` or `"exact phrase"`
+        query: String,
+    },
+    /// Mark task nodes done or to do as a Taskwarrior JSON export or a
+    /// todo.txt file says, and save the map
+    SyncTasks {
+        /// The mind map file to update
+        file: PathBuf,
+        /// The task list exported with `--format taskwarrior` or `todo-txt`
+        tasks: PathBuf,
+    },
+}
+
+#[cfg(feature = "cli")]
+impl CliArgs {
+    /// Columns chosen on the command line for a CSV/TSV import
+    pub fn table_hierarchy(&self) -> TableHierarchy {
+        match (&self.csv_path, &self.csv_level) {
+            (Some(column), _) => TableHierarchy::Path {
+                column: column.clone(),
+            },
+            (None, Some(column)) => TableHierarchy::Level {
+                column: column.clone(),
+                title: self
+                    .csv_title
+                    .clone()
+                    .unwrap_or_else(|| "title".to_string()),
+            },
+            (None, None) => TableHierarchy::Auto,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,6 +223,26 @@ pub struct AppConfig {
     #[serde(default = "default_line_spacing")]
     pub line_spacing: usize,
 
+    /// Blank lines below nodes at depth 1, 2, …; deeper nodes fall back to
+    /// `leaf_line_spacing` and `line_spacing`
+    #[serde(default)]
+    pub depth_line_spacing: Vec<usize>,
+
+    /// Blank lines below leaves not covered by `depth_line_spacing`
+    #[serde(default)]
+    pub leaf_line_spacing: Option<usize>,
+
+    /// Leaf children beyond this many are packed into columns side by side
+    /// instead of one tall column; 0 never packs them
+    #[serde(default)]
+    pub pack_leaves_after: usize,
+
+    /// Title the root is shown with instead of its own; `{title}` is its own
+    /// title and `{file}` the map's file name without extension. Empty shows
+    /// the root's own title.
+    #[serde(default)]
+    pub root_title: String,
+
     #[serde(default = "default_symbol1")]
     pub symbol1: String,
 
@@ -61,21 +261,88 @@ pub struct AppConfig {
     #[serde(default = "default_focus_lock")]
     pub focus_lock: bool,
 
+    /// Collapse the branches beside the active node whenever it moves
+    #[serde(default)]
+    pub accordion: bool,
+
+    /// Scroll to keep the node being edited in view as its text wraps
+    #[serde(default = "default_follow_edits")]
+    pub follow_edits: bool,
+
     #[serde(default = "default_max_undo_steps")]
     pub max_undo_steps: usize,
 
+    /// Undo history memory limit in MiB; older snapshots are dropped beyond it
+    #[serde(default = "default_max_undo_memory")]
+    pub max_undo_memory: usize,
+
+    /// Record collapsing and expanding in the undo history, a run of such
+    /// changes as one step
+    #[serde(default)]
+    pub undo_view_changes: bool,
+
     #[serde(default = "default_auto_save")]
     pub auto_save: bool,
 
     #[serde(default = "default_auto_save_interval")]
     pub auto_save_interval: usize,
 
+    /// Maps never auto-saved, by path, even with `auto_save` on
+    #[serde(default)]
+    pub no_auto_save: Vec<PathBuf>,
+
+    /// Minutes between snapshots of the open map; 0 takes none
+    #[serde(default = "default_snapshot_interval")]
+    pub snapshot_interval: usize,
+
+    /// Snapshots kept per map; older ones are removed
+    #[serde(default = "default_snapshot_keep")]
+    pub snapshot_keep: usize,
+
+    /// What saving a map inside a git repository does in git
+    #[serde(default)]
+    pub git_on_save: GitOnSave,
+
+    /// Message of commits made on save; `{file}` is the file name and `{time}`
+    /// the time of the save
+    #[serde(default = "default_git_commit_message")]
+    pub git_commit_message: String,
+
     #[serde(default = "default_echo_keys")]
     pub echo_keys: bool,
 
     #[serde(default = "default_post_export_command")]
     pub post_export_command: String,
 
+    /// Title prefix of comment nodes, shown while editing but left out of exports
+    #[serde(default = "default_comment_prefix")]
+    pub comment_prefix: String,
+
+    /// Export comment nodes like any other node
+    #[serde(default)]
+    pub export_comments: bool,
+
+    /// Put the ancestors of an exported subtree above it, one per level
+    #[serde(default)]
+    pub export_breadcrumbs: bool,
+
+    /// Line put below collapsed branches in exports, `{count}` standing for
+    /// the nodes folded away; empty leaves them out without a trace
+    #[serde(default)]
+    pub export_collapsed_placeholder: String,
+
+    /// Title of the node below the root that captured lines go under
+    #[serde(default = "default_inbox_title")]
+    pub inbox_title: String,
+
+    /// Put pasted and imported outlines written in capitals only in sentence case
+    #[serde(default)]
+    pub fix_shouting_case: bool,
+
+    /// Words kept in capitals when titles are put in sentence case
+    #[serde(default = "default_acronyms")]
+    pub acronyms: Vec<String>,
+
     #[serde(default = "default_clipboard")]
     pub clipboard: ClipboardType,
 
@@ -87,6 +354,105 @@ pub struct AppConfig {
 
     #[serde(default)]
     pub clipboard_out_command: String,
+
+    #[serde(default = "default_sort_collation")]
+    pub sort_collation: SortCollation,
+
+    /// Patterns of title prefixes sorting passes over, such as task symbols,
+    /// ranks or articles, each a regular expression matched at the start
+    #[serde(default)]
+    pub sort_ignore_prefixes: Vec<String>,
+
+    /// What node backgrounds are shaded by, if anything
+    #[serde(default)]
+    pub heatmap: HeatmapMode,
+
+    /// Number of nodes that can be pinned to the bar above the map, at most 9
+    #[serde(default = "default_max_pins")]
+    pub max_pins: usize,
+
+    /// Pin the titles of ancestors scrolled above the view to its top row
+    #[serde(default)]
+    pub sticky_headers: bool,
+
+    /// Maps with more nodes than this switch to lighter settings, with a
+    /// warning; 0 never does
+    #[serde(default = "default_large_map_nodes")]
+    pub large_map_nodes: usize,
+
+    /// Faint vertical lines marking where each depth of the map starts
+    #[serde(default)]
+    pub depth_guides: bool,
+
+    /// Color of the depth guides: a name, an index such as `240` or `#rrggbb`
+    #[serde(default = "default_depth_guide_color")]
+    pub depth_guide_color: String,
+
+    /// Built-in colors of the map, see [`crate::theme`]
+    #[serde(default)]
+    pub palette: Palette,
+
+    /// Colors overriding the palette's, by role, the `[theme]` table
+    #[serde(default)]
+    pub theme: HashMap<String, String>,
+
+    /// Show `$...$` math in titles with a unicode approximation of its LaTeX
+    #[serde(default = "default_render_math")]
+    pub render_math: bool,
+
+    /// Outline printed to stdout after leaving the alternate screen on exit
+    #[serde(default)]
+    pub print_on_exit: PrintOnExit,
+
+    /// Name the open map and whether it is modified in the terminal's title
+    #[serde(default = "default_terminal_title")]
+    pub terminal_title: bool,
+
+    /// Tell the terminal the directory of the open map with OSC 7, so that
+    /// new tabs and splits open there
+    #[serde(default)]
+    pub report_cwd: bool,
+
+    /// Key that `<leader>` stands for in key sequences
+    #[serde(default = "default_leader")]
+    pub leader: String,
+
+    /// Milliseconds a key sequence waits for its next key
+    #[serde(default = "default_key_sequence_timeout")]
+    pub key_sequence_timeout: u64,
+
+    /// Key remapping: command name to whitespace-separated keys, e.g. `redo = "C-r U"`
+    #[serde(default)]
+    pub keys: HashMap<String, String>,
+
+    /// UI language such as `de`; unset means the locale environment, then English
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// Directory of maps browsed by the workspace picker
+    #[serde(default)]
+    pub workspace: Option<PathBuf>,
+
+    /// Renaming a map file or a node rewrites the links to it in the other
+    /// maps of the workspace; for a node, once confirmed
+    #[serde(default)]
+    pub update_links: bool,
+
+    /// Directory of daily journal maps; defaults to `journal` in the workspace
+    #[serde(default)]
+    pub journal_dir: Option<PathBuf>,
+
+    /// Map copied for each new journal day, with `{date}` and `{weekday}` filled in
+    #[serde(default)]
+    pub journal_template: Option<PathBuf>,
+
+    /// Export settings, the `[export]` table
+    #[serde(default)]
+    pub export: ExportConfig,
+
+    /// Shell commands run on events, the `[hooks]` table
+    #[serde(default)]
+    pub hooks: HooksConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -97,27 +463,210 @@ pub enum ClipboardType {
     Command,
 }
 
+/// How titles are compared when sorting siblings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortCollation {
+    /// Case- and accent-insensitive, with digit runs compared numerically ("item 9" < "item 10")
+    Natural,
+    /// Case- and accent-insensitive, digits compared as text
+    CaseInsensitive,
+    /// Plain byte-wise comparison of the raw titles
+    Bytewise,
+}
+
+/// The `[hooks]` table of the config, see [`crate::hooks`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HooksConfig {
+    /// Run after the map was saved, by hand or automatically
+    #[serde(default)]
+    pub save: Option<String>,
+    /// Run after an automatic save failed
+    #[serde(default)]
+    pub auto_save_failed: Option<String>,
+    /// Run after a task was marked done
+    #[serde(default)]
+    pub task_done: Option<String>,
+}
+
+/// The `[export]` table of the config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExportConfig {
+    /// Export templates by name, each the path of a minijinja template
+    #[serde(default)]
+    pub templates: HashMap<String, PathBuf>,
+    /// Commands the export_pipe key sends an export of the map to, by name
+    #[serde(default)]
+    pub pipes: HashMap<String, ExportPipe>,
+}
+
+/// A command of `[export.pipes]` and the format it reads
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportPipe {
+    /// Shell command reading the export on its standard input
+    pub command: String,
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+impl ExportConfig {
+    /// Source of the export template called `name`
+    pub fn template(&self, name: &str) -> Result<String> {
+        let Some(path) = self.templates.get(name) else {
+            let mut names: Vec<_> = self.templates.keys().map(String::as_str).collect();
+            names.sort_unstable();
+            if names.is_empty() {
+                bail!("no export template named '{name}'; add one under [export.templates]");
+            }
+            bail!(
+                "no export template named '{name}'; configured are {}",
+                names.join(", ")
+            );
+        };
+        let path = crate::workspace::expand_home(path);
+        std::fs::read_to_string(&path)
+            .with_context(|| format!("cannot read template {}", path.display()))
+    }
+}
+
+/// What saving a map inside a git repository does in git
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GitOnSave {
+    #[default]
+    Off,
+    /// Show the lines changed since the last commit in the status line
+    Stat,
+    /// Stage and commit the map
+    Commit,
+}
+
+/// What is printed to stdout once the editor has quit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "snake_case")]
+pub enum PrintOnExit {
+    #[default]
+    Off,
+    /// The outline as the map shows it, without collapsed branches and,
+    /// unless they are shown, hidden nodes
+    Visible,
+    /// Every node of the map
+    Full,
+}
+
+/// What `--pick` prints of the chosen node
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum PickOutput {
+    /// Its title
+    #[default]
+    Title,
+    /// The titles from the root down to it, separated by `/`
+    Path,
+}
+
+/// What the heatmap shades node backgrounds by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatmapMode {
+    #[default]
+    Off,
+    /// Number of nodes in the subtree, on a log scale
+    Size,
+    /// How recently anything in the subtree was edited this session
+    Activity,
+}
+
+impl HeatmapMode {
+    /// The mode the heatmap key switches to next
+    pub fn next(self) -> Self {
+        match self {
+            HeatmapMode::Off => HeatmapMode::Size,
+            HeatmapMode::Size => HeatmapMode::Activity,
+            HeatmapMode::Activity => HeatmapMode::Off,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Blank lines below a node at `depth` (the root is 0); `is_leaf` also
+    /// covers collapsed nodes
+    pub fn line_spacing_at(&self, depth: usize, is_leaf: bool) -> usize {
+        depth
+            .checked_sub(1)
+            .and_then(|index| self.depth_line_spacing.get(index))
+            .copied()
+            .or(self.leaf_line_spacing.filter(|_| is_leaf))
+            .unwrap_or(self.line_spacing)
+    }
+}
+
 impl Default for AppConfig {
     fn default() -> Self {
         Self {
             max_parent_node_width: default_max_parent_node_width(),
             max_leaf_node_width: default_max_leaf_node_width(),
             line_spacing: default_line_spacing(),
+            depth_line_spacing: Vec::new(),
+            leaf_line_spacing: None,
+            pack_leaves_after: 0,
+            root_title: String::new(),
             symbol1: default_symbol1(),
             symbol2: default_symbol2(),
             show_hidden: default_show_hidden(),
             initial_depth: default_initial_depth(),
             center_lock: default_center_lock(),
             focus_lock: default_focus_lock(),
+            accordion: false,
+            follow_edits: default_follow_edits(),
             max_undo_steps: default_max_undo_steps(),
+            max_undo_memory: default_max_undo_memory(),
+            undo_view_changes: false,
             auto_save: default_auto_save(),
             auto_save_interval: default_auto_save_interval(),
+            no_auto_save: Vec::new(),
+            snapshot_interval: default_snapshot_interval(),
+            snapshot_keep: default_snapshot_keep(),
+            git_on_save: GitOnSave::Off,
+            git_commit_message: default_git_commit_message(),
             echo_keys: default_echo_keys(),
             post_export_command: default_post_export_command(),
+            comment_prefix: default_comment_prefix(),
+            export_comments: false,
+            export_breadcrumbs: false,
+            export_collapsed_placeholder: String::new(),
+            inbox_title: default_inbox_title(),
+            fix_shouting_case: false,
+            acronyms: default_acronyms(),
             clipboard: default_clipboard(),
             clipboard_file: default_clipboard_file(),
             clipboard_in_command: String::new(),
             clipboard_out_command: String::new(),
+            sort_collation: default_sort_collation(),
+            sort_ignore_prefixes: Vec::new(),
+            heatmap: HeatmapMode::Off,
+            max_pins: default_max_pins(),
+            sticky_headers: false,
+            large_map_nodes: default_large_map_nodes(),
+            depth_guides: false,
+            depth_guide_color: default_depth_guide_color(),
+            palette: Palette::Default,
+            theme: HashMap::new(),
+            render_math: default_render_math(),
+            print_on_exit: PrintOnExit::Off,
+            terminal_title: default_terminal_title(),
+            report_cwd: false,
+            leader: default_leader(),
+            key_sequence_timeout: default_key_sequence_timeout(),
+            keys: HashMap::new(),
+            language: None,
+            workspace: None,
+            update_links: false,
+            journal_dir: None,
+            journal_template: None,
+            export: ExportConfig::default(),
+            hooks: HooksConfig::default(),
         }
     }
 }
@@ -131,6 +680,7 @@ fn default_max_leaf_node_width() -> usize {
 fn default_line_spacing() -> usize {
     1
 }
+
 fn default_symbol1() -> String {
     "✓".to_string()
 }
@@ -149,9 +699,18 @@ fn default_center_lock() -> bool {
 fn default_focus_lock() -> bool {
     false
 }
+fn default_follow_edits() -> bool {
+    true
+}
 fn default_max_undo_steps() -> usize {
     24
 }
+fn default_large_map_nodes() -> usize {
+    50_000
+}
+fn default_max_undo_memory() -> usize {
+    64
+}
 fn default_auto_save() -> bool {
     false
 }
@@ -159,34 +718,311 @@ fn default_auto_save() -> bool {
 fn default_auto_save_interval() -> usize {
     30 // 30 seconds default
 }
+
+fn default_snapshot_interval() -> usize {
+    10
+}
+
+fn default_snapshot_keep() -> usize {
+    100
+}
+
+fn default_git_commit_message() -> String {
+    "Update {file}".to_string()
+}
+fn default_leader() -> String {
+    crate::keymap::DEFAULT_LEADER.to_string()
+}
+fn default_key_sequence_timeout() -> u64 {
+    1000
+}
 fn default_echo_keys() -> bool {
     false
 }
 fn default_post_export_command() -> String {
     String::new()
 }
+fn default_comment_prefix() -> String {
+    crate::export::DEFAULT_COMMENT_PREFIX.to_string()
+}
+fn default_inbox_title() -> String {
+    "Inbox".to_string()
+}
+fn default_acronyms() -> Vec<String> {
+    [
+        "API", "CEO", "CFO", "CTO", "EU", "HR", "ID", "IT", "KPI", "OKR", "PDF", "UI", "UK", "URL",
+        "US", "USA", "UX",
+    ]
+    .map(String::from)
+    .to_vec()
+}
 fn default_clipboard() -> ClipboardType {
     ClipboardType::Os
 }
 fn default_clipboard_file() -> PathBuf {
     PathBuf::from("/tmp/h-m-m")
 }
+fn default_sort_collation() -> SortCollation {
+    SortCollation::Natural
+}
+fn default_max_pins() -> usize {
+    5
+}
+fn default_depth_guide_color() -> String {
+    "darkgray".to_string()
+}
+fn default_render_math() -> bool {
+    true
+}
+fn default_terminal_title() -> bool {
+    true
+}
+
+/// Commented config file written on first run; every setting shows its default
+pub const DEFAULT_CONFIG: &str = r#"# h-m-m configuration
+#
+# Every setting below is commented out and shows its default value.
+# Uncomment a line to change it. Settings can also be overridden with
+# HMM_-prefixed environment variables, e.g. HMM_LINE_SPACING=2.
+
+# Maximum width of nodes that have children, and of leaf nodes
+# max_parent_node_width = 25
+# max_leaf_node_width = 55
+
+# Blank lines between sibling nodes
+# line_spacing = 1
+
+# Spacing by depth, starting with the top-level branches, and for leaves;
+# unset depths and leaves use line_spacing. [2] spaces out the branches and
+# a leaf spacing of 0 packs the leaves tightly.
+# depth_line_spacing = []
+# leaf_line_spacing = 0
+
+# A node with more leaf children than this shows them in columns of at most
+# this many rows, side by side, instead of one tall column; 0 never does
+# pack_leaves_after = 0
+
+# Show the root under another title: {title} is its own title and {file} the
+# file name without extension, e.g. "{file}"; empty shows its own title
+# root_title = ""
+
+# Symbols cycled through with the toggle_symbol key
+# symbol1 = "✓"
+# symbol2 = "✗"
+
+# show_hidden = false
+# initial_depth = 1
+# center_lock = false
+# focus_lock = false
+
+# Accordion mode: entering or expanding a branch collapses the branches
+# beside it, so only one branch per level is open
+# accordion = false
+
+# Scroll while editing so the edited node stays in view as its text wraps onto
+# more or fewer lines
+# follow_edits = true
+# max_undo_steps = 24
+
+# Memory the undo history may use, in MiB; older steps are dropped beyond it
+# max_undo_memory = 64
+
+# Make collapsing and expanding undoable too; a run of them, up to the next
+# edit, is undone as one step
+# undo_view_changes = false
+
+# Save automatically this many seconds after the last change
+# auto_save = false
+# auto_save_interval = 30
+
+# Maps left out of auto-saving, e.g. ones shared with others
+# no_auto_save = ["~/maps/team.hmm"]
+
+# Snapshot the open map every snapshot_interval minutes when it changed,
+# keeping the newest snapshot_keep; 0 takes none
+# snapshot_interval = 10
+# snapshot_keep = 100
+
+# After saving a map inside a git repository, show the lines changed since
+# its last commit ("stat") or commit it ("commit") with git_commit_message,
+# where {file} is the file name and {time} the time of the save
+# git_on_save = "off"
+# git_commit_message = "Update {file}"
+
+# echo_keys = false
+# post_export_command = ""
+
+# Nodes whose title starts with comment_prefix are editorial comments: shown
+# in the map but left out of exports, along with everything under them.
+# An empty prefix turns comments off.
+# comment_prefix = "//"
+# export_comments = false
+
+# Subtrees exported from the active node keep their place in the map: their
+# ancestors come first, one per level, down from the root
+# export_breadcrumbs = false
+
+# Exports of the visible outline as text, Markdown or HTML leave collapsed
+# branches out. A placeholder puts a line below them instead, {count} being
+# the number of nodes folded away, e.g. "… ({count} hidden items)"
+# export_collapsed_placeholder = ""
+
+# The capture key asks for a line and adds it under this node below the root,
+# creating it when missing, without moving the selection
+# inbox_title = "Inbox"
+
+# Outlines pasted or imported in capitals only are put in sentence case, keeping
+# the acronyms listed here and words with digits; fix_case does it on demand
+# fix_shouting_case = false
+# acronyms = ["API", "CEO", "CFO", "CTO", "EU", "HR", "ID", "IT", "KPI", "OKR", "PDF", "UI", "UK", "URL", "US", "USA", "UX"]
+
+# Clipboard backend: "os", "file" or "command"
+# clipboard = "os"
+# clipboard_file = "/tmp/h-m-m"
+# clipboard_in_command = ""
+# clipboard_out_command = ""
+
+# Sibling sort order: "natural", "case_insensitive" or "bytewise"
+# sort_collation = "natural"
+
+# Title prefixes sorting passes over, so that "✓ Apple" sorts next to
+# "Apricot": regular expressions matched at the start of titles, as many
+# times over as they match
+# sort_ignore_prefixes = ['[✓✗] ', '\d+\. ', '(?i)(the|a|an) ']
+
+# Shade node backgrounds by subtree size ("size") or by how recently the
+# subtree was edited ("activity"), to spot overgrown or stale branches
+# heatmap = "off"
+
+# Nodes that can be pinned to the bar above the map (up to 9); Alt and the
+# pin's number jump to it
+# max_pins = 5
+
+# Keep the ancestors of the topmost visible node on the top row while
+# scrolling through a long branch, like sticky headers in a code editor
+# sticky_headers = false
+
+# Maps growing past this many nodes turn off the heatmap, sticky headers, depth
+# guides and math rendering and keep fewer undo steps, so they stay responsive;
+# 0 keeps the settings as they are
+# large_map_nodes = 50000
+
+# Vertical guides at the column where each depth starts, like an editor's
+# indent guides; the color is a name, a 256-color index or a hex code
+# depth_guides = false
+# depth_guide_color = "darkgray"
+
+# Colors of the map: "default" uses the terminal's colors; "deuteranopia" and
+# "protanopia" are tuned for red-green color blindness
+# palette = "default"
+
+# Show $...$ math such as $\alpha^2 \leq \frac{1}{n}$ as unicode (α²≤1/n);
+# the LaTeX is kept in the file and shown while editing
+# render_math = true
+
+# Print the outline of the map to stdout on exit: "off", "visible" (without
+# collapsed branches) or "full", for shell scripts using the map as a picker
+# print_on_exit = "off"
+
+# Set the terminal title to "hmm — notes.hmm (modified)", and report the map's
+# directory to terminals that open new tabs in the current one (OSC 7)
+# terminal_title = true
+# report_cwd = false
+
+# UI language, e.g. "de"; defaults to the locale environment
+# language = "en"
+
+# Directory searched recursively for .hmm files by the workspace picker
+# workspace = "~/maps"
+
+# Renaming the map file or a node updates the links to it in the other maps of
+# the workspace, listing the lines it changed. Retitling a node asks first,
+# since undo only reaches the open map
+# update_links = false
+
+# Daily journal maps opened by `hmm-rs journal`; journal_dir defaults to
+# "journal" inside the workspace. The template's {date} and {weekday} are
+# filled in, and the cursor starts on its "Today" node.
+# journal_dir = "~/maps/journal"
+# journal_template = "~/maps/journal-template.hmm"
+
+# Key that <leader> stands for in key sequences, and how many milliseconds a
+# sequence waits for its next key. A key that is bound on its own and also
+# starts a sequence runs once the wait is over.
+# leader = "\\"
+# key_sequence_timeout = 1000
+
+# Key remapping: command name = space-separated keys.
+# Run `hmm-rs --print-keys` to list the command names and current keys.
+# Sequences start with a bracketed key, e.g. "<leader>ex" or "<C-x><C-s>".
+# [keys]
+# redo = "C-r U"
+# export_text = "X <leader>ex"
+
+# Colors overriding the palette's by role: active, ticked (while picking),
+# added, removed and changed (comparing), done, pending and muted. Roles shown
+# side by side in colors hard to tell apart get a warning on start.
+# [theme]
+# active = "lightyellow"
+# done = "33"
+
+# Templates for `hmm-rs export --template NAME` and the export_template key,
+# each a name and the path of a minijinja template. Templates get the nested
+# `root` node and the flat `nodes` list in outline order, each with title,
+# text (without task symbol and due date), depth, path, task ("done" or
+# "todo"), due, collapsed, hidden, leaf and children.
+# [export.templates]
+# latex = "~/.config/h-m-m/outline.tex.j2"
+
+# Commands for the export_pipe key, each a name, a shell command reading an
+# export of the map on its standard input and the format of the export, text
+# unless given (see `hmm-rs formats`). They run from the map's directory with
+# the environment hooks get, and their exit status shows in the status line.
+# [export.pipes]
+# pdf = { command = "pandoc -f markdown -o \"${HMM_FILE%.*}.pdf\"", format = "markdown" }
+# copy = { command = "wl-copy" }
+
+# Shell commands run in the background on events: save, auto_save_failed and
+# task_done. They get HMM_EVENT, HMM_FILE, HMM_NODE (the selected node),
+# HMM_NODE_PATH (titles from the root, joined by " > ") and, on failures,
+# HMM_ERROR in their environment.
+# [hooks]
+# task_done = "notify-send Done \"$HMM_NODE\""
+"#;
+
+/// Path of the config file `load_config` reads
+#[cfg(feature = "cli")]
+pub fn config_path(args: &CliArgs) -> PathBuf {
+    args.config.clone().unwrap_or_else(get_default_config_path)
+}
+
+/// Write [`DEFAULT_CONFIG`] to `path`, creating its directory
+#[cfg(feature = "cli")]
+pub fn write_default_config(path: &std::path::Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, DEFAULT_CONFIG)?;
+    Ok(())
+}
 
+#[cfg(feature = "cli")]
 pub fn load_config(args: &CliArgs) -> Result<AppConfig> {
     let mut config = config::Config::builder();
 
     // Start with default values
     config = config.add_source(config::Config::try_from(&AppConfig::default())?);
 
-    // Try to load from config file
-    let config_path = if let Some(ref path) = args.config {
-        path.clone()
-    } else {
-        get_default_config_path()
-    };
+    // Try to load from config file; h-m-m.conf and other unknown extensions are TOML
+    let config_path = config_path(args);
 
     if config_path.exists() {
-        config = config.add_source(config::File::from(config_path));
+        let file = config::File::from(config_path.as_path());
+        let file = match config_path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml" | "json" | "json5" | "yaml" | "yml" | "ini" | "ron") => file,
+            _ => file.format(config::FileFormat::Toml),
+        };
+        config = config.add_source(file);
     }
 
     // Apply environment variables prefixed with HMM_
@@ -202,11 +1038,22 @@ pub fn load_config(args: &CliArgs) -> Result<AppConfig> {
     if let Some(auto) = args.auto_save {
         config = config.set_override("auto_save", auto)?;
     }
+    if let Some(ref dir) = args.workspace {
+        config = config.set_override("workspace", dir.to_string_lossy().into_owned())?;
+    }
 
     let config = config.build()?;
-    Ok(config.try_deserialize()?)
+    let mut config: AppConfig = config.try_deserialize()?;
+    if config.language.is_none() {
+        config.language = crate::i18n::locale_from_env();
+    }
+    if let Some(mode) = args.print_on_exit {
+        config.print_on_exit = mode;
+    }
+    Ok(config)
 }
 
+#[cfg(feature = "cli")]
 fn get_default_config_path() -> PathBuf {
     if let Some(proj_dirs) = ProjectDirs::from("", "", "h-m-m") {
         let config_dir = proj_dirs.config_dir();
@@ -220,3 +1067,104 @@ fn get_default_config_path() -> PathBuf {
             .join("h-m-m.conf")
     }
 }
+
+/// File holding the recently opened maps, in the platform state directory
+#[cfg(feature = "cli")]
+pub fn recent_files_path() -> PathBuf {
+    state_dir().join("recent")
+}
+
+/// File holding the pinned nodes of each map, in the platform state directory
+#[cfg(feature = "cli")]
+pub fn pins_path() -> PathBuf {
+    state_dir().join("pins.json")
+}
+
+/// Directory holding the snapshots of each map, in the platform state directory
+#[cfg(feature = "cli")]
+pub fn snapshots_dir() -> PathBuf {
+    state_dir().join("snapshots")
+}
+
+#[cfg(feature = "cli")]
+fn state_dir() -> PathBuf {
+    if let Some(proj_dirs) = ProjectDirs::from("", "", "h-m-m") {
+        proj_dirs
+            .state_dir()
+            .unwrap_or_else(|| proj_dirs.data_local_dir())
+            .to_path_buf()
+    } else {
+        let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+        PathBuf::from(home)
+            .join(".local")
+            .join("state")
+            .join("h-m-m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config_template_matches_defaults() {
+        let uncommented: String = DEFAULT_CONFIG
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            .filter(|line| {
+                let key = line.split(" = ").next().unwrap_or_default();
+                line.contains(" = ")
+                    && key.chars().all(|c| c.is_ascii_lowercase() || c == '_')
+                    && !matches!(
+                        key,
+                        "language"
+                            | "workspace"
+                            | "no_auto_save"
+                            | "sort_ignore_prefixes"
+                            | "journal_dir"
+                            | "journal_template"
+                            | "redo"
+                            | "export_text"
+                            | "latex"
+                            | "task_done"
+                            | "leaf_line_spacing"
+                    )
+            })
+            .map(|line| format!("{}\n", line))
+            .collect();
+
+        let parsed: AppConfig = toml::from_str(&uncommented).unwrap();
+        assert_eq!(
+            format!("{:?}", parsed),
+            format!("{:?}", AppConfig::default())
+        );
+    }
+
+    #[test]
+    fn test_line_spacing_by_depth() {
+        let config = AppConfig {
+            line_spacing: 1,
+            depth_line_spacing: vec![3, 2],
+            leaf_line_spacing: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(config.line_spacing_at(0, false), 1);
+        assert_eq!(config.line_spacing_at(1, false), 3);
+        assert_eq!(config.line_spacing_at(2, true), 2);
+        assert_eq!(config.line_spacing_at(3, false), 1);
+        assert_eq!(config.line_spacing_at(3, true), 0);
+        assert_eq!(AppConfig::default().line_spacing_at(2, true), 1);
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_journal_subcommand_and_filename() {
+        let args = CliArgs::try_parse_from(["hmm-rs", "journal"]).unwrap();
+        assert_eq!(args.command, Some(Command::Journal));
+        assert_eq!(args.filename, None);
+
+        let args = CliArgs::try_parse_from(["hmm-rs", "notes.hmm"]).unwrap();
+        assert_eq!(args.command, None);
+        assert_eq!(args.filename, Some(PathBuf::from("notes.hmm")));
+    }
+}
@@ -0,0 +1,154 @@
+//! Structural differences between two versions of a map.
+//!
+//! The roots are matched with each other, then the children of matched
+//! nodes by equal title. Children left over on both sides are paired in
+//! order as renamed nodes; any still left were added or removed along with
+//! everything below them.
+
+use crate::model::{Node, NodeId};
+use indextree::Arena;
+use std::collections::HashMap;
+
+/// How a node differs from the other version
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    /// Only in the newer version
+    Added,
+    /// Only in the older version
+    Removed,
+    /// In both versions under another title
+    Changed,
+}
+
+/// Changed nodes of both versions of a map; unchanged nodes are absent
+#[derive(Debug, Default)]
+pub struct TreeDiff {
+    pub old: HashMap<NodeId, Change>,
+    pub new: HashMap<NodeId, Change>,
+}
+
+impl TreeDiff {
+    /// Numbers of added, removed and changed nodes
+    pub fn counts(&self) -> (usize, usize, usize) {
+        let count = |changes: &HashMap<NodeId, Change>, kind| {
+            changes.values().filter(|&&change| change == kind).count()
+        };
+        (
+            count(&self.new, Change::Added),
+            count(&self.old, Change::Removed),
+            count(&self.new, Change::Changed),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.old.is_empty() && self.new.is_empty()
+    }
+}
+
+/// Differences between the map at `old_root` and the one at `new_root`
+pub fn diff_trees(
+    old: &Arena<Node>,
+    old_root: NodeId,
+    new: &Arena<Node>,
+    new_root: NodeId,
+) -> TreeDiff {
+    let mut diff = TreeDiff::default();
+    let mut pairs = vec![(old_root, new_root)];
+
+    while let Some((old_id, new_id)) = pairs.pop() {
+        if old[old_id].get().title != new[new_id].get().title {
+            diff.old.insert(old_id, Change::Changed);
+            diff.new.insert(new_id, Change::Changed);
+        }
+
+        let mut old_children: Vec<Option<NodeId>> = old_id.children(old).map(Some).collect();
+        let mut unmatched_new = Vec::new();
+        for new_child in new_id.children(new) {
+            let title = &new[new_child].get().title;
+            let same = old_children
+                .iter_mut()
+                .find(|old_child| old_child.is_some_and(|id| &old[id].get().title == title));
+            match same.and_then(Option::take) {
+                Some(old_child) => pairs.push((old_child, new_child)),
+                None => unmatched_new.push(new_child),
+            }
+        }
+
+        let mut unmatched_old = old_children.into_iter().flatten();
+        for new_child in unmatched_new {
+            match unmatched_old.next() {
+                Some(old_child) => pairs.push((old_child, new_child)),
+                None => mark_subtree(&mut diff.new, new, new_child, Change::Added),
+            }
+        }
+        for old_child in unmatched_old {
+            mark_subtree(&mut diff.old, old, old_child, Change::Removed);
+        }
+    }
+    diff
+}
+
+fn mark_subtree(
+    changes: &mut HashMap<NodeId, Change>,
+    tree: &Arena<Node>,
+    node_id: NodeId,
+    change: Change,
+) {
+    changes.extend(node_id.descendants(tree).map(|id| (id, change)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_hmm_content;
+
+    fn change_of(
+        diff: &HashMap<NodeId, Change>,
+        tree: &Arena<Node>,
+        root: NodeId,
+        title: &str,
+    ) -> Option<Change> {
+        let id = root
+            .descendants(tree)
+            .find(|&id| tree[id].get().title == title)
+            .unwrap();
+        diff.get(&id).copied()
+    }
+
+    #[test]
+    fn test_diff_trees() {
+        let (old, old_root) =
+            parse_hmm_content("Plan\n\tKeep\n\t\tDetail\n\tDrop\n\t\tGone\n\tRename me\n").unwrap();
+        let (new, new_root) =
+            parse_hmm_content("Plan\n\tRenamed\n\tKeep\n\t\tDetail\n\t\tMore\n").unwrap();
+
+        let diff = diff_trees(&old, old_root, &new, new_root);
+        assert_eq!(diff.counts(), (1, 2, 1));
+
+        assert_eq!(change_of(&diff.new, &new, new_root, "Keep"), None);
+        assert_eq!(
+            change_of(&diff.new, &new, new_root, "More"),
+            Some(Change::Added)
+        );
+        // Renamed pairs with the first unmatched old child
+        assert_eq!(
+            change_of(&diff.new, &new, new_root, "Renamed"),
+            Some(Change::Changed)
+        );
+        assert_eq!(
+            change_of(&diff.old, &old, old_root, "Drop"),
+            Some(Change::Changed)
+        );
+        assert_eq!(
+            change_of(&diff.old, &old, old_root, "Gone"),
+            Some(Change::Removed)
+        );
+        assert_eq!(
+            change_of(&diff.old, &old, old_root, "Rename me"),
+            Some(Change::Removed)
+        );
+
+        let same = diff_trees(&old, old_root, &old, old_root);
+        assert!(same.is_empty());
+    }
+}
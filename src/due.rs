@@ -0,0 +1,74 @@
+//! Due dates of nodes.
+//!
+//! A `due:2024-05-01` tag anywhere in a title, as in todo.txt, gives the
+//! node a due date, and `due:2024-05-01T14:30` a time of day as well. The tag
+//! stays part of the title, so it survives every format the map is saved in.
+
+use chrono::{NaiveDate, NaiveTime};
+
+/// Prefix of the due date tag
+pub const DUE_TAG: &str = "due:";
+
+/// When a node is due
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Due {
+    pub date: NaiveDate,
+    /// Time of day, if the tag names one
+    pub time: Option<NaiveTime>,
+}
+
+/// The due date tagged in `title`, if any
+pub fn due_date(title: &str) -> Option<Due> {
+    title.split_whitespace().find_map(parse_tag)
+}
+
+/// `title` without its due date tag
+pub fn without_due(title: &str) -> String {
+    if due_date(title).is_none() {
+        return title.to_string();
+    }
+    title
+        .split_whitespace()
+        .filter(|word| parse_tag(word).is_none())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn parse_tag(word: &str) -> Option<Due> {
+    let value = word.strip_prefix(DUE_TAG)?;
+    let (date, time) = match value.split_once('T') {
+        Some((date, time)) => (date, Some(NaiveTime::parse_from_str(time, "%H:%M").ok()?)),
+        None => (value, None),
+    };
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    Some(Due { date, time })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_date() {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        assert_eq!(
+            due_date("Ship due:2024-05-01"),
+            Some(Due { date, time: None })
+        );
+        assert_eq!(
+            due_date("due:2024-05-01T14:30 Review"),
+            Some(Due {
+                date,
+                time: NaiveTime::from_hms_opt(14, 30, 0)
+            })
+        );
+        assert_eq!(due_date("Ship due:tomorrow"), None);
+        assert_eq!(due_date("Ship undue:2024-05-01"), None);
+    }
+
+    #[test]
+    fn test_without_due() {
+        assert_eq!(without_due("Ship  due:2024-05-01 v2"), "Ship v2");
+        assert_eq!(without_due("Keep  spacing"), "Keep  spacing");
+    }
+}
@@ -1,129 +1,149 @@
 use crate::actions::Action;
-use crate::app::{AppMode, AppState};
+use crate::app::{AppMode, AppState, Damage, QuitChoice};
+use crate::keymap::{Key, KeyMatch};
+use crate::parser::MapFormat;
 use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyModifiers};
-use std::time::Duration;
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use std::time::{Duration, Instant};
 
 pub fn handle_events(app: &mut AppState) -> Result<Option<Action>> {
     if event::poll(Duration::from_millis(10))? {
-        if let Event::Key(key) = event::read()? {
-            return Ok(handle_key_event(app, key));
+        match event::read()? {
+            Event::Key(key) => return Ok(handle_key_event(app, key)),
+            Event::Mouse(mouse) => return Ok(handle_mouse_event(app, mouse)),
+            Event::Resize(..) => app.mark_damage(Damage::View),
+            _ => {}
         }
     }
-    Ok(None)
+    Ok(expire_pending_keys(app))
 }
 
-fn handle_key_event(app: &AppState, key: KeyEvent) -> Option<Action> {
+/// Whether input is waiting to be handled
+pub fn has_pending() -> Result<bool> {
+    Ok(event::poll(Duration::ZERO)?)
+}
+
+/// Dragging with the left button pans the map in normal mode. Each drag
+/// event pans by the distance since the previous one; the event loop draws
+/// the moves of a fast drag together, at most once a frame.
+fn handle_mouse_event(app: &mut AppState, mouse: MouseEvent) -> Option<Action> {
+    if !matches!(app.mode, AppMode::Normal) {
+        app.drag_from = None;
+        return None;
+    }
+    let cell = (mouse.column, mouse.row);
+    match mouse.kind {
+        MouseEventKind::Down(MouseButton::Left) => {
+            app.drag_from = Some(cell);
+            None
+        }
+        MouseEventKind::Drag(MouseButton::Left) => {
+            let (column, row) = app.drag_from.replace(cell)?;
+            let columns = i32::from(cell.0) - i32::from(column);
+            let rows = i32::from(cell.1) - i32::from(row);
+            (columns != 0 || rows != 0).then_some(Action::PanView(columns, rows))
+        }
+        MouseEventKind::Up(MouseButton::Left) => {
+            // The last frame of the drag may have been held back
+            app.drag_from = None;
+            app.mark_damage(Damage::View);
+            None
+        }
+        _ => None,
+    }
+}
+
+fn handle_key_event(app: &mut AppState, key: KeyEvent) -> Option<Action> {
     match &app.mode {
-        AppMode::Normal => handle_normal_mode(key),
+        AppMode::Normal => handle_normal_mode(app, key),
         AppMode::Editing { .. } => handle_editing_mode(key),
         AppMode::Search { .. } => handle_search_mode(key),
         AppMode::Help => handle_help_mode(key),
+        AppMode::Picker { .. } => handle_picker_mode(key),
+        AppMode::QuitPrompt { selected } => handle_quit_prompt(*selected, key),
+        AppMode::SaveAs { .. } => handle_save_as_mode(key),
+        AppMode::RenameFile { .. } => handle_rename_file_mode(key),
+        AppMode::Capture { .. } => handle_capture_mode(key),
+        AppMode::FormatPrompt { selected } => handle_format_prompt(*selected, key),
+        AppMode::Settings { buffer, .. } => handle_settings_mode(buffer.is_some(), key),
+        AppMode::PasteMerge { .. } => handle_paste_merge(key),
+        AppMode::Snapshots { .. } => handle_snapshots_mode(key),
+        AppMode::Compare { .. } => handle_compare_mode(key),
+        AppMode::ConfirmRun { .. } => handle_confirm_run(key),
+        AppMode::ConfirmLinks { .. } => handle_confirm_links(key),
+        AppMode::Rename { .. } => handle_rename_mode(key),
     }
 }
 
-fn handle_normal_mode(key: KeyEvent) -> Option<Action> {
-    use KeyCode::*;
-
-    match (key.code, key.modifiers) {
-        // Quit
-        (Char('q'), KeyModifiers::NONE) => Some(Action::Quit),
-        (Char('Q'), KeyModifiers::SHIFT) => Some(Action::ForceQuit),
-        (Char('c'), KeyModifiers::CONTROL) => Some(Action::Quit),
-
-        // Movement
-        (Char('h'), KeyModifiers::NONE) | (Left, _) => Some(Action::GoLeft),
-        (Char('j'), KeyModifiers::NONE) | (Down, _) => Some(Action::GoDown),
-        (Char('k'), KeyModifiers::NONE) | (Up, _) => Some(Action::GoUp),
-        (Char('l'), KeyModifiers::NONE) => Some(Action::GoRight),
-
-        // Node manipulation
-        (Char('o'), KeyModifiers::NONE) | (Enter, KeyModifiers::NONE) => {
-            Some(Action::InsertSibling)
+/// Match the key against the keymap, together with any sequence it continues
+fn handle_normal_mode(app: &mut AppState, key: KeyEvent) -> Option<Action> {
+    // Picking nodes takes Enter, space and Esc over from the key bindings
+    if app.pick.is_some() && app.pending_keys.is_empty() {
+        match key.code {
+            KeyCode::Enter => return Some(Action::PickNode),
+            KeyCode::Char(' ') => return Some(Action::TogglePickSelection),
+            KeyCode::Esc => return Some(Action::CancelPick),
+            _ => {}
         }
-        (Char('O'), KeyModifiers::SHIFT) | (Tab, KeyModifiers::NONE) => Some(Action::InsertChild),
-        (Char(' '), KeyModifiers::NONE) => Some(Action::ToggleCollapse),
-        (Char('d'), KeyModifiers::NONE) => Some(Action::DeleteNode),
-        (Char('D'), KeyModifiers::SHIFT) => Some(Action::DeleteChildren),
-
-        // Editing
-        (Char('e'), KeyModifiers::NONE) | (Char('i'), KeyModifiers::NONE) => {
-            Some(Action::EditNodeAppend)
+    }
+    let key = key_from_event(key)?;
+    app.pending_keys.push(key);
+    // The pending keys show in the status line
+    app.mark_damage(Damage::View);
+
+    match app.keymap.match_keys(&app.pending_keys) {
+        KeyMatch::Prefix(_) => {
+            app.pending_keys_at = Some(Instant::now());
+            None
         }
-        (Char('E'), KeyModifiers::SHIFT) | (Char('I'), KeyModifiers::SHIFT) => {
-            Some(Action::EditNodeReplace)
+        KeyMatch::Action(action) => {
+            clear_pending_keys(app);
+            Some(action)
         }
-        (Char('a'), KeyModifiers::NONE) => Some(Action::EditNodeAppend),
-        (Char('A'), KeyModifiers::SHIFT) => Some(Action::EditNodeReplace),
-
-        // View control
-        (Char('c'), KeyModifiers::NONE) => Some(Action::CenterActiveNode),
-        (Char('C'), KeyModifiers::SHIFT) => Some(Action::ToggleCenterLock),
-        (Char('f'), KeyModifiers::NONE) => Some(Action::Focus),
-        (Char('F'), KeyModifiers::SHIFT) => Some(Action::ToggleFocusLock),
-
-        // Collapsing
-        (Char('v'), KeyModifiers::NONE) => Some(Action::CollapseAll),
-        (Char('b'), KeyModifiers::NONE) => Some(Action::ExpandAll),
-        (Char('V'), KeyModifiers::SHIFT) => Some(Action::CollapseChildren),
-        (Char('r'), KeyModifiers::NONE) => Some(Action::CollapseOtherBranches),
-        (Char('1'), KeyModifiers::NONE) => Some(Action::CollapseToLevel(1)),
-        (Char('2'), KeyModifiers::NONE) => Some(Action::CollapseToLevel(2)),
-        (Char('3'), KeyModifiers::NONE) => Some(Action::CollapseToLevel(3)),
-        (Char('4'), KeyModifiers::NONE) => Some(Action::CollapseToLevel(4)),
-        (Char('5'), KeyModifiers::NONE) => Some(Action::CollapseToLevel(5)),
-
-        // Navigation
-        (Char('g'), KeyModifiers::NONE) => Some(Action::GoToTop),
-        (Char('G'), KeyModifiers::SHIFT) => Some(Action::GoToBottom),
-        (Char('m'), KeyModifiers::NONE) | (Char('~'), KeyModifiers::NONE) => Some(Action::GoToRoot),
-
-        // File operations
-        (Char('s'), KeyModifiers::NONE) => Some(Action::Save),
-        (Char('S'), KeyModifiers::SHIFT) => Some(Action::SaveAs),
-
-        // Export
-        (Char('X'), KeyModifiers::SHIFT) => Some(Action::ExportText),
-
-        // Clipboard
-        (Char('y'), KeyModifiers::NONE) => Some(Action::YankNode),
-        (Char('Y'), KeyModifiers::SHIFT) => Some(Action::YankChildren),
-        (Char('p'), KeyModifiers::NONE) => Some(Action::PasteAsChildren),
-        (Char('P'), KeyModifiers::SHIFT) => Some(Action::PasteAsSiblings),
-
-        // Node movement
-        (Char('J'), KeyModifiers::SHIFT) => Some(Action::MoveNodeDown),
-        (Char('K'), KeyModifiers::SHIFT) => Some(Action::MoveNodeUp),
-
-        // Undo/Redo
-        (Char('u'), KeyModifiers::NONE) => Some(Action::Undo),
-        (Char('r'), KeyModifiers::CONTROL) => Some(Action::Redo),
-
-        // Search
-        (Char('/'), KeyModifiers::NONE) | (Char('f'), KeyModifiers::CONTROL) => {
-            Some(Action::Search)
+        KeyMatch::None => {
+            clear_pending_keys(app);
+            None
         }
-        (Char('n'), KeyModifiers::NONE) => Some(Action::NextSearchResult),
-        (Char('N'), KeyModifiers::SHIFT) => Some(Action::PreviousSearchResult),
-
-        // Symbols
-        (Char('t'), KeyModifiers::NONE) => Some(Action::ToggleSymbol),
-        (Char('T'), KeyModifiers::SHIFT) => Some(Action::SortSiblings),
-        (Char('#'), KeyModifiers::NONE) => Some(Action::ToggleNumbers),
-
-        // Layout
-        (Char('w'), KeyModifiers::NONE) => Some(Action::IncreaseTextWidth),
-        (Char('W'), KeyModifiers::SHIFT) => Some(Action::DecreaseTextWidth),
-        (Char('z'), KeyModifiers::NONE) => Some(Action::DecreaseLineSpacing),
-        (Char('Z'), KeyModifiers::SHIFT) => Some(Action::IncreaseLineSpacing),
+    }
+}
 
-        // Hidden nodes
-        (Char('H'), KeyModifiers::SHIFT) => Some(Action::ToggleHide),
-        (Char('h'), KeyModifiers::CONTROL) => Some(Action::ToggleShowHidden),
+/// Once a sequence has waited too long for its next key, give up on it and
+/// run what its keys so far are bound to, if anything
+fn expire_pending_keys(app: &mut AppState) -> Option<Action> {
+    let timeout = Duration::from_millis(app.config.key_sequence_timeout);
+    if app.pending_keys_at?.elapsed() < timeout {
+        return None;
+    }
+    let action = match app.keymap.match_keys(&app.pending_keys) {
+        KeyMatch::Prefix(action) => action,
+        _ => None,
+    };
+    clear_pending_keys(app);
+    app.mark_damage(Damage::View);
+    action
+}
 
-        // Help
-        (Char('?'), KeyModifiers::NONE) => Some(Action::ShowHelp),
+fn clear_pending_keys(app: &mut AppState) {
+    app.pending_keys.clear();
+    app.pending_keys_at = None;
+}
 
+/// Translate a terminal key event into a keymap key
+fn key_from_event(key: KeyEvent) -> Option<Key> {
+    match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Key::Ctrl(c.to_ascii_lowercase()))
+        }
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::ALT) => Some(Key::Alt(c)),
+        KeyCode::Char(c) => Some(Key::Char(c)),
+        KeyCode::Left => Some(Key::Left),
+        KeyCode::Right => Some(Key::Right),
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Enter if key.modifiers.is_empty() => Some(Key::Enter),
+        KeyCode::Tab if key.modifiers.is_empty() => Some(Key::Tab),
         _ => None,
     }
 }
@@ -187,3 +207,255 @@ fn handle_help_mode(key: KeyEvent) -> Option<Action> {
         _ => None,
     }
 }
+
+fn handle_picker_mode(key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match (key.code, key.modifiers) {
+        (Esc, _) => Some(Action::CancelPicker),
+        (Enter, _) => Some(Action::ConfirmPicker),
+        (Up, _) | (Char('p'), KeyModifiers::CONTROL) => Some(Action::PickerUp),
+        (Down, _) | (Char('n'), KeyModifiers::CONTROL) => Some(Action::PickerDown),
+        (Backspace, _) => Some(Action::BackspacePicker),
+        (Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => Some(Action::TypePickerChar(c)),
+        _ => None,
+    }
+}
+
+fn handle_settings_mode(editing: bool, key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    if editing {
+        return match (key.code, key.modifiers) {
+            (Esc, _) => Some(Action::CancelSetting),
+            (Enter, _) => Some(Action::ActivateSetting),
+            (Backspace, _) => Some(Action::BackspaceSetting),
+            (Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => Some(Action::TypeSettingChar(c)),
+            _ => None,
+        };
+    }
+    match key.code {
+        Esc | Char('q') | Char(',') => Some(Action::CloseSettings),
+        Up | Char('k') => Some(Action::SettingsUp),
+        Down | Char('j') => Some(Action::SettingsDown),
+        Enter | Char(' ') => Some(Action::ActivateSetting),
+        Char('w') => Some(Action::WriteSettings),
+        _ => None,
+    }
+}
+
+fn handle_quit_prompt(selected: QuitChoice, key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match key.code {
+        Char('s') | Char('y') => Some(Action::ChooseQuit(QuitChoice::Save)),
+        Char('d') | Char('n') => Some(Action::ChooseQuit(QuitChoice::Discard)),
+        Char('c') | Esc => Some(Action::ChooseQuit(QuitChoice::Cancel)),
+        Enter => Some(Action::ChooseQuit(selected)),
+        Right | Tab | Char('l') => Some(Action::QuitPromptNext),
+        Left | BackTab | Char('h') => Some(Action::QuitPromptPrevious),
+        _ => None,
+    }
+}
+
+fn handle_paste_merge(key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match key.code {
+        Esc | Char('q') => Some(Action::CancelPaste),
+        Enter | Char('m') => Some(Action::MergePaste),
+        Char('d') => Some(Action::DuplicatePaste),
+        _ => None,
+    }
+}
+
+fn handle_snapshots_mode(key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match key.code {
+        Esc | Char('q') => Some(Action::CloseSnapshots),
+        Enter | Char('r') => Some(Action::RestoreSnapshot),
+        Down | Char('j') => Some(Action::SnapshotOlder),
+        Up | Char('k') => Some(Action::SnapshotNewer),
+        Char('c') => Some(Action::CompareSnapshot),
+        _ => None,
+    }
+}
+
+fn handle_compare_mode(key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    // Only moving around; the map is not edited while compared
+    match key.code {
+        Esc | Char('q') => Some(Action::CloseCompare),
+        Left | Char('h') => Some(Action::GoLeft),
+        Down | Char('j') => Some(Action::GoDown),
+        Up | Char('k') => Some(Action::GoUp),
+        Right | Char('l') => Some(Action::GoRight),
+        _ => None,
+    }
+}
+
+fn handle_confirm_run(key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match key.code {
+        Enter | Char('y') => Some(Action::ConfirmRun),
+        Esc | Char('n') | Char('q') => Some(Action::CancelRun),
+        _ => None,
+    }
+}
+
+fn handle_confirm_links(key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match key.code {
+        Enter | Char('y') => Some(Action::ConfirmLinkUpdates),
+        Esc | Char('n') | Char('q') => Some(Action::CancelLinkUpdates),
+        _ => None,
+    }
+}
+
+fn handle_rename_mode(key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match (key.code, key.modifiers) {
+        (Esc, _) => Some(Action::CancelRename),
+        (Enter, _) => Some(Action::ConfirmRename),
+        (Backspace, _) => Some(Action::BackspaceRename),
+        (Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => Some(Action::TypeRenameChar(c)),
+        _ => None,
+    }
+}
+
+fn handle_format_prompt(selected: MapFormat, key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match key.code {
+        Esc => Some(Action::CancelSaveAs),
+        Enter => Some(Action::ChooseSaveFormat(selected)),
+        Right | Down | Tab | Char('l') | Char('j') => Some(Action::FormatPromptNext),
+        Left | Up | BackTab | Char('h') | Char('k') => Some(Action::FormatPromptPrevious),
+        _ => None,
+    }
+}
+
+fn handle_save_as_mode(key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match (key.code, key.modifiers) {
+        (Esc, _) => Some(Action::CancelSaveAs),
+        (Enter, _) => Some(Action::ConfirmSaveAs),
+        (Backspace, _) => Some(Action::BackspaceSaveAs),
+        (Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => Some(Action::TypeSaveAsChar(c)),
+        _ => None,
+    }
+}
+
+fn handle_rename_file_mode(key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match (key.code, key.modifiers) {
+        (Esc, _) => Some(Action::CancelRenameFile),
+        (Enter, _) => Some(Action::ConfirmRenameFile),
+        (Backspace, _) => Some(Action::BackspaceRenameFile),
+        (Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => Some(Action::TypeRenameFileChar(c)),
+        _ => None,
+    }
+}
+
+fn handle_capture_mode(key: KeyEvent) -> Option<Action> {
+    use KeyCode::*;
+
+    match (key.code, key.modifiers) {
+        (Esc, _) => Some(Action::CancelCapture),
+        (Enter, _) => Some(Action::ConfirmCapture),
+        (Backspace, _) => Some(Action::BackspaceCapture),
+        (Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => Some(Action::TypeCaptureChar(c)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use std::collections::HashMap;
+
+    fn press(app: &mut AppState, c: char) -> Option<Action> {
+        handle_key_event(app, KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn test_key_sequences_wait_for_their_next_key() {
+        let config = AppConfig {
+            leader: "space".to_string(),
+            keys: HashMap::from([("export_text".to_string(), "<leader>ex".to_string())]),
+            ..AppConfig::default()
+        };
+        let mut app = AppState::new(config);
+
+        assert_eq!(press(&mut app, ' '), None);
+        assert_eq!(press(&mut app, 'e'), None);
+        assert_eq!(app.pending_keys, [Key::Char(' '), Key::Char('e')]);
+        assert_eq!(press(&mut app, 'x'), Some(Action::ExportText));
+        assert!(app.pending_keys.is_empty());
+
+        // An unbound continuation drops the sequence
+        press(&mut app, ' ');
+        assert_eq!(press(&mut app, 'j'), None);
+        assert!(app.pending_keys.is_empty());
+        assert_eq!(press(&mut app, 'j'), Some(Action::GoDown));
+
+        // Space is still collapse on its own, once the sequence times out
+        press(&mut app, ' ');
+        assert_eq!(expire_pending_keys(&mut app), None);
+        app.config.key_sequence_timeout = 0;
+        assert_eq!(expire_pending_keys(&mut app), Some(Action::ToggleCollapse));
+        assert!(app.pending_keys.is_empty());
+    }
+
+    #[test]
+    fn test_drag_pans_by_the_distance_moved() {
+        let mut app = AppState::new(AppConfig::default());
+        let mut mouse = |kind, column, row| {
+            let event = MouseEvent {
+                kind,
+                column,
+                row,
+                modifiers: KeyModifiers::NONE,
+            };
+            handle_mouse_event(&mut app, event)
+        };
+
+        assert_eq!(mouse(MouseEventKind::Drag(MouseButton::Left), 5, 5), None);
+        assert_eq!(mouse(MouseEventKind::Down(MouseButton::Left), 10, 4), None);
+        assert_eq!(
+            mouse(MouseEventKind::Drag(MouseButton::Left), 13, 2),
+            Some(Action::PanView(3, -2))
+        );
+        assert_eq!(
+            mouse(MouseEventKind::Drag(MouseButton::Left), 12, 2),
+            Some(Action::PanView(-1, 0))
+        );
+        assert_eq!(mouse(MouseEventKind::Up(MouseButton::Left), 12, 2), None);
+        assert_eq!(app.drag_from, None);
+    }
+
+    #[test]
+    fn test_pick_takes_over_enter_and_esc() {
+        let mut app = AppState::new(AppConfig::default());
+        let enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE);
+        let esc = KeyEvent::new(KeyCode::Esc, KeyModifiers::NONE);
+        assert_eq!(
+            handle_key_event(&mut app, enter),
+            Some(Action::InsertSibling)
+        );
+
+        app.pick = Some(crate::config::PickOutput::Title);
+        assert_eq!(handle_key_event(&mut app, enter), Some(Action::PickNode));
+        assert_eq!(handle_key_event(&mut app, esc), Some(Action::CancelPick));
+        assert_eq!(press(&mut app, ' '), Some(Action::TogglePickSelection));
+        assert_eq!(press(&mut app, 'j'), Some(Action::GoDown));
+    }
+}
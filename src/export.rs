@@ -0,0 +1,1033 @@
+//! Conversions of a map into other text formats.
+//!
+//! Exporters are pure functions over the tree and perform no I/O, so they are
+//! available in every build, including `wasm32-unknown-unknown`.
+
+use crate::aggregates::Aggregates;
+use crate::config::AppConfig;
+use crate::due::{due_date, without_due, Due};
+use crate::import::{task_state, to_taskwarrior, to_todo_txt};
+use crate::kind::{without_kind, NodeKind};
+use crate::model::{Node, NodeId};
+use anyhow::Result;
+use indextree::Arena;
+use serde::{Deserialize, Serialize};
+use std::rc::Rc;
+
+/// Title prefix marking comment nodes unless configured otherwise
+pub const DEFAULT_COMMENT_PREFIX: &str = "//";
+
+/// Which nodes the exporters leave out besides collapsed branches, and how
+/// they recognize tasks
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExportOptions {
+    /// Title prefix of comment nodes, which are left out with everything
+    /// under them; `None` exports comments like any other node
+    pub comment_prefix: Option<String>,
+    /// Symbol of completed tasks, `symbol1`
+    pub done_symbol: String,
+    /// Symbol of tasks still to do, `symbol2`
+    pub pending_symbol: String,
+    /// Line put below collapsed branches in text, Markdown and HTML, with
+    /// `{count}` standing for the number of nodes folded away; `None` leaves
+    /// collapsed branches out without a trace
+    pub collapsed_placeholder: Option<String>,
+    /// Node counts of the map being exported, so that placeholders need not
+    /// walk what is folded away; `None` counts by walking
+    pub folded_counts: Option<Rc<Aggregates>>,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            comment_prefix: Some(DEFAULT_COMMENT_PREFIX.to_string()),
+            done_symbol: "✓".to_string(),
+            pending_symbol: "✗".to_string(),
+            collapsed_placeholder: None,
+            folded_counts: None,
+        }
+    }
+}
+
+impl ExportOptions {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let comment_prefix = Some(config.comment_prefix.clone())
+            .filter(|prefix| !config.export_comments && !prefix.is_empty());
+        Self {
+            comment_prefix,
+            done_symbol: config.symbol1.clone(),
+            pending_symbol: config.symbol2.clone(),
+            collapsed_placeholder: Some(config.export_collapsed_placeholder.clone())
+                .filter(|placeholder| !placeholder.is_empty()),
+            folded_counts: None,
+        }
+    }
+
+    /// Whether `node` and its subtree stay out of the export
+    pub(crate) fn excludes(&self, node: &Node) -> bool {
+        self.comment_prefix
+            .as_deref()
+            .is_some_and(|prefix| node.is_comment(prefix))
+    }
+
+    /// The line standing in for what is folded away below `node_id`, if it
+    /// is collapsed over nodes an export would show and placeholders are on
+    pub(crate) fn placeholder(&self, tree: &Arena<Node>, node_id: NodeId) -> Option<String> {
+        let placeholder = self.collapsed_placeholder.as_deref()?;
+        if !tree[node_id].get().is_collapsed {
+            return None;
+        }
+        let counted = self
+            .folded_counts
+            .as_deref()
+            .filter(|counts| counts.comment_prefix == self.comment_prefix)
+            .and_then(|counts| counts.exported.get(&node_id));
+        let count = match counted {
+            Some(&count) => count,
+            None => self.count_exported(tree, node_id),
+        };
+        (count > 0).then(|| placeholder.replace("{count}", &count.to_string()))
+    }
+
+    /// Nodes below `node_id` an export would show if it were unfolded
+    fn count_exported(&self, tree: &Arena<Node>, node_id: NodeId) -> usize {
+        let mut count = 0;
+        let mut stack: Vec<NodeId> = node_id.children(tree).collect();
+        while let Some(id) = stack.pop() {
+            let node = tree[id].get();
+            if node.is_hidden() || self.excludes(node) {
+                continue;
+            }
+            count += 1;
+            stack.extend(id.children(tree));
+        }
+        count
+    }
+}
+
+/// A copy of the subtree at `node_id` below a chain of its ancestors up to
+/// `root_id`, one node per level, so that an export of it keeps its place in
+/// the map
+pub fn with_breadcrumbs(
+    tree: &Arena<Node>,
+    root_id: NodeId,
+    node_id: NodeId,
+) -> (Arena<Node>, NodeId) {
+    let mut copy = Arena::new();
+    let mut ancestors: Vec<NodeId> = if node_id == root_id {
+        Vec::new()
+    } else {
+        let above = node_id.ancestors(tree).skip(1);
+        let mut ancestors: Vec<NodeId> = above.take_while(|&id| id != root_id).collect();
+        ancestors.push(root_id);
+        ancestors
+    };
+    ancestors.reverse();
+
+    let mut parent: Option<NodeId> = None;
+    let mut root = None;
+    for ancestor_id in ancestors {
+        let mut node = Node::new(tree[ancestor_id].get().title.clone());
+        node.is_hidden = tree[ancestor_id].get().is_hidden;
+        let id = match parent {
+            Some(parent) => parent.append_value(node, &mut copy),
+            None => copy.new_node(node),
+        };
+        root.get_or_insert(id);
+        parent = Some(id);
+    }
+
+    let mut stack = vec![(node_id, parent)];
+    while let Some((source_id, parent)) = stack.pop() {
+        let node = tree[source_id].get().clone();
+        let id = match parent {
+            Some(parent) => parent.append_value(node, &mut copy),
+            None => copy.new_node(node),
+        };
+        root.get_or_insert(id);
+        // Children are appended in order, so push them last-first
+        stack.extend(
+            source_id
+                .children(tree)
+                .rev()
+                .map(|child| (child, Some(id))),
+        );
+    }
+
+    let root = root.expect("the subtree has at least its own node");
+    (copy, root)
+}
+
+/// Export the visible outline as tab-indented text; collapsed branches and
+/// comments are omitted
+pub fn to_text(tree: &Arena<Node>, root_id: NodeId, options: &ExportOptions) -> String {
+    let mut output = String::new();
+    write_text_node(tree, root_id, options, &mut output, 0);
+    output
+}
+
+fn write_text_node(
+    tree: &Arena<Node>,
+    node_id: NodeId,
+    options: &ExportOptions,
+    output: &mut String,
+    depth: usize,
+) {
+    let mut stack = vec![(node_id, depth)];
+    while let Some((node_id, depth)) = stack.pop() {
+        let node = tree.get(node_id).unwrap().get();
+
+        // Add the current node with proper indentation
+        output.push_str(&"\t".repeat(depth));
+        output.push_str(&node.title);
+        output.push('\n');
+
+        if let Some(placeholder) = options.placeholder(tree, node_id) {
+            output.push_str(&"\t".repeat(depth + 1));
+            output.push_str(&placeholder);
+            output.push('\n');
+        }
+
+        // Process children if node is not collapsed
+        if !node.is_collapsed {
+            let children = node_id
+                .children(tree)
+                .rev()
+                .filter(|&child| !options.excludes(tree[child].get()));
+            stack.extend(children.map(|child| (child, depth + 1)));
+        }
+    }
+}
+
+/// Export the visible outline as Markdown, each node as its kind says:
+/// headings as `#` headings one level below the heading above them, notes
+/// as paragraphs, quotes as block quotes and items, as well as nodes of no
+/// kind, as bullets nested below the bullet above them. A root of no kind is
+/// the top heading. Collapsed branches, hidden nodes and comments are omitted.
+pub fn to_markdown(tree: &Arena<Node>, root_id: NodeId, options: &ExportOptions) -> String {
+    let mut output = String::new();
+    // Node, level of the heading above it and nesting of the bullet above it
+    let mut stack = vec![(root_id, 0usize, 0usize)];
+    while let Some((node_id, level, nesting)) = stack.pop() {
+        let node = tree[node_id].get();
+        let title = without_kind(&node.title);
+        let kind = match node.kind() {
+            None if node_id == root_id => NodeKind::Heading,
+            kind => kind.unwrap_or(NodeKind::Item),
+        };
+        // Blocks stand apart from what comes before them
+        if kind != NodeKind::Item && !output.is_empty() && !output.ends_with("\n\n") {
+            output.push('\n');
+        }
+        let (level, nesting) = match kind {
+            NodeKind::Heading => {
+                let level = (level + 1).min(6);
+                output.push_str(&format!("{} {title}\n\n", "#".repeat(level)));
+                (level, 0)
+            }
+            NodeKind::Note => {
+                output.push_str(&format!("{title}\n\n"));
+                (level, 0)
+            }
+            NodeKind::Quote => {
+                output.push_str(&format!("> {title}\n\n"));
+                (level, 0)
+            }
+            NodeKind::Item => {
+                output.push_str(&format!("{}- {title}\n", "  ".repeat(nesting)));
+                (level, nesting + 1)
+            }
+        };
+
+        if !node.is_collapsed {
+            let children = node_id.children(tree).rev().filter(|&child| {
+                let child = tree[child].get();
+                !child.is_hidden() && !options.excludes(child)
+            });
+            stack.extend(children.map(|child| (child, level, nesting)));
+        } else if let Some(placeholder) = options.placeholder(tree, node_id) {
+            output.push_str(&format!("{}- {placeholder}\n", "  ".repeat(nesting)));
+        }
+    }
+    let length = output.trim_end().len();
+    output.truncate(length);
+    output.push('\n');
+    output
+}
+
+/// Export the visible outline as a standalone HTML page: the root as its
+/// title and heading, and everything below it as nested lists. Collapsed
+/// branches, hidden nodes and comments are omitted.
+pub fn to_html(tree: &Arena<Node>, root_id: NodeId, options: &ExportOptions) -> String {
+    let title = html_escape(&tree[root_id].get().title);
+    let mut output = String::from("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    output.push_str(&format!(
+        "<title>{title}</title>\n</head>\n<body>\n<h1>{title}</h1>\n"
+    ));
+
+    let visible_children = |node_id: NodeId| -> Vec<NodeId> {
+        if tree[node_id].get().is_collapsed {
+            return Vec::new();
+        }
+        node_id
+            .children(tree)
+            .filter(|&child| {
+                let child = tree[child].get();
+                !child.is_hidden() && !options.excludes(child)
+            })
+            .collect()
+    };
+
+    /// A node to open, or the list and item around one to close
+    enum Step {
+        Open(NodeId, usize),
+        Close(usize),
+    }
+    let children = visible_children(root_id);
+    if !children.is_empty() {
+        output.push_str("<ul>\n");
+        let mut stack: Vec<Step> = children
+            .into_iter()
+            .rev()
+            .map(|child| Step::Open(child, 1))
+            .collect();
+        while let Some(step) = stack.pop() {
+            match step {
+                Step::Open(node_id, depth) => {
+                    let indent = "  ".repeat(depth);
+                    let title = html_escape(&tree[node_id].get().title);
+                    let children = visible_children(node_id);
+                    if children.is_empty() {
+                        match options.placeholder(tree, node_id) {
+                            Some(placeholder) => output.push_str(&format!(
+                                "{indent}<li>{title}\n{indent}<ul>\n{indent}  \
+                                 <li class=\"collapsed\">{}</li>\n{indent}</ul></li>\n",
+                                html_escape(&placeholder)
+                            )),
+                            None => output.push_str(&format!("{indent}<li>{title}</li>\n")),
+                        }
+                        continue;
+                    }
+                    output.push_str(&format!("{indent}<li>{title}\n{indent}<ul>\n"));
+                    stack.push(Step::Close(depth));
+                    stack.extend(
+                        children
+                            .into_iter()
+                            .rev()
+                            .map(|child| Step::Open(child, depth + 1)),
+                    );
+                }
+                Step::Close(depth) => {
+                    let indent = "  ".repeat(depth);
+                    output.push_str(&format!("{indent}</ul></li>\n"));
+                }
+            }
+        }
+        output.push_str("</ul>\n");
+    }
+    output.push_str("</body>\n</html>\n");
+    output
+}
+
+/// `s` with the characters HTML gives a meaning to written as entities
+pub(crate) fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Output formats available to `hmm-rs export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+    /// Tab-indented outline
+    #[default]
+    Text,
+    /// Markdown with headings, bullets, paragraphs and quotes by node kind
+    Markdown,
+    /// Mermaid `mindmap` diagram
+    Mermaid,
+    /// Mermaid left-to-right `flowchart`
+    MermaidFlowchart,
+    /// Task nodes as a Taskwarrior JSON array, for `task import`
+    Taskwarrior,
+    /// Task nodes as todo.txt lines
+    TodoTxt,
+    /// Nodes with a due date as an iCalendar file
+    Ical,
+    /// Standalone HTML page of nested lists
+    Html,
+}
+
+/// Registry entry for an export format
+struct ExportInfo {
+    /// Name on the command line
+    name: &'static str,
+    description: &'static str,
+    /// Extension of files in this format
+    extension: &'static str,
+    export: fn(&Arena<Node>, NodeId, &ExportOptions) -> String,
+}
+
+impl ExportFormat {
+    /// Every export format, in the order `hmm-rs formats` lists them
+    pub const ALL: [ExportFormat; 8] = [
+        ExportFormat::Text,
+        ExportFormat::Markdown,
+        ExportFormat::Mermaid,
+        ExportFormat::MermaidFlowchart,
+        ExportFormat::Taskwarrior,
+        ExportFormat::TodoTxt,
+        ExportFormat::Ical,
+        ExportFormat::Html,
+    ];
+
+    fn info(self) -> ExportInfo {
+        match self {
+            ExportFormat::Text => ExportInfo {
+                name: "text",
+                description: "Tab-indented outline",
+                extension: "txt",
+                export: to_text,
+            },
+            ExportFormat::Markdown => ExportInfo {
+                name: "markdown",
+                description: "Markdown document by node kind",
+                extension: "md",
+                export: to_markdown,
+            },
+            ExportFormat::Mermaid => ExportInfo {
+                name: "mermaid",
+                description: "Mermaid mindmap diagram",
+                extension: "mmd",
+                export: |tree, root_id, options| {
+                    to_mermaid(tree, root_id, MermaidStyle::Mindmap, options)
+                },
+            },
+            ExportFormat::MermaidFlowchart => ExportInfo {
+                name: "mermaid-flowchart",
+                description: "Mermaid left-to-right flowchart",
+                extension: "mmd",
+                export: |tree, root_id, options| {
+                    to_mermaid(tree, root_id, MermaidStyle::Flowchart, options)
+                },
+            },
+            ExportFormat::Taskwarrior => ExportInfo {
+                name: "taskwarrior",
+                description: "Task nodes as a Taskwarrior JSON array",
+                extension: "json",
+                export: to_taskwarrior,
+            },
+            ExportFormat::TodoTxt => ExportInfo {
+                name: "todo-txt",
+                description: "Task nodes as todo.txt lines",
+                extension: "txt",
+                export: to_todo_txt,
+            },
+            ExportFormat::Ical => ExportInfo {
+                name: "ical",
+                description: "Nodes with a due date as an iCalendar file",
+                extension: "ics",
+                export: to_ical,
+            },
+            ExportFormat::Html => ExportInfo {
+                name: "html",
+                description: "Standalone HTML page of nested lists",
+                extension: "html",
+                export: to_html,
+            },
+        }
+    }
+
+    /// Name of the format on the command line, as `--format` takes it
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
+
+    pub fn description(self) -> &'static str {
+        self.info().description
+    }
+
+    /// Extension of files in this format
+    pub fn extension(self) -> &'static str {
+        self.info().extension
+    }
+}
+
+/// Export the subtree at `root_id` in `format`
+pub fn export(
+    tree: &Arena<Node>,
+    root_id: NodeId,
+    format: ExportFormat,
+    options: &ExportOptions,
+) -> String {
+    (format.info().export)(tree, root_id, options)
+}
+
+/// Diagram type for [`to_mermaid`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MermaidStyle {
+    Mindmap,
+    Flowchart,
+}
+
+/// Export the visible outline as Mermaid diagram source, ready to paste into
+/// a ```` ```mermaid ```` block. Collapsed branches, hidden nodes and comments
+/// are omitted.
+pub fn to_mermaid(
+    tree: &Arena<Node>,
+    root_id: NodeId,
+    style: MermaidStyle,
+    options: &ExportOptions,
+) -> String {
+    let mut output = String::new();
+    let mut ids = 0usize;
+    let mut stack = vec![(root_id, 0usize, None)];
+
+    match style {
+        MermaidStyle::Mindmap => output.push_str("mindmap\n"),
+        MermaidStyle::Flowchart => output.push_str("flowchart LR\n"),
+    }
+
+    while let Some((node_id, depth, parent)) = stack.pop() {
+        let node = tree[node_id].get();
+        let id = ids;
+        ids += 1;
+
+        match style {
+            MermaidStyle::Mindmap => {
+                output.push_str(&"  ".repeat(depth + 1));
+                if depth == 0 {
+                    output.push_str(&format!("root((\"{}\"))", mermaid_escape(&node.title)));
+                } else if is_plain_mindmap_text(&node.title) {
+                    output.push_str(&node.title);
+                } else {
+                    output.push_str(&format!("n{id}[\"{}\"]", mermaid_escape(&node.title)));
+                }
+            }
+            MermaidStyle::Flowchart => {
+                output.push_str("  ");
+                if let Some(parent) = parent {
+                    output.push_str(&format!("n{parent} --> "));
+                }
+                output.push_str(&format!("n{id}[\"{}\"]", mermaid_escape(&node.title)));
+            }
+        }
+        output.push('\n');
+
+        if !node.is_collapsed {
+            let children = node_id.children(tree).rev().filter(|&child| {
+                let child = tree[child].get();
+                !child.is_hidden() && !options.excludes(child)
+            });
+            stack.extend(children.map(|child| (child, depth + 1, Some(id))));
+        }
+    }
+    output
+}
+
+/// Whether a mindmap line can hold `title` as is, without being read as a
+/// node shape, icon or class
+fn is_plain_mindmap_text(title: &str) -> bool {
+    !title.trim().is_empty()
+        && !title.starts_with("::")
+        && !title.contains(['(', ')', '[', ']', '{', '}', '"'])
+}
+
+/// Quote-safe node text; Mermaid uses entity codes inside quoted labels
+fn mermaid_escape(title: &str) -> String {
+    title.replace('"', "#quot;")
+}
+
+/// Export the nodes with a due date as an iCalendar file: tasks as to-dos
+/// due then, other nodes as events on that day, each described by the path
+/// of titles above it. Collapsed branches count, hidden nodes and comments
+/// do not.
+pub fn to_ical(tree: &Arena<Node>, root_id: NodeId, options: &ExportOptions) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//hmm-rs//hmm-rs//EN".to_string(),
+    ];
+
+    let mut stack = vec![(root_id, Vec::<String>::new())];
+    while let Some((node_id, path)) = stack.pop() {
+        let node = tree[node_id].get();
+        if node.is_hidden() || options.excludes(node) {
+            continue;
+        }
+        let title = without_due(&node.title);
+
+        if let Some(due) = due_date(&node.title) {
+            let (state, summary) = match task_state(&title, options) {
+                Some((done, text)) => (Some(done), text),
+                None => (None, title.as_str()),
+            };
+            // Derived from where the node is, so importing the calendar again
+            // updates the entries instead of adding new ones
+            let uid = format!(
+                "{:016x}@hmm-rs",
+                fnv1a(&format!("{}\n{}", path.join("\n"), summary))
+            );
+            let stamp = format!("DTSTAMP:{}T000000Z", due.date.format("%Y%m%d"));
+
+            let component = if state.is_some() { "VTODO" } else { "VEVENT" };
+            lines.push(format!("BEGIN:{component}"));
+            lines.push(format!("UID:{uid}"));
+            lines.push(stamp);
+            lines.push(format!("SUMMARY:{}", ical_escape(summary)));
+            if !path.is_empty() {
+                lines.push(format!("DESCRIPTION:{}", ical_escape(&path.join(" › "))));
+            }
+            match state {
+                Some(done) => {
+                    lines.push(ical_date("DUE", due));
+                    let status = if done { "COMPLETED" } else { "NEEDS-ACTION" };
+                    lines.push(format!("STATUS:{status}"));
+                }
+                None => lines.push(ical_date("DTSTART", due)),
+            }
+            lines.push(format!("END:{component}"));
+        }
+
+        let mut child_path = path;
+        child_path.push(title);
+        let children: Vec<_> = node_id.children(tree).collect();
+        stack.extend(
+            children
+                .into_iter()
+                .rev()
+                .map(|child| (child, child_path.clone())),
+        );
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    let mut output = String::new();
+    for line in lines {
+        output.push_str(&ical_fold(&line));
+    }
+    output
+}
+
+/// A date property, a whole day unless `due` has a time of day
+fn ical_date(property: &str, due: Due) -> String {
+    match due.time {
+        Some(time) => format!(
+            "{property}:{}T{}",
+            due.date.format("%Y%m%d"),
+            time.format("%H%M%S")
+        ),
+        None => format!("{property};VALUE=DATE:{}", due.date.format("%Y%m%d")),
+    }
+}
+
+/// `text` with the characters iCalendar gives a meaning escaped
+fn ical_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// `line` folded into lines of at most 75 bytes, each ending in CRLF
+fn ical_fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+/// A node as export templates see it
+#[derive(Debug, Clone, Serialize)]
+struct TemplateNode {
+    /// The title as written, with any task symbol and due date tag
+    title: String,
+    /// The title without its task symbol and due date tag
+    text: String,
+    /// 0 for the root
+    depth: usize,
+    /// Texts of the nodes above, from the root down
+    path: Vec<String>,
+    /// `"done"` or `"todo"` for task nodes
+    task: Option<&'static str>,
+    /// `YYYY-MM-DD`, followed by `THH:MM` for a time of day
+    due: Option<String>,
+    collapsed: bool,
+    hidden: bool,
+    leaf: bool,
+    /// Empty in the flat `nodes` list
+    children: Vec<TemplateNode>,
+}
+
+/// Render a [minijinja](https://docs.rs/minijinja) `template` over the
+/// subtree at `root_id`. Templates get the nested `root` node and the flat
+/// `nodes` list in outline order; collapsed branches and hidden nodes are
+/// included and flagged, comments left out.
+pub fn to_template(
+    tree: &Arena<Node>,
+    root_id: NodeId,
+    template: &str,
+    options: &ExportOptions,
+) -> Result<String> {
+    // Nodes in outline order with the index of their parent
+    let mut nodes: Vec<(TemplateNode, Option<usize>)> = Vec::new();
+    let mut stack = vec![(root_id, None, Vec::<String>::new())];
+    while let Some((node_id, parent, path)) = stack.pop() {
+        let node = tree[node_id].get();
+        let title = without_due(&node.title);
+        let (task, text) = match task_state(&title, options) {
+            Some((done, text)) => (Some(if done { "done" } else { "todo" }), text.to_string()),
+            None => (None, title.clone()),
+        };
+        let due = due_date(&node.title).map(|due| match due.time {
+            Some(time) => format!("{}T{}", due.date.format("%Y-%m-%d"), time.format("%H:%M")),
+            None => due.date.format("%Y-%m-%d").to_string(),
+        });
+        let children: Vec<_> = node_id
+            .children(tree)
+            .filter(|&child| !options.excludes(tree[child].get()))
+            .collect();
+
+        let index = nodes.len();
+        let mut child_path = path.clone();
+        child_path.push(text.clone());
+        stack.extend(
+            children
+                .iter()
+                .rev()
+                .map(|&child| (child, Some(index), child_path.clone())),
+        );
+        nodes.push((
+            TemplateNode {
+                title: node.title.clone(),
+                text,
+                depth: path.len(),
+                path,
+                task,
+                due,
+                collapsed: node.is_collapsed,
+                hidden: node.is_hidden(),
+                leaf: children.is_empty(),
+                children: Vec::new(),
+            },
+            parent,
+        ));
+    }
+
+    // Children come after their parent, so building the tree from the end
+    // finds every child complete
+    let mut children: Vec<Vec<TemplateNode>> = vec![Vec::new(); nodes.len()];
+    let mut root = None;
+    for (index, (node, parent)) in nodes.iter().enumerate().rev() {
+        let mut node = node.clone();
+        node.children = std::mem::take(&mut children[index]);
+        node.children.reverse();
+        match parent {
+            Some(parent) => children[*parent].push(node),
+            None => root = Some(node),
+        }
+    }
+    let flat: Vec<TemplateNode> = nodes.into_iter().map(|(node, _)| node).collect();
+
+    let mut env = minijinja::Environment::new();
+    env.set_trim_blocks(true);
+    env.set_lstrip_blocks(true);
+    env.set_keep_trailing_newline(true);
+    Ok(env.render_str(template, minijinja::context! { root, nodes => flat })?)
+}
+
+/// 64-bit FNV-1a hash, stable across builds unlike the standard hasher
+pub(crate) fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_breadcrumbs() {
+        let (tree, root) = crate::parser::parse_hmm_content(
+            "Plan\n\tLater\n\tWork\n\t\tShip\n\t\t\tTest\n\t\t\tRelease\n\t\tOther\n",
+        )
+        .unwrap();
+        let ship = root
+            .descendants(&tree)
+            .find(|&id| tree[id].get().title == "Ship")
+            .unwrap();
+
+        let (copy, copy_root) = with_breadcrumbs(&tree, root, ship);
+        assert_eq!(
+            to_text(&copy, copy_root, &ExportOptions::default()),
+            "Plan\n\tWork\n\t\tShip\n\t\t\tTest\n\t\t\tRelease\n"
+        );
+
+        let (copy, copy_root) = with_breadcrumbs(&tree, root, root);
+        assert_eq!(copy.count(), root.descendants(&tree).count());
+        assert_eq!(copy[copy_root].get().title, "Plan");
+    }
+
+    #[test]
+    fn test_to_text_skips_collapsed_branches() {
+        let mut tree = Arena::new();
+        let root = tree.new_node(Node::new("Root".to_string()));
+        let child1 = tree.new_node(Node::new("Child 1".to_string()));
+        let child2 = tree.new_node(Node::new("Child 2".to_string()));
+        let grandchild = tree.new_node(Node::new("Grandchild".to_string()));
+        root.append(child1, &mut tree);
+        root.append(child2, &mut tree);
+        child1.append(grandchild, &mut tree);
+
+        assert_eq!(
+            to_text(&tree, root, &ExportOptions::default()),
+            "Root\n\tChild 1\n\t\tGrandchild\n\tChild 2\n"
+        );
+
+        tree.get_mut(child1).unwrap().get_mut().is_collapsed = true;
+        assert_eq!(
+            to_text(&tree, root, &ExportOptions::default()),
+            "Root\n\tChild 1\n\tChild 2\n"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_by_kind() {
+        let (tree, root) = crate::parser::parse_hmm_content(
+            "Handbook\n\tWhy kind:heading\n\t\tWe ship. kind:note\n\t\tSmall steps\n\t\t\tOften\n\t\tkind:quote Less is more\n\tHow kind:heading\n\t\tReview kind:item\n",
+        )
+        .unwrap();
+        assert_eq!(
+            to_markdown(&tree, root, &ExportOptions::default()),
+            "# Handbook\n\n## Why\n\nWe ship.\n\n- Small steps\n  - Often\n\n> Less is more\n\n## How\n\n- Review\n"
+        );
+    }
+
+    fn create_test_tree() -> (Arena<Node>, NodeId) {
+        let mut tree = Arena::new();
+        let root = tree.new_node(Node::new("Plan \"Q3\"".to_string()));
+        let design = root.append_value(Node::new("Design".to_string()), &mut tree);
+        design.append_value(Node::new("Mockups (v2)".to_string()), &mut tree);
+        root.append_value(Node::new("[HIDDEN] note".to_string()), &mut tree);
+        let ship = root.append_value(Node::new("Ship".to_string()), &mut tree);
+        ship.append_value(Node::new("Folded".to_string()), &mut tree);
+        tree[ship].get_mut().is_collapsed = true;
+        (tree, root)
+    }
+
+    #[test]
+    fn test_to_html_escapes_titles() {
+        let (mut tree, root) = create_test_tree();
+        tree[root].get_mut().title = "R&D <plan>".to_string();
+        let html = to_html(&tree, root, &ExportOptions::default());
+        assert!(html.contains("<h1>R&amp;D &lt;plan&gt;</h1>"));
+        assert!(html.contains("  <li>Ship</li>\n</ul>\n"));
+        assert!(!html.contains("note"));
+    }
+
+    #[test]
+    fn test_collapsed_placeholders() {
+        let (mut tree, root) = create_test_tree();
+        let ship = root.children(&tree).next_back().unwrap();
+        ship.append_value(Node::new("// aside".to_string()), &mut tree);
+        let options = ExportOptions {
+            collapsed_placeholder: Some("… ({count} hidden items)".to_string()),
+            ..ExportOptions::default()
+        };
+
+        assert!(to_text(&tree, root, &options).ends_with("\tShip\n\t\t… (1 hidden items)\n"));
+        assert!(to_markdown(&tree, root, &options).ends_with("- Ship\n  - … (1 hidden items)\n"));
+        assert!(to_html(&tree, root, &options).contains(
+            "  <li>Ship\n  <ul>\n    <li class=\"collapsed\">… (1 hidden items)</li>\n  </ul></li>\n"
+        ));
+        // Counted ahead by the aggregates, the same line
+        let counted = ExportOptions {
+            folded_counts: Some(Rc::new(Aggregates::compute(&tree, root, Some("//")))),
+            ..options.clone()
+        };
+        assert_eq!(
+            to_text(&tree, root, &counted),
+            to_text(&tree, root, &options)
+        );
+
+        // Nothing folded away, nothing to stand in for
+        tree[ship].get_mut().is_collapsed = false;
+        let design = root.children(&tree).next().unwrap();
+        let mockups = design.children(&tree).next().unwrap();
+        tree[mockups].get_mut().is_collapsed = true;
+        assert!(!to_text(&tree, root, &options).contains("hidden items"));
+    }
+
+    #[test]
+    fn test_to_mermaid_mindmap() {
+        let (tree, root) = create_test_tree();
+        assert_eq!(
+            to_mermaid(&tree, root, MermaidStyle::Mindmap, &ExportOptions::default()),
+            "mindmap\n  root((\"Plan #quot;Q3#quot;\"))\n    Design\n      n2[\"Mockups (v2)\"]\n    Ship\n"
+        );
+    }
+
+    #[test]
+    fn test_to_mermaid_flowchart() {
+        let (tree, root) = create_test_tree();
+        assert_eq!(
+            export(
+                &tree,
+                root,
+                ExportFormat::MermaidFlowchart,
+                &ExportOptions::default()
+            ),
+            "flowchart LR\n  n0[\"Plan #quot;Q3#quot;\"]\n  n0 --> n1[\"Design\"]\n  n1 --> n2[\"Mockups (v2)\"]\n  n0 --> n3[\"Ship\"]\n"
+        );
+    }
+
+    #[test]
+    fn test_comments_are_left_out_unless_configured() {
+        let mut tree = Arena::new();
+        let root = tree.new_node(Node::new("Root".to_string()));
+        let comment = root.append_value(Node::new("// check numbers".to_string()), &mut tree);
+        comment.append_value(Node::new("Under the comment".to_string()), &mut tree);
+        root.append_value(Node::new("See https://example.com".to_string()), &mut tree);
+
+        let options = ExportOptions::default();
+        assert_eq!(
+            to_text(&tree, root, &options),
+            "Root\n\tSee https://example.com\n"
+        );
+        assert!(!to_mermaid(&tree, root, MermaidStyle::Mindmap, &options).contains("check"));
+
+        let config = AppConfig {
+            export_comments: true,
+            ..AppConfig::default()
+        };
+        let options = ExportOptions::from_config(&config);
+        assert!(to_text(&tree, root, &options).contains("\t\tUnder the comment\n"));
+
+        let config = AppConfig {
+            comment_prefix: "%%".to_string(),
+            ..AppConfig::default()
+        };
+        let options = ExportOptions::from_config(&config);
+        assert!(to_text(&tree, root, &options).contains("// check numbers"));
+    }
+
+    #[test]
+    fn test_to_ical() {
+        let mut tree = Arena::new();
+        let root = tree.new_node(Node::new("Launch".to_string()));
+        let site = root.append_value(Node::new("Site, docs".to_string()), &mut tree);
+        site.append_value(
+            Node::new("✗ Write copy due:2024-05-01".to_string()),
+            &mut tree,
+        );
+        root.append_value(
+            Node::new("Demo due:2024-05-03T14:30".to_string()),
+            &mut tree,
+        );
+        root.append_value(Node::new("No date".to_string()), &mut tree);
+        tree[site].get_mut().is_collapsed = true;
+
+        let ical = to_ical(&tree, root, &ExportOptions::default());
+        assert!(ical.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ical.ends_with("END:VCALENDAR\r\n"));
+        let lines: Vec<_> = ical
+            .lines()
+            .filter(|line| !line.starts_with("UID:"))
+            .collect();
+        assert_eq!(
+            lines[3..],
+            [
+                "BEGIN:VTODO",
+                "DTSTAMP:20240501T000000Z",
+                "SUMMARY:Write copy",
+                "DESCRIPTION:Launch › Site\\, docs",
+                "DUE;VALUE=DATE:20240501",
+                "STATUS:NEEDS-ACTION",
+                "END:VTODO",
+                "BEGIN:VEVENT",
+                "DTSTAMP:20240503T000000Z",
+                "SUMMARY:Demo",
+                "DESCRIPTION:Launch",
+                "DTSTART:20240503T143000",
+                "END:VEVENT",
+                "END:VCALENDAR",
+            ]
+        );
+
+        // Marking the task done keeps its UID
+        let uid = |ical: &str| {
+            ical.lines()
+                .find(|line| line.starts_with("UID:"))
+                .unwrap()
+                .to_string()
+        };
+        let task = site.children(&tree).next().unwrap();
+        tree[task].get_mut().title = "✓ Write copy due:2024-05-01".to_string();
+        let done = to_ical(&tree, root, &ExportOptions::default());
+        assert_eq!(uid(&done), uid(&ical));
+        assert!(done.contains("STATUS:COMPLETED"));
+    }
+
+    #[test]
+    fn test_ical_fold() {
+        let line = format!("SUMMARY:{}", "é".repeat(40));
+        let folded = ical_fold(&line);
+        assert!(folded.split("\r\n").all(|part| part.len() <= 75));
+        assert_eq!(folded.replace("\r\n ", ""), format!("{line}\r\n"));
+    }
+
+    #[test]
+    fn test_to_template() {
+        let (mut tree, root) = crate::parser::parse_hmm_content(
+            "Launch\n\t✗ Write copy due:2024-05-01\n\t\tDraft\n\t// Internal\n\tDemo\n",
+        )
+        .unwrap();
+        let demo = root.children(&tree).next_back().unwrap();
+        tree[demo].get_mut().is_collapsed = true;
+
+        let template = "{% macro outline(node) %}\n\
+                        {{ '  ' * node.depth }}{{ node.text }}\n\
+                        {% for child in node.children %}{{ outline(child) }}{% endfor %}\n\
+                        {% endmacro %}\n\
+                        {{ outline(root) -}}\n\
+                        {% for node in nodes if node.task %}\n\
+                        {{ node.task }}: {{ node.path | join(' / ') }} / {{ node.text }} by {{ node.due }}\n\
+                        {% endfor %}\n\
+                        {{ nodes | selectattr('collapsed') | map(attribute='title') | join }}\n";
+        let output = to_template(&tree, root, template, &ExportOptions::default()).unwrap();
+        assert_eq!(
+            output,
+            "Launch\n  Write copy\n    Draft\n  Demo\n\
+             todo: Launch / Write copy by 2024-05-01\n\
+             Demo\n"
+        );
+
+        assert!(to_template(&tree, root, "{% for %}", &ExportOptions::default()).is_err());
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_export_format_names_match_the_command_line() {
+        use clap::ValueEnum;
+
+        assert_eq!(
+            ExportFormat::ALL.len(),
+            ExportFormat::value_variants().len()
+        );
+        for format in ExportFormat::ALL {
+            let value = format.to_possible_value().unwrap();
+            assert_eq!(value.get_name(), format.name());
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! The list of formats `hmm-rs formats` prints.
+//!
+//! Formats are registered where they are handled: map formats, which are
+//! opened and saved by extension, in [`MapFormat`], export formats in
+//! [`ExportFormat`], and templates in the `[export.templates]` config table.
+//! A format added there shows up here, in the save format prompt and on the
+//! command line.
+
+use crate::export::ExportFormat;
+use crate::parser::MapFormat;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// Every format maps can be opened from, saved in or exported to, with the
+/// configured `templates`, as a plain text table
+pub fn list_formats(templates: &HashMap<String, PathBuf>) -> String {
+    let mut output = String::from("Map formats, opened and saved by extension:\n");
+    for format in MapFormat::ALL {
+        let extensions: Vec<String> = format
+            .extensions()
+            .iter()
+            .map(|extension| format!(".{extension}"))
+            .collect();
+        let access = if format.is_writable() {
+            "open, save"
+        } else {
+            "open"
+        };
+        output.push_str(&format!(
+            "  {:<18} {:<12} {}\n",
+            format.name(),
+            extensions.join(" "),
+            access
+        ));
+    }
+
+    output.push_str("\nExport formats, for `hmm-rs export --format NAME`:\n");
+    for format in ExportFormat::ALL {
+        output.push_str(&format!(
+            "  {:<18} {:<12} {}\n",
+            format.name(),
+            format!(".{}", format.extension()),
+            format.description()
+        ));
+    }
+
+    output.push_str("\nExport templates, for `hmm-rs export --template NAME`:\n");
+    let mut templates: Vec<_> = templates.iter().collect();
+    templates.sort_unstable();
+    if templates.is_empty() {
+        output.push_str("  none; add them under [export.templates] in the config\n");
+    }
+    for (name, path) in templates {
+        output.push_str(&format!("  {:<18} {}\n", name, path.display()));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_formats() {
+        let templates = HashMap::from([("latex".to_string(), PathBuf::from("outline.tex"))]);
+        let list = list_formats(&templates);
+        assert!(list.contains("  hmm                .hmm         open, save\n"));
+        assert!(list.contains("  TSV                .tsv .tab    open, save\n"));
+        assert!(list.contains("  XMind              .xmind       open\n"));
+        assert!(list.contains("  todo-txt           .txt         Task nodes as todo.txt lines\n"));
+        assert!(list.ends_with("  latex              outline.tex\n"));
+        assert!(list_formats(&HashMap::new()).contains("none; add them"));
+    }
+}
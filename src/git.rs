@@ -0,0 +1,125 @@
+//! Maps kept in git repositories.
+//!
+//! Everything runs the `git` command in the directory of the map, so it sees
+//! the same repository, hooks and identity as the user's own commands.
+
+use anyhow::{bail, Context, Result};
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Run git with `args` in the directory of `path`
+fn git(path: &Path, args: &[&str]) -> Result<Output> {
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("cannot run git")
+}
+
+/// Like [`git`], failing with git's own message when it does
+fn git_ok(path: &Path, args: &[&str]) -> Result<Output> {
+    let output = git(path, args)?;
+    if !output.status.success() {
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(output)
+}
+
+/// The file name of `path`, as git arguments name it from its directory
+fn file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .with_context(|| format!("{} is not a file", path.display()))
+}
+
+/// Whether `path` is inside a git work tree
+pub fn in_repository(path: &Path) -> bool {
+    git(path, &["rev-parse", "--is-inside-work-tree"]).is_ok_and(|output| output.status.success())
+}
+
+/// The contents of `path` as committed in `HEAD`
+pub fn show_head(path: &Path) -> Result<Vec<u8>> {
+    let spec = format!("HEAD:./{}", file_name(path)?);
+    Ok(git_ok(path, &["show", &spec])?.stdout)
+}
+
+/// Stage `path` and commit it alone with `message`, leaving anything else
+/// staged as it is. Returns whether there was a change to commit.
+pub fn commit_file(path: &Path, message: &str) -> Result<bool> {
+    let name = file_name(path)?;
+    git_ok(path, &["add", "--", &name])?;
+    let unchanged = git(path, &["diff", "--cached", "--quiet", "--", &name])?
+        .status
+        .success();
+    if unchanged {
+        return Ok(false);
+    }
+    git_ok(
+        path,
+        &["commit", "--quiet", "--message", message, "--", &name],
+    )?;
+    Ok(true)
+}
+
+/// Lines added and removed in `path` since its last commit, as git's short
+/// stat puts it, or `None` when it is unchanged or not tracked
+pub fn diff_stat(path: &Path) -> Result<Option<String>> {
+    let name = file_name(path)?;
+    let output = git_ok(path, &["diff", "--shortstat", "HEAD", "--", &name])?;
+    let stat = String::from_utf8_lossy(&output.stdout);
+    // Only the one file is ever listed
+    let stat = stat
+        .trim()
+        .split_once(", ")
+        .map(|(_, changes)| changes.to_string());
+    Ok(stat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_commit_file() {
+        let dir = TempDir::new().unwrap();
+        let map = dir.path().join("plan.hmm");
+        fs::write(&map, "Plan\n").unwrap();
+        assert!(!in_repository(&map));
+
+        for args in [
+            &["init", "--quiet"][..],
+            &["config", "user.name", "Test"],
+            &["config", "user.email", "test@example.com"],
+        ] {
+            git_ok(&map, args).unwrap();
+        }
+        fs::write(dir.path().join("other.txt"), "staged").unwrap();
+        git_ok(&map, &["add", "other.txt"]).unwrap();
+
+        assert!(in_repository(&map));
+        assert!(commit_file(&map, "Add plan").unwrap());
+        assert!(!commit_file(&map, "Nothing").unwrap());
+        assert_eq!(show_head(&map).unwrap(), b"Plan\n");
+        assert_eq!(diff_stat(&map).unwrap(), None);
+
+        fs::write(&map, "Plan v2\n\tStep\n").unwrap();
+        assert_eq!(
+            diff_stat(&map).unwrap().as_deref(),
+            Some("2 insertions(+), 1 deletion(-)")
+        );
+
+        // Other staged files stay out of the commit
+        let log = git_ok(&map, &["show", "--name-only", "--format=%s", "HEAD"]).unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&log.stdout),
+            "Add plan\n\nplan.hmm\n"
+        );
+    }
+}
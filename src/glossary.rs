@@ -0,0 +1,189 @@
+//! A "Glossary" branch under the root listing the acronyms used in the map.
+//!
+//! Acronyms are all-caps words with at least two capital letters, such as
+//! `API` or `HTTP2`. Each gets one glossary entry titled `API (3)`, the
+//! count being the number of nodes that mention it. Updating keeps whatever
+//! was written after the acronym and under the entry, such as a definition.
+
+use crate::model::{Node, NodeId};
+use indextree::Arena;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// Title of the branch holding the glossary
+pub const GLOSSARY_TITLE: &str = "Glossary";
+
+/// What an update changed
+#[derive(Debug, Default, PartialEq)]
+pub struct GlossaryUpdate {
+    pub glossary_id: Option<NodeId>,
+    /// Acronyms that got a new entry
+    pub added: usize,
+    /// Whether any title or the order of entries changed
+    pub changed: bool,
+}
+
+/// Acronyms used outside the glossary, with the number of nodes mentioning each
+pub fn collect_acronyms(tree: &Arena<Node>, root_id: NodeId) -> BTreeMap<String, usize> {
+    let glossary_id = find_glossary(tree, root_id);
+    let pattern = acronym_pattern();
+
+    let mut counts = BTreeMap::new();
+    let mut stack = vec![root_id];
+    while let Some(node_id) = stack.pop() {
+        if Some(node_id) == glossary_id {
+            continue;
+        }
+        let mut found: Vec<&str> = pattern
+            .find_iter(&tree[node_id].get().title)
+            .map(|m| m.as_str())
+            .filter(|word| is_acronym(word))
+            .collect();
+        found.sort_unstable();
+        found.dedup();
+        for acronym in found {
+            *counts.entry(acronym.to_string()).or_insert(0) += 1;
+        }
+        stack.extend(node_id.children(tree));
+    }
+    counts
+}
+
+/// Create or refresh the glossary branch: every acronym gets an entry with its
+/// current count, and entries are kept in alphabetical order. Entries whose
+/// acronym is no longer used stay, with a count of 0, so no definition is lost.
+pub fn update_glossary(tree: &mut Arena<Node>, root_id: NodeId) -> GlossaryUpdate {
+    let counts = collect_acronyms(tree, root_id);
+    let existing = find_glossary(tree, root_id);
+    if existing.is_none() && counts.is_empty() {
+        return GlossaryUpdate::default();
+    }
+
+    let mut update = GlossaryUpdate::default();
+    let glossary_id = existing.unwrap_or_else(|| {
+        update.changed = true;
+        root_id.append_value(Node::new(GLOSSARY_TITLE.to_string()), tree)
+    });
+    update.glossary_id = Some(glossary_id);
+
+    // Entries by acronym; other children of the glossary are left alone
+    let mut entries: BTreeMap<String, NodeId> = BTreeMap::new();
+    for entry_id in glossary_id.children(tree) {
+        let title = &tree[entry_id].get().title;
+        if let Some(acronym) = title.split_whitespace().next().filter(|w| is_acronym(w)) {
+            entries.entry(acronym.to_string()).or_insert(entry_id);
+        }
+    }
+    for acronym in counts.keys() {
+        if !entries.contains_key(acronym) {
+            let entry_id = tree.new_node(Node::new(acronym.clone()));
+            glossary_id.append(entry_id, tree);
+            entries.insert(acronym.clone(), entry_id);
+            update.added += 1;
+        }
+    }
+
+    for (acronym, &entry_id) in &entries {
+        let count = counts.get(acronym).copied().unwrap_or(0);
+        let title = &mut tree[entry_id].get_mut().title;
+        let counted = with_count(title, count);
+        if *title != counted {
+            *title = counted;
+            update.changed = true;
+        }
+    }
+
+    // Entries go first, alphabetically, followed by anything else in the branch
+    let others: Vec<NodeId> = glossary_id
+        .children(tree)
+        .filter(|id| !entries.values().any(|entry_id| entry_id == id))
+        .collect();
+    let order: Vec<NodeId> = entries.values().copied().chain(others).collect();
+    if !order.iter().copied().eq(glossary_id.children(tree)) {
+        for node_id in order {
+            node_id.detach(tree);
+            glossary_id.append(node_id, tree);
+        }
+        update.changed = true;
+    }
+
+    update
+}
+
+/// The root's child titled "Glossary", if any
+pub fn find_glossary(tree: &Arena<Node>, root_id: NodeId) -> Option<NodeId> {
+    root_id
+        .children(tree)
+        .find(|&id| tree[id].get().title.eq_ignore_ascii_case(GLOSSARY_TITLE))
+}
+
+/// Words of capital letters and digits starting with a letter
+fn acronym_pattern() -> Regex {
+    Regex::new(r"\b[A-Z][A-Z0-9]+\b").expect("valid acronym pattern")
+}
+
+fn is_acronym(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_uppercase())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+        && word.chars().filter(char::is_ascii_uppercase).count() >= 2
+}
+
+/// `title` with its trailing `(n)` count replaced by `count`
+fn with_count(title: &str, count: usize) -> String {
+    let pattern = Regex::new(r"\s*\(\d+\)$").expect("valid count pattern");
+    let text = pattern.replace(title, "");
+    format!("{text} ({count})")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{map_to_list, parse_hmm_content};
+
+    #[test]
+    fn test_collect_acronyms() {
+        let (tree, root_id) = parse_hmm_content(
+            "Spec\n\tREST API\n\t\tAPI keys, not Api or A\n\tHTTP2 and S3 over TCP/IP\n",
+        )
+        .unwrap();
+        let counts = collect_acronyms(&tree, root_id);
+        let expected = [("API", 2), ("HTTP2", 1), ("IP", 1), ("REST", 1), ("TCP", 1)];
+        assert_eq!(
+            counts
+                .iter()
+                .map(|(k, &v)| (k.as_str(), v))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_update_keeps_definitions() {
+        let content = "Spec\n\tUse the API\n\tand the CLI\n\tGlossary\n\t\tCLI - command line (1)\n\t\t\tRun with --help\n\t\tOLD (4)\n\t\tSee also\n";
+        let (mut tree, root_id) = parse_hmm_content(content).unwrap();
+
+        let update = update_glossary(&mut tree, root_id);
+        assert_eq!(update.added, 1);
+        assert!(update.changed);
+        assert_eq!(
+            map_to_list(&tree, update.glossary_id.unwrap(), false, 0),
+            "Glossary\n\tAPI (1)\n\tCLI - command line (1)\n\t\tRun with --help\n\tOLD (0)\n\tSee also\n"
+        );
+
+        // A second run finds nothing to do
+        let again = update_glossary(&mut tree, root_id);
+        assert_eq!((again.added, again.changed), (0, false));
+    }
+
+    #[test]
+    fn test_no_glossary_without_acronyms() {
+        let (mut tree, root_id) = parse_hmm_content("Notes\n\tnothing loud here\n").unwrap();
+        assert_eq!(
+            update_glossary(&mut tree, root_id),
+            GlossaryUpdate::default()
+        );
+        assert_eq!(root_id.children(&tree).count(), 1);
+    }
+}
@@ -0,0 +1,238 @@
+//! Undo history storage. Recent snapshots are kept as plain trees; with the
+//! `undo-compression` feature older ones are serialized and zstd-compressed
+//! to bound memory in long sessions. Without it, as on wasm where zstd does
+//! not build, every snapshot stays a plain tree.
+
+use crate::model::{Node, NodeId};
+use anyhow::Result;
+use indextree::Arena;
+use std::mem::size_of;
+
+/// Snapshots this close to the newest one stay uncompressed, so ordinary
+/// undo/redo never pays for decompression
+const UNCOMPRESSED_SNAPSHOTS: usize = 4;
+
+/// zstd level used for older snapshots; low levels are fast and still shrink
+/// repetitive node titles well
+#[cfg(feature = "undo-compression")]
+const COMPRESSION_LEVEL: i32 = 3;
+
+enum Snapshot {
+    Plain(Arena<Node>),
+    Compressed(Vec<u8>),
+}
+
+impl Snapshot {
+    fn memory_usage(&self) -> usize {
+        match self {
+            Snapshot::Plain(tree) => tree_memory_usage(tree),
+            Snapshot::Compressed(bytes) => bytes.capacity(),
+        }
+    }
+}
+
+/// Approximate heap memory held by a tree
+fn tree_memory_usage(tree: &Arena<Node>) -> usize {
+    tree.iter()
+        .map(|node| size_of::<indextree::Node<Node>>() + node.get().title.capacity())
+        .sum()
+}
+
+#[cfg(feature = "undo-compression")]
+fn compress(tree: &Arena<Node>) -> Result<Vec<u8>> {
+    let bytes = postcard::to_stdvec(tree)?;
+    Ok(zstd::bulk::compress(&bytes, COMPRESSION_LEVEL)?)
+}
+
+#[cfg(feature = "undo-compression")]
+fn decompress(bytes: &[u8]) -> Result<Arena<Node>> {
+    let bytes = zstd::decode_all(bytes)?;
+    Ok(postcard::from_bytes(&bytes)?)
+}
+
+#[cfg(not(feature = "undo-compression"))]
+fn compress(_tree: &Arena<Node>) -> Result<Vec<u8>> {
+    anyhow::bail!("built without undo compression")
+}
+
+#[cfg(not(feature = "undo-compression"))]
+fn decompress(_bytes: &[u8]) -> Result<Arena<Node>> {
+    anyhow::bail!("built without undo compression")
+}
+
+/// Selection and scroll position recorded alongside a snapshot. Node ids stay
+/// valid across snapshots because each one is a clone of the same arena.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ViewState {
+    pub active_node_id: Option<NodeId>,
+    pub viewport_top: f64,
+    pub viewport_left: f64,
+}
+
+struct Entry {
+    snapshot: Snapshot,
+    view: ViewState,
+}
+
+/// Snapshots of the map, oldest first
+#[derive(Default)]
+pub struct History {
+    entries: Vec<Entry>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Drop every snapshot from `len` on
+    pub fn truncate(&mut self, len: usize) {
+        self.entries.truncate(len);
+    }
+
+    /// Append a snapshot and compress the ones that are no longer recent
+    pub fn push(&mut self, tree: Arena<Node>, view: ViewState) {
+        self.entries.push(Entry {
+            snapshot: Snapshot::Plain(tree),
+            view,
+        });
+
+        if !cfg!(feature = "undo-compression") {
+            return;
+        }
+        let recent = self.entries.len().saturating_sub(UNCOMPRESSED_SNAPSHOTS);
+        for entry in &mut self.entries[..recent] {
+            if let Snapshot::Plain(tree) = &entry.snapshot {
+                // A snapshot that fails to serialize simply stays uncompressed
+                if let Ok(bytes) = compress(tree) {
+                    entry.snapshot = Snapshot::Compressed(bytes);
+                }
+            }
+        }
+    }
+
+    /// Drop the oldest snapshot
+    pub fn remove_oldest(&mut self) {
+        if !self.entries.is_empty() {
+            self.entries.remove(0);
+        }
+    }
+
+    /// A copy of the snapshot at `index` and the view it was taken with
+    pub fn get(&self, index: usize) -> Option<(Arena<Node>, ViewState)> {
+        let entry = self.entries.get(index)?;
+        let tree = match &entry.snapshot {
+            Snapshot::Plain(tree) => tree.clone(),
+            Snapshot::Compressed(bytes) => decompress(bytes).ok()?,
+        };
+        Some((tree, entry.view))
+    }
+
+    /// Approximate memory held by all snapshots, in bytes
+    pub fn memory_usage(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| size_of::<Entry>() + entry.snapshot.memory_usage())
+            .sum()
+    }
+
+    /// Number of snapshots stored compressed
+    pub fn compressed_count(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|entry| matches!(entry.snapshot, Snapshot::Compressed(_)))
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::NodeId;
+
+    fn create_tree(title: &str, children: usize) -> (Arena<Node>, NodeId) {
+        let mut tree = Arena::new();
+        let root = tree.new_node(Node::new(title.to_string()));
+        for i in 0..children {
+            let child = tree.new_node(Node::new(format!("{title} child {i}")));
+            root.append(child, &mut tree);
+        }
+        (tree, root)
+    }
+
+    #[test]
+    #[cfg(feature = "undo-compression")]
+    fn test_older_snapshots_are_compressed() {
+        let mut history = History::new();
+        for i in 0..10 {
+            history.push(
+                create_tree(&format!("Map {i}"), 200).0,
+                ViewState::default(),
+            );
+        }
+
+        assert_eq!(history.len(), 10);
+        assert_eq!(history.compressed_count(), 10 - UNCOMPRESSED_SNAPSHOTS);
+        let plain = tree_memory_usage(&create_tree("Map 0", 200).0);
+        assert!(history.memory_usage() < 10 * plain);
+    }
+
+    #[test]
+    #[cfg(feature = "undo-compression")]
+    fn test_compressed_snapshot_round_trips() {
+        let (mut original, root) = create_tree("Root", 3);
+        let removed = root.children(&original).nth(1).unwrap();
+        removed.remove_subtree(&mut original);
+        original[root].get_mut().is_collapsed = true;
+
+        let view = ViewState {
+            active_node_id: Some(root),
+            viewport_top: 12.0,
+            viewport_left: -3.5,
+        };
+        let mut history = History::new();
+        history.push(original.clone(), view);
+        for _ in 0..UNCOMPRESSED_SNAPSHOTS {
+            history.push(create_tree("Later", 1).0, ViewState::default());
+        }
+        assert_eq!(history.compressed_count(), 1);
+
+        // Node ids, removed slots and flags all survive compression
+        let (restored, restored_view) = history.get(0).unwrap();
+        assert_eq!(restored_view, view);
+        assert_eq!(restored.count(), original.count());
+        assert!(restored[root].get().is_collapsed);
+        assert!(restored[removed].is_removed());
+        let titles = |tree: &Arena<Node>| -> Vec<String> {
+            root.descendants(tree)
+                .map(|id| tree[id].get().title.clone())
+                .collect()
+        };
+        assert_eq!(titles(&restored), titles(&original));
+    }
+
+    #[test]
+    #[cfg(not(feature = "undo-compression"))]
+    fn test_snapshots_stay_plain_without_compression() {
+        let mut history = History::new();
+        for i in 0..10 {
+            history.push(create_tree(&format!("Map {i}"), 3).0, ViewState::default());
+        }
+
+        assert_eq!(history.compressed_count(), 0);
+        let (restored, _) = history.get(0).unwrap();
+        assert_eq!(restored.count(), create_tree("Map 0", 3).0.count());
+    }
+}
@@ -0,0 +1,210 @@
+//! Shell commands run on editor events.
+//!
+//! The `[hooks]` config table names a command for each event, run in the
+//! background by the shell so that it cannot hold up the editor. The command
+//! gets the event in `HMM_EVENT`, the map's file in `HMM_FILE`, the title of
+//! the selected node in `HMM_NODE` and the titles from the root down to it,
+//! separated by ` > `, in `HMM_NODE_PATH`. Failures come with `HMM_ERROR`.
+
+use crate::app::AppState;
+use crate::config::HooksConfig;
+use crate::pins::title_path;
+use anyhow::Result;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+/// Events hooks can run on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookEvent {
+    Save,
+    AutoSaveFailed,
+    TaskDone,
+}
+
+impl HookEvent {
+    /// Name of the event in the config and in `HMM_EVENT`
+    pub fn name(self) -> &'static str {
+        match self {
+            HookEvent::Save => "save",
+            HookEvent::AutoSaveFailed => "auto_save_failed",
+            HookEvent::TaskDone => "task_done",
+        }
+    }
+
+    fn command(self, hooks: &HooksConfig) -> Option<&str> {
+        match self {
+            HookEvent::Save => hooks.save.as_deref(),
+            HookEvent::AutoSaveFailed => hooks.auto_save_failed.as_deref(),
+            HookEvent::TaskDone => hooks.task_done.as_deref(),
+        }
+        .filter(|command| !command.trim().is_empty())
+    }
+}
+
+/// Environment a hook for `event` runs with
+pub fn hook_env(
+    app: &AppState,
+    event: HookEvent,
+    error: Option<&str>,
+) -> Vec<(&'static str, String)> {
+    let mut env = vec![("HMM_EVENT", event.name().to_string())];
+    env.extend(node_env(app));
+    if let Some(error) = error {
+        env.push(("HMM_ERROR", error.to_string()));
+    }
+    env
+}
+
+/// The map's file and the selected node, as commands run from the map get
+/// them in their environment
+pub fn node_env(app: &AppState) -> Vec<(&'static str, String)> {
+    let path = match (app.root_id, app.active_node_id) {
+        (Some(root_id), Some(node_id)) if app.is_in_map(node_id) => {
+            title_path(&app.tree, root_id, node_id)
+        }
+        _ => Vec::new(),
+    };
+    let file = app
+        .filename
+        .as_ref()
+        .map(|path| path.display().to_string())
+        .unwrap_or_default();
+
+    vec![
+        ("HMM_FILE", file),
+        ("HMM_NODE", path.last().cloned().unwrap_or_default()),
+        ("HMM_NODE_PATH", path.join(" > ")),
+    ]
+}
+
+/// Start the hook configured for `event`, if any, without waiting for it
+pub fn run_hook(app: &mut AppState, event: HookEvent, error: Option<&str>) {
+    let Some(command) = event.command(&app.config.hooks) else {
+        return;
+    };
+    let command = command.to_string();
+    let env = hook_env(app, event, error);
+    if let Err(e) = spawn_shell(&command, env, None) {
+        let name = event.name();
+        app.notify_with("status.hook_failed", &[("event", &name), ("error", &e)]);
+    }
+}
+
+/// `command` for the shell with `env` added to its environment, in `dir` or
+/// else the editor's working directory
+fn shell(command: &str, env: Vec<(&'static str, String)>, dir: Option<&Path>) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    if let Some(dir) = dir {
+        shell.current_dir(dir);
+    }
+    shell.arg(command).envs(env);
+    shell
+}
+
+/// Start `command` in the shell with `env` added to its environment, in
+/// `dir` or else the editor's working directory, without waiting for it
+pub fn spawn_shell(
+    command: &str,
+    env: Vec<(&'static str, String)>,
+    dir: Option<&Path>,
+) -> Result<()> {
+    let mut child = shell(command, env, dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    // Reap the command once it exits
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Run `command` in the shell as [`spawn_shell`] does, with `input` on its
+/// standard input, and wait for it to exit. Its standard error is kept for
+/// reporting failures.
+pub fn pipe_to_shell(
+    command: &str,
+    input: String,
+    env: Vec<(&'static str, String)>,
+    dir: Option<&Path>,
+) -> Result<Output> {
+    let mut child = shell(command, env, dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("standard input is piped");
+    // Written from another thread so that a command filling up its standard
+    // error before reading everything cannot deadlock with the editor
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    // A command is free to exit without reading all of its input
+    let _ = writer.join();
+    Ok(output)
+}
+
+/// Directory of the map, where commands run from the map start
+pub fn map_dir(app: &AppState) -> Option<PathBuf> {
+    app.filename
+        .as_deref()
+        .and_then(Path::parent)
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(Path::to_path_buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::model::Node;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_hook_env() {
+        let mut app = AppState::new(AppConfig::default());
+        let root = app.tree.new_node(Node::new("Plan".to_string()));
+        let task = root.append_value(Node::new("✓ Ship".to_string()), &mut app.tree);
+        app.root_id = Some(root);
+        app.active_node_id = Some(task);
+        app.filename = Some("plan.hmm".into());
+
+        assert_eq!(
+            hook_env(&app, HookEvent::TaskDone, None),
+            vec![
+                ("HMM_EVENT", "task_done".to_string()),
+                ("HMM_FILE", "plan.hmm".to_string()),
+                ("HMM_NODE", "✓ Ship".to_string()),
+                ("HMM_NODE_PATH", "Plan > ✓ Ship".to_string()),
+            ]
+        );
+        let env = hook_env(&app, HookEvent::AutoSaveFailed, Some("disk full"));
+        assert_eq!(env.last(), Some(&("HMM_ERROR", "disk full".to_string())));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_hook() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let out = dir.path().join("event");
+        let mut app = AppState::new(AppConfig::default());
+        app.config.hooks.save = Some(format!("echo \"$HMM_EVENT\" > '{}'", out.display()));
+
+        run_hook(&mut app, HookEvent::TaskDone, None);
+        run_hook(&mut app, HookEvent::Save, None);
+        let start = Instant::now();
+        while !std::fs::read_to_string(&out).is_ok_and(|text| text.ends_with('\n')) {
+            assert!(start.elapsed() < Duration::from_secs(5), "hook did not run");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), "save\n");
+        assert_eq!(app.message, None);
+    }
+}
@@ -0,0 +1,177 @@
+//! Localized UI strings.
+//!
+//! Status messages and help text are looked up by key (e.g. `status.saved_to`)
+//! in TOML catalogs under `locales/`, which are compiled into the binary.
+//! `en.toml` holds every key and is the fallback for missing translations.
+//!
+//! To add a language, copy `locales/en.toml` to `locales/<code>.toml`,
+//! translate the values and register the file in [`LOCALES`].
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Built-in catalogs as `(language code, TOML source)`
+pub const LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.toml")),
+    ("de", include_str!("../locales/de.toml")),
+];
+
+const FALLBACK_LOCALE: &str = "en";
+
+/// The UI strings for one locale, with English filling any gaps
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    locale: &'static str,
+    messages: HashMap<String, String>,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self::new(FALLBACK_LOCALE)
+    }
+}
+
+impl Catalog {
+    /// Load the catalog for a locale such as `de`, `de_DE.UTF-8` or `pt-BR`.
+    /// Unknown locales get the English catalog.
+    pub fn new(locale: &str) -> Self {
+        let language = language_code(locale);
+        let (locale, source) = LOCALES
+            .iter()
+            .find(|(code, _)| *code == language)
+            .copied()
+            .unwrap_or(LOCALES[0]);
+
+        let mut messages = HashMap::new();
+        flatten_catalog(LOCALES[0].1, &mut messages);
+        if locale != FALLBACK_LOCALE {
+            flatten_catalog(source, &mut messages);
+        }
+
+        Self { locale, messages }
+    }
+
+    /// Language code of the loaded catalog
+    pub fn locale(&self) -> &str {
+        self.locale
+    }
+
+    /// The string for `key`, or the key itself if no catalog defines it
+    pub fn get(&self, key: &str) -> String {
+        self.messages
+            .get(key)
+            .cloned()
+            .unwrap_or_else(|| key.to_string())
+    }
+
+    /// The string for `key` with `{name}` placeholders replaced by `args`
+    pub fn format(&self, key: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+        let mut text = self.get(key);
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+}
+
+/// The locale requested by the environment (`LC_ALL`, `LC_MESSAGES`, then `LANG`)
+pub fn locale_from_env() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+}
+
+/// `de_DE.UTF-8` -> `de`; `C` and `POSIX` map to English
+fn language_code(locale: &str) -> String {
+    let code = locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    match code.as_str() {
+        "c" | "posix" => FALLBACK_LOCALE.to_string(),
+        _ => code,
+    }
+}
+
+/// Parse a catalog into dotted keys (`[status] undone = ...` -> `status.undone`)
+fn flatten_catalog(source: &str, messages: &mut HashMap<String, String>) {
+    fn flatten(prefix: &str, table: &toml::Table, messages: &mut HashMap<String, String>) {
+        for (key, value) in table {
+            let key = if prefix.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{}", prefix, key)
+            };
+            match value {
+                toml::Value::String(text) => {
+                    messages.insert(key, text.clone());
+                }
+                toml::Value::Table(table) => flatten(&key, table, messages),
+                _ => {}
+            }
+        }
+    }
+
+    // Built-in catalogs are checked by the tests below, so a parse error is a bug
+    let table: toml::Table = source.parse().expect("invalid built-in locale catalog");
+    flatten("", &table, messages);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keymap::{COMMANDS, SECTIONS};
+
+    #[test]
+    fn test_language_code() {
+        assert_eq!(language_code("de_DE.UTF-8"), "de");
+        assert_eq!(language_code("pt-BR"), "pt");
+        assert_eq!(language_code("C.UTF-8"), "en");
+        assert_eq!(language_code("EN"), "en");
+    }
+
+    #[test]
+    fn test_lookup_and_fallback() {
+        let de = Catalog::new("de_DE.UTF-8");
+        assert_eq!(de.locale(), "de");
+        assert_eq!(de.get("status.undone"), "Rückgängig gemacht");
+
+        let unknown = Catalog::new("xx");
+        assert_eq!(unknown.locale(), "en");
+        assert_eq!(unknown.get("status.undone"), "Undone");
+        assert_eq!(unknown.get("status.no_such_key"), "status.no_such_key");
+    }
+
+    #[test]
+    fn test_format_placeholders() {
+        let catalog = Catalog::default();
+        assert_eq!(
+            catalog.format("status.search_result", &[("index", &2), ("count", &5)]),
+            "Result 2/5"
+        );
+    }
+
+    #[test]
+    fn test_catalogs_are_complete() {
+        let mut english = HashMap::new();
+        flatten_catalog(LOCALES[0].1, &mut english);
+
+        for command in COMMANDS {
+            assert!(english.contains_key(&format!("help.{}", command.name)));
+        }
+        for section in SECTIONS {
+            assert!(english.contains_key(&format!("section.{}", section)));
+        }
+
+        // Translations may lag behind, but must not carry keys English lacks
+        for (code, source) in &LOCALES[1..] {
+            let mut translated = HashMap::new();
+            flatten_catalog(source, &mut translated);
+            for key in translated.keys() {
+                assert!(english.contains_key(key), "{}: unknown key {}", code, key);
+            }
+        }
+    }
+}
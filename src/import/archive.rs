@@ -0,0 +1,310 @@
+//! Zipped mind map files from XMind and MindManager, read best-effort.
+//!
+//! XMind stores `content.json` (XMind Zen and later) or `content.xml`
+//! (XMind 8); MindManager `.mmap` files store `Document.xml`. Only the topic
+//! hierarchy, plain-text notes and task completion are kept.
+
+use super::{append_note, finish_root, item_title};
+use crate::model::{Node, NodeId};
+use anyhow::{bail, Result};
+use indextree::Arena;
+use roxmltree::Document;
+use serde_json::Value;
+use std::io::{Cursor, Read};
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+/// Parse an `.xmind` file; every sheet contributes its root topic
+pub fn parse_xmind(bytes: &[u8], root_title: &str) -> Result<(Arena<Node>, NodeId)> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    if let Some(content) = read_entry(&mut archive, "content.json")? {
+        return parse_xmind_json(&content, root_title);
+    }
+    if let Some(content) = read_entry(&mut archive, "content.xml")? {
+        return parse_xmind_xml(&content, root_title);
+    }
+    bail!("no content.json or content.xml in the archive");
+}
+
+/// Parse a MindManager `.mmap` file
+pub fn parse_mindmanager(bytes: &[u8], root_title: &str) -> Result<(Arena<Node>, NodeId)> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let Some(content) = read_entry(&mut archive, "Document.xml")? else {
+        bail!("no Document.xml in the archive");
+    };
+    let document = Document::parse(&content)?;
+
+    let mut tree = Arena::new();
+    let root_id = tree.new_node(Node::new(String::new()));
+    let roots = document
+        .root_element()
+        .children()
+        .filter(|node| is_named(*node, "OneTopic"))
+        .filter_map(|one| child(one, "Topic"));
+    let mut stack: Vec<_> = roots.map(|topic| (topic, root_id)).collect();
+    stack.reverse();
+
+    while let Some((topic, parent)) = stack.pop() {
+        let title = child(topic, "Text")
+            .and_then(|text| text.attribute("PlainText"))
+            .unwrap_or_default();
+        let done = child(topic, "Task")
+            .and_then(|task| task.attribute("TaskPercentage"))
+            .is_some_and(|percentage| percentage == "100");
+        let node_id = parent.append_value(Node::new(item_title(title, done)), &mut tree);
+
+        let note = child(topic, "NotesGroup")
+            .and_then(|notes| child(notes, "NotesXhtmlData"))
+            .and_then(|data| data.attribute("PreviewPlainText"));
+        if let Some(note) = note {
+            append_note(&mut tree, node_id, note);
+        }
+
+        if let Some(subtopics) = child(topic, "SubTopics") {
+            let children: Vec<_> = subtopics
+                .children()
+                .filter(|node| is_named(*node, "Topic"))
+                .collect();
+            stack.extend(children.into_iter().rev().map(|topic| (topic, node_id)));
+        }
+    }
+
+    Ok(finish_root(tree, root_id, root_title))
+}
+
+/// Whether `bytes` is a zip archive laid out like a MindManager file
+pub fn is_mindmanager_archive(bytes: &[u8]) -> bool {
+    ZipArchive::new(Cursor::new(bytes))
+        .is_ok_and(|archive| archive.index_for_name("Document.xml").is_some())
+}
+
+/// The text of an archive entry, or `None` if the archive has no such entry
+fn read_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<Option<String>> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+    Ok(Some(content))
+}
+
+fn parse_xmind_json(content: &str, root_title: &str) -> Result<(Arena<Node>, NodeId)> {
+    let sheets: Vec<Value> = serde_json::from_str(content)?;
+
+    let mut tree = Arena::new();
+    let root_id = tree.new_node(Node::new(String::new()));
+    let mut stack: Vec<(&Value, NodeId)> = sheets
+        .iter()
+        .rev()
+        .filter_map(|sheet| sheet.get("rootTopic"))
+        .map(|topic| (topic, root_id))
+        .collect();
+
+    while let Some((topic, parent)) = stack.pop() {
+        let title = topic["title"].as_str().unwrap_or_default();
+        let done = topic["markers"].as_array().is_some_and(|markers| {
+            markers.iter().any(|marker| {
+                marker["markerId"]
+                    .as_str()
+                    .is_some_and(|id| id.starts_with("task-done"))
+            })
+        });
+        let node_id = parent.append_value(Node::new(item_title(title, done)), &mut tree);
+
+        if let Some(note) = topic
+            .pointer("/notes/plain/content")
+            .and_then(Value::as_str)
+        {
+            append_note(&mut tree, node_id, note);
+        }
+
+        if let Some(children) = topic
+            .pointer("/children/attached")
+            .and_then(Value::as_array)
+        {
+            stack.extend(children.iter().rev().map(|child| (child, node_id)));
+        }
+    }
+
+    Ok(finish_root(tree, root_id, root_title))
+}
+
+fn parse_xmind_xml(content: &str, root_title: &str) -> Result<(Arena<Node>, NodeId)> {
+    let document = Document::parse(content)?;
+
+    let mut tree = Arena::new();
+    let root_id = tree.new_node(Node::new(String::new()));
+    let roots = document
+        .root_element()
+        .children()
+        .filter(|node| is_named(*node, "sheet"))
+        .filter_map(|sheet| child(sheet, "topic"));
+    let mut stack: Vec<_> = roots.map(|topic| (topic, root_id)).collect();
+    stack.reverse();
+
+    while let Some((topic, parent)) = stack.pop() {
+        let title = child(topic, "title").map(text).unwrap_or_default();
+        let done = child(topic, "marker-refs").is_some_and(|refs| {
+            refs.children().any(|marker| {
+                marker
+                    .attribute("marker-id")
+                    .is_some_and(|id| id.starts_with("task-done"))
+            })
+        });
+        let node_id = parent.append_value(Node::new(item_title(&title, done)), &mut tree);
+
+        if let Some(note) = child(topic, "notes").and_then(|notes| child(notes, "plain")) {
+            append_note(&mut tree, node_id, &text(note));
+        }
+
+        // Detached (floating) topics are not part of the hierarchy
+        let attached = child(topic, "children").into_iter().flat_map(|children| {
+            children
+                .children()
+                .filter(|topics| {
+                    is_named(*topics, "topics") && topics.attribute("type") == Some("attached")
+                })
+                .flat_map(|topics| topics.children().filter(|node| is_named(*node, "topic")))
+        });
+        let attached: Vec<_> = attached.collect();
+        stack.extend(attached.into_iter().rev().map(|topic| (topic, node_id)));
+    }
+
+    Ok(finish_root(tree, root_id, root_title))
+}
+
+/// Whether `node` is an element with this local name, whatever its namespace
+pub(super) fn is_named(node: roxmltree::Node, name: &str) -> bool {
+    node.is_element() && node.tag_name().name() == name
+}
+
+/// First child element with this local name
+pub(super) fn child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|child| is_named(*child, name))
+}
+
+/// All text inside an element
+pub(super) fn text(node: roxmltree::Node) -> String {
+    node.descendants()
+        .filter(|node| node.is_text())
+        .filter_map(|node| node.text())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::map_to_list;
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+    use zip::{CompressionMethod, ZipWriter};
+
+    fn zip_with(name: &str, content: &str) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        writer.start_file("META-INF/manifest.xml", options).unwrap();
+        writer.start_file(name, options).unwrap();
+        writer.write_all(content.as_bytes()).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn outline((tree, root_id): (Arena<Node>, NodeId)) -> String {
+        map_to_list(&tree, root_id, false, 0)
+    }
+
+    #[test]
+    fn test_xmind_json() {
+        let content = r#"[{
+            "id": "s1", "class": "sheet", "title": "Sheet 1",
+            "rootTopic": {
+                "id": "t0", "title": "Strategy",
+                "notes": {"plain": {"content": "Owned by ops"}},
+                "children": {
+                    "attached": [
+                        {"id": "t1", "title": "Hire", "markers": [{"markerId": "task-done"}]},
+                        {"id": "t2", "title": "Grow", "children": {"attached": [{"id": "t3", "title": "EU"}]}}
+                    ],
+                    "detached": [{"id": "t4", "title": "Floating"}]
+                }
+            }
+        }]"#;
+
+        let map = parse_xmind(&zip_with("content.json", content), "plan").unwrap();
+        assert_eq!(
+            outline(map),
+            "Strategy\n\t[HIDDEN] Owned by ops\n\t✓ Hire\n\tGrow\n\t\tEU\n"
+        );
+    }
+
+    #[test]
+    fn test_xmind_xml_with_two_sheets() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+            <xmap-content xmlns="urn:xmind:xmap:xmlns:content:2.0" version="2.0">
+              <sheet id="s1">
+                <topic id="a"><title>First</title>
+                  <children><topics type="attached">
+                    <topic id="b"><title>Child</title>
+                      <notes><plain>Line one
+                        Line two</plain></notes>
+                      <marker-refs><marker-ref marker-id="task-done"/></marker-refs>
+                    </topic>
+                  </topics></children>
+                </topic>
+              </sheet>
+              <sheet id="s2"><topic id="c"><title>Second</title></topic></sheet>
+            </xmap-content>"#;
+
+        let map = parse_xmind(&zip_with("content.xml", content), "plan").unwrap();
+        assert_eq!(
+            outline(map),
+            "plan\n\tFirst\n\t\t✓ Child\n\t\t\t[HIDDEN] Line one\n\t\t\t[HIDDEN] Line two\n\tSecond\n"
+        );
+    }
+
+    #[test]
+    fn test_mindmanager() {
+        let content = r#"<?xml version="1.0" encoding="UTF-8"?>
+            <ap:Map xmlns:ap="http://schemas.mindjet.com/MindManager/Application/2003">
+              <ap:OneTopic>
+                <ap:Topic>
+                  <ap:SubTopics>
+                    <ap:Topic><ap:Text PlainText="Budget"/><ap:Task TaskPercentage="100"/></ap:Topic>
+                    <ap:Topic>
+                      <ap:Text PlainText="Team"/>
+                      <ap:NotesGroup><ap:NotesXhtmlData PreviewPlainText="Two hires"/></ap:NotesGroup>
+                    </ap:Topic>
+                  </ap:SubTopics>
+                  <ap:Text PlainText="Project X"/>
+                </ap:Topic>
+              </ap:OneTopic>
+            </ap:Map>"#;
+
+        let map = parse_mindmanager(&zip_with("Document.xml", content), "x").unwrap();
+        assert_eq!(
+            outline(map),
+            "Project X\n\t✓ Budget\n\tTeam\n\t\t[HIDDEN] Two hires\n"
+        );
+    }
+
+    #[test]
+    fn test_rejects_other_archives() {
+        let err = parse_xmind(&zip_with("readme.txt", "hi"), "").unwrap_err();
+        assert!(err.to_string().contains("content.json"));
+        assert!(parse_mindmanager(b"not a zip", "").is_err());
+    }
+
+    #[test]
+    fn test_recognizes_mindmanager_archives() {
+        assert!(is_mindmanager_archive(&zip_with(
+            "Document.xml",
+            "<ap:Map/>"
+        )));
+        assert!(!is_mindmanager_archive(&zip_with("content.json", "[]")));
+        assert!(!is_mindmanager_archive(b"not a zip"));
+    }
+}
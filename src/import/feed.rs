@@ -0,0 +1,198 @@
+//! RSS and Atom feeds.
+//!
+//! Each entry of a feed becomes a node titled with the entry's title and
+//! link, `Release notes <https://example.com/notes>`. Importing the feed
+//! again adds only the entries whose link is not among the node's children
+//! yet, so a feed node works as a reading queue. Fetching needs the
+//! `network` feature.
+
+use super::archive::{child, is_named, text};
+use super::item_title;
+use super::web::{decode_entities, fetch_page, page_url, strip_tags};
+use crate::model::{Node, NodeId};
+use anyhow::{bail, Result};
+use indextree::Arena;
+use roxmltree::Document;
+
+/// An entry of a feed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedEntry {
+    pub title: String,
+    pub link: String,
+}
+
+impl FeedEntry {
+    /// Title of the node showing this entry
+    pub fn node_title(&self) -> String {
+        if self.title.is_empty() {
+            self.link.clone()
+        } else {
+            format!("{} <{}>", self.title, self.link)
+        }
+    }
+}
+
+/// Entries of an RSS 2.0, RSS 1.0 or Atom feed, in feed order. Entries
+/// without a link are skipped.
+pub fn parse_feed(xml: &str) -> Result<Vec<FeedEntry>> {
+    let document = Document::parse(xml)?;
+    let root = document.root_element();
+
+    let entries: Vec<_> = match root.tag_name().name() {
+        // RSS 2.0 nests items in the channel, RSS 1.0 puts them beside it
+        "rss" | "RDF" => root
+            .descendants()
+            .filter(|node| is_named(*node, "item"))
+            .filter_map(|item| {
+                let link = child(item, "link").map(text)?;
+                Some((child(item, "title").map(text), link))
+            })
+            .collect(),
+        "feed" => root
+            .children()
+            .filter(|node| is_named(*node, "entry"))
+            .filter_map(|entry| {
+                let link = entry
+                    .children()
+                    .filter(|node| is_named(*node, "link"))
+                    .find(|link| link.attribute("rel").is_none_or(|rel| rel == "alternate"))?
+                    .attribute("href")?
+                    .to_string();
+                Some((child(entry, "title").map(text), link))
+            })
+            .collect(),
+        other => bail!("not an RSS or Atom feed: <{other}>"),
+    };
+
+    Ok(entries
+        .into_iter()
+        .map(|(title, link)| FeedEntry {
+            // Titles may hold escaped markup
+            title: item_title(
+                &decode_entities(&strip_tags(&title.unwrap_or_default())),
+                false,
+            ),
+            link: link.trim().to_string(),
+        })
+        .filter(|entry| !entry.link.is_empty())
+        .collect())
+}
+
+/// Fetch the feed at `url` and read its entries
+pub fn fetch_feed(url: &str) -> Result<Vec<FeedEntry>> {
+    parse_feed(&fetch_page(url)?)
+}
+
+/// The `entries` whose link is not among the children of `parent` yet
+pub fn new_feed_entries(
+    tree: &Arena<Node>,
+    parent: NodeId,
+    entries: &[FeedEntry],
+) -> Vec<FeedEntry> {
+    let mut known: Vec<&str> = parent
+        .children(tree)
+        .filter_map(|child| page_url(&tree[child].get().title))
+        .collect();
+
+    let mut new = Vec::new();
+    for entry in entries {
+        if !known.contains(&entry.link.as_str()) {
+            known.push(&entry.link);
+            new.push(entry.clone());
+        }
+    }
+    new
+}
+
+/// Add `entries` above the existing children of `parent`, so the newest stay
+/// on top
+pub fn add_feed_entries(tree: &mut Arena<Node>, parent: NodeId, entries: &[FeedEntry]) {
+    let first_existing = parent.children(tree).next();
+    for entry in entries {
+        let node = tree.new_node(Node::new(entry.node_title()));
+        match first_existing {
+            Some(existing) => existing.insert_before(node, tree),
+            None => parent.append(node, tree),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RSS: &str = r#"<?xml version="1.0"?>
+        <rss version="2.0"><channel><title>Blog</title>
+          <item><title>Second &lt;b&gt;post&lt;/b&gt;</title><link>https://blog.example/2</link></item>
+          <item><title>First post</title><link> https://blog.example/1 </link></item>
+          <item><title>No link</title></item>
+        </channel></rss>"#;
+
+    #[test]
+    fn test_parse_rss_and_atom() {
+        assert_eq!(
+            parse_feed(RSS).unwrap(),
+            vec![
+                FeedEntry {
+                    title: "Second post".to_string(),
+                    link: "https://blog.example/2".to_string()
+                },
+                FeedEntry {
+                    title: "First post".to_string(),
+                    link: "https://blog.example/1".to_string()
+                },
+            ]
+        );
+
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>News</title>
+            <entry><title>Launch</title>
+              <link rel="self" href="https://news.example/launch.atom"/>
+              <link href="https://news.example/launch"/></entry>
+            <entry><link rel="alternate" href="https://news.example/untitled"/></entry>
+          </feed>"#;
+        let entries = parse_feed(atom).unwrap();
+        assert_eq!(
+            entries[0].node_title(),
+            "Launch <https://news.example/launch>"
+        );
+        assert_eq!(entries[1].node_title(), "https://news.example/untitled");
+
+        assert!(parse_feed("<html><body/></html>").is_err());
+    }
+
+    #[test]
+    fn test_new_feed_entries_skip_known_links() {
+        let mut tree = Arena::new();
+        let feed = tree.new_node(Node::new("Blog https://blog.example/feed".to_string()));
+        feed.append_value(
+            Node::new("Read <https://blog.example/1>".to_string()),
+            &mut tree,
+        );
+
+        let mut entries = parse_feed(RSS).unwrap();
+        entries.insert(
+            0,
+            FeedEntry {
+                title: "Third post".to_string(),
+                link: "https://blog.example/3".to_string(),
+            },
+        );
+        let new = new_feed_entries(&tree, feed, &entries);
+        assert_eq!(new.len(), 2);
+        add_feed_entries(&mut tree, feed, &new);
+        assert!(new_feed_entries(&tree, feed, &entries).is_empty());
+
+        let titles: Vec<_> = feed
+            .children(&tree)
+            .map(|child| tree[child].get().title.clone())
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                "Third post <https://blog.example/3>",
+                "Second post <https://blog.example/2>",
+                "Read <https://blog.example/1>",
+            ]
+        );
+    }
+}
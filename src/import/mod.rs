@@ -0,0 +1,99 @@
+//! Readers and writers for other outliners' file formats.
+//!
+//! JSON and table files are written back in their own format; XMind and
+//! MindManager files are read only and have to be saved under another format.
+//! Taskwarrior and todo.txt task lists are written from task nodes, and read
+//! back only for the completion of those tasks. Web pages contribute their
+//! heading outline, and feeds their entries, to an existing map.
+
+mod archive;
+mod feed;
+mod outliner;
+mod table;
+mod tasks;
+mod web;
+
+pub use archive::{is_mindmanager_archive, parse_mindmanager, parse_xmind};
+pub use feed::{add_feed_entries, fetch_feed, new_feed_entries, parse_feed, FeedEntry};
+pub use outliner::{parse_outliner_json, to_outliner_json};
+pub use table::{parse_table, to_table, TableHierarchy};
+pub(crate) use tasks::task_state;
+pub use tasks::{apply_task_status, to_taskwarrior, to_todo_txt};
+pub use web::{append_headings, fetch_outline, heading_outline, page_url, Heading};
+
+use crate::model::{Node, NodeId, NEW_MAP_TITLE};
+use crate::parser::MapFormat;
+use anyhow::{bail, Context, Result};
+use indextree::Arena;
+use std::fs;
+use std::path::Path;
+
+/// Title prefix for completed items; matches the default `symbol1`
+pub(crate) const DONE_PREFIX: &str = "✓ ";
+
+/// Title prefix marking a node as hidden
+pub(crate) const HIDDEN_PREFIX: &str = "[HIDDEN] ";
+
+/// File name without extension, used to title maps with several top-level items
+pub(crate) fn file_title(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Field delimiter of the CSV or TSV file at `path`
+pub fn table_delimiter(path: &Path) -> Result<u8> {
+    match MapFormat::from_path(path).and_then(MapFormat::delimiter) {
+        Some(delimiter) => Ok(delimiter),
+        None => bail!("{} is not a .csv or .tsv file", path.display()),
+    }
+}
+
+/// Load a CSV or TSV file with the hierarchy taken from the given columns
+pub fn load_table(path: &Path, hierarchy: &TableHierarchy) -> Result<(Arena<Node>, NodeId)> {
+    let delimiter = table_delimiter(path)?;
+    let content = fs::read_to_string(path)?;
+    parse_table(&content, delimiter, hierarchy, &file_title(path))
+        .with_context(|| format!("cannot import {}", path.display()))
+}
+
+/// Node title for an outliner item: one line, with completion shown as a task symbol
+pub(crate) fn item_title(text: &str, completed: bool) -> String {
+    let title = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if completed {
+        format!("{DONE_PREFIX}{title}")
+    } else {
+        title
+    }
+}
+
+/// Finish a map built under a placeholder root: a single top-level node
+/// becomes the root itself, several share a root titled `root_title`
+pub(crate) fn finish_root(
+    mut tree: Arena<Node>,
+    root_id: NodeId,
+    root_title: &str,
+) -> (Arena<Node>, NodeId) {
+    let mut top_level = root_id.children(&tree);
+    if let (Some(only), None) = (top_level.next(), top_level.next()) {
+        only.detach(&mut tree);
+        root_id.remove(&mut tree);
+        return (tree, only);
+    }
+
+    let title = if root_title.is_empty() {
+        NEW_MAP_TITLE
+    } else {
+        root_title
+    };
+    tree[root_id].get_mut().title = title.to_string();
+    (tree, root_id)
+}
+
+/// Attach a note as hidden children of `parent`, one per non-empty line, so it
+/// survives saving as `.hmm` without cluttering the map
+pub(crate) fn append_note(tree: &mut Arena<Node>, parent: NodeId, note: &str) {
+    for line in note.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        parent.append_value(Node::new(format!("{HIDDEN_PREFIX}{line}")), tree);
+    }
+}
@@ -0,0 +1,328 @@
+//! Workflowy and Dynalist JSON exports.
+//!
+//! Workflowy backups nest items as `{"nm", "no", "cp", "ch"}` objects (the
+//! long names `name`, `note`, `completed` and `children` are accepted too).
+//! Dynalist documents are a flat `nodes` list whose entries refer to their
+//! children by id, starting from the node with id `root`. Maps are written
+//! back as Workflowy backups.
+
+use super::{append_note, item_title, DONE_PREFIX, HIDDEN_PREFIX};
+use crate::model::{Node, NodeId, NEW_MAP_TITLE};
+use anyhow::{bail, Result};
+use indextree::Arena;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Export {
+    Dynalist(DynalistDocument),
+    WorkflowyItems(Vec<WorkflowyItem>),
+    WorkflowyItem(WorkflowyItem),
+}
+
+#[derive(Deserialize)]
+struct WorkflowyItem {
+    #[serde(alias = "nm")]
+    name: String,
+    #[serde(alias = "no", default)]
+    note: Option<String>,
+    /// Completion timestamp in backups, a flag in some exports
+    #[serde(alias = "cp", default)]
+    completed: Value,
+    #[serde(alias = "ch", default)]
+    children: Vec<WorkflowyItem>,
+}
+
+impl WorkflowyItem {
+    fn is_completed(&self) -> bool {
+        !matches!(self.completed, Value::Null | Value::Bool(false))
+    }
+}
+
+#[derive(Deserialize)]
+struct DynalistDocument {
+    #[serde(default)]
+    title: Option<String>,
+    nodes: Vec<DynalistNode>,
+}
+
+#[derive(Deserialize)]
+struct DynalistNode {
+    id: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    note: String,
+    #[serde(default)]
+    checked: bool,
+    #[serde(default)]
+    collapsed: bool,
+    #[serde(default)]
+    children: Vec<String>,
+}
+
+/// Parse a Workflowy or Dynalist JSON export. `root_title` names the map when
+/// the export has several top-level items and no title of its own.
+pub fn parse_outliner_json(content: &str, root_title: &str) -> Result<(Arena<Node>, NodeId)> {
+    match serde_json::from_str(content)? {
+        Export::Dynalist(document) => build_dynalist(document, root_title),
+        Export::WorkflowyItems(mut items) if items.len() == 1 => {
+            Ok(build_workflowy(items.remove(0)))
+        }
+        Export::WorkflowyItems(items) => {
+            let title = if root_title.is_empty() {
+                NEW_MAP_TITLE
+            } else {
+                root_title
+            };
+            Ok(build_workflowy(WorkflowyItem {
+                name: title.to_string(),
+                note: None,
+                completed: Value::Null,
+                children: items,
+            }))
+        }
+        Export::WorkflowyItem(item) => Ok(build_workflowy(item)),
+    }
+}
+
+fn build_workflowy(root: WorkflowyItem) -> (Arena<Node>, NodeId) {
+    let mut tree = Arena::new();
+    let root_id = tree.new_node(Node::new(item_title(&strip_html(&root.name), false)));
+    let mut stack = vec![(root, root_id)];
+
+    while let Some((item, node_id)) = stack.pop() {
+        if let Some(note) = &item.note {
+            append_note(&mut tree, node_id, &strip_html(note));
+        }
+        for child in item.children {
+            let title = item_title(&strip_html(&child.name), child.is_completed());
+            let child_id = node_id.append_value(Node::new(title), &mut tree);
+            stack.push((child, child_id));
+        }
+    }
+
+    (tree, root_id)
+}
+
+fn build_dynalist(document: DynalistDocument, root_title: &str) -> Result<(Arena<Node>, NodeId)> {
+    let nodes: HashMap<&str, &DynalistNode> = document
+        .nodes
+        .iter()
+        .map(|node| (node.id.as_str(), node))
+        .collect();
+    let Some(root) = nodes.get("root").copied().or(document.nodes.first()) else {
+        bail!("the document has no nodes");
+    };
+
+    // Dynalist leaves the root's content empty and keeps the title separately
+    let title = match (root.content.trim(), document.title.as_deref()) {
+        ("", Some(title)) if !title.trim().is_empty() => title,
+        ("", _) if !root_title.is_empty() => root_title,
+        ("", _) => NEW_MAP_TITLE,
+        (content, _) => content,
+    };
+
+    let mut tree = Arena::new();
+    let root_id = tree.new_node(Node::new(item_title(title, false)));
+    let mut visited = HashSet::from([root.id.as_str()]);
+    let mut stack = vec![(root, root_id)];
+
+    while let Some((node, node_id)) = stack.pop() {
+        tree[node_id].get_mut().is_collapsed = node.collapsed && !node.children.is_empty();
+        append_note(&mut tree, node_id, &node.note);
+
+        // Unknown ids and repeated references are skipped rather than failing the import
+        for child in node.children.iter().filter_map(|id| nodes.get(id.as_str())) {
+            if !visited.insert(child.id.as_str()) {
+                continue;
+            }
+            let title = item_title(&child.content, child.checked);
+            let child_id = node_id.append_value(Node::new(title), &mut tree);
+            stack.push((child, child_id));
+        }
+    }
+
+    Ok((tree, root_id))
+}
+
+/// Write a map as a Workflowy backup, the inverse of [`parse_outliner_json`]:
+/// hidden leaves become their parent's note and the done prefix the completed flag
+pub fn to_outliner_json(tree: &Arena<Node>, root_id: NodeId) -> Result<String> {
+    // Items are built bottom-up so deep maps need no recursion
+    let order: Vec<NodeId> = root_id.descendants(tree).collect();
+    let mut items: HashMap<NodeId, Value> = HashMap::new();
+
+    for &node_id in order.iter().rev() {
+        let mut notes = Vec::new();
+        let mut children = Vec::new();
+        for child_id in node_id.children(tree) {
+            let child = items.remove(&child_id);
+            let is_leaf = child_id.children(tree).next().is_none();
+            match tree[child_id].get().title.strip_prefix(HIDDEN_PREFIX) {
+                Some(line) if is_leaf => notes.push(escape_html(line)),
+                _ => children.extend(child),
+            }
+        }
+
+        // The reader never marks the top item completed, so its prefix stays in the name
+        let title = &tree[node_id].get().title;
+        let (name, completed) = match title.strip_prefix(DONE_PREFIX) {
+            Some(name) if node_id != root_id => (name, true),
+            _ => (title.as_str(), false),
+        };
+
+        let mut item = Map::new();
+        item.insert("nm".to_string(), escape_html(name).into());
+        if !notes.is_empty() {
+            item.insert("no".to_string(), notes.join("\n").into());
+        }
+        if completed {
+            item.insert("cp".to_string(), true.into());
+        }
+        if !children.is_empty() {
+            item.insert("ch".to_string(), children.into());
+        }
+        items.insert(node_id, item.into());
+    }
+
+    let root = items.remove(&root_id).unwrap_or_default();
+    Ok(serde_json::to_string_pretty(&[root])? + "\n")
+}
+
+/// Workflowy stores names as HTML; keep the text only
+fn strip_html(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => output.push(c),
+            _ => {}
+        }
+    }
+    output
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(tree: &Arena<Node>, root_id: NodeId) -> Vec<String> {
+        root_id
+            .descendants(tree)
+            .map(|id| tree[id].get().title.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_workflowy_backup() {
+        let content = r#"[{
+            "id": "a", "nm": "Projects", "no": "Active work\nand ideas",
+            "ch": [
+                {"id": "b", "nm": "Write <b>docs</b> &amp; tests", "cp": 1700000000},
+                {"id": "c", "nm": "Ship", "ch": [{"id": "d", "nm": "Tag release"}]}
+            ]
+        }]"#;
+
+        let (tree, root_id) = parse_outliner_json(content, "backup").unwrap();
+        assert_eq!(
+            titles(&tree, root_id),
+            [
+                "Projects",
+                "[HIDDEN] Active work",
+                "[HIDDEN] and ideas",
+                "✓ Write docs & tests",
+                "Ship",
+                "Tag release",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_workflowy_several_top_level_items() {
+        let content = r#"[
+            {"name": "One", "completed": false},
+            {"name": "Two", "completed": true, "children": []}
+        ]"#;
+
+        let (tree, root_id) = parse_outliner_json(content, "inbox").unwrap();
+        assert_eq!(titles(&tree, root_id), ["inbox", "One", "✓ Two"]);
+    }
+
+    #[test]
+    fn test_dynalist_document() {
+        let content = r#"{
+            "file_id": "f", "title": "Reading list",
+            "nodes": [
+                {"id": "root", "content": "", "children": ["x", "y"]},
+                {"id": "y", "content": "Finished", "checked": true},
+                {"id": "x", "content": "Books", "note": "from **friends**",
+                 "collapsed": true, "children": ["z", "missing", "x"]},
+                {"id": "z", "content": "Dune"}
+            ]
+        }"#;
+
+        let (tree, root_id) = parse_outliner_json(content, "ignored").unwrap();
+        assert_eq!(
+            titles(&tree, root_id),
+            [
+                "Reading list",
+                "Books",
+                "[HIDDEN] from **friends**",
+                "Dune",
+                "✓ Finished",
+            ]
+        );
+        let books = root_id.children(&tree).next().unwrap();
+        assert!(tree[books].get().is_collapsed);
+    }
+
+    #[test]
+    fn test_rejects_other_json() {
+        assert!(parse_outliner_json(r#"{"hello": "world"}"#, "x").is_err());
+        assert!(parse_outliner_json("not json", "x").is_err());
+    }
+
+    #[test]
+    fn test_written_backup_reads_back() {
+        let mut tree = Arena::new();
+        let root_id = tree.new_node(Node::new("✓ Plans".to_string()));
+        let a = root_id.append_value(Node::new("<b> & co".to_string()), &mut tree);
+        a.append_value(Node::new("[HIDDEN] a note".to_string()), &mut tree);
+        a.append_value(Node::new("✓ Done".to_string()), &mut tree);
+        let hidden = root_id.append_value(Node::new("[HIDDEN] Archive".to_string()), &mut tree);
+        hidden.append_value(Node::new("Old".to_string()), &mut tree);
+
+        let json = to_outliner_json(&tree, root_id).unwrap();
+        assert!(json.contains(r#""nm": "&lt;b&gt; &amp; co""#));
+
+        let (loaded, loaded_root) = parse_outliner_json(&json, "x").unwrap();
+        assert_eq!(
+            titles(&loaded, loaded_root),
+            [
+                "✓ Plans",
+                "<b> & co",
+                "[HIDDEN] a note",
+                "✓ Done",
+                "[HIDDEN] Archive",
+                "Old"
+            ]
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! CSV and TSV tables whose rows are the nodes of a map.
+//!
+//! The hierarchy comes either from a path column (`Fruit/Citrus/Lemon`) or
+//! from a level column next to a title column, where each row nests under the
+//! closest preceding row with a lower level. Maps are written back in the
+//! level form, which keeps siblings with the same title apart.
+
+use super::finish_root;
+use crate::model::{Node, NodeId};
+use anyhow::{anyhow, bail, Context, Result};
+use indextree::Arena;
+use std::collections::HashMap;
+
+/// Separator between the segments of a path column
+const PATH_SEPARATOR: char = '/';
+
+/// Header names recognized when the columns are not given explicitly
+const PATH_HEADERS: [&str; 3] = ["path", "hierarchy", "category"];
+const LEVEL_HEADERS: [&str; 4] = ["level", "indent", "depth", "outline level"];
+const TITLE_HEADERS: [&str; 5] = ["title", "name", "item", "text", "topic"];
+
+/// Which columns define the hierarchy of a table import
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum TableHierarchy {
+    /// Detect the columns from the header row
+    #[default]
+    Auto,
+    /// A column of `A/B/C` paths
+    Path { column: String },
+    /// A column of nesting levels and a column of titles
+    Level { column: String, title: String },
+}
+
+/// Parse a table with `delimiter`-separated fields and a header row.
+/// `root_title` names the map when the table has several top-level rows.
+pub fn parse_table(
+    content: &str,
+    delimiter: u8,
+    hierarchy: &TableHierarchy,
+    root_title: &str,
+) -> Result<(Arena<Node>, NodeId)> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let headers = reader.headers()?.clone();
+    let rows = reader
+        .records()
+        .collect::<Result<Vec<_>, _>>()
+        .context("malformed row")?;
+
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header.eq_ignore_ascii_case(name))
+            .ok_or_else(|| anyhow!("no column named \"{name}\""))
+    };
+    let detect = |names: &[&str]| names.iter().find_map(|name| column(name).ok());
+
+    let mut tree = Arena::new();
+    let root_id = tree.new_node(Node::new(String::new()));
+    match hierarchy {
+        TableHierarchy::Path { column: path } => {
+            build_from_paths(&mut tree, root_id, &rows, column(path)?)
+        }
+        TableHierarchy::Level {
+            column: level,
+            title,
+        } => build_from_levels(&mut tree, root_id, &rows, column(level)?, column(title)?)?,
+        TableHierarchy::Auto => match (detect(&PATH_HEADERS), detect(&LEVEL_HEADERS)) {
+            (Some(path), _) => build_from_paths(&mut tree, root_id, &rows, path),
+            (None, Some(level)) => {
+                let title = detect(&TITLE_HEADERS)
+                    .or_else(|| (0..headers.len()).find(|&i| i != level))
+                    .ok_or_else(|| anyhow!("no title column next to the level column"))?;
+                build_from_levels(&mut tree, root_id, &rows, level, title)?
+            }
+            // Without a known header the first column holds the paths
+            (None, None) => build_from_paths(&mut tree, root_id, &rows, 0),
+        },
+    }
+
+    Ok(finish_root(tree, root_id, root_title))
+}
+
+/// Write a map as a table with `level` and `title` columns, the root at level 1
+pub fn to_table(tree: &Arena<Node>, root_id: NodeId, delimiter: u8) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    writer.write_record(["level", "title"])?;
+
+    let mut stack = vec![(root_id, 1)];
+    while let Some((node_id, level)) = stack.pop() {
+        writer.write_record([level.to_string().as_str(), &tree[node_id].get().title])?;
+        let children: Vec<_> = node_id.children(tree).collect();
+        stack.extend(children.into_iter().rev().map(|child| (child, level + 1)));
+    }
+
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+fn build_from_paths(
+    tree: &mut Arena<Node>,
+    root_id: NodeId,
+    rows: &[csv::StringRecord],
+    column: usize,
+) {
+    // Rows sharing a path prefix share the nodes for it
+    let mut nodes: HashMap<(NodeId, &str), NodeId> = HashMap::new();
+    for row in rows {
+        let mut parent = root_id;
+        let segments = row.get(column).unwrap_or_default().split(PATH_SEPARATOR);
+        for segment in segments.map(str::trim).filter(|s| !s.is_empty()) {
+            parent = *nodes
+                .entry((parent, segment))
+                .or_insert_with(|| parent.append_value(Node::new(segment.to_string()), tree));
+        }
+    }
+}
+
+fn build_from_levels(
+    tree: &mut Arena<Node>,
+    root_id: NodeId,
+    rows: &[csv::StringRecord],
+    level_column: usize,
+    title_column: usize,
+) -> Result<()> {
+    // Open ancestors with their levels, innermost last
+    let mut open: Vec<(usize, NodeId)> = Vec::new();
+    for (line, row) in rows.iter().enumerate() {
+        let title = row.get(title_column).unwrap_or_default();
+        let level = row.get(level_column).unwrap_or_default();
+        if title.is_empty() && level.is_empty() {
+            continue;
+        }
+        let Ok(level) = level.parse::<usize>() else {
+            // Line numbers count the header row
+            bail!("row {}: level \"{level}\" is not a number", line + 2);
+        };
+
+        while open
+            .last()
+            .is_some_and(|&(open_level, _)| open_level >= level)
+        {
+            open.pop();
+        }
+        let parent = open.last().map_or(root_id, |&(_, id)| id);
+        let node_id = parent.append_value(Node::new(title.to_string()), tree);
+        open.push((level, node_id));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::map_to_list;
+
+    fn outline(content: &str, delimiter: u8, hierarchy: &TableHierarchy) -> String {
+        let (tree, root_id) = parse_table(content, delimiter, hierarchy, "table").unwrap();
+        map_to_list(&tree, root_id, false, 0)
+    }
+
+    #[test]
+    fn test_path_column() {
+        let content =
+            "id,path,price\n1,Fruit/Citrus/Lemon,2\n2,Fruit/Citrus/Lime,3\n3,Fruit/Apple,1\n";
+        assert_eq!(
+            outline(content, b',', &TableHierarchy::Auto),
+            "Fruit\n\tCitrus\n\t\tLemon\n\t\tLime\n\tApple\n"
+        );
+    }
+
+    #[test]
+    fn test_level_column() {
+        let content = "Level\tName\n1\tPlan\n2\tDesign\n3\tMockups\n2\tBuild\n1\tLaunch\n";
+        assert_eq!(
+            outline(content, b'\t', &TableHierarchy::Auto),
+            "table\n\tPlan\n\t\tDesign\n\t\t\tMockups\n\t\tBuild\n\tLaunch\n"
+        );
+    }
+
+    #[test]
+    fn test_explicit_columns() {
+        let content = "a,b,c\n\"x, y\",0,top\nz,1,child\n";
+        let level = TableHierarchy::Level {
+            column: "b".to_string(),
+            title: "c".to_string(),
+        };
+        assert_eq!(outline(content, b',', &level), "top\n\tchild\n");
+
+        let path = TableHierarchy::Path {
+            column: "A".to_string(),
+        };
+        assert_eq!(outline(content, b',', &path), "table\n\tx, y\n\tz\n");
+
+        let missing = TableHierarchy::Path {
+            column: "nope".to_string(),
+        };
+        let err = parse_table(content, b',', &missing, "").unwrap_err();
+        assert!(err.to_string().contains("nope"));
+    }
+
+    #[test]
+    fn test_bad_level_reports_row() {
+        let content = "level,title\n1,ok\nx,bad\n";
+        let err = parse_table(content, b',', &TableHierarchy::Auto, "").unwrap_err();
+        assert!(err.to_string().contains("row 3"));
+    }
+
+    #[test]
+    fn test_written_table_reads_back() {
+        let content = "level,title\n1,Plan\n2,\"Design, v2\"\n3,Plan\n2,Build\n";
+        let (tree, root_id) = parse_table(content, b',', &TableHierarchy::Auto, "").unwrap();
+        assert_eq!(to_table(&tree, root_id, b',').unwrap(), content);
+
+        let tsv = to_table(&tree, root_id, b'\t').unwrap();
+        assert!(tsv.starts_with("level\ttitle\n1\tPlan\n2\tDesign, v2\n"));
+    }
+}
@@ -0,0 +1,298 @@
+//! Taskwarrior JSON and todo.txt task lists.
+//!
+//! Nodes starting with `symbol1` (done) or `symbol2` (to do) are tasks. Each
+//! becomes a task whose project is the path of titles above it, joined by
+//! dots as Taskwarrior nests projects: `✗ Dig` under `Garden` in a map titled
+//! `Home` is in project `Home.Garden`. Reading a task list back marks the
+//! matching nodes done or to do, so planning can stay in the map while the
+//! work is tracked elsewhere.
+
+use crate::export::ExportOptions;
+use crate::model::{Node, NodeId};
+use anyhow::Result;
+use indextree::Arena;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A task node
+struct Task {
+    node_id: NodeId,
+    description: String,
+    project: String,
+    done: bool,
+}
+
+/// A task as `task import` reads it and `task export` writes it
+#[derive(Serialize, Deserialize)]
+struct TaskwarriorTask {
+    description: String,
+    status: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    project: String,
+}
+
+/// Whether `title` is a done or to-do task, and its text after the symbol
+pub(crate) fn task_state<'a>(title: &'a str, options: &ExportOptions) -> Option<(bool, &'a str)> {
+    [
+        (true, &options.done_symbol),
+        (false, &options.pending_symbol),
+    ]
+    .into_iter()
+    .find_map(|(done, symbol)| {
+        let text = title.strip_prefix(symbol.as_str())?.strip_prefix(' ')?;
+        Some((done, text))
+    })
+}
+
+/// One line of text, words separated by single spaces
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A title as one level of a project path, which cannot hold spaces or dots
+fn project_segment(title: &str, options: &ExportOptions) -> String {
+    let text = task_state(title, options).map_or(title, |(_, text)| text);
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+        .replace('.', "_")
+}
+
+/// Task nodes in the subtree at `root_id`, in outline order. Collapsed
+/// branches count, hidden nodes and comments do not.
+fn tasks(tree: &Arena<Node>, root_id: NodeId, options: &ExportOptions) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    let mut stack = vec![(root_id, Vec::<String>::new())];
+    while let Some((node_id, path)) = stack.pop() {
+        let node = tree[node_id].get();
+        if node.is_hidden() || options.excludes(node) {
+            continue;
+        }
+        if let Some((done, text)) = task_state(&node.title, options) {
+            tasks.push(Task {
+                node_id,
+                description: one_line(text),
+                project: path.join("."),
+                done,
+            });
+        }
+
+        let mut child_path = path;
+        child_path.push(project_segment(&node.title, options));
+        let children: Vec<_> = node_id.children(tree).collect();
+        stack.extend(
+            children
+                .into_iter()
+                .rev()
+                .map(|child| (child, child_path.clone())),
+        );
+    }
+    tasks
+}
+
+/// Export the task nodes as a Taskwarrior JSON array
+pub fn to_taskwarrior(tree: &Arena<Node>, root_id: NodeId, options: &ExportOptions) -> String {
+    let tasks: Vec<_> = tasks(tree, root_id, options)
+        .into_iter()
+        .map(|task| TaskwarriorTask {
+            description: task.description,
+            status: if task.done { "completed" } else { "pending" }.to_string(),
+            project: task.project,
+        })
+        .collect();
+    let mut output = serde_json::to_string_pretty(&tasks).unwrap_or_default();
+    output.push('\n');
+    output
+}
+
+/// Export the task nodes as todo.txt lines, the project as a `+project` tag
+pub fn to_todo_txt(tree: &Arena<Node>, root_id: NodeId, options: &ExportOptions) -> String {
+    let mut output = String::new();
+    for task in tasks(tree, root_id, options) {
+        if task.done {
+            output.push_str("x ");
+        }
+        output.push_str(&task.description);
+        if !task.project.is_empty() {
+            output.push_str(" +");
+            output.push_str(&task.project);
+        }
+        output.push('\n');
+    }
+    output
+}
+
+/// Project, description and completion of each task in a todo.txt file
+fn parse_todo_txt(content: &str) -> Vec<(String, String, bool)> {
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (done, rest) = match line.trim().strip_prefix("x ") {
+                Some(rest) => (true, rest),
+                None => (false, line.trim()),
+            };
+            // Leading priority and completion or creation dates are no part of
+            // the description
+            let is_date = |word: &str| {
+                word.len() == 10
+                    && word.char_indices().all(|(i, c)| {
+                        if i == 4 || i == 7 {
+                            c == '-'
+                        } else {
+                            c.is_ascii_digit()
+                        }
+                    })
+            };
+            let is_priority = |word: &str| {
+                let bytes = word.as_bytes();
+                bytes.len() == 3
+                    && bytes[0] == b'('
+                    && bytes[1].is_ascii_uppercase()
+                    && bytes[2] == b')'
+            };
+            let words = rest
+                .split_whitespace()
+                .skip_while(|word| is_date(word) || is_priority(word));
+
+            let mut project = String::new();
+            let mut description = Vec::new();
+            for word in words {
+                match word.strip_prefix('+') {
+                    Some(tag) if project.is_empty() && !tag.is_empty() => project = tag.to_string(),
+                    _ if word.len() > 1 && (word.starts_with('+') || word.starts_with('@')) => {}
+                    _ => description.push(word),
+                }
+            }
+            (project, description.join(" "), done)
+        })
+        .collect()
+}
+
+/// Mark the task nodes under `root_id` done or to do as a Taskwarrior JSON
+/// export or a todo.txt file says, matching tasks by project and
+/// description. Tasks deleted in Taskwarrior are left alone. Returns the
+/// number of nodes changed.
+pub fn apply_task_status(
+    tree: &mut Arena<Node>,
+    root_id: NodeId,
+    content: &str,
+    options: &ExportOptions,
+) -> Result<usize> {
+    let listed: Vec<(String, String, bool)> = if content.trim_start().starts_with('[') {
+        serde_json::from_str::<Vec<TaskwarriorTask>>(content)?
+            .into_iter()
+            .filter_map(|task| {
+                let done = match task.status.as_str() {
+                    "completed" => true,
+                    "pending" | "waiting" | "recurring" => false,
+                    _ => return None,
+                };
+                Some((task.project, one_line(&task.description), done))
+            })
+            .collect()
+    } else {
+        parse_todo_txt(content)
+    };
+    let status: HashMap<(String, String), bool> = listed
+        .into_iter()
+        .map(|(project, description, done)| ((project, description), done))
+        .collect();
+
+    let mut changed = 0;
+    for task in tasks(tree, root_id, options) {
+        let Some(&done) = status.get(&(task.project, task.description)) else {
+            continue;
+        };
+        if done == task.done {
+            continue;
+        }
+        let node = tree[task.node_id].get_mut();
+        let text = task_state(&node.title, options)
+            .map(|(_, text)| text.to_string())
+            .unwrap_or_default();
+        let symbol = if done {
+            &options.done_symbol
+        } else {
+            &options.pending_symbol
+        };
+        node.title = format!("{symbol} {text}");
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_tree() -> (Arena<Node>, NodeId) {
+        let mut tree = Arena::new();
+        let root = tree.new_node(Node::new("Home".to_string()));
+        let garden = root.append_value(Node::new("Garden beds".to_string()), &mut tree);
+        garden.append_value(Node::new("✗ Dig".to_string()), &mut tree);
+        let plant = garden.append_value(Node::new("✓ Plant v1.0".to_string()), &mut tree);
+        plant.append_value(Node::new("✗ Water".to_string()), &mut tree);
+        tree[garden].get_mut().is_collapsed = true;
+        root.append_value(Node::new("✗ Call  plumber".to_string()), &mut tree);
+        root.append_value(Node::new("// ✗ Not a task".to_string()), &mut tree);
+        root.append_value(Node::new("Notes".to_string()), &mut tree);
+        (tree, root)
+    }
+
+    #[test]
+    fn test_to_todo_txt() {
+        let (tree, root) = create_test_tree();
+        assert_eq!(
+            to_todo_txt(&tree, root, &ExportOptions::default()),
+            "Dig +Home.Garden_beds\n\
+             x Plant v1.0 +Home.Garden_beds\n\
+             Water +Home.Garden_beds.Plant_v1_0\n\
+             Call plumber +Home\n"
+        );
+    }
+
+    #[test]
+    fn test_to_taskwarrior() {
+        let (tree, root) = create_test_tree();
+        let json = to_taskwarrior(&tree, root, &ExportOptions::default());
+        let tasks: Vec<serde_json::Value> = serde_json::from_str(&json).unwrap();
+        assert_eq!(tasks.len(), 4);
+        assert_eq!(
+            tasks[1],
+            serde_json::json!({
+                "description": "Plant v1.0",
+                "status": "completed",
+                "project": "Home.Garden_beds",
+            })
+        );
+    }
+
+    #[test]
+    fn test_apply_task_status() {
+        let options = ExportOptions::default();
+        let (mut tree, root) = create_test_tree();
+        let todo = "x 2024-05-01 2024-04-01 Dig +Home.Garden_beds @outside\n\
+                    (A) Plant v1.0 +Home.Garden_beds\n\
+                    x Unknown task +Home\n";
+        assert_eq!(
+            apply_task_status(&mut tree, root, todo, &options).unwrap(),
+            2
+        );
+        let exported = to_todo_txt(&tree, root, &options);
+        assert!(exported.starts_with("x Dig +Home.Garden_beds\nPlant v1.0"));
+
+        let json = r#"[{"description": "Call plumber", "status": "completed", "project": "Home", "uuid": "1"},
+                       {"description": "Water", "status": "deleted", "project": "Home.Garden_beds.Plant_v1_0"}]"#;
+        assert_eq!(
+            apply_task_status(&mut tree, root, json, &options).unwrap(),
+            1
+        );
+        let titles: Vec<_> = root
+            .descendants(&tree)
+            .map(|id| tree[id].get().title.clone())
+            .collect();
+        assert!(titles.contains(&"✓ Call  plumber".to_string()));
+        assert!(titles.contains(&"✗ Water".to_string()));
+    }
+}
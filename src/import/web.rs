@@ -0,0 +1,274 @@
+//! Heading outlines of web pages.
+//!
+//! The `<h1>`–`<h6>` headings of a page are read in document order, each
+//! nested under the closest heading before it with a higher rank, which
+//! turns documentation pages into reading maps. Fetching needs the
+//! `network` feature.
+
+use super::item_title;
+use crate::model::{Node, NodeId};
+use anyhow::Result;
+use indextree::Arena;
+
+/// A heading of a page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// 1 for `<h1>` through 6 for `<h6>`
+    pub level: u8,
+    pub title: String,
+}
+
+/// The `http://` or `https://` URL in a node title, if any
+pub fn page_url(title: &str) -> Option<&str> {
+    title
+        .split_whitespace()
+        .map(|word| word.trim_matches(|c| matches!(c, '<' | '>' | '(' | ')' | '"' | '\'')))
+        .find(|word| {
+            ["http://", "https://"]
+                .iter()
+                .any(|scheme| word.len() > scheme.len() && word.starts_with(scheme))
+        })
+}
+
+/// Headings of an HTML page in document order; empty headings are skipped
+pub fn heading_outline(html: &str) -> Vec<Heading> {
+    let lower = html.to_ascii_lowercase();
+    let mut headings = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = lower[pos..].find('<') {
+        let start = pos + offset;
+        pos = start + 1;
+
+        // Headings in scripts, styles and comments are not part of the page
+        for (open, close) in [
+            ("<!--", "-->"),
+            ("<script", "</script"),
+            ("<style", "</style"),
+        ] {
+            if lower[start..].starts_with(open) {
+                pos = lower[start..]
+                    .find(close)
+                    .map_or(lower.len(), |end| start + end + close.len());
+            }
+        }
+        if pos != start + 1 {
+            continue;
+        }
+
+        let bytes = lower.as_bytes();
+        let level = match (
+            bytes.get(start + 1),
+            bytes.get(start + 2),
+            bytes.get(start + 3),
+        ) {
+            (Some(b'h'), Some(&digit @ b'1'..=b'6'), Some(b'>' | b' ' | b'\t' | b'\n' | b'\r')) => {
+                digit - b'0'
+            }
+            _ => continue,
+        };
+        let Some(content_start) = lower[start..].find('>').map(|end| start + end + 1) else {
+            break;
+        };
+        let close = format!("</h{level}");
+        let content_end = lower[content_start..]
+            .find(&close)
+            .map_or(lower.len(), |end| content_start + end);
+
+        let title = item_title(
+            &decode_entities(&strip_tags(&html[content_start..content_end])),
+            false,
+        );
+        if !title.is_empty() {
+            headings.push(Heading { level, title });
+        }
+        pos = content_end;
+    }
+    headings
+}
+
+/// Append `headings` under `parent`, nested by level. Returns the number of
+/// nodes added.
+pub fn append_headings(tree: &mut Arena<Node>, parent: NodeId, headings: &[Heading]) -> usize {
+    // Open headings, from the outermost one in
+    let mut open: Vec<(u8, NodeId)> = Vec::new();
+    for heading in headings {
+        while open
+            .last()
+            .is_some_and(|&(level, _)| level >= heading.level)
+        {
+            open.pop();
+        }
+        let under = open.last().map_or(parent, |&(_, id)| id);
+        let id = under.append_value(Node::new(heading.title.clone()), tree);
+        open.push((heading.level, id));
+    }
+    headings.len()
+}
+
+/// Fetch the page at `url` and read its headings
+pub fn fetch_outline(url: &str) -> Result<Vec<Heading>> {
+    Ok(heading_outline(&fetch_page(url)?))
+}
+
+/// The body of the page at `url`
+#[cfg(feature = "network")]
+pub(crate) fn fetch_page(url: &str) -> Result<String> {
+    use anyhow::Context;
+    use std::time::Duration;
+
+    /// How long a page may take to load before giving up
+    const TIMEOUT: Duration = Duration::from_secs(15);
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(TIMEOUT))
+        .build()
+        .into();
+    agent
+        .get(url)
+        .call()
+        .and_then(|mut response| response.body_mut().read_to_string())
+        .with_context(|| format!("cannot fetch {url}"))
+}
+
+#[cfg(not(feature = "network"))]
+pub(crate) fn fetch_page(_url: &str) -> Result<String> {
+    anyhow::bail!("built without the network feature")
+}
+
+/// `html` without its tags
+pub(crate) fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// `text` with the common named and numeric character references decoded
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| Some((decode_entity(&rest[1..end + 1])?, end + 2)));
+        match entity {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(name: &str) -> Option<char> {
+    if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        return char::from_u32(code);
+    }
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        "hellip" => '…',
+        "rsquo" => '’',
+        "lsquo" => '‘',
+        "rdquo" => '”',
+        "ldquo" => '“',
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heading_outline() {
+        let html = r#"<html><head><title>Guide</title>
+            <style>h1 { color: red }</style></head>
+            <body><H1 class="top">The <em>Guide</em></H1>
+            <!-- <h2>Commented out</h2> -->
+            <h2 id="install">Install &amp; run</h2><p>text</p>
+            <h3>From&nbsp;source</h3>
+            <h2></h2>
+            <header>not a heading</header>
+            <h2>Usage</h2></body></html>"#;
+        let titles: Vec<_> = heading_outline(html)
+            .into_iter()
+            .map(|h| (h.level, h.title))
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                (1, "The Guide".to_string()),
+                (2, "Install & run".to_string()),
+                (3, "From source".to_string()),
+                (2, "Usage".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_append_headings_nests_by_level() {
+        let mut tree = Arena::new();
+        let root = tree.new_node(Node::new("https://example.com".to_string()));
+        let heading = |level, title: &str| Heading {
+            level,
+            title: title.to_string(),
+        };
+        let headings = [
+            heading(2, "Intro"),
+            heading(3, "Why"),
+            heading(1, "Reference"),
+            heading(3, "Flags"),
+        ];
+        assert_eq!(append_headings(&mut tree, root, &headings), 4);
+
+        let titles = |id: NodeId, tree: &Arena<Node>| -> Vec<String> {
+            id.children(tree)
+                .map(|c| tree[c].get().title.clone())
+                .collect()
+        };
+        assert_eq!(titles(root, &tree), vec!["Intro", "Reference"]);
+        let intro = root.children(&tree).next().unwrap();
+        let reference = root.children(&tree).nth(1).unwrap();
+        assert_eq!(titles(intro, &tree), vec!["Why"]);
+        assert_eq!(titles(reference, &tree), vec!["Flags"]);
+    }
+
+    #[test]
+    fn test_page_url() {
+        assert_eq!(
+            page_url("Docs <https://docs.rs/indextree>"),
+            Some("https://docs.rs/indextree")
+        );
+        assert_eq!(page_url("http://a.b"), Some("http://a.b"));
+        assert_eq!(page_url("No link here"), None);
+        assert_eq!(page_url("https://"), None);
+    }
+}
@@ -0,0 +1,359 @@
+//! Commands from other programs while the editor runs.
+//!
+//! With `--ipc-socket PATH`, scripts connect to a Unix socket and send one
+//! command line per connection, e.g. `add-child "Call Anna"`. The reply is
+//! `ok` followed by any output lines, or `error: …`. Arguments are split on
+//! whitespace; double quotes group words and `\` escapes a quote.
+//!
+//! - `add-child TEXT`, `add-sibling TEXT`: add a node beside the selection,
+//!   which stays where it is
+//! - `get-active`: title of the selected node
+//! - `get-active-path`: titles from the root down to the selected node, one
+//!   per line
+//! - `select TITLE`: select the first node titled TITLE, or containing it
+//! - `export FORMAT PATH`: write the map to PATH in an export format from
+//!   `hmm-rs formats` or through a configured export template; a relative
+//!   PATH is taken from the map's directory, and PATH may not leave it
+//! - `save`: save the map to its file
+
+use crate::actions::{self, EditScope};
+use crate::app::{AppState, Damage};
+use crate::export::{self, ExportFormat};
+use crate::model::{Node, NodeId};
+use crate::pins::title_path;
+use anyhow::{anyhow, bail, Result};
+use indextree::Arena;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Split a command line into its words
+pub fn split_args(line: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = line.chars();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.get_or_insert_with(String::new);
+            }
+            '\\' => {
+                let escaped = chars.next().ok_or_else(|| anyhow!("trailing backslash"))?;
+                current.get_or_insert_with(String::new).push(escaped);
+            }
+            c if c.is_whitespace() && !quoted => args.extend(current.take()),
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quoted {
+        bail!("unclosed quote");
+    }
+    args.extend(current);
+    Ok(args)
+}
+
+/// Run the command `line` on the open map and return its output
+pub fn run_command(app: &mut AppState, line: &str) -> Result<String> {
+    let args = split_args(line)?;
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let (Some(root_id), Some(active_id)) = (app.root_id, app.active_node_id) else {
+        bail!("no map is open");
+    };
+
+    let output = match args.as_slice() {
+        ["add-child", text] => {
+            check_editable(app, EditScope::Node)?;
+            add_node(app, text, |tree, node| active_id.append(node, tree));
+            if let Some(node) = app.tree.get_mut(active_id) {
+                node.get_mut().is_collapsed = false;
+            }
+            String::new()
+        }
+        ["add-sibling", text] => {
+            if active_id == root_id {
+                bail!("the root has no siblings");
+            }
+            check_editable(app, EditScope::Parent)?;
+            add_node(app, text, |tree, node| active_id.insert_after(node, tree));
+            String::new()
+        }
+        ["get-active"] => app.tree[active_id].get().title.clone(),
+        ["get-active-path"] => title_path(&app.tree, root_id, active_id).join("\n"),
+        ["select", title] => {
+            let node_id = actions::find_node_by_title(app, title)
+                .ok_or_else(|| anyhow!("no node matches '{title}'"))?;
+            actions::reveal_and_select(app, node_id);
+            String::new()
+        }
+        ["export", format, path] => {
+            let options = app.export_options();
+            let output = match ExportFormat::ALL.into_iter().find(|f| f.name() == *format) {
+                Some(format) => export::export(&app.tree, root_id, format, &options),
+                None if app.config.export.templates.contains_key(*format) => {
+                    let template = app.config.export.template(format)?;
+                    export::to_template(&app.tree, root_id, &template, &options)?
+                }
+                None => bail!("unknown export format '{format}'; see hmm-rs formats"),
+            };
+            fs::write(export_path(app, path)?, output)?;
+            String::new()
+        }
+        ["save"] => {
+            check_editable(app, EditScope::Map)?;
+            if actions::needs_save_as(app) {
+                bail!("the map has no file it can be saved to");
+            }
+            actions::save(app)?;
+            String::new()
+        }
+        [] => bail!("empty command"),
+        [command, ..] => bail!("unknown command or arguments: {command}"),
+    };
+    app.mark_damage(Damage::Layout);
+    Ok(output)
+}
+
+/// Where `export` writes `path`: inside the map's directory only, so a
+/// client cannot overwrite files elsewhere as the editor's user
+fn export_path(app: &AppState, path: &str) -> Result<PathBuf> {
+    let Some(filename) = app.filename.as_deref() else {
+        bail!("export needs a map saved to a file");
+    };
+    let dir = filename
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+        .canonicalize()?;
+    let target = dir.join(path);
+    let (Some(parent), Some(name)) = (target.parent(), target.file_name()) else {
+        bail!("cannot export to {path}");
+    };
+    let mut resolved = parent.canonicalize()?.join(name);
+    // A link already there would send the write elsewhere
+    if resolved.exists() {
+        resolved = resolved.canonicalize()?;
+    }
+    if !resolved.starts_with(&dir) {
+        bail!("cannot export to {path}: it is outside {}", dir.display());
+    }
+    Ok(resolved)
+}
+
+/// Fail with the reason an edit of `scope` is turned down, if it is
+fn check_editable(app: &AppState, scope: EditScope) -> Result<()> {
+    match actions::edit_denial(app, scope) {
+        Some(key) => bail!("{}", app.catalog.get(key)),
+        None => Ok(()),
+    }
+}
+
+/// Add a node titled `text` with `attach`, as one undoable change
+fn add_node(app: &mut AppState, text: &str, attach: impl FnOnce(&mut Arena<Node>, NodeId)) {
+    app.push_history();
+    let node = app.tree.new_node(Node::new(text.to_string()));
+    attach(&mut app.tree, node);
+    app.is_dirty = true;
+    app.last_modify_time = Some(Instant::now());
+}
+
+/// Reply sent for the result of a command
+pub fn reply(result: &Result<String>) -> String {
+    match result {
+        Ok(output) if output.is_empty() => "ok\n".to_string(),
+        Ok(output) => format!("ok\n{output}\n"),
+        Err(e) => format!("error: {e:#}\n"),
+    }
+}
+
+#[cfg(unix)]
+pub use server::IpcServer;
+
+#[cfg(unix)]
+mod server {
+    use super::{reply, run_command};
+    use crate::app::AppState;
+    use anyhow::{bail, Context, Result};
+    use std::fs::Permissions;
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+    use std::os::unix::net::UnixListener;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    /// How long a client may take to send its command before it is dropped,
+    /// so a stuck script cannot freeze the editor
+    const READ_TIMEOUT: Duration = Duration::from_millis(200);
+
+    /// Unix socket taking commands, polled from the event loop
+    pub struct IpcServer {
+        listener: UnixListener,
+        path: PathBuf,
+    }
+
+    impl IpcServer {
+        /// Listen on `path`, replacing a socket left over from an earlier run;
+        /// anything else already there is left alone. Only the editor's user
+        /// may connect, as commands edit, save and write files
+        pub fn bind(path: PathBuf) -> Result<Self> {
+            if let Ok(metadata) = std::fs::symlink_metadata(&path) {
+                if !metadata.file_type().is_socket() {
+                    bail!("{} already exists and is not a socket", path.display());
+                }
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("cannot replace {}", path.display()))?;
+            }
+            let listener = UnixListener::bind(&path)
+                .with_context(|| format!("cannot listen on {}", path.display()))?;
+            if let Err(e) = std::fs::set_permissions(&path, Permissions::from_mode(0o600)) {
+                let _ = std::fs::remove_file(&path);
+                return Err(e).with_context(|| format!("cannot restrict {}", path.display()));
+            }
+            listener.set_nonblocking(true)?;
+            Ok(Self { listener, path })
+        }
+
+        /// Run the commands of every client waiting to connect
+        pub fn handle_pending(&self, app: &mut AppState) {
+            loop {
+                // Stops once no client is waiting
+                let Ok((stream, _)) = self.listener.accept() else {
+                    return;
+                };
+                let _ = stream
+                    .set_nonblocking(false)
+                    .and_then(|()| stream.set_read_timeout(Some(READ_TIMEOUT)));
+                let mut line = String::new();
+                if BufReader::new(&stream).read_line(&mut line).is_err() {
+                    continue;
+                }
+                let result = run_command(app, line.trim_end());
+                // A client that went away needs no reply
+                let _ = (&stream).write_all(reply(&result).as_bytes());
+            }
+        }
+    }
+
+    impl Drop for IpcServer {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    fn create_test_app() -> AppState {
+        let mut app = AppState::new(AppConfig::default());
+        let root = app.tree.new_node(Node::new("Root".to_string()));
+        let ideas = root.append_value(Node::new("Ideas".to_string()), &mut app.tree);
+        app.root_id = Some(root);
+        app.active_node_id = Some(ideas);
+        app
+    }
+
+    #[test]
+    fn test_split_args() {
+        assert_eq!(
+            split_args(r#"add-child  "Call \"Anna\"" now"#).unwrap(),
+            vec!["add-child", r#"Call "Anna""#, "now"]
+        );
+        assert_eq!(
+            split_args(r#"add-child """#).unwrap(),
+            vec!["add-child", ""]
+        );
+        assert!(split_args(r#"add-child "open"#).is_err());
+    }
+
+    #[test]
+    fn test_run_command() {
+        let mut app = create_test_app();
+        run_command(&mut app, r#"add-child "Write docs""#).unwrap();
+        run_command(&mut app, "add-sibling Later").unwrap();
+        assert_eq!(run_command(&mut app, "get-active").unwrap(), "Ideas");
+        assert_eq!(
+            crate::parser::map_to_list(&app.tree, app.root_id.unwrap(), false, 0),
+            "Root\n\tIdeas\n\t\tWrite docs\n\tLater\n"
+        );
+        assert!(app.is_dirty);
+
+        run_command(&mut app, "select docs").unwrap();
+        assert_eq!(
+            run_command(&mut app, "get-active-path").unwrap(),
+            "Root\nIdeas\nWrite docs"
+        );
+
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(run_command(&mut app, "export mermaid map.mmd").is_err());
+        app.filename = Some(dir.path().join("map.hmm"));
+        run_command(&mut app, "export mermaid map.mmd").unwrap();
+        let path = dir.path().join("map.mmd");
+        assert!(fs::read_to_string(&path).unwrap().starts_with("mindmap"));
+        let command = format!("export mermaid \"{}\"", path.display());
+        run_command(&mut app, &command).unwrap();
+        assert!(run_command(&mut app, "export mermaid ../escaped.mmd").is_err());
+        assert!(!dir.path().parent().unwrap().join("escaped.mmd").exists());
+        app.filename = None;
+
+        app.undo();
+        assert!(!app
+            .tree
+            .iter()
+            .any(|n| !n.is_removed() && n.get().title == "Later"));
+
+        let error = run_command(&mut app, "export pdf out.pdf").unwrap_err();
+        assert_eq!(
+            reply(&Err(error)),
+            "error: unknown export format 'pdf'; see hmm-rs formats\n"
+        );
+        assert!(run_command(&mut app, "save").is_err());
+        assert!(run_command(&mut app, "fly").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_server_replies_to_clients() {
+        use std::io::{Read, Write};
+        use std::os::unix::fs::PermissionsExt;
+        use std::os::unix::net::UnixStream;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("hmm.sock");
+        let server = IpcServer::bind(path.clone()).unwrap();
+        let mut app = create_test_app();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let mut client = UnixStream::connect(&path).unwrap();
+        client.write_all(b"get-active-path\n").unwrap();
+        server.handle_pending(&mut app);
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert_eq!(response, "ok\nRoot\nIdeas\n");
+
+        drop(server);
+        assert!(!path.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_bind_replaces_only_sockets() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("hmm.sock");
+
+        // Dropping a listener leaves its socket behind, like an editor that
+        // did not exit cleanly
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(IpcServer::bind(path.clone()).is_ok());
+
+        let notes = dir.path().join("notes.txt");
+        std::fs::write(&notes, "keep me").unwrap();
+        assert!(IpcServer::bind(notes.clone()).is_err());
+        assert_eq!(std::fs::read_to_string(&notes).unwrap(), "keep me");
+    }
+}
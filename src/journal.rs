@@ -0,0 +1,79 @@
+//! Daily journal maps: one dated `.hmm` file per day, created from a template.
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Title of the node the cursor starts on in a journal map
+pub const TODAY_TITLE: &str = "Today";
+
+/// Template used when no `journal_template` is configured
+pub const DEFAULT_TEMPLATE: &str = "{date} {weekday}\n\tToday\n";
+
+/// Path of the journal map for `date`, e.g. `<dir>/2024-03-01.hmm`
+pub fn journal_file(dir: &Path, date: NaiveDate) -> PathBuf {
+    dir.join(format!("{}.hmm", date.format("%Y-%m-%d")))
+}
+
+/// Fill in the `{date}` and `{weekday}` placeholders of a template
+pub fn render_template(template: &str, date: NaiveDate) -> String {
+    template
+        .replace("{date}", &date.format("%Y-%m-%d").to_string())
+        .replace("{weekday}", &date.format("%A").to_string())
+}
+
+/// Create the journal map for `date` from `template` unless it already exists,
+/// and return its path
+pub fn ensure_journal(dir: &Path, template: Option<&Path>, date: NaiveDate) -> Result<PathBuf> {
+    let path = journal_file(dir, date);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    let template = match template {
+        Some(template) => fs::read_to_string(template)?,
+        None => DEFAULT_TEMPLATE.to_string(),
+    };
+    fs::create_dir_all(dir)?;
+    fs::write(&path, render_template(&template, date))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn date() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap()
+    }
+
+    #[test]
+    fn test_render_template() {
+        assert_eq!(
+            render_template(DEFAULT_TEMPLATE, date()),
+            "2024-03-01 Friday\n\tToday\n"
+        );
+    }
+
+    #[test]
+    fn test_ensure_journal_creates_once() {
+        let dir = TempDir::new().unwrap();
+        let journal = dir.path().join("journal");
+        let template = dir.path().join("template.hmm");
+        fs::write(&template, "Journal {date}\n\tToday\n\tHabits\n").unwrap();
+
+        let path = ensure_journal(&journal, Some(&template), date()).unwrap();
+        assert_eq!(path, journal.join("2024-03-01.hmm"));
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "Journal 2024-03-01\n\tToday\n\tHabits\n"
+        );
+
+        // An existing entry is never overwritten
+        fs::write(&path, "Edited").unwrap();
+        ensure_journal(&journal, Some(&template), date()).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "Edited");
+    }
+}
@@ -0,0 +1,674 @@
+//! Normal-mode key bindings.
+//!
+//! [`COMMANDS`] is the single source of truth for what every key does: the
+//! event loop looks keys up in a [`Keymap`], and both the in-app help screen
+//! and the printable cheat sheet are generated from the same table, with any
+//! `[keys]` remapping from the config applied. Section titles and command
+//! descriptions come from the `section.*` and `help.*` catalog entries.
+//!
+//! Besides single keys, commands can be bound to vim-style key sequences such
+//! as `<leader>ex`, which wait for the next key until the configured timeout.
+
+use crate::actions::Action;
+use crate::export::html_escape;
+use crate::i18n::Catalog;
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+/// Leader key used by `<leader>` sequences unless the config picks another
+pub const DEFAULT_LEADER: &str = "\\";
+
+/// Keys pressed one after another that together trigger a command
+pub type KeySequence = Vec<Key>;
+
+/// What the keys pressed so far amount to
+#[derive(Debug, Clone, PartialEq)]
+pub enum KeyMatch {
+    /// A complete binding
+    Action(Action),
+    /// The start of a longer sequence, itself bound to the action if any
+    Prefix(Option<Action>),
+    /// Nothing is bound to these keys
+    None,
+}
+
+/// A key press as seen by the normal-mode keymap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Char(char),
+    Ctrl(char),
+    Alt(char),
+    Left,
+    Right,
+    Up,
+    Down,
+    Enter,
+    Tab,
+}
+
+impl Key {
+    /// Parse a key spec such as `j`, `G`, `C-r`, `ctrl+r`, `M-t`, `alt+t`, `left` or `space`
+    pub fn parse(spec: &str) -> Option<Key> {
+        let lower = spec.to_lowercase();
+        let modified = |prefixes: &[&str]| {
+            prefixes
+                .iter()
+                .find_map(|prefix| lower.strip_prefix(prefix).map(|_| &spec[prefix.len()..]))
+                .and_then(single_char)
+        };
+
+        if let Some(c) = modified(&["c-", "ctrl+", "ctrl-"]) {
+            return Some(Key::Ctrl(c.to_ascii_lowercase()));
+        }
+        if let Some(c) = modified(&["m-", "alt+", "alt-"]) {
+            return Some(Key::Alt(c));
+        }
+
+        match lower.as_str() {
+            "left" | "←" => Some(Key::Left),
+            "right" | "→" => Some(Key::Right),
+            "up" | "↑" => Some(Key::Up),
+            "down" | "↓" => Some(Key::Down),
+            "enter" | "return" | "⏎" => Some(Key::Enter),
+            "tab" | "⇥" => Some(Key::Tab),
+            "space" | "␣" => Some(Key::Char(' ')),
+            _ => single_char(spec).map(Key::Char),
+        }
+    }
+}
+
+/// Parse a binding: a single key spec, or a sequence such as `<leader>ex` or
+/// `<C-x><C-s>` that starts with a bracketed key, `<leader>` meaning `leader`
+fn parse_sequence(spec: &str, leader: Key) -> Option<KeySequence> {
+    if let Some(key) = Key::parse(spec) {
+        return Some(vec![key]);
+    }
+    if !spec.starts_with('<') {
+        return None;
+    }
+
+    let mut sequence = Vec::new();
+    let mut rest = spec;
+    while let Some(c) = rest.chars().next() {
+        if c == '<' {
+            let end = rest.find('>')?;
+            let name = &rest[1..end];
+            let key = if name.eq_ignore_ascii_case("leader") {
+                leader
+            } else {
+                Key::parse(name)?
+            };
+            sequence.push(key);
+            rest = &rest[end + 1..];
+        } else {
+            sequence.push(Key::Char(c));
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    Some(sequence)
+}
+
+fn single_char(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(' ') => write!(f, "␣"),
+            Key::Char(c) => write!(f, "{}", c),
+            Key::Ctrl(c) => write!(f, "C-{}", c),
+            Key::Alt(c) => write!(f, "M-{}", c),
+            Key::Left => write!(f, "←"),
+            Key::Right => write!(f, "→"),
+            Key::Up => write!(f, "↑"),
+            Key::Down => write!(f, "↓"),
+            Key::Enter => write!(f, "⏎"),
+            Key::Tab => write!(f, "⇥"),
+        }
+    }
+}
+
+/// A bindable normal-mode command and its default keys
+pub struct Command {
+    /// Name used in the `[keys]` config table
+    pub name: &'static str,
+    pub section: &'static str,
+    pub action: Action,
+    pub keys: &'static [Key],
+}
+
+/// Section order for help and cheat sheets
+pub const SECTIONS: &[&str] = &[
+    "navigation",
+    "editing",
+    "view",
+    "layout",
+    "symbols",
+    "search",
+    "clipboard",
+    "file",
+];
+
+macro_rules! command {
+    ($name:literal, $section:literal, $action:expr, [$($key:expr),*]) => {
+        Command {
+            name: $name,
+            section: $section,
+            action: $action,
+            keys: &[$($key),*],
+        }
+    };
+}
+
+use Key::*;
+
+#[rustfmt::skip]
+pub const COMMANDS: &[Command] = &[
+    // Navigation
+    command!("go_left", "navigation", Action::GoLeft, [Char('h'), Left]),
+    command!("go_down", "navigation", Action::GoDown, [Char('j'), Down]),
+    command!("go_up", "navigation", Action::GoUp, [Char('k'), Up]),
+    command!("go_right", "navigation", Action::GoRight, [Char('l'), Right]),
+    command!("go_to_top", "navigation", Action::GoToTop, [Char('g')]),
+    command!("go_to_bottom", "navigation", Action::GoToBottom, [Char('G')]),
+    command!("go_to_root", "navigation", Action::GoToRoot, [Char('m'), Char('~')]),
+    command!("follow_reference", "navigation", Action::FollowReference, [Char('R')]),
+    command!("toggle_pin", "navigation", Action::TogglePin, [Char('+')]),
+    command!("jump_to_pin_1", "navigation", Action::JumpToPin(1), [Alt('1')]),
+    command!("jump_to_pin_2", "navigation", Action::JumpToPin(2), [Alt('2')]),
+    command!("jump_to_pin_3", "navigation", Action::JumpToPin(3), [Alt('3')]),
+    command!("jump_to_pin_4", "navigation", Action::JumpToPin(4), [Alt('4')]),
+    command!("jump_to_pin_5", "navigation", Action::JumpToPin(5), [Alt('5')]),
+    command!("jump_to_pin_6", "navigation", Action::JumpToPin(6), [Alt('6')]),
+    command!("jump_to_pin_7", "navigation", Action::JumpToPin(7), [Alt('7')]),
+    command!("jump_to_pin_8", "navigation", Action::JumpToPin(8), [Alt('8')]),
+    command!("jump_to_pin_9", "navigation", Action::JumpToPin(9), [Alt('9')]),
+    // Editing
+    command!("edit_append", "editing", Action::EditNodeAppend, [Char('e'), Char('i'), Char('a')]),
+    command!("edit_replace", "editing", Action::EditNodeReplace, [Char('E'), Char('I'), Char('A')]),
+    command!("insert_sibling", "editing", Action::InsertSibling, [Char('o'), Enter]),
+    command!("insert_sibling_above", "editing", Action::InsertSiblingAbove, [Alt('o')]),
+    command!("insert_child", "editing", Action::InsertChild, [Char('O'), Tab]),
+    command!("insert_child_first", "editing", Action::InsertChildFirst, [Alt('O')]),
+    command!("repeat_insert", "editing", Action::RepeatInsert, [Char('.')]),
+    command!("repeat_insert_increment", "editing", Action::RepeatInsertIncrement, [Ctrl('a')]),
+    command!("batch_rename", "editing", Action::BatchRename, [Alt('R')]),
+    command!("delete_node", "editing", Action::DeleteNode, [Char('d')]),
+    command!("delete_children", "editing", Action::DeleteChildren, [Char('D')]),
+    command!("move_node_down", "editing", Action::MoveNodeDown, [Char('J')]),
+    command!("move_node_up", "editing", Action::MoveNodeUp, [Char('K')]),
+    command!("undo", "editing", Action::Undo, [Char('u')]),
+    command!("redo", "editing", Action::Redo, [Ctrl('r')]),
+    // View
+    command!("toggle_collapse", "view", Action::ToggleCollapse, [Char(' ')]),
+    command!("collapse_all", "view", Action::CollapseAll, [Char('v')]),
+    command!("expand_all", "view", Action::ExpandAll, [Char('b')]),
+    command!("collapse_children", "view", Action::CollapseChildren, [Char('V')]),
+    command!("collapse_other_branches", "view", Action::CollapseOtherBranches, [Char('r')]),
+    command!("collapse_to_level_1", "view", Action::CollapseToLevel(1), [Char('1')]),
+    command!("collapse_to_level_2", "view", Action::CollapseToLevel(2), [Char('2')]),
+    command!("collapse_to_level_3", "view", Action::CollapseToLevel(3), [Char('3')]),
+    command!("collapse_to_level_4", "view", Action::CollapseToLevel(4), [Char('4')]),
+    command!("collapse_to_level_5", "view", Action::CollapseToLevel(5), [Char('5')]),
+    command!("center", "view", Action::CenterActiveNode, [Char('c')]),
+    command!("toggle_center_lock", "view", Action::ToggleCenterLock, [Char('C')]),
+    command!("scroll_to_top", "view", Action::ScrollActiveToTop, [Alt('t')]),
+    command!("scroll_to_center", "view", Action::ScrollActiveToCenter, [Alt('z')]),
+    command!("scroll_to_bottom", "view", Action::ScrollActiveToBottom, [Alt('b')]),
+    command!("focus", "view", Action::Focus, [Char('f')]),
+    command!("toggle_focus_lock", "view", Action::ToggleFocusLock, [Char('F')]),
+    command!("toggle_accordion", "view", Action::ToggleAccordion, [Alt('a')]),
+    command!("toggle_hide", "view", Action::ToggleHide, [Char('H')]),
+    command!("toggle_lock", "editing", Action::ToggleLock, [Alt('l')]),
+    command!("toggle_show_hidden", "view", Action::ToggleShowHidden, [Ctrl('h')]),
+    command!("cycle_heatmap", "view", Action::CycleHeatmap, [Alt('h')]),
+    command!("toggle_depth_guides", "view", Action::ToggleDepthGuides, [Char('|')]),
+    // Layout
+    command!("increase_text_width", "layout", Action::IncreaseTextWidth, [Char('w')]),
+    command!("decrease_text_width", "layout", Action::DecreaseTextWidth, [Char('W')]),
+    command!("decrease_line_spacing", "layout", Action::DecreaseLineSpacing, [Char('z')]),
+    command!("increase_line_spacing", "layout", Action::IncreaseLineSpacing, [Char('Z')]),
+    // Symbols
+    command!("toggle_symbol", "symbols", Action::ToggleSymbol, [Char('t')]),
+    command!("sort_siblings", "symbols", Action::SortSiblings, [Char('T')]),
+    command!("cycle_kind", "symbols", Action::CycleKind, [Alt('k')]),
+    command!("fix_case", "symbols", Action::FixCase, [Alt('c')]),
+    command!("update_glossary", "symbols", Action::UpdateGlossary, [Alt('g')]),
+    command!("toggle_numbers", "symbols", Action::ToggleNumbers, [Char('#')]),
+    // Search
+    command!("search", "search", Action::Search, [Char('/'), Ctrl('f')]),
+    command!("next_search_result", "search", Action::NextSearchResult, [Char('n')]),
+    command!("previous_search_result", "search", Action::PreviousSearchResult, [Char('N')]),
+    command!("fold_to_search_results", "search", Action::FoldToSearchResults, [Alt('f')]),
+    command!("clear_search", "search", Action::ClearSearch, [Alt('/')]),
+    // Clipboard
+    command!("yank_node", "clipboard", Action::YankNode, [Char('y')]),
+    command!("yank_children", "clipboard", Action::YankChildren, [Char('Y')]),
+    command!("paste_as_children", "clipboard", Action::PasteAsChildren, [Char('p')]),
+    command!("paste_as_siblings", "clipboard", Action::PasteAsSiblings, [Char('P')]),
+    // File
+    command!("open_recent", "file", Action::OpenRecent, [Ctrl('o')]),
+    command!("open_workspace", "file", Action::OpenWorkspace, [Ctrl('p')]),
+    command!("open_link", "file", Action::OpenLink, [Char('L')]),
+    command!("activate", "file", Action::Activate, [Char('!')]),
+    command!("import_url", "file", Action::ImportUrl, [Alt('u')]),
+    command!("import_feed", "file", Action::ImportFeed, [Alt('r')]),
+    command!("open_journal", "file", Action::OpenJournal, [Alt('j')]),
+    command!("capture", "file", Action::Capture, [Alt('i')]),
+    command!("save", "file", Action::Save, [Char('s')]),
+    command!("save_as", "file", Action::SaveAs, [Char('S')]),
+    command!("rename_file", "file", Action::RenameFile, [Alt('n')]),
+    command!("save_as_format", "file", Action::SaveAsFormat, [Alt('s')]),
+    command!("export_text", "file", Action::ExportText, [Char('X')]),
+    command!("export_subtree", "file", Action::ExportSubtree, [Alt('X')]),
+    command!("export_mermaid", "file", Action::ExportMermaid, [Alt('x')]),
+    command!("export_html", "file", Action::ExportHtml, [Alt('w')]),
+    command!("export_template", "file", Action::ExportTemplate, [Alt('e')]),
+    command!("export_pipe", "file", Action::ExportPipe, [Alt('|')]),
+    command!("snapshots", "file", Action::OpenSnapshots, [Alt('m')]),
+    command!("compare_head", "file", Action::CompareHead, [Alt('d')]),
+    command!("settings", "file", Action::OpenSettings, [Char(',')]),
+    command!("help", "file", Action::ShowHelp, [Char('?')]),
+    command!("show_stats", "file", Action::ShowStats, [Ctrl('g')]),
+    command!("quit", "file", Action::Quit, [Char('q'), Ctrl('c')]),
+    command!("force_quit", "file", Action::ForceQuit, [Char('Q')]),
+];
+
+/// Output format for [`Keymap::cheat_sheet`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum CheatSheetFormat {
+    Text,
+    Markdown,
+    Html,
+}
+
+/// The effective normal-mode keymap: the defaults from [`COMMANDS`] with config remapping applied
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    /// Bound key sequences per command, indexed like `COMMANDS`
+    keys: Vec<Vec<KeySequence>>,
+    lookup: HashMap<KeySequence, usize>,
+    /// Keys that start a longer bound sequence
+    prefixes: HashSet<KeySequence>,
+    leader: Key,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let leader = Key::parse(DEFAULT_LEADER).expect("valid default leader");
+        Self::from_keys(default_keys(), leader)
+    }
+}
+
+fn default_keys() -> Vec<Vec<KeySequence>> {
+    COMMANDS
+        .iter()
+        .map(|c| c.keys.iter().map(|&key| vec![key]).collect())
+        .collect()
+}
+
+impl Keymap {
+    /// Build the keymap from `[keys]` overrides mapping command names to whitespace-separated
+    /// key specs or sequences. An override replaces the command's default keys and takes those
+    /// keys away from any other command; an empty value unbinds the command. `leader` is the
+    /// key spec `<leader>` stands for in sequences.
+    pub fn new(overrides: &HashMap<String, String>, leader: &str) -> Result<Self> {
+        let leader =
+            Key::parse(leader).ok_or_else(|| anyhow!("Invalid leader key '{}'", leader))?;
+        let mut keys = default_keys();
+        let mut overridden = vec![false; COMMANDS.len()];
+
+        let mut names: Vec<&String> = overrides.keys().collect();
+        names.sort();
+        for name in names {
+            let index = COMMANDS
+                .iter()
+                .position(|c| c.name == name)
+                .ok_or_else(|| anyhow!("Unknown command '{}' in [keys]", name))?;
+            keys[index] = overrides[name]
+                .split_whitespace()
+                .map(|spec| {
+                    parse_sequence(spec, leader)
+                        .ok_or_else(|| anyhow!("Invalid key '{}' for {}", spec, name))
+                })
+                .collect::<Result<_>>()?;
+            overridden[index] = true;
+        }
+
+        let remapped: Vec<KeySequence> = keys
+            .iter()
+            .zip(&overridden)
+            .filter(|(_, &o)| o)
+            .flat_map(|(k, _)| k.iter().cloned())
+            .collect();
+        for (command_keys, _) in keys.iter_mut().zip(&overridden).filter(|(_, &o)| !o) {
+            command_keys.retain(|key| !remapped.contains(key));
+        }
+
+        Ok(Self::from_keys(keys, leader))
+    }
+
+    fn from_keys(keys: Vec<Vec<KeySequence>>, leader: Key) -> Self {
+        let mut lookup = HashMap::new();
+        let mut prefixes = HashSet::new();
+        for (index, command_keys) in keys.iter().enumerate() {
+            for sequence in command_keys {
+                lookup.entry(sequence.clone()).or_insert(index);
+                for len in 1..sequence.len() {
+                    prefixes.insert(sequence[..len].to_vec());
+                }
+            }
+        }
+        Self {
+            keys,
+            lookup,
+            prefixes,
+            leader,
+        }
+    }
+
+    /// The action bound to a key, if any
+    pub fn action_for(&self, key: Key) -> Option<Action> {
+        self.lookup
+            .get([key].as_slice())
+            .map(|&index| COMMANDS[index].action.clone())
+    }
+
+    /// What the keys pressed so far are bound to
+    pub fn match_keys(&self, keys: &[Key]) -> KeyMatch {
+        let action = self
+            .lookup
+            .get(keys)
+            .map(|&index| COMMANDS[index].action.clone());
+        match action {
+            _ if self.prefixes.contains(keys) => KeyMatch::Prefix(action),
+            Some(action) => KeyMatch::Action(action),
+            None => KeyMatch::None,
+        }
+    }
+
+    /// Label for a key or sequence, e.g. `j`, `C-r` or `<leader>e<C-x>`
+    pub fn label(&self, sequence: &[Key]) -> String {
+        if let [key] = sequence {
+            return key.to_string();
+        }
+        sequence
+            .iter()
+            .enumerate()
+            .map(|(i, key)| match key.to_string() {
+                _ if i == 0 && *key == self.leader => "<leader>".to_string(),
+                label if label.chars().count() > 1 || label == "<" => format!("<{}>", label),
+                label => label,
+            })
+            .collect()
+    }
+
+    /// Keys currently bound to the named command
+    pub fn keys_for(&self, name: &str) -> &[KeySequence] {
+        COMMANDS
+            .iter()
+            .position(|c| c.name == name)
+            .map(|index| self.keys[index].as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Bound commands grouped by section, as `(section title, [(keys label, description)])`
+    pub fn sections(&self, catalog: &Catalog) -> Vec<(String, Vec<(String, String)>)> {
+        SECTIONS
+            .iter()
+            .map(|&section| {
+                let items = COMMANDS
+                    .iter()
+                    .zip(&self.keys)
+                    .filter(|(command, keys)| command.section == section && !keys.is_empty())
+                    .map(|(command, keys)| {
+                        let label: Vec<String> = keys.iter().map(|k| self.label(k)).collect();
+                        let description = catalog.get(&format!("help.{}", command.name));
+                        (label.join("/"), description)
+                    })
+                    .collect();
+                (catalog.get(&format!("section.{}", section)), items)
+            })
+            .filter(|(_, items): &(_, Vec<_>)| !items.is_empty())
+            .collect()
+    }
+
+    /// Render the keymap as a cheat sheet
+    pub fn cheat_sheet(&self, format: CheatSheetFormat, catalog: &Catalog) -> String {
+        let title = catalog.get("ui.key_bindings");
+        let mut out = String::new();
+        match format {
+            CheatSheetFormat::Text => {
+                for (section, items) in self.sections(catalog) {
+                    let width = label_width(&items);
+                    out.push_str(&format!("{}:\n", section));
+                    for (keys, description) in items {
+                        out.push_str(&format!("  {}  {}\n", pad(&keys, width), description));
+                    }
+                    out.push('\n');
+                }
+            }
+            CheatSheetFormat::Markdown => {
+                out.push_str(&format!("# {}\n", title));
+                for (section, items) in self.sections(catalog) {
+                    out.push_str(&format!(
+                        "\n## {}\n\n| {} | {} |\n|-----|--------|\n",
+                        section,
+                        catalog.get("ui.key_column"),
+                        catalog.get("ui.action_column")
+                    ));
+                    for (keys, description) in items {
+                        let keys = keys.replace('|', "\\|");
+                        out.push_str(&format!("| `{}` | {} |\n", keys, description));
+                    }
+                }
+            }
+            CheatSheetFormat::Html => {
+                out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+                out.push_str(&format!(
+                    "<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n",
+                    html_escape(&title)
+                ));
+                for (section, items) in self.sections(catalog) {
+                    out.push_str(&format!("<h2>{}</h2>\n<table>\n", html_escape(&section)));
+                    for (keys, description) in items {
+                        out.push_str(&format!(
+                            "<tr><td><kbd>{}</kbd></td><td>{}</td></tr>\n",
+                            html_escape(&keys),
+                            html_escape(&description)
+                        ));
+                    }
+                    out.push_str("</table>\n");
+                }
+                out.push_str("</body>\n</html>\n");
+            }
+        }
+        out
+    }
+}
+
+/// Display width of the widest key label in a section
+pub fn label_width(items: &[(String, String)]) -> usize {
+    items
+        .iter()
+        .map(|(keys, _)| keys.chars().count())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Pad a key label to `width` columns
+pub fn pad(label: &str, width: usize) -> String {
+    format!(
+        "{}{}",
+        label,
+        " ".repeat(width.saturating_sub(label.chars().count()))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_keymap_lookup() {
+        let keymap = Keymap::default();
+        assert!(matches!(
+            keymap.action_for(Key::Char('j')),
+            Some(Action::GoDown)
+        ));
+        assert!(matches!(
+            keymap.action_for(Key::Ctrl('r')),
+            Some(Action::Redo)
+        ));
+        assert!(matches!(
+            keymap.action_for(Key::Char('3')),
+            Some(Action::CollapseToLevel(3))
+        ));
+        assert!(keymap.action_for(Key::Char('x')).is_none());
+    }
+
+    #[test]
+    fn test_default_keys_are_unique() {
+        let mut seen = HashMap::new();
+        for command in COMMANDS {
+            assert!(SECTIONS.contains(&command.section), "{}", command.name);
+            for key in command.keys {
+                if let Some(other) = seen.insert(*key, command.name) {
+                    panic!("{} bound to both {} and {}", key, other, command.name);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_key_parse_round_trip() {
+        assert_eq!(Key::parse("ctrl+R"), Some(Key::Ctrl('r')));
+        assert_eq!(Key::parse("alt-t"), Some(Key::Alt('t')));
+        assert_eq!(Key::parse("space"), Some(Key::Char(' ')));
+        assert_eq!(Key::parse("G"), Some(Key::Char('G')));
+        assert_eq!(Key::parse("Enter"), Some(Key::Enter));
+        assert_eq!(Key::parse("ctrl+"), None);
+        assert_eq!(Key::parse("foo"), None);
+
+        for command in COMMANDS {
+            for key in command.keys {
+                assert_eq!(Key::parse(&key.to_string()), Some(*key));
+            }
+        }
+    }
+
+    #[test]
+    fn test_remapping() {
+        let overrides = HashMap::from([
+            ("go_to_bottom".to_string(), "C-g j".to_string()),
+            ("toggle_numbers".to_string(), String::new()),
+        ]);
+        let keymap = Keymap::new(&overrides, DEFAULT_LEADER).unwrap();
+
+        assert!(matches!(
+            keymap.action_for(Key::Char('j')),
+            Some(Action::GoToBottom)
+        ));
+        assert!(matches!(
+            keymap.action_for(Key::Ctrl('g')),
+            Some(Action::GoToBottom)
+        ));
+        assert!(keymap.action_for(Key::Char('G')).is_none());
+        assert!(keymap.action_for(Key::Char('#')).is_none());
+        assert_eq!(keymap.keys_for("go_down"), &[vec![Key::Down]]);
+    }
+
+    #[test]
+    fn test_remapping_errors() {
+        let unknown = HashMap::from([("fly".to_string(), "x".to_string())]);
+        assert!(Keymap::new(&unknown, DEFAULT_LEADER).is_err());
+
+        let invalid = HashMap::from([("save".to_string(), "ctrl+ss".to_string())]);
+        assert!(Keymap::new(&invalid, DEFAULT_LEADER).is_err());
+    }
+
+    #[test]
+    fn test_key_sequences() {
+        let overrides = HashMap::from([
+            ("export_text".to_string(), "<leader>ex X".to_string()),
+            ("save".to_string(), "<C-x><C-s>".to_string()),
+            ("toggle_collapse".to_string(), "space".to_string()),
+        ]);
+        let keymap = Keymap::new(&overrides, "space").unwrap();
+        let leader = Key::Char(' ');
+
+        assert_eq!(
+            keymap.match_keys(&[leader]),
+            KeyMatch::Prefix(Some(Action::ToggleCollapse))
+        );
+        assert_eq!(
+            keymap.match_keys(&[leader, Key::Char('e')]),
+            KeyMatch::Prefix(None)
+        );
+        assert_eq!(
+            keymap.match_keys(&[leader, Key::Char('e'), Key::Char('x')]),
+            KeyMatch::Action(Action::ExportText)
+        );
+        assert_eq!(keymap.match_keys(&[leader, Key::Char('q')]), KeyMatch::None);
+        assert_eq!(
+            keymap.match_keys(&[Key::Ctrl('x'), Key::Ctrl('s')]),
+            KeyMatch::Action(Action::Save)
+        );
+        assert_eq!(
+            keymap.match_keys(&[Key::Char('j')]),
+            KeyMatch::Action(Action::GoDown)
+        );
+
+        // Labels read back as the same binding
+        let labels: Vec<String> = keymap
+            .keys_for("save")
+            .iter()
+            .map(|k| keymap.label(k))
+            .collect();
+        assert_eq!(labels, ["<C-x><C-s>"]);
+        let export = &keymap.keys_for("export_text")[0];
+        assert_eq!(keymap.label(export), "<leader>ex");
+        assert_eq!(
+            parse_sequence(&keymap.label(export), leader).as_ref(),
+            Some(export)
+        );
+
+        assert!(Keymap::new(&HashMap::new(), "leader").is_err());
+        let unclosed = HashMap::from([("save".to_string(), "<C-x".to_string())]);
+        assert!(Keymap::new(&unclosed, DEFAULT_LEADER).is_err());
+    }
+
+    #[test]
+    fn test_cheat_sheet_follows_remapping() {
+        let overrides = HashMap::from([
+            ("save".to_string(), "C-s".to_string()),
+            ("toggle_numbers".to_string(), String::new()),
+        ]);
+        let keymap = Keymap::new(&overrides, DEFAULT_LEADER).unwrap();
+
+        let catalog = Catalog::default();
+
+        let text = keymap.cheat_sheet(CheatSheetFormat::Text, &catalog);
+        assert!(text.starts_with("Navigation:\n  h/←  Move left (parent)\n"));
+        assert!(text.contains("  C-s    Save\n"));
+        assert!(!text.contains("Toggle numbers"));
+
+        let markdown = keymap.cheat_sheet(CheatSheetFormat::Markdown, &catalog);
+        assert!(markdown.contains("## File\n\n| Key | Action |\n"));
+        assert!(markdown.contains("| `C-s` | Save |\n"));
+
+        let html = keymap.cheat_sheet(CheatSheetFormat::Html, &catalog);
+        assert!(html.contains("<tr><td><kbd>C-s</kbd></td><td>Save</td></tr>"));
+
+        let german = keymap.cheat_sheet(CheatSheetFormat::Markdown, &Catalog::new("de"));
+        assert!(german.starts_with("# h-m-m Tastenbelegung\n"));
+        assert!(german.contains("| `C-s` | Speichern |\n"));
+    }
+}
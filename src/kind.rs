@@ -0,0 +1,126 @@
+//! What a node is in the structure of a document.
+//!
+//! A `kind:heading`, `kind:item`, `kind:note` or `kind:quote` tag anywhere in
+//! a title gives the node a kind, in the manner of the `due:` tag. The tag
+//! stays part of the title, so it survives every format the map is saved in,
+//! but the map shows the title without it: headings bold, notes dimmed and
+//! quotes in italics. The Markdown export writes headings as `#` headings,
+//! items as bullets, notes as paragraphs and quotes as block quotes.
+
+use crate::math::render_math;
+use std::borrow::Cow;
+
+/// Prefix of the kind tag
+pub const KIND_TAG: &str = "kind:";
+
+/// The part a node plays in the document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    Heading,
+    Item,
+    Note,
+    Quote,
+}
+
+impl NodeKind {
+    /// Every kind, in the order `cycle_kind` steps through them
+    pub const ALL: [NodeKind; 4] = [
+        NodeKind::Heading,
+        NodeKind::Item,
+        NodeKind::Note,
+        NodeKind::Quote,
+    ];
+
+    /// Name in the tag
+    pub fn name(self) -> &'static str {
+        match self {
+            NodeKind::Heading => "heading",
+            NodeKind::Item => "item",
+            NodeKind::Note => "note",
+            NodeKind::Quote => "quote",
+        }
+    }
+
+    /// The kind after this one, or none after the last
+    pub fn next(kind: Option<NodeKind>) -> Option<NodeKind> {
+        match kind {
+            None => Some(NodeKind::ALL[0]),
+            Some(kind) => {
+                let index = NodeKind::ALL.iter().position(|&k| k == kind)?;
+                NodeKind::ALL.get(index + 1).copied()
+            }
+        }
+    }
+}
+
+/// The kind tagged in `title`, if any
+pub fn node_kind(title: &str) -> Option<NodeKind> {
+    title.split_whitespace().find_map(parse_tag)
+}
+
+/// `title` without its kind tag
+pub fn without_kind(title: &str) -> Cow<'_, str> {
+    if node_kind(title).is_none() {
+        return Cow::Borrowed(title);
+    }
+    let words: Vec<&str> = title
+        .split_whitespace()
+        .filter(|word| parse_tag(word).is_none())
+        .collect();
+    Cow::Owned(words.join(" "))
+}
+
+/// `title` tagged with `kind` in place of any kind it had, or untagged
+pub fn with_kind(title: &str, kind: Option<NodeKind>) -> String {
+    let title = without_kind(title);
+    match kind {
+        Some(kind) if title.is_empty() => format!("{KIND_TAG}{}", kind.name()),
+        Some(kind) => format!("{title} {KIND_TAG}{}", kind.name()),
+        None => title.into_owned(),
+    }
+}
+
+/// The title as the map shows it: without its kind tag, and with its math
+/// rendered when `math` is set
+pub fn shown_title(title: &str, math: bool) -> Cow<'_, str> {
+    match without_kind(title) {
+        shown if !math => shown,
+        Cow::Borrowed(shown) => render_math(shown),
+        Cow::Owned(shown) => Cow::Owned(render_math(&shown).into_owned()),
+    }
+}
+
+fn parse_tag(word: &str) -> Option<NodeKind> {
+    let name = word.strip_prefix(KIND_TAG)?;
+    NodeKind::ALL.into_iter().find(|kind| kind.name() == name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_kind() {
+        assert_eq!(node_kind("Goals kind:heading"), Some(NodeKind::Heading));
+        assert_eq!(node_kind("kind:quote To be"), Some(NodeKind::Quote));
+        assert_eq!(node_kind("kind:chapter Goals"), None);
+        assert_eq!(without_kind("Goals  kind:heading now"), "Goals now");
+        assert_eq!(without_kind("Keep  spacing"), "Keep  spacing");
+    }
+
+    #[test]
+    fn test_with_kind_replaces_the_tag() {
+        let title = with_kind("Goals", Some(NodeKind::Heading));
+        assert_eq!(title, "Goals kind:heading");
+        assert_eq!(with_kind(&title, Some(NodeKind::Note)), "Goals kind:note");
+        assert_eq!(with_kind(&title, None), "Goals");
+
+        let mut kind = None;
+        let mut cycle = Vec::new();
+        while let Some(next) = NodeKind::next(kind) {
+            cycle.push(next);
+            kind = Some(next);
+        }
+        assert_eq!(cycle, NodeKind::ALL);
+    }
+}
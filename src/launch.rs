@@ -0,0 +1,43 @@
+//! Commands launched from nodes.
+//!
+//! A `run:` tag in a title makes the rest of the title a shell command, as in
+//! `Docs run: xdg-open https://docs.rs`. Activating the node asks before the
+//! command starts in the background from the map's directory, so a map can
+//! serve as a launcher for URLs, builds or terminals.
+
+/// Tag starting the command of a node
+pub const RUN_TAG: &str = "run:";
+
+/// The command tagged in `title`, if any
+pub fn node_command(title: &str) -> Option<&str> {
+    title
+        .match_indices(RUN_TAG)
+        .find(|&(start, _)| {
+            title[..start]
+                .chars()
+                .next_back()
+                .is_none_or(char::is_whitespace)
+        })
+        .map(|(start, _)| title[start + RUN_TAG.len()..].trim())
+        .filter(|command| !command.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_command() {
+        assert_eq!(
+            node_command("Build run: cargo build --release"),
+            Some("cargo build --release")
+        );
+        assert_eq!(node_command("run:make"), Some("make"));
+        assert_eq!(
+            node_command("rerun:x then run: echo run: twice"),
+            Some("echo run: twice")
+        );
+        assert_eq!(node_command("Empty run:  "), None);
+        assert_eq!(node_command("Nothing to run"), None);
+    }
+}
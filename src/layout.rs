@@ -1,7 +1,15 @@
+use crate::aggregates::Aggregates;
 use crate::app::AppState;
-use crate::model::NodeId;
-use crate::ui::text::TextWrapper;
+use crate::code::code_block;
+use crate::config::{AppConfig, HeatmapMode};
+use crate::kind::shown_title;
+use crate::model::{Node, NodeId};
+use crate::table::{table_lines, table_rows};
+use crate::text::TextWrapper;
+use indextree::Arena;
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use unicode_width::UnicodeWidthStr;
 
 /// Ratio threshold for when text should wrap (1.3 = 130% of max width)
@@ -13,6 +21,49 @@ const LEFT_PADDING: usize = 1;
 /// Space allocated for connection lines between parent and child nodes
 pub const NODE_CONNECTION_SPACING: f64 = 6.0;
 
+/// Maps smaller than this are laid out on one thread; spawning costs more
+/// than it saves
+pub const PARALLEL_MIN_NODES: usize = 4096;
+
+/// Time for an edit's heat in the activity heatmap to fade by half
+const ACTIVITY_HALF_LIFE: Duration = Duration::from_secs(5 * 60);
+
+/// The parts of the app state layout reads, shareable across threads
+struct LayoutContext<'a> {
+    tree: &'a Arena<Node>,
+    config: &'a AppConfig,
+    edit_times: &'a HashMap<NodeId, Instant>,
+    /// The node being edited and its edit buffer, laid out in place of its title
+    edit_preview: Option<(NodeId, &'a str)>,
+    /// The root and the title `root_title` shows it with
+    root_title: Option<(NodeId, String)>,
+    /// Node counts of the map, when already worked out since it last changed
+    aggregates: Option<&'a Aggregates>,
+}
+
+impl LayoutContext<'_> {
+    /// The title as [`AppState::display_title`] shows it
+    fn title<'b>(&'b self, node_id: NodeId, node: &'b Node) -> Cow<'b, str> {
+        match self.edit_preview {
+            Some((edited_id, buffer)) if edited_id == node_id => Cow::Borrowed(buffer),
+            _ => match &self.root_title {
+                Some((root_id, title)) if *root_id == node_id => Cow::Borrowed(title),
+                _ => shown_title(&node.title, self.config.render_math),
+            },
+        }
+    }
+}
+
+/// A node reached by the top-down pass, waiting for its height
+struct Visit {
+    node_id: NodeId,
+    /// Position of the parent in the visit list
+    parent: Option<usize>,
+    is_leaf: bool,
+    own_height: f64,
+    children_height: f64,
+}
+
 #[derive(Debug, Clone)]
 pub struct LayoutNode {
     // Position
@@ -25,14 +76,27 @@ pub struct LayoutNode {
     // Offsets
     pub yo: f64, // Y offset for vertical centering
     pub xo: f64, // X offset for unicode width compensation
+    // Heatmap intensity from 0 to 1
+    pub heat: f64,
 }
 
+/// Positions of the nodes of a map on the character grid.
+///
+/// Layout, like every walk over a map in this crate, keeps its own stack
+/// instead of recursing, so maps as deep as the 100,000 levels in
+/// `tests/deep_tree_tests.rs` cannot overflow the call stack.
 pub struct LayoutEngine {
     pub nodes: HashMap<NodeId, LayoutNode>,
     pub map_width: f64,
     pub map_height: f64,
     pub map_top: f64,
     pub map_bottom: f64,
+    /// Leftmost column of each depth, the root's first; empty unless depth
+    /// guides are on
+    pub depth_columns: Vec<f64>,
+    /// Parents whose leaf children are packed into columns, with the number
+    /// of rows in each column
+    pub columns: HashMap<NodeId, usize>,
 }
 
 impl Default for LayoutEngine {
@@ -49,36 +113,57 @@ impl LayoutEngine {
             map_height: 0.0,
             map_top: 0.0,
             map_bottom: 0.0,
+            depth_columns: Vec::new(),
+            columns: HashMap::new(),
         }
     }
 
     pub fn calculate_layout(app: &AppState) -> Self {
+        let parallel = cfg!(feature = "parallel") && app.tree.count() >= PARALLEL_MIN_NODES;
+        Self::calculate_layout_with(app, parallel)
+    }
+
+    /// Lay out the map, spreading independent subtrees over threads when
+    /// `parallel` is set and the `parallel` feature is enabled. The result is
+    /// the same either way.
+    pub fn calculate_layout_with(app: &AppState, parallel: bool) -> Self {
+        let ctx = LayoutContext {
+            tree: &app.tree,
+            config: &app.config,
+            edit_times: &app.edit_times,
+            edit_preview: app.edit_preview(),
+            root_title: app.root_title(),
+            aggregates: app.cached_aggregates(),
+        };
         let mut engine = Self::new();
 
         if let Some(root_id) = app.root_id {
-            // First pass: calculate widths and line heights
-            engine.calculate_x_and_lh(app, root_id, 0.0);
-
-            // Second pass: calculate heights
-            engine.calculate_h(app, root_id);
+            // First pass: widths, line heights, heights and unicode x offsets,
+            // which only depend on each node's own subtree
+            engine.nodes.reserve(app.tree.count());
+            engine.calculate_subtree(&ctx, root_id, LEFT_PADDING as f64, 0, parallel);
 
-            // Third pass: calculate y positions
-            engine.calculate_y(app, root_id, 0.0);
+            // Second pass: y positions, which depend on the siblings above
+            engine.calculate_y(&ctx, root_id);
 
-            // Fourth pass: calculate x offsets for unicode width
-            engine.calculate_xo(app);
+            if ctx.config.heatmap != HeatmapMode::Off {
+                engine.calculate_heat(&ctx, root_id);
+            }
+            if ctx.config.depth_guides {
+                engine.calculate_depth_columns(&ctx, root_id);
+            }
         }
 
         engine
     }
 
     /// Get children of a node that should be displayed (respecting hidden nodes)
-    fn get_filtered_children(app: &AppState, node_id: NodeId) -> Vec<NodeId> {
+    fn get_filtered_children(ctx: &LayoutContext, node_id: NodeId) -> Vec<NodeId> {
         node_id
-            .children(&app.tree)
+            .children(ctx.tree)
             .filter(|child_id| {
-                if !app.config.show_hidden {
-                    app.tree
+                if !ctx.config.show_hidden {
+                    ctx.tree
                         .get(*child_id)
                         .map(|n| !n.get().is_hidden())
                         .unwrap_or(false)
@@ -90,8 +175,8 @@ impl LayoutEngine {
     }
 
     /// Check if a node should be treated as a leaf (collapsed or no children)
-    fn is_leaf_like(app: &AppState, node_id: NodeId, children: &[NodeId]) -> bool {
-        let node = match app.tree.get(node_id) {
+    fn is_leaf_like(ctx: &LayoutContext, node_id: NodeId, children: &[NodeId]) -> bool {
+        let node = match ctx.tree.get(node_id) {
             Some(n) => n.get(),
             None => return true,
         };
@@ -99,159 +184,399 @@ impl LayoutEngine {
         children.is_empty() || node.is_collapsed
     }
 
-    fn calculate_x_and_lh(&mut self, app: &AppState, node_id: NodeId, parent_x: f64) {
-        let node = match app.tree.get(node_id) {
-            Some(n) => n.get(),
-            None => return,
-        };
+    /// Lay out `root_id` at column `x` and `depth` and everything below it, returning its
+    /// height. Widths are worked out top-down and heights bottom-up.
+    fn calculate_subtree(
+        &mut self,
+        ctx: &LayoutContext,
+        root_id: NodeId,
+        x: f64,
+        depth: usize,
+        parallel: bool,
+    ) -> f64 {
+        // Nodes in pre-order, each knowing its parent's position in the list
+        let mut visited: Vec<Visit> = Vec::new();
+        let mut stack = vec![(root_id, x, depth, None, parallel)];
+
+        while let Some((node_id, x, depth, parent, parallel)) = stack.pop() {
+            let Some(node) = ctx.tree.get(node_id).map(|n| n.get()) else {
+                continue;
+            };
+
+            // Get children (respecting hidden nodes)
+            let children = Self::get_filtered_children(ctx, node_id);
+            let at_the_end = Self::is_leaf_like(ctx, node_id, &children);
+
+            // Get max width for this node type
+            let max_width = if at_the_end {
+                ctx.config.max_leaf_node_width
+            } else {
+                ctx.config.max_parent_node_width
+            };
+
+            // Calculate width and line height
+            let (w, lh, xo) = Self::measure(ctx, node_id, node, max_width);
+
+            // Update map width
+            self.map_width = self.map_width.max(x + w);
+
+            // Store the layout node
+            self.nodes.insert(
+                node_id,
+                LayoutNode {
+                    x,
+                    y: 0.0, // Will be calculated later
+                    w,
+                    h: 0.0, // Will be calculated bottom-up below
+                    lh,
+                    yo: 0.0, // Will be calculated later
+                    xo,
+                    heat: 0.0, // Filled in by the heatmap pass
+                },
+            );
+
+            let index = visited.len();
+            visited.push(Visit {
+                node_id,
+                parent,
+                is_leaf: at_the_end,
+                // Own height: line height plus spacing
+                own_height: lh + ctx.config.line_spacing_at(depth, at_the_end) as f64,
+                children_height: 0.0,
+            });
+
+            if !at_the_end {
+                let child_x = x + w + NODE_CONNECTION_SPACING;
+                let child_depth = depth + 1;
+                if let Some(h) =
+                    self.calculate_packed(ctx, node_id, &children, child_x, child_depth)
+                {
+                    visited[index].children_height = h;
+                } else if let Some(h) =
+                    self.calculate_parallel(ctx, &children, child_x, child_depth, parallel)
+                {
+                    visited[index].children_height = h;
+                } else {
+                    // A single child passes the parallel fan-out further down
+                    let parallel = parallel && children.len() == 1;
+                    stack.extend(
+                        children.iter().rev().map(|&child_id| {
+                            (child_id, child_x, child_depth, Some(index), parallel)
+                        }),
+                    );
+                }
+            }
+        }
 
-        // Calculate x position
-        let x = if Some(node_id) == app.root_id {
-            LEFT_PADDING as f64
-        } else {
-            // Get parent node's width
-            let parent_width = node_id
-                .ancestors(&app.tree)
-                .nth(1)
-                .and_then(|parent| self.nodes.get(&parent))
-                .map(|p| p.w)
-                .unwrap_or(0.0);
-
-            parent_x + parent_width + NODE_CONNECTION_SPACING
-        };
+        // Children follow their parent in pre-order, so walking backwards
+        // finishes every subtree before the node that owns it
+        let mut height = 0.0;
+        for index in (0..visited.len()).rev() {
+            let visit = &visited[index];
+
+            // Leaf and collapsed nodes take their own height; parents take the
+            // sum of their children's heights when that is larger
+            let h = if visit.is_leaf {
+                visit.own_height
+            } else {
+                visit.children_height.max(visit.own_height)
+            };
+            if let Some(layout) = self.nodes.get_mut(&visit.node_id) {
+                layout.h = h;
+            }
 
-        // Get children (respecting hidden nodes)
-        let children = Self::get_filtered_children(app, node_id);
-        let at_the_end = Self::is_leaf_like(app, node_id, &children);
+            match visit.parent {
+                Some(parent) => visited[parent].children_height += h,
+                None => height = h,
+            }
+        }
 
-        // Get max width for this node type
-        let max_width = if at_the_end {
-            app.config.max_leaf_node_width
-        } else {
-            app.config.max_parent_node_width
-        };
+        height
+    }
 
-        // Calculate width and line height
-        let title_width = node.title.width();
-        let (w, lh) = if title_width as f32 > WRAP_THRESHOLD_RATIO * max_width as f32 {
+    /// Width, line height and unicode x offset of a node's title
+    fn measure(
+        ctx: &LayoutContext,
+        node_id: NodeId,
+        node: &Node,
+        max_width: usize,
+    ) -> (f64, f64, f64) {
+        let title = ctx.title(node_id, node);
+        let title_width = title.width();
+        let (w, lh) = if let Some(code) = code_block(&title) {
+            // Code is cut short rather than wrapped
+            (code.code.width().min(max_width) as f64, 1.0)
+        } else if let Some(rows) = table_rows(&title) {
+            // A table takes a line per row
+            let lines = table_lines(&rows, max_width);
+            let max_line_width = lines.iter().map(|l| l.width()).max().unwrap_or(0);
+            (max_line_width as f64, lines.len() as f64)
+        } else if title_width as f32 > WRAP_THRESHOLD_RATIO * max_width as f32 {
             // Need to wrap text
-            let lines = TextWrapper::wrap(&node.title, max_width);
+            let lines = TextWrapper::wrap(&title, max_width);
             let max_line_width = lines.iter().map(|l| l.width()).max().unwrap_or(0);
             (max_line_width as f64, lines.len() as f64)
         } else {
             (title_width as f64, 1.0)
         };
+        // X offset to compensate for unicode width differences
+        (w, lh, (title.len() - title_width) as f64)
+    }
 
-        // Store the layout node
-        self.nodes.insert(
-            node_id,
-            LayoutNode {
-                x,
-                y: 0.0, // Will be calculated later
-                w,
-                h: 0.0, // Will be calculated later
-                lh,
-                yo: 0.0, // Will be calculated later
-                xo: 0.0, // Will be calculated later
-            },
-        );
-
-        // Update map width
-        self.map_width = self.map_width.max(x + w);
+    /// Lay out the children of `parent_id` in columns side by side when they
+    /// are all leaves and more than `pack_leaves_after`, returning the height
+    /// of the tallest column, or `None` to stack them as usual
+    fn calculate_packed(
+        &mut self,
+        ctx: &LayoutContext,
+        parent_id: NodeId,
+        children: &[NodeId],
+        x: f64,
+        depth: usize,
+    ) -> Option<f64> {
+        let max_rows = ctx.config.pack_leaves_after;
+        if max_rows == 0 || children.len() <= max_rows {
+            return None;
+        }
+        let all_leaves = children.iter().all(|&child_id| {
+            Self::is_leaf_like(ctx, child_id, &Self::get_filtered_children(ctx, child_id))
+        });
+        if !all_leaves {
+            return None;
+        }
 
-        // Recurse for children only if node is not collapsed
-        if !node.is_collapsed {
-            for child_id in children {
-                self.calculate_x_and_lh(app, child_id, x);
+        // As few columns as fit, filled evenly
+        let columns = children.len().div_ceil(max_rows);
+        let rows = children.len().div_ceil(columns);
+        self.columns.insert(parent_id, rows);
+
+        let spacing = ctx.config.line_spacing_at(depth, true) as f64;
+        let mut column_x = x;
+        let mut height: f64 = 0.0;
+        for column in children.chunks(rows) {
+            let mut column_width: f64 = 0.0;
+            let mut column_height = 0.0;
+            for &child_id in column {
+                let node = ctx.tree[child_id].get();
+                let (w, lh, xo) =
+                    Self::measure(ctx, child_id, node, ctx.config.max_leaf_node_width);
+                self.nodes.insert(
+                    child_id,
+                    LayoutNode {
+                        x: column_x,
+                        y: 0.0,
+                        w,
+                        h: lh + spacing,
+                        lh,
+                        yo: 0.0,
+                        xo,
+                        heat: 0.0,
+                    },
+                );
+                column_width = column_width.max(w);
+                column_height += lh + spacing;
             }
+            self.map_width = self.map_width.max(column_x + column_width);
+            height = height.max(column_height);
+            column_x += column_width + NODE_CONNECTION_SPACING;
         }
+        Some(height)
     }
 
-    fn calculate_h(&mut self, app: &AppState, node_id: NodeId) -> f64 {
-        let node = match app.tree.get(node_id) {
-            Some(n) => n.get(),
-            None => return 0.0,
-        };
+    /// Lay out sibling subtrees on separate threads and return their total
+    /// height, or `None` when they should be laid out in place
+    #[cfg(feature = "parallel")]
+    fn calculate_parallel(
+        &mut self,
+        ctx: &LayoutContext,
+        children: &[NodeId],
+        x: f64,
+        depth: usize,
+        parallel: bool,
+    ) -> Option<f64> {
+        use rayon::prelude::*;
+
+        if !parallel || children.len() < 2 {
+            return None;
+        }
 
-        let children = Self::get_filtered_children(app, node_id);
-        let at_the_end = Self::is_leaf_like(app, node_id, &children);
+        // Each sibling subtree gets its own engine; merging them in sibling
+        // order keeps the result independent of thread timing
+        let subtrees: Vec<(Self, f64)> = children
+            .par_iter()
+            .map(|&child_id| {
+                let mut subtree = Self::new();
+                let h = subtree.calculate_subtree(ctx, child_id, x, depth, false);
+                (subtree, h)
+            })
+            .collect();
+
+        let mut total = 0.0;
+        for (subtree, h) in subtrees {
+            self.map_width = self.map_width.max(subtree.map_width);
+            self.nodes.extend(subtree.nodes);
+            self.columns.extend(subtree.columns);
+            total += h;
+        }
+        Some(total)
+    }
 
-        let h = if at_the_end || node.is_collapsed {
-            // Leaf node or collapsed node: height is line height plus spacing
-            self.nodes
-                .get(&node_id)
-                .map(|layout| app.config.line_spacing as f64 + layout.lh)
-                .unwrap_or(app.config.line_spacing as f64)
-        } else {
-            // Parent node: height is sum of children or own line height
-            let children_height: f64 = children
-                .iter()
-                .map(|child_id| self.calculate_h(app, *child_id))
-                .sum();
+    #[cfg(not(feature = "parallel"))]
+    fn calculate_parallel(
+        &mut self,
+        _ctx: &LayoutContext,
+        _children: &[NodeId],
+        _x: f64,
+        _depth: usize,
+        _parallel: bool,
+    ) -> Option<f64> {
+        None
+    }
 
-            let own_height = self
-                .nodes
-                .get(&node_id)
-                .map(|layout| layout.lh + app.config.line_spacing as f64)
-                .unwrap_or(app.config.line_spacing as f64);
+    /// Assign y positions top-down: each child starts below the subtrees of
+    /// its earlier siblings
+    fn calculate_y(&mut self, ctx: &LayoutContext, root_id: NodeId) {
+        let mut stack = vec![(root_id, 0.0)];
 
-            children_height.max(own_height)
-        };
+        while let Some((node_id, current_y)) = stack.pop() {
+            let Some(node) = ctx.tree.get(node_id).map(|n| n.get()) else {
+                continue;
+            };
 
-        // Update the layout node with calculated height
-        if let Some(layout) = self.nodes.get_mut(&node_id) {
-            layout.h = h;
-        }
+            // Set this node's y position
+            if let Some(layout) = self.nodes.get_mut(&node_id) {
+                layout.y = current_y;
 
-        h
-    }
-
-    fn calculate_y(&mut self, app: &AppState, node_id: NodeId, current_y: f64) {
-        let node = match app.tree.get(node_id) {
-            Some(n) => n.get(),
-            None => return,
-        };
+                // Calculate y offset for vertical centering
+                layout.yo = ((layout.h - layout.lh) / 2.0).round();
+            }
 
-        // Set this node's y position
-        if let Some(layout) = self.nodes.get_mut(&node_id) {
-            layout.y = current_y;
+            // Update map boundaries; a node's height covers its spacing and children
+            if let Some(layout) = self.nodes.get(&node_id) {
+                self.map_bottom = self.map_bottom.max(current_y + layout.h);
+                self.map_top = self.map_top.min(current_y);
+            }
 
-            // Calculate y offset for vertical centering
-            layout.yo = ((layout.h - layout.lh) / 2.0).round();
+            // Process children
+            if !node.is_collapsed {
+                // Packed children start over at the top of each column
+                let rows = self.columns.get(&node_id).copied().unwrap_or(usize::MAX);
+                let mut child_y = current_y;
+                let mut positioned = Vec::new();
+                for (i, child_id) in Self::get_filtered_children(ctx, node_id)
+                    .into_iter()
+                    .enumerate()
+                {
+                    if i % rows == 0 {
+                        child_y = current_y;
+                    }
+                    positioned.push((child_id, child_y));
+                    if let Some(child_layout) = self.nodes.get(&child_id) {
+                        child_y += child_layout.h;
+                    }
+                }
+                stack.extend(positioned.into_iter().rev());
+            }
         }
 
-        // Update map boundaries
-        if let Some(layout) = self.nodes.get(&node_id) {
-            self.map_bottom = self
-                .map_bottom
-                .max(current_y + layout.lh + app.config.line_spacing as f64);
-            self.map_top = self.map_top.min(current_y);
+        self.map_height = self.map_bottom - self.map_top;
+    }
+
+    /// Heatmap intensity of every laid-out node, from an aggregate over its
+    /// subtree. Collapsed nodes include everything folded away under them.
+    fn calculate_heat(&mut self, ctx: &LayoutContext, root_id: NodeId) {
+        // Laid-out nodes in pre-order, each with its parent's position
+        let mut order: Vec<(NodeId, Option<usize>)> = Vec::new();
+        let mut stack = vec![(root_id, None)];
+        while let Some((node_id, parent)) = stack.pop() {
+            let index = order.len();
+            order.push((node_id, parent));
+            if !ctx.tree[node_id].get().is_collapsed {
+                let children = Self::get_filtered_children(ctx, node_id);
+                stack.extend(children.into_iter().rev().map(|child| (child, Some(index))));
+            }
         }
 
-        // Process children
-        if !node.is_collapsed {
-            let children = Self::get_filtered_children(app, node_id);
-            let mut child_y = current_y;
+        // Node counts, summed bottom-up; a collapsed node counts everything
+        // folded away under it, read from the aggregates when they are at hand
+        let mut sizes = Vec::with_capacity(order.len());
+        for &(node_id, _) in &order {
+            let size = if ctx.tree[node_id].get().is_collapsed {
+                ctx.aggregates
+                    .and_then(|aggregates| aggregates.descendants.get(&node_id))
+                    .map_or_else(|| node_id.descendants(ctx.tree).count(), |count| count + 1)
+            } else {
+                1
+            };
+            sizes.push(size);
+        }
 
-            for child_id in children {
-                self.calculate_y(app, child_id, child_y);
-                if let Some(child_layout) = self.nodes.get(&child_id) {
-                    child_y += child_layout.h;
+        // Latest edits, each counted at the node itself or at the collapsed
+        // node it is folded away under, then carried up
+        let mut latest_edits = vec![None; order.len()];
+        if ctx.config.heatmap == HeatmapMode::Activity {
+            let positions: HashMap<NodeId, usize> = order
+                .iter()
+                .enumerate()
+                .map(|(index, &(node_id, _))| (node_id, index))
+                .collect();
+            for (&edited_id, &edit) in ctx.edit_times {
+                if ctx.tree.get(edited_id).is_none_or(|node| node.is_removed()) {
+                    continue;
+                }
+                let shown = edited_id
+                    .ancestors(ctx.tree)
+                    .find(|id| positions.contains_key(id));
+                if let Some(shown_id) =
+                    shown.filter(|&id| id == edited_id || ctx.tree[id].get().is_collapsed)
+                {
+                    let index = positions[&shown_id];
+                    latest_edits[index] = latest_edits[index].max(Some(edit));
                 }
             }
         }
+        for index in (1..order.len()).rev() {
+            if let Some(parent) = order[index].1 {
+                sizes[parent] += sizes[index];
+                latest_edits[parent] = latest_edits[parent].max(latest_edits[index]);
+            }
+        }
 
-        self.map_height = self.map_bottom - self.map_top;
+        // The root would outweigh everything, so sizes scale to the largest branch
+        let largest = sizes.iter().skip(1).max().copied().unwrap_or(0);
+        let now = Instant::now();
+        for (index, &(node_id, _)) in order.iter().enumerate() {
+            let heat = match ctx.config.heatmap {
+                HeatmapMode::Off => 0.0,
+                HeatmapMode::Size if largest > 1 => {
+                    ((sizes[index] as f64).ln() / (largest as f64).ln()).min(1.0)
+                }
+                HeatmapMode::Size => 0.0,
+                HeatmapMode::Activity => latest_edits[index].map_or(0.0, |edit| {
+                    let age = now.duration_since(edit).as_secs_f64();
+                    0.5_f64.powf(age / ACTIVITY_HALF_LIFE.as_secs_f64())
+                }),
+            };
+            if let Some(layout) = self.nodes.get_mut(&node_id) {
+                layout.heat = heat;
+            }
+        }
     }
 
-    fn calculate_xo(&mut self, app: &AppState) {
-        // Calculate x offset to compensate for unicode width differences
-        for (node_id, layout) in self.nodes.iter_mut() {
-            if let Some(node_ref) = app.tree.get(*node_id) {
-                let node = node_ref.get();
-                let title_len = node.title.len();
-                let title_width = node.title.width();
-                layout.xo = (title_len - title_width) as f64;
+    /// Leftmost x of the laid-out nodes at each depth
+    fn calculate_depth_columns(&mut self, ctx: &LayoutContext, root_id: NodeId) {
+        let mut stack = vec![(root_id, 0)];
+        while let Some((node_id, depth)) = stack.pop() {
+            let Some(layout) = self.nodes.get(&node_id) else {
+                continue;
+            };
+            match self.depth_columns.get_mut(depth) {
+                Some(column) => *column = column.min(layout.x),
+                None => self.depth_columns.push(layout.x),
+            }
+            if !ctx.tree[node_id].get().is_collapsed {
+                let children = Self::get_filtered_children(ctx, node_id);
+                stack.extend(children.into_iter().map(|child| (child, depth + 1)));
             }
         }
     }
@@ -509,6 +834,7 @@ mod tests {
                 lh: 1.0,
                 yo: 0.0,
                 xo: 0.0,
+                heat: 0.0,
             },
         );
 
@@ -522,6 +848,7 @@ mod tests {
                 lh: 1.0,
                 yo: 0.0,
                 xo: 0.0,
+                heat: 0.0,
             },
         );
 
@@ -644,4 +971,169 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_parallel_layout_matches_sequential() {
+        let config = AppConfig::default();
+        let mut app = AppState::new(config);
+        let root = app.tree.new_node(Node::new("Root".to_string()));
+        app.root_id = Some(root);
+
+        // Uneven branches with long, wide and collapsed nodes
+        for i in 0..12 {
+            let branch = app.tree.new_node(Node::new(format!("Branch {i}")));
+            root.append(branch, &mut app.tree);
+            for j in 0..(i * 40) {
+                let title = format!("Node {i}.{j} {}", "漢字 word ".repeat(j % 9));
+                let child = app.tree.new_node(Node::new(title));
+                branch.append(child, &mut app.tree);
+                for k in 0..(j % 4) {
+                    let leaf = app.tree.new_node(Node::new(format!("Leaf {k}")));
+                    child.append(leaf, &mut app.tree);
+                }
+                app.tree[child].get_mut().is_collapsed = j % 7 == 0;
+            }
+        }
+
+        let sequential = LayoutEngine::calculate_layout_with(&app, false);
+        let parallel = LayoutEngine::calculate_layout_with(&app, true);
+
+        assert_eq!(parallel.nodes.len(), sequential.nodes.len());
+        assert_eq!(parallel.map_width, sequential.map_width);
+        assert_eq!(parallel.map_height, sequential.map_height);
+        for (id, expected) in &sequential.nodes {
+            let actual = &parallel.nodes[id];
+            assert_eq!(
+                (actual.x, actual.y, actual.w, actual.h, actual.lh, actual.yo, actual.xo),
+                (
+                    expected.x,
+                    expected.y,
+                    expected.w,
+                    expected.h,
+                    expected.lh,
+                    expected.yo,
+                    expected.xo
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_heatmap_by_size_and_activity() {
+        let mut app = create_test_app();
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert!(layout.nodes.values().all(|node| node.heat == 0.0));
+
+        let root = app.root_id.unwrap();
+        let children: Vec<_> = root.children(&app.tree).collect();
+        let (child1, child2) = (children[0], children[1]);
+
+        app.config.heatmap = HeatmapMode::Size;
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert_eq!(layout.nodes[&child1].heat, 0.0);
+        assert_eq!(layout.nodes[&child2].heat, 1.0);
+        assert_eq!(layout.nodes[&root].heat, 1.0);
+
+        // Folding a branch keeps its size
+        app.tree[child2].get_mut().is_collapsed = true;
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert_eq!(layout.nodes[&child2].heat, 1.0);
+        app.aggregates();
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert_eq!(layout.nodes[&child2].heat, 1.0);
+
+        app.config.heatmap = HeatmapMode::Activity;
+        app.edit_times.insert(child1, Instant::now());
+        app.edit_times
+            .insert(child2, Instant::now() - ACTIVITY_HALF_LIFE * 2);
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert!(layout.nodes[&child1].heat > 0.9);
+        assert!((layout.nodes[&child2].heat - 0.25).abs() < 0.01);
+        assert!(layout.nodes[&root].heat > 0.9);
+
+        // Edits folded away count for the collapsed node
+        let grandchild = child2.children(&app.tree).next().unwrap();
+        app.edit_times.insert(grandchild, Instant::now());
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert!(layout.nodes[&child2].heat > 0.9);
+    }
+
+    #[test]
+    fn test_depth_columns() {
+        let mut app = create_test_app();
+        assert!(LayoutEngine::calculate_layout(&app)
+            .depth_columns
+            .is_empty());
+
+        app.config.depth_guides = true;
+        let layout = LayoutEngine::calculate_layout(&app);
+        let root = app.root_id.unwrap();
+        let children: Vec<_> = root.children(&app.tree).collect();
+        let grandchild = children[1].children(&app.tree).next().unwrap();
+        assert_eq!(
+            layout.depth_columns,
+            [
+                LEFT_PADDING as f64,
+                layout.nodes[&children[0]].x,
+                layout.nodes[&grandchild].x
+            ]
+        );
+
+        // Folded levels get no guide
+        app.tree[children[1]].get_mut().is_collapsed = true;
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert_eq!(layout.depth_columns.len(), 2);
+    }
+
+    #[test]
+    fn test_line_spacing_by_depth() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let children: Vec<_> = root.children(&app.tree).collect();
+        let even = LayoutEngine::calculate_layout(&app);
+        assert_eq!(even.nodes[&children[1]].y - even.nodes[&children[0]].y, 2.0);
+
+        app.config.depth_line_spacing = vec![3];
+        app.config.leaf_line_spacing = Some(0);
+        let spaced = LayoutEngine::calculate_layout(&app);
+        assert_eq!(
+            spaced.nodes[&children[1]].y - spaced.nodes[&children[0]].y,
+            4.0
+        );
+        let grandchild = children[1].children(&app.tree).next().unwrap();
+        assert_eq!(spaced.nodes[&grandchild].h, 1.0);
+    }
+
+    #[test]
+    fn test_pack_leaves_into_columns() {
+        let mut app = create_test_app();
+        let root = app.root_id.unwrap();
+        let child1 = root.children(&app.tree).next().unwrap();
+        for i in 0..5 {
+            let leaf = app.tree.new_node(Node::new(format!("Leaf {}", i)));
+            child1.append(leaf, &mut app.tree);
+        }
+        let leaves: Vec<_> = child1.children(&app.tree).collect();
+
+        // Below the threshold the leaves stay in one column
+        app.config.pack_leaves_after = 5;
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert!(layout.columns.is_empty());
+
+        // Five leaves with at most three rows make columns of three and two
+        app.config.pack_leaves_after = 3;
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert_eq!(layout.columns.get(&child1), Some(&3));
+        let (first, fourth) = (&layout.nodes[&leaves[0]], &layout.nodes[&leaves[3]]);
+        assert_eq!(first.y, fourth.y);
+        assert_eq!(fourth.x, first.x + first.w + NODE_CONNECTION_SPACING);
+        assert_eq!(layout.nodes[&leaves[2]].y - first.y, 4.0);
+        assert_eq!(layout.nodes[&child1].h, 6.0);
+
+        // A branch among the children keeps them stacked
+        let nested = app.tree.new_node(Node::new("Nested".to_string()));
+        leaves[0].append(nested, &mut app.tree);
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert!(layout.columns.is_empty());
+    }
 }
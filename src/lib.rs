@@ -1,15 +1,107 @@
+//! hmm-rs: a keyboard-centric mind map editor, usable as a library.
+//!
+//! The core modules have no terminal dependencies:
+//!
+//! - [`model`]: the [`Node`] type stored in an [`indextree::Arena`]
+//! - [`parser`]: the format registry and reading and writing the indented `.hmm` text format
+//! - [`layout`]: the [`LayoutEngine`] that positions nodes on a character grid
+//! - [`export`]: conversions of a map into other text formats
+//! - [`formats`]: the list of every map and export format
+//! - [`import`]: Workflowy, Dynalist, XMind and MindManager files and CSV/TSV tables
+//! - [`keymap`]: normal-mode key bindings, help and cheat sheet generation
+//! - [`i18n`]: localized status messages and help text
+//! - [`app`] and [`actions`]: editor state and the operations the UI triggers
+//!
+//! Optional cargo features add the frontends:
+//!
+//! - `render`: the `ui` module, including `ui::render_to_string` for
+//!   terminal-free, deterministic output
+//! - `clipboard`: system clipboard integration for yank, paste and export
+//! - `cli`: command line arguments and config file loading
+//! - `sqlite`: the [`sqlite`](crate::sqlite) storage backend for `.hmmdb` maps
+//! - `highlight` (default): syntax highlighting of code nodes in the renderer
+//! - `network`: fetching web pages and feeds, for importing their headings and entries
+//! - `ipc`: the [`ipc`](crate::ipc) commands other programs send over a Unix socket
+//! - `undo-compression` (with `tui`): older undo snapshots kept zstd-compressed
+//! - `tui` (default): the crossterm event loop and the `hmm-rs` binary
+//!
+//! With default features disabled the library has no terminal, clipboard or
+//! filesystem-discovery dependencies and builds for `wasm32-unknown-unknown`,
+//! e.g. for a browser previewer sharing the same layout math:
+//!
+//! ```text
+//! cargo build --lib --no-default-features --features render --target wasm32-unknown-unknown
+//! ```
+//!
+//! ```
+//! use hmm_rs::{parser, AppConfig, AppState, LayoutEngine};
+//!
+//! let (tree, root_id) = parser::parse_hmm_content("Root\n\tChild 1\n\tChild 2").unwrap();
+//!
+//! let mut app = AppState::new(AppConfig::default());
+//! app.tree = tree;
+//! app.root_id = Some(root_id);
+//!
+//! let layout = LayoutEngine::calculate_layout(&app);
+//! assert_eq!(layout.nodes.len(), 3);
+//! assert_eq!(parser::map_to_list(&app.tree, root_id, false, 0), "Root\n\tChild 1\n\tChild 2\n");
+//! ```
+
+pub mod aggregates;
 pub mod app;
+pub mod casing;
+pub mod check;
+pub mod code;
 pub mod config;
+pub mod diff;
+pub mod due;
+pub mod export;
+pub mod formats;
+pub mod git;
+pub mod glossary;
+pub mod history;
+pub mod hooks;
+pub mod i18n;
+pub mod import;
+#[cfg(feature = "ipc")]
+pub mod ipc;
+pub mod journal;
+pub mod keymap;
+pub mod kind;
+pub mod launch;
 pub mod layout;
+pub mod link;
+pub mod lock;
+pub mod math;
 pub mod model;
 pub mod parser;
+pub mod pins;
+pub mod recent;
+pub mod reference;
+pub mod search_index;
+pub mod settings;
+pub mod snapshots;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod status;
+pub mod table;
+pub mod text;
+pub mod theme;
+pub mod tutorial;
+pub mod window;
+pub mod workspace;
+#[cfg(feature = "render")]
 pub mod ui;
 
 // Internal modules
 pub mod actions;
+#[cfg(feature = "tui")]
 pub mod event;
 
 // Re-export commonly used types
 pub use app::{AppMode, AppState};
 pub use config::AppConfig;
+pub use layout::{LayoutEngine, LayoutNode};
 pub use model::{Node, NodeId};
+#[cfg(feature = "render")]
+pub use ui::render_to_string;
@@ -0,0 +1,433 @@
+//! Links from a node to another map, written as `@other-map.hmm` or
+//! `other.hmm#Node Title`.
+//!
+//! When a map file or a linked node is renamed, [`update_links`] rewrites the
+//! links to it in the other maps of the workspace, so they keep working.
+//! [`LinkIndex`] tells which maps link to a node title without reading every
+//! map each time one is retitled.
+
+use crate::workspace::{expand_home, list_maps};
+use anyhow::Result;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fs;
+use std::ops::Range;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// A reference to another map, optionally to a node in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapLink {
+    pub path: PathBuf,
+    /// Title of the node to select after opening
+    pub anchor: Option<String>,
+}
+
+/// Find the first map link in a node title. The anchor after `#` runs to the
+/// end of the title, so node titles with spaces can be targeted.
+pub fn parse_link(title: &str) -> Option<MapLink> {
+    let (path, anchor) = link_spans(title)?;
+    Some(MapLink {
+        path: PathBuf::from(&title[path]),
+        anchor: anchor.map(|anchor| title[anchor].to_string()),
+    })
+}
+
+/// Where the path and the anchor, trimmed and if not empty, of the first map
+/// link in `title` are
+fn link_spans(title: &str) -> Option<(Range<usize>, Option<Range<usize>>)> {
+    let pattern = Regex::new(r"(?:^|\s)@?([^\s#@]+\.hmm)(?:#(.*))?(?:\s|$)").ok()?;
+    let captures = pattern.captures(title)?;
+
+    let anchor = captures.get(2).and_then(|m| {
+        let text = m.as_str();
+        let start = m.start() + (text.len() - text.trim_start().len());
+        let end = m.start() + text.trim_end().len();
+        (start < end).then_some(start..end)
+    });
+    Some((captures.get(1)?.range(), anchor))
+}
+
+/// Locate the linked file. Relative links are looked up next to the current
+/// map first, then in the workspace, and default to the current map's directory.
+pub fn resolve_link(link: &Path, current: Option<&Path>, workspace: Option<&Path>) -> PathBuf {
+    let link = expand_home(link);
+    if link.is_absolute() {
+        return link;
+    }
+
+    let beside_current = current
+        .and_then(Path::parent)
+        .map(|dir| dir.join(&link))
+        .unwrap_or_else(|| link.clone());
+    if beside_current.exists() {
+        return beside_current;
+    }
+
+    match workspace.map(|dir| expand_home(dir).join(&link)) {
+        Some(in_workspace) if in_workspace.exists() => in_workspace,
+        _ => beside_current,
+    }
+}
+
+/// A renamed link target
+#[derive(Debug, Clone, Copy)]
+pub enum LinkRename<'a> {
+    /// A map file moved from one path to another
+    File { from: &'a Path, to: &'a Path },
+    /// A node of the map in `map` retitled
+    Node {
+        map: &'a Path,
+        from: &'a str,
+        to: &'a str,
+    },
+}
+
+/// A line of a map whose link [`update_links`] rewrote
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkUpdate {
+    pub path: PathBuf,
+    /// Line number, from 1
+    pub line: usize,
+}
+
+/// `title`, found in the map at `map`, with its link following the rename,
+/// if it links to the renamed target
+pub fn retarget_link(
+    title: &str,
+    map: &Path,
+    workspace: Option<&Path>,
+    rename: LinkRename,
+) -> Option<String> {
+    let (path_span, anchor_span) = link_spans(title)?;
+    let link = expand_home(Path::new(&title[path_span.clone()]));
+    // The target is gone after a file rename, so every directory the link
+    // could be looked up in is tried instead of the one it resolves to
+    let bases: Vec<Option<PathBuf>> = if link.is_absolute() {
+        vec![None]
+    } else {
+        let dirs = [
+            map.parent().map(Path::to_path_buf),
+            workspace.map(expand_home),
+        ];
+        dirs.into_iter().flatten().map(Some).collect()
+    };
+    let base_linking_to = |target: &Path| {
+        bases.iter().find(|base| {
+            let candidate = base
+                .as_ref()
+                .map_or_else(|| link.clone(), |dir| dir.join(&link));
+            same_file(&candidate, target)
+        })
+    };
+
+    let (span, replacement) = match rename {
+        LinkRename::File { from, to } => {
+            let to = match base_linking_to(from)? {
+                Some(base) => relative_to(&absolute(to), &absolute(base)),
+                None => absolute(to),
+            };
+            (path_span, to.display().to_string())
+        }
+        LinkRename::Node {
+            map: target,
+            from,
+            to,
+        } => {
+            let anchor_span = anchor_span.filter(|span| &title[span.clone()] == from)?;
+            base_linking_to(target)?;
+            (anchor_span, to.to_string())
+        }
+    };
+    let mut retargeted = title.to_string();
+    retargeted.replace_range(span, &replacement);
+    Some(retargeted)
+}
+
+/// Rewrite the links to a renamed map or node in every map of `workspace`
+/// but `skip`, the open one, returning the lines that changed
+pub fn update_links(
+    workspace: &Path,
+    skip: Option<&Path>,
+    rename: LinkRename,
+) -> Result<Vec<LinkUpdate>> {
+    let maps: Vec<PathBuf> = list_maps(workspace)
+        .into_iter()
+        .filter(|path| !skip.is_some_and(|skip| same_file(path, skip)))
+        .collect();
+    rewrite_links(&maps, workspace, rename)
+}
+
+/// Rewrite the links to a renamed map or node in `maps`, returning the lines
+/// that changed
+pub fn rewrite_links(
+    maps: &[PathBuf],
+    workspace: &Path,
+    rename: LinkRename,
+) -> Result<Vec<LinkUpdate>> {
+    let mut updates = Vec::new();
+    for path in maps {
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        let (rewritten, lines) = retarget_map(&content, path, workspace, rename);
+        if !lines.is_empty() {
+            fs::write(path, rewritten)?;
+        }
+        updates.extend(lines.into_iter().map(|line| LinkUpdate {
+            path: path.clone(),
+            line,
+        }));
+    }
+    Ok(updates)
+}
+
+/// The lines of `maps` whose links [`rewrite_links`] would change, leaving
+/// the files alone
+pub fn find_link_updates(
+    maps: &[PathBuf],
+    workspace: &Path,
+    rename: LinkRename,
+) -> Vec<LinkUpdate> {
+    let mut updates = Vec::new();
+    for path in maps {
+        let Ok(content) = fs::read_to_string(path) else {
+            continue;
+        };
+        let (_, lines) = retarget_map(&content, path, workspace, rename);
+        updates.extend(lines.into_iter().map(|line| LinkUpdate {
+            path: path.clone(),
+            line,
+        }));
+    }
+    updates
+}
+
+/// `content` of the map at `path` with its links following the rename, and
+/// the numbers of the lines that changed
+fn retarget_map(
+    content: &str,
+    path: &Path,
+    workspace: &Path,
+    rename: LinkRename,
+) -> (String, Vec<usize>) {
+    let mut lines = Vec::new();
+    let mut rewritten = String::with_capacity(content.len());
+    for (index, line) in content.split_inclusive('\n').enumerate() {
+        let body = line.trim_end_matches(['\n', '\r']);
+        let indent = body.len() - body.trim_start().len();
+        match retarget_link(&body[indent..], path, Some(workspace), rename) {
+            Some(title) => {
+                rewritten.push_str(&body[..indent]);
+                rewritten.push_str(&title);
+                rewritten.push_str(&line[body.len()..]);
+                lines.push(index + 1);
+            }
+            None => rewritten.push_str(line),
+        }
+    }
+    (rewritten, lines)
+}
+
+/// The anchors of the links in each map of a workspace, read again only for
+/// maps modified since
+#[derive(Debug, Default)]
+pub struct LinkIndex {
+    maps: HashMap<PathBuf, (SystemTime, Vec<String>)>,
+}
+
+impl LinkIndex {
+    /// The maps of `workspace` but `skip` with a link anchored at `title`;
+    /// which map each link points to is left to [`find_link_updates`]
+    pub fn maps_linking_to(
+        &mut self,
+        workspace: &Path,
+        skip: Option<&Path>,
+        title: &str,
+    ) -> Vec<PathBuf> {
+        let maps = list_maps(workspace);
+        self.maps.retain(|path, _| maps.contains(path));
+
+        let mut linking = Vec::new();
+        for path in maps {
+            if skip.is_some_and(|skip| same_file(&path, skip)) {
+                continue;
+            }
+            let Ok(modified) = fs::metadata(&path).and_then(|metadata| metadata.modified()) else {
+                continue;
+            };
+            let fresh = self
+                .maps
+                .get(&path)
+                .is_some_and(|(indexed, _)| *indexed == modified);
+            if !fresh {
+                let Ok(content) = fs::read_to_string(&path) else {
+                    continue;
+                };
+                let anchors = content
+                    .lines()
+                    .filter_map(|line| parse_link(line.trim())?.anchor)
+                    .collect();
+                self.maps.insert(path.clone(), (modified, anchors));
+            }
+            if self.maps[&path].1.iter().any(|anchor| anchor == title) {
+                linking.push(path);
+            }
+        }
+        linking
+    }
+}
+
+/// Whether two paths name the same file, which need not exist anymore
+pub(crate) fn same_file(a: &Path, b: &Path) -> bool {
+    absolute(a) == absolute(b)
+}
+
+/// `path` with its directory resolved, so that it can be compared and made
+/// relative even when the file itself is gone
+fn absolute(path: &Path) -> PathBuf {
+    if let Ok(path) = fs::canonicalize(path) {
+        return path;
+    }
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    let dir = dir.map_or_else(|| fs::canonicalize("."), fs::canonicalize);
+    match (dir, path.file_name()) {
+        (Ok(dir), Some(name)) => dir.join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// `path` relative to the directory `base`, both absolute
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let ups = base.components().count() - common;
+    let mut relative: PathBuf = std::iter::repeat_n(Component::ParentDir, ups).collect();
+    relative.extend(path.components().skip(common));
+    relative
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_link() {
+        let link = |path: &str, anchor: Option<&str>| MapLink {
+            path: PathBuf::from(path),
+            anchor: anchor.map(str::to_string),
+        };
+
+        assert_eq!(
+            parse_link("See @other-map.hmm for details"),
+            Some(link("other-map.hmm", None))
+        );
+        assert_eq!(
+            parse_link("other.hmm#Node Title"),
+            Some(link("other.hmm", Some("Node Title")))
+        );
+        assert_eq!(
+            parse_link("Plan: projects/plan.hmm#Q3 goals"),
+            Some(link("projects/plan.hmm", Some("Q3 goals")))
+        );
+        assert_eq!(parse_link("@notes.hmm#"), Some(link("notes.hmm", None)));
+        assert_eq!(parse_link("Just a node"), None);
+        assert_eq!(parse_link("user@example.hmm.com"), None);
+    }
+
+    #[test]
+    fn test_resolve_link() {
+        let dir = TempDir::new().unwrap();
+        let maps = dir.path().join("maps");
+        let workspace = dir.path().join("workspace");
+        fs::create_dir_all(&maps).unwrap();
+        fs::create_dir_all(&workspace).unwrap();
+        fs::write(maps.join("sibling.hmm"), "Sibling").unwrap();
+        fs::write(workspace.join("shared.hmm"), "Shared").unwrap();
+
+        let current = maps.join("current.hmm");
+        let resolve = |link: &str| resolve_link(Path::new(link), Some(&current), Some(&workspace));
+
+        assert_eq!(resolve("sibling.hmm"), maps.join("sibling.hmm"));
+        assert_eq!(resolve("shared.hmm"), workspace.join("shared.hmm"));
+        assert_eq!(resolve("missing.hmm"), maps.join("missing.hmm"));
+        assert_eq!(resolve("/abs/map.hmm"), PathBuf::from("/abs/map.hmm"));
+    }
+
+    #[test]
+    fn test_retarget_link() {
+        let dir = TempDir::new().unwrap();
+        let projects = dir.path().join("projects");
+        fs::create_dir_all(&projects).unwrap();
+        let map = dir.path().join("index.hmm");
+        let from = projects.join("plan.hmm");
+        let to = dir.path().join("archive").join("plan-2024.hmm");
+        fs::create_dir_all(to.parent().unwrap()).unwrap();
+        let file = LinkRename::File {
+            from: &from,
+            to: &to,
+        };
+
+        assert_eq!(
+            retarget_link("See projects/plan.hmm#Goals", &map, None, file).as_deref(),
+            Some("See archive/plan-2024.hmm#Goals")
+        );
+        let inside = projects.join("notes.hmm");
+        assert_eq!(
+            retarget_link("@plan.hmm", &inside, None, file).as_deref(),
+            Some("@../archive/plan-2024.hmm")
+        );
+        assert_eq!(retarget_link("other.hmm", &map, None, file), None);
+
+        let node = LinkRename::Node {
+            map: &from,
+            from: "Goals",
+            to: "Q3 goals",
+        };
+        assert_eq!(
+            retarget_link("projects/plan.hmm# Goals ", &map, None, node).as_deref(),
+            Some("projects/plan.hmm# Q3 goals ")
+        );
+        assert_eq!(
+            retarget_link("projects/plan.hmm#Budget", &map, None, node),
+            None
+        );
+    }
+
+    #[test]
+    fn test_update_links_reports_changed_lines() {
+        let dir = TempDir::new().unwrap();
+        let workspace = dir.path();
+        let index = workspace.join("index.hmm");
+        fs::write(
+            &index,
+            "Index\r\n\tplan.hmm#Goals\r\n\tplan.hmm#Budget\r\n\t\tplan.hmm#Goals\r\n",
+        )
+        .unwrap();
+        let plan = workspace.join("plan.hmm");
+        fs::write(&plan, "Plan\n\tGoals\n\tplan.hmm#Goals\n").unwrap();
+
+        let rename = LinkRename::Node {
+            map: &plan,
+            from: "Goals",
+            to: "Aims",
+        };
+        let updates = update_links(workspace, Some(&plan), rename).unwrap();
+        let lines: Vec<usize> = updates.iter().map(|update| update.line).collect();
+        assert_eq!(lines, [2, 4]);
+        assert!(updates.iter().all(|update| update.path == index));
+        assert_eq!(
+            fs::read_to_string(&index).unwrap(),
+            "Index\r\n\tplan.hmm#Aims\r\n\tplan.hmm#Budget\r\n\t\tplan.hmm#Aims\r\n"
+        );
+        assert_eq!(
+            fs::read_to_string(&plan).unwrap(),
+            "Plan\n\tGoals\n\tplan.hmm#Goals\n"
+        );
+    }
+}
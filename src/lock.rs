@@ -0,0 +1,115 @@
+//! Maps and nodes that cannot be edited.
+//!
+//! A whole map is read-only when it was opened with `--read-only`, its file
+//! is not writable or it holds unresolved git conflict markers, which editing
+//! in the map would only bury deeper. Single nodes are locked with a
+//! `[LOCKED] ` title prefix, which covers everything below them as well.
+//! Edits of such nodes are turned down before they happen instead of failing
+//! when the map is saved.
+
+use crate::model::{Node, NodeId};
+use indextree::Arena;
+use std::path::Path;
+
+/// Title prefix of locked nodes
+pub const LOCK_PREFIX: &str = "[LOCKED] ";
+
+/// Why a map cannot be edited
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReadOnly {
+    /// Opened with `--read-only`
+    Requested,
+    /// The file is not writable
+    NotWritable,
+    /// The file holds git conflict markers
+    Conflict,
+    /// Opened with `--pick` to choose a node
+    Picking,
+}
+
+impl ReadOnly {
+    /// Catalog key of the message explaining it
+    pub fn message_key(self) -> &'static str {
+        match self {
+            ReadOnly::Requested => "status.read_only_requested",
+            ReadOnly::NotWritable => "status.read_only_not_writable",
+            ReadOnly::Conflict => "status.read_only_conflict",
+            ReadOnly::Picking => "status.read_only_picking",
+        }
+    }
+}
+
+/// Why the map in `path` has to be opened read-only, if it does
+pub fn read_only_reason(path: &Path) -> Option<ReadOnly> {
+    let metadata = std::fs::metadata(path).ok()?;
+    if metadata.permissions().readonly() {
+        return Some(ReadOnly::NotWritable);
+    }
+    let text = std::fs::read(path).ok()?;
+    has_conflict_markers(&String::from_utf8_lossy(&text)).then_some(ReadOnly::Conflict)
+}
+
+/// Whether `text` holds the marker lines git leaves in a conflicted file
+pub fn has_conflict_markers(text: &str) -> bool {
+    let mut lines = text.lines();
+    lines.any(|line| line.starts_with("<<<<<<< ")) && lines.any(|line| line.starts_with(">>>>>>> "))
+}
+
+/// Whether `node_id` or a node above it is locked
+pub fn is_locked(tree: &Arena<Node>, node_id: NodeId) -> bool {
+    node_id
+        .ancestors(tree)
+        .any(|id| tree.get(id).is_some_and(|node| node.get().is_locked()))
+}
+
+/// Whether `node_id` or a node below it is locked
+pub fn subtree_has_lock(tree: &Arena<Node>, node_id: NodeId) -> bool {
+    is_locked(tree, node_id)
+        || node_id
+            .descendants(tree)
+            .any(|id| tree[id].get().is_locked())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_hmm_content;
+
+    #[test]
+    fn test_locks_cover_subtrees() {
+        let (tree, root) =
+            parse_hmm_content("Plan\n\t[LOCKED] Shared\n\t\tDetail\n\tOwn\n").unwrap();
+        let find = |title: &str| {
+            root.descendants(&tree)
+                .find(|&id| tree[id].get().title.ends_with(title))
+                .unwrap()
+        };
+
+        assert!(is_locked(&tree, find("Detail")));
+        assert!(is_locked(&tree, find("Shared")));
+        assert!(!is_locked(&tree, find("Own")));
+        assert!(!is_locked(&tree, root));
+        assert!(subtree_has_lock(&tree, root));
+        assert!(!subtree_has_lock(&tree, find("Own")));
+    }
+
+    #[test]
+    fn test_read_only_reason() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("map.hmm");
+        std::fs::write(&path, "Plan\n\tStep\n").unwrap();
+        assert_eq!(read_only_reason(&path), None);
+
+        std::fs::write(
+            &path,
+            "Plan\n<<<<<<< HEAD\n\tOurs\n=======\n\tTheirs\n>>>>>>> topic\n",
+        )
+        .unwrap();
+        assert_eq!(read_only_reason(&path), Some(ReadOnly::Conflict));
+
+        let mut permissions = std::fs::metadata(&path).unwrap().permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(&path, permissions).unwrap();
+        assert_eq!(read_only_reason(&path), Some(ReadOnly::NotWritable));
+    }
+}
@@ -1,98 +1,591 @@
-use hmm_rs::{actions, app, config, event, model, parser, ui};
+use hmm_rs::{
+    actions, app, check, config, event, export, formats, import, model, parser, tutorial, ui,
+};
 
-use anyhow::Result;
-use app::AppState;
+use anyhow::{anyhow, bail, Context, Result};
+use app::{AppState, Damage};
 use clap::Parser;
-use config::{load_config, CliArgs};
+use config::{load_config, CliArgs, Command};
 use crossterm::{
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{
+        disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle,
+    },
 };
+use hmm_rs::hooks::{self, HookEvent};
+use hmm_rs::i18n::Catalog;
+use hmm_rs::import::TableHierarchy;
+#[cfg(all(feature = "ipc", unix))]
+use hmm_rs::ipc::IpcServer;
+use hmm_rs::keymap::Keymap;
+use hmm_rs::lock::{self, ReadOnly};
+use hmm_rs::model::NodeId;
+use hmm_rs::parser::MapFormat;
+use hmm_rs::pins::PinStore;
+use hmm_rs::recent::RecentFiles;
+use hmm_rs::snapshots::SnapshotStore;
+#[cfg(feature = "sqlite")]
+use hmm_rs::sqlite;
+use hmm_rs::status::StatusFile;
+use hmm_rs::theme;
+use hmm_rs::window::{WindowState, RESTORE_TITLE, SAVE_TITLE};
+use indextree::Arena;
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::io;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::ExitCode;
 use std::time::{Duration, Instant};
 
-fn main() -> Result<()> {
+/// Longest stretch of queued input handled before the screen is redrawn
+const INPUT_BATCH_TIME: Duration = Duration::from_millis(50);
+
+/// Shortest time between frames while the map is dragged with the mouse
+const DRAG_FRAME_TIME: Duration = Duration::from_millis(16);
+
+fn main() -> ExitCode {
     // Parse command line arguments
     let args = CliArgs::parse();
 
+    match run(args) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {err:#}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(args: CliArgs) -> Result<ExitCode> {
+    // A missing config file means this is the first run
+    let config_path = config::config_path(&args);
+    let first_run = !config_path.exists();
+
     // Load configuration
     let config = load_config(&args)?;
 
     if args.debug_config {
         println!("Configuration:");
         println!("{:#?}", config);
-        return Ok(());
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    // Validate key remapping and colors up front so config mistakes are reported
+    let keymap = Keymap::new(&config.keys, &config.leader)?;
+    theme::check_theme(&config)?;
+
+    if let Some(format) = args.print_keys {
+        let catalog = config
+            .language
+            .as_deref()
+            .map(Catalog::new)
+            .unwrap_or_default();
+        print!("{}", keymap.cheat_sheet(format, &catalog));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Command::Export {
+        ref file,
+        format,
+        ref template,
+        ref branch,
+    }) = args.command
+    {
+        let (tree, root_id) = load_branch(&args, file, branch)
+            .with_context(|| format!("cannot open {}", file.display()))?;
+        let options = export::ExportOptions::from_config(&config);
+        let output = match template {
+            Some(name) => {
+                let template = config.export.template(name)?;
+                export::to_template(&tree, root_id, &template, &options)
+                    .with_context(|| format!("cannot render template '{name}'"))?
+            }
+            None => export::export(&tree, root_id, format, &options),
+        };
+        print!("{output}");
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if args.command == Some(Command::Formats) {
+        print!("{}", formats::list_formats(&config.export.templates));
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Command::Check {
+        ref files,
+        max_line_length,
+    }) = args.command
+    {
+        let mut problems = 0;
+        for file in files {
+            let content = fs::read_to_string(file)
+                .with_context(|| format!("cannot read {}", file.display()))?;
+            for issue in check::check_map(&content, max_line_length) {
+                println!("{}:{}: {}", file.display(), issue.line, issue.problem);
+                problems += 1;
+            }
+        }
+        if problems > 0 {
+            bail!("problems found: {problems}");
+        }
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(Command::Capture {
+        ref file,
+        ref title,
+        ref under,
+    }) = args.command
+    {
+        check_db_path(file)?;
+        let under: Vec<&str> = under.iter().map(String::as_str).collect();
+        sqlite::append_child(file, &under, title)
+            .with_context(|| format!("cannot add to {}", file.display()))?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if let Some(Command::Search {
+        ref file,
+        ref query,
+    }) = args.command
+    {
+        check_db_path(file)?;
+        let matches = sqlite::search(file, query)
+            .with_context(|| format!("cannot search {}", file.display()))?;
+        for path in &matches {
+            println!("{}", path.join(" > "));
+        }
+        // Like grep, finding nothing fails
+        return Ok(if matches.is_empty() {
+            ExitCode::FAILURE
+        } else {
+            ExitCode::SUCCESS
+        });
+    }
+
+    if let Some(Command::SyncTasks {
+        ref file,
+        ref tasks,
+    }) = args.command
+    {
+        let (mut tree, root_id) =
+            load_map(&args, file).with_context(|| format!("cannot open {}", file.display()))?;
+        let content = std::fs::read_to_string(tasks)
+            .with_context(|| format!("cannot read {}", tasks.display()))?;
+        let options = export::ExportOptions::from_config(&config);
+        let changed = import::apply_task_status(&mut tree, root_id, &content, &options)
+            .with_context(|| format!("cannot read tasks from {}", tasks.display()))?;
+        if changed > 0 {
+            parser::save_file(&mut tree, root_id, file)
+                .with_context(|| format!("cannot save {}", file.display()))?;
+        }
+        println!("{} tasks updated", changed);
+        return Ok(ExitCode::SUCCESS);
     }
 
     // Create application state
     let mut app = AppState::new(config);
+    app.config_path = Some(config_path.clone());
+    if let Some((first, second)) = theme::clashes(&app.config).first() {
+        let (first, second) = (first.name(), second.name());
+        app.notify_with(
+            "status.theme_clash",
+            &[("first", &first), ("second", &second)],
+        );
+    }
+    app.recent = RecentFiles::load(config::recent_files_path());
+    app.pin_store = PinStore::load(config::pins_path());
+    app.snapshot_store = SnapshotStore::new(config::snapshots_dir());
+
+    // Check the map before taking over the terminal, so a missing or
+    // unreadable file fails the command with a plain error and a non-zero
+    // exit code; parsing it waits for the loading frame
+    check_initial_map(&args)?;
+
+    let ipc = bind_ipc(&args)?;
+    let mut terminal = setup_terminal()?;
+    let res =
+        start_session(&mut terminal, &mut app, &args, first_run, &config_path).and_then(|()| {
+            run_app(
+                &mut terminal,
+                &mut app,
+                args.status_file.map(StatusFile::new),
+                ipc,
+            )
+        });
+
+    restore_terminal(&mut terminal)?;
+    res?;
+    if app.pick.is_some() {
+        // Like a cancelled selection in other pickers, quitting without
+        // picking fails, so that scripts can tell
+        return Ok(match app.picked {
+            Some(picked) => {
+                println!("{picked}");
+                ExitCode::SUCCESS
+            }
+            None => ExitCode::FAILURE,
+        });
+    }
+    if let Some(outline) = actions::exit_outline(&app) {
+        print!("{outline}");
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Stand-in for builds without the command socket
+#[cfg(not(all(feature = "ipc", unix)))]
+struct IpcServer;
+
+#[cfg(not(all(feature = "ipc", unix)))]
+impl IpcServer {
+    fn handle_pending(&self, _app: &mut AppState) {}
+}
+
+/// Listen on the socket asked for with --ipc-socket
+#[cfg(all(feature = "ipc", unix))]
+fn bind_ipc(args: &CliArgs) -> Result<Option<IpcServer>> {
+    args.ipc_socket.clone().map(IpcServer::bind).transpose()
+}
+
+#[cfg(all(feature = "ipc", not(unix)))]
+fn bind_ipc(args: &CliArgs) -> Result<Option<IpcServer>> {
+    if args.ipc_socket.is_some() {
+        bail!("--ipc-socket needs Unix domain sockets, which this system lacks");
+    }
+    Ok(None)
+}
+
+#[cfg(not(feature = "ipc"))]
+fn bind_ipc(_args: &CliArgs) -> Result<Option<IpcServer>> {
+    Ok(None)
+}
+
+/// Tell the terminal the title and directory of the map when they change
+fn update_window(out: &mut impl Write, window: &mut WindowState, app: &AppState) -> Result<()> {
+    if let Some(title) = window.new_title(app) {
+        execute!(out, SetTitle(title))?;
+    }
+    if let Some(report) = window.cwd_report(app) {
+        out.write_all(report.as_bytes())?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+/// The terminal the editor draws on
+type Tui = Terminal<CrosstermBackend<Box<dyn Write>>>;
+
+/// Where to draw: stdout, unless it is captured, as in
+/// `notes=$(hmm-rs --print-on-exit visible pick.hmm)`, which leaves stdout
+/// for the outline printed on exit
+fn terminal_output() -> Result<Box<dyn Write>> {
+    #[cfg(unix)]
+    if !io::IsTerminal::is_terminal(&io::stdout()) {
+        let tty = fs::OpenOptions::new()
+            .write(true)
+            .open("/dev/tty")
+            .context("cannot open the terminal")?;
+        return Ok(Box::new(tty));
+    }
+    Ok(Box::new(io::stdout()))
+}
+
+fn setup_terminal() -> Result<Tui> {
+    let mut out = terminal_output()?;
+    enable_raw_mode()?;
+    execute!(out, EnterAlternateScreen, EnableMouseCapture)?;
+    // Terminals without a title stack ignore this, keeping the editor's title
+    out.write_all(SAVE_TITLE.as_bytes())?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(out))?;
+    terminal.clear()?;
+    Ok(terminal)
+}
+
+fn restore_terminal(terminal: &mut Tui) -> Result<()> {
+    disable_raw_mode()?;
+    terminal.backend_mut().write_all(RESTORE_TITLE.as_bytes())?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        DisableMouseCapture
+    )?;
+    terminal.show_cursor()?;
+    Ok(())
+}
 
+/// Cheap checks on the map named on the command line, creating it for
+/// `--create`; everything that needs the parsed map is left to
+/// [`open_initial_map`]
+fn check_initial_map(args: &CliArgs) -> Result<()> {
+    let Some(ref filename) = args.filename else {
+        return Ok(());
+    };
+    if args.command == Some(Command::Journal) {
+        return Ok(());
+    }
+    if !filename.exists() {
+        if !args.create {
+            bail!(
+                "{} does not exist; pass --create to start a new map there",
+                filename.display()
+            );
+        }
+        create_map_file(filename)?;
+    }
+    // Reading one byte catches directories as well as missing permissions
+    fs::File::open(filename)
+        .and_then(|mut file| io::Read::read(&mut file, &mut [0; 1]))
+        .with_context(|| format!("cannot open {}", filename.display()))?;
+    if args.table_hierarchy() != TableHierarchy::Auto {
+        import::table_delimiter(filename)?;
+    }
+    let format = parser::detect_format(filename)
+        .with_context(|| format!("cannot open {}", filename.display()))?;
+    // Line numbers only mean something in the indented text of .hmm maps
+    if args.select_line.is_some() && format != MapFormat::Hmm {
+        bail!(
+            "--select-line needs a .hmm map, and {} is a {} map",
+            filename.display(),
+            format.name()
+        );
+    }
+    Ok(())
+}
+
+/// Load the map chosen on the command line and find the node to select
+fn open_initial_map(
+    args: &CliArgs,
+    app: &mut AppState,
+    first_run: bool,
+    config_path: &Path,
+) -> Result<Option<NodeId>> {
     // Load file if provided
-    if let Some(ref filename) = args.filename {
-        let (tree, root_id) = parser::load_file(filename)?;
+    if args.command == Some(Command::Journal) {
+        actions::open_journal_for(app, chrono::Local::now().date_naive())?;
+    } else if let Some(ref filename) = args.filename {
+        // check_initial_map has made sure the file exists
+        let (tree, root_id) = load_map(args, filename)
+            .with_context(|| format!("cannot open {}", filename.display()))?;
         app.tree = tree;
         app.root_id = Some(root_id);
         app.active_node_id = Some(root_id);
         app.filename = Some(filename.clone());
+        app.format = parser::detect_format(filename)?;
+        app.pick = args.pick;
+        app.read_only = if args.pick.is_some() {
+            Some(ReadOnly::Picking)
+        } else if args.read_only {
+            Some(ReadOnly::Requested)
+        } else {
+            lock::read_only_reason(filename)
+        };
+        if let Some(read_only) = app.read_only {
+            app.notify(read_only.message_key());
+        }
+        actions::fix_imported_case(app);
+        app.check_map_size();
+        app.load_pins();
+        // Failing to update the recent list should not keep the map from opening
+        let _ = app.recent.record(filename);
+    } else if args.scratch {
+        let (tree, root_id) = parser::create_empty_map()?;
+        app.tree = tree;
+        app.root_id = Some(root_id);
+        app.active_node_id = Some(root_id);
+        app.scratch = true;
+    } else if args.tutorial || first_run {
+        // Start newcomers on the tutorial instead of an empty map
+        let (tree, root_id) = tutorial::tutorial_map(&app.keymap, &app.catalog);
+        app.tree = tree;
+        app.root_id = Some(root_id);
+        app.active_node_id = Some(root_id);
     } else {
         // Create a new empty map
         let root = app
             .tree
-            .new_node(model::Node::new("New Mind Map".to_string()));
+            .new_node(model::Node::new(model::NEW_MAP_TITLE.to_string()));
         app.root_id = Some(root);
         app.active_node_id = Some(root);
+        // Offer workspace or recent maps; the empty map stays underneath if cancelled
+        if args.workspace.is_some() {
+            actions::open_workspace(app);
+        } else if !app.recent.is_empty() {
+            actions::open_recent(app);
+        }
     }
 
-    // Initialize the first history entry
-    app.push_history();
+    if first_run {
+        match config::write_default_config(config_path) {
+            Ok(()) => {
+                let path = config_path.display();
+                app.notify_with("status.config_created", &[("path", &path)]);
+            }
+            Err(e) => app.notify_with("status.config_create_failed", &[("error", &e)]),
+        }
+    }
 
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+    // Resolve a node requested with --select or --select-line
+    let selected_node = if let Some(ref title) = args.select {
+        let found = actions::find_node_by_title(app, title);
+        if found.is_none() {
+            app.notify_with("status.no_node_matching", &[("title", title)]);
+        }
+        found
+    } else if let (Some(line), Some(filename)) = (args.select_line, args.filename.as_ref()) {
+        let content = fs::read_to_string(filename)
+            .with_context(|| format!("cannot read {}", filename.display()))?;
+        let found = parser::node_at_line(&app.tree, app.root_id.unwrap(), &content, line);
+        if found.is_none() {
+            app.notify_with("status.no_node_on_line", &[("line", &line)]);
+        }
+        found
+    } else if args.command == Some(Command::Journal) {
+        app.active_node_id
+    } else {
+        None
+    };
 
-    // Clear the terminal
-    terminal.clear()?;
+    if let Some(ref other) = args.compare {
+        actions::compare_file(app, other)
+            .with_context(|| format!("cannot compare with {}", other.display()))?;
+    }
 
-    // Run the main loop
-    let res = run_app(&mut terminal, &mut app);
+    // Initialize the first history entry; opening a journal already did
+    if app.history.is_empty() {
+        app.push_history();
+    }
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
-        LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+    Ok(selected_node)
+}
 
-    // Handle any errors from the main loop
-    if let Err(err) = res {
-        eprintln!("Error: {}", err);
+/// Load `path`, importing tables with the columns chosen on the command line
+fn load_map(args: &CliArgs, path: &Path) -> Result<(Arena<model::Node>, NodeId)> {
+    match args.table_hierarchy() {
+        TableHierarchy::Auto => parser::load_file(path),
+        hierarchy => import::load_table(path, &hierarchy),
     }
+}
+
+/// Load the map at `path`, or only the branch reached by following `branch`
+/// titles down from its root
+fn load_branch(
+    args: &CliArgs,
+    path: &Path,
+    branch: &[String],
+) -> Result<(Arena<model::Node>, NodeId)> {
+    #[cfg(feature = "sqlite")]
+    if !branch.is_empty() && sqlite::is_db_path(path) {
+        let titles: Vec<&str> = branch.iter().map(String::as_str).collect();
+        return sqlite::load_branch(path, &titles);
+    }
+    let (tree, root_id) = load_map(args, path)?;
+    let branch_id = branch.iter().try_fold(root_id, |parent, title| {
+        parent
+            .children(&tree)
+            .find(|&child_id| tree[child_id].get().title == *title)
+            .ok_or_else(|| anyhow!("No node named '{}'", title))
+    })?;
+    Ok((tree, branch_id))
+}
 
+/// Make sure `path` is an existing `.hmmdb` map, rather than letting SQLite
+/// create an empty one
+#[cfg(feature = "sqlite")]
+fn check_db_path(path: &Path) -> Result<()> {
+    if !sqlite::is_db_path(path) {
+        bail!("{} is not a .{} map", path.display(), sqlite::DB_EXTENSION);
+    }
+    if !path.exists() {
+        bail!("{} does not exist", path.display());
+    }
     Ok(())
 }
 
-fn run_app<B: ratatui::backend::Backend>(
+/// Write a new empty map to `path` so `--create` fails early on an unwritable location
+fn create_map_file(path: &Path) -> Result<()> {
+    let (mut tree, root_id) = parser::create_empty_map()?;
+    parser::save_file(&mut tree, root_id, path)
+        .with_context(|| format!("cannot create {}", path.display()))
+}
+
+/// Show a loading frame, open the map and center the selected node using the
+/// real terminal size
+fn start_session(
+    terminal: &mut Tui,
+    app: &mut AppState,
+    args: &CliArgs,
+    first_run: bool,
+    config_path: &Path,
+) -> Result<()> {
+    // Show a skeleton frame first; parsing and laying out a large map can take a moment
+    let loading = args
+        .filename
+        .as_ref()
+        .map(|path| path.display().to_string());
+    terminal.draw(|frame| ui::render_loading(frame, &app.catalog, loading.as_deref()))?;
+    let selected_node = open_initial_map(args, app, first_run, config_path)?;
+    let size = terminal.size()?;
+    app.terminal_width = size.width;
+    app.terminal_height = size.height;
+
+    if let Some(node_id) = selected_node {
+        actions::reveal_and_select(app, node_id);
+    }
+    Ok(())
+}
+
+fn run_app<B: ratatui::backend::Backend + Write>(
     terminal: &mut Terminal<B>,
     app: &mut AppState,
+    mut status_file: Option<StatusFile>,
+    ipc: Option<IpcServer>,
 ) -> Result<()> {
+    let mut renderer = ui::Renderer::new();
+    let mut last_draw = Instant::now();
+    let mut window = WindowState::new();
     while app.running {
-        // Draw the UI
-        terminal.draw(|frame| ui::render(frame, app))?;
+        // Redraw only when something changed since the last frame. A mouse
+        // drag sends events faster than frames are worth drawing, so while
+        // one lasts the moves collect until the next frame is due.
+        let frame_due = app.drag_from.is_none() || last_draw.elapsed() >= DRAG_FRAME_TIME;
+        if frame_due && app.take_damage() != Damage::None {
+            terminal.draw(|frame| renderer.render(frame, app))?;
+            last_draw = Instant::now();
+        }
 
-        // Handle events
+        update_window(terminal.backend_mut(), &mut window, app)?;
+
+        // Status scripts only miss an update if the file cannot be written
+        if let Some(status_file) = &mut status_file {
+            status_file.update(app).ok();
+        }
+
+        if let Some(ipc) = &ipc {
+            ipc.handle_pending(app);
+        }
+
+        // Handle events. Keys that queued up while the last frame was drawn,
+        // such as a held key repeating, are all handled before the next
+        // redraw, so the layout is only recomputed once for the batch.
         if let Some(action) = event::handle_events(app)? {
             actions::execute_action(action, app)?;
+            let batch_start = Instant::now();
+            while app.running && batch_start.elapsed() < INPUT_BATCH_TIME && event::has_pending()? {
+                if let Some(action) = event::handle_events(app)? {
+                    actions::execute_action(action, app)?;
+                }
+            }
+        } else {
+            // Nothing to do, and the last change is already drawn
+            app.precompute();
         }
 
-        // Auto-save if enabled
-        if app.config.auto_save && app.filename.is_some() && app.is_dirty {
+        // Auto-save if enabled; read-only formats wait for an explicit Save As
+        if app.auto_saves() && app.is_dirty {
             let should_save = if let Some(last_modify) = app.last_modify_time {
                 // Check if enough time has passed since last modification
                 let elapsed = Instant::now().duration_since(last_modify);
@@ -103,12 +596,16 @@ fn run_app<B: ratatui::backend::Backend>(
 
             if should_save {
                 if let Err(e) = actions::save(app) {
-                    app.set_message(format!("Auto-save failed: {}", e));
+                    app.notify_with("status.auto_save_failed", &[("error", &e)]);
+                    let error = format!("{e:#}");
+                    hooks::run_hook(app, HookEvent::AutoSaveFailed, Some(&error));
                 } else {
                     app.last_save_time = Some(Instant::now());
                 }
             }
         }
+
+        actions::take_snapshot_if_due(app);
     }
 
     Ok(())
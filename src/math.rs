@@ -0,0 +1,378 @@
+//! Inline `$...$` math in node titles.
+//!
+//! Math spans are kept on one line when titles wrap, and with
+//! `render_math` they are shown with a unicode approximation of their
+//! LaTeX: `$\alpha^2 \leq \frac{1}{n}$` reads `α²≤1/n`. Titles themselves
+//! keep the LaTeX, so editing and exports see the raw text.
+
+use crate::code::code_block;
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// Byte ranges of the `$...$` spans in `text`, delimiters included.
+///
+/// Like pandoc, a span opens at a `$` followed by a non-space and closes at
+/// a `$` after a non-space that is not followed by a digit, so prices such as
+/// "$5 or $10" are left alone. `\$` is a literal dollar sign.
+pub fn math_spans(text: &str) -> Vec<Range<usize>> {
+    let bytes = text.as_bytes();
+    let is_dollar = |i: usize| bytes[i] == b'$' && (i == 0 || bytes[i - 1] != b'\\');
+
+    let mut spans = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        if !is_dollar(i) || bytes.get(i + 1).is_none_or(|b| b.is_ascii_whitespace()) {
+            i += 1;
+            continue;
+        }
+        let close = (i + 2..bytes.len()).find(|&j| {
+            is_dollar(j)
+                && !bytes[j - 1].is_ascii_whitespace()
+                && !bytes.get(j + 1).is_some_and(u8::is_ascii_digit)
+        });
+        match close {
+            Some(end) => {
+                spans.push(i..end + 1);
+                i = end + 1;
+            }
+            None => break,
+        }
+    }
+    spans
+}
+
+/// `text` with its math spans replaced by their unicode approximation; code
+/// is left as it is
+pub fn render_math(text: &str) -> Cow<'_, str> {
+    let spans = math_spans(text);
+    if spans.is_empty() || code_block(text).is_some() {
+        return Cow::Borrowed(text);
+    }
+
+    let mut rendered = String::with_capacity(text.len());
+    let mut last = 0;
+    for span in spans {
+        rendered.push_str(&text[last..span.start]);
+        rendered.push_str(&render_latex(&text[span.start + 1..span.end - 1]));
+        last = span.end;
+    }
+    rendered.push_str(&text[last..]);
+    Cow::Owned(rendered)
+}
+
+/// Unicode approximation of a LaTeX math expression. Spaces are dropped, as
+/// TeX drops them, which also keeps the result on one line when wrapped.
+fn render_latex(latex: &str) -> String {
+    let mut parser = Parser {
+        chars: latex.chars().collect(),
+        pos: 0,
+    };
+    parser.expression(None)
+}
+
+/// Commands written as their name, such as `\sin`
+const FUNCTIONS: &[&str] = &[
+    "log", "ln", "exp", "sin", "cos", "tan", "lim", "max", "min", "det",
+];
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    /// Render up to `until` or the end of the input
+    fn expression(&mut self, until: Option<char>) -> String {
+        let mut out = String::new();
+        while let Some(&c) = self.chars.get(self.pos) {
+            if Some(c) == until {
+                self.pos += 1;
+                break;
+            }
+            self.pos += 1;
+            match c {
+                c if c.is_whitespace() => {}
+                '{' => out.push_str(&self.expression(Some('}'))),
+                '\\' => out.push_str(&self.command()),
+                '^' => {
+                    let arg = self.argument();
+                    out.push_str(&script(&arg, superscript, '^'));
+                }
+                '_' => {
+                    let arg = self.argument();
+                    out.push_str(&script(&arg, subscript, '_'));
+                }
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    /// A `{...}` group, a command or a single character
+    fn argument(&mut self) -> String {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+        let Some(&c) = self.chars.get(self.pos) else {
+            return String::new();
+        };
+        self.pos += 1;
+        match c {
+            '{' => self.expression(Some('}')),
+            '\\' => self.command(),
+            c => c.to_string(),
+        }
+    }
+
+    /// The command after a backslash
+    fn command(&mut self) -> String {
+        let start = self.pos;
+        while self
+            .chars
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_alphabetic())
+        {
+            self.pos += 1;
+        }
+        if self.pos == start {
+            // An escaped character such as `\{` or `\$`, or `\,` spacing
+            let Some(&c) = self.chars.get(self.pos) else {
+                return "\\".to_string();
+            };
+            self.pos += 1;
+            return if matches!(c, ',' | ';' | ':' | '!' | ' ') {
+                String::new()
+            } else {
+                c.to_string()
+            };
+        }
+
+        let name: String = self.chars[start..self.pos].iter().collect();
+        match name.as_str() {
+            "frac" => {
+                let numerator = self.argument();
+                let denominator = self.argument();
+                format!("{}/{}", grouped(&numerator), grouped(&denominator))
+            }
+            "sqrt" => format!("√{}", grouped(&self.argument())),
+            "mathrm" | "mathbf" | "mathit" | "text" | "operatorname" => self.argument(),
+            "mathbb" => {
+                let arg = self.argument();
+                arg.chars().map(double_struck).collect()
+            }
+            "left" | "right" | "quad" | "qquad" => String::new(),
+            name if FUNCTIONS.contains(&name) => name.to_string(),
+            name => match symbol(name) {
+                Some(symbol) => symbol.to_string(),
+                None => format!("\\{}", name),
+            },
+        }
+    }
+}
+
+/// `text`, parenthesized if it is longer than one character
+fn grouped(text: &str) -> String {
+    if text.chars().count() > 1 {
+        format!("({})", text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// `text` raised or lowered with `map`, or written out after `marker` if some
+/// character has no unicode form
+fn script(text: &str, map: fn(char) -> Option<char>, marker: char) -> String {
+    match text.chars().map(map).collect::<Option<String>>() {
+        Some(script) if !script.is_empty() => script,
+        _ => format!("{}{}", marker, grouped(text)),
+    }
+}
+
+fn superscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'i' => 'ⁱ',
+        'n' => 'ⁿ',
+        'T' => 'ᵀ',
+        '′' | '\'' => '′',
+        _ => return None,
+    })
+}
+
+fn subscript(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        _ => return None,
+    })
+}
+
+fn double_struck(c: char) -> char {
+    match c {
+        'C' => 'ℂ',
+        'N' => 'ℕ',
+        'P' => 'ℙ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'Z' => 'ℤ',
+        c => c,
+    }
+}
+
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" | "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" | "vartheta" => "θ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "rho" => "ρ",
+        "sigma" => "σ",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" | "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "sum" => "∑",
+        "prod" => "∏",
+        "int" => "∫",
+        "oint" => "∮",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "infty" => "∞",
+        "pm" => "±",
+        "mp" => "∓",
+        "times" => "×",
+        "div" => "÷",
+        "cdot" => "·",
+        "circ" => "∘",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "propto" => "∝",
+        "in" => "∈",
+        "notin" => "∉",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "cup" => "∪",
+        "cap" => "∩",
+        "emptyset" | "varnothing" => "∅",
+        "forall" => "∀",
+        "exists" => "∃",
+        "neg" | "lnot" => "¬",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" | "implies" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "mapsto" => "↦",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "degree" => "°",
+        "prime" => "′",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_math_spans_skip_prices() {
+        assert_eq!(math_spans("area $\\pi r^2$ here"), vec![5..14]);
+        assert_eq!(math_spans("costs $5 or $10"), Vec::<Range<usize>>::new());
+        assert_eq!(math_spans("a $x$ and $y$"), vec![2..5, 10..13]);
+        assert_eq!(math_spans("escaped \\$x$ only"), Vec::<Range<usize>>::new());
+        assert_eq!(math_spans("open $x"), Vec::<Range<usize>>::new());
+    }
+
+    #[test]
+    fn test_render_math() {
+        assert_eq!(render_math("no math"), "no math");
+        assert!(matches!(render_math("no math"), Cow::Borrowed(_)));
+        assert_eq!(render_math("Energy $E = mc^2$"), "Energy E=mc²");
+        assert_eq!(render_math("$\\alpha^2 \\leq \\frac{1}{n}$"), "α²≤1/n");
+        assert_eq!(render_math("$x_{i+1}$ and $a_{big}$"), "xᵢ₊₁ and a_(big)");
+        assert_eq!(render_math("$\\sqrt{x+1}$"), "√(x+1)");
+        assert_eq!(render_math("$\\mathbb{R}^n$"), "ℝⁿ");
+        assert_eq!(render_math("$\\sum_{k=0}^{n} k$"), "∑ₖ₌₀ⁿk");
+        assert_eq!(render_math("$\\unknown{x}$"), "\\unknownx");
+        assert_eq!(render_math("`echo $a$`"), "`echo $a$`");
+    }
+}
@@ -1,12 +1,45 @@
 use indextree::NodeId as TreeNodeId;
+use serde::{Deserialize, Serialize};
 
 pub type NodeId = TreeNodeId;
 
-#[derive(Debug, Clone)]
+/// Title of the root of a freshly created map
+pub const NEW_MAP_TITLE: &str = "New Mind Map";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
     pub title: String,
     pub is_collapsed: bool,
     pub is_hidden: bool,
+    /// Where the node came from in the `.hmm` file it was loaded from
+    pub source: Option<Box<SourceLine>>,
+    /// The node's row in the `.hmmdb` database it was loaded from
+    pub row: Option<DbRow>,
+}
+
+/// A node's row in a `.hmmdb` database, so saving updates that row instead of
+/// writing a new one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DbRow {
+    pub id: i64,
+    /// Highest row id in the database when the node was loaded; rows above it
+    /// were added by other writers since
+    pub loaded_up_to: i64,
+}
+
+/// A node's line as it was read, so saving an unchanged node writes the same
+/// bytes back
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SourceLine {
+    /// Blank lines above the node's line
+    pub before: String,
+    /// The line itself with its line ending, if it had one; empty for a root
+    /// the parser made up to hold several top-level lines
+    pub text: String,
+    /// Depth of the line in the file's outline
+    pub depth: usize,
+    /// Blank lines below the last line of the file; only kept on the root
+    pub after: String,
 }
 
 impl Node {
@@ -15,12 +48,30 @@ impl Node {
             title,
             is_collapsed: false,
             is_hidden: false,
+            source: None,
+            row: None,
         }
     }
 
     pub fn is_hidden(&self) -> bool {
         self.is_hidden || self.title.starts_with("[HIDDEN] ")
     }
+
+    /// Whether the node is locked against edits, along with everything below it
+    pub fn is_locked(&self) -> bool {
+        self.title.starts_with(crate::lock::LOCK_PREFIX)
+    }
+
+    /// The kind tagged in the title, if any
+    pub fn kind(&self) -> Option<crate::kind::NodeKind> {
+        crate::kind::node_kind(&self.title)
+    }
+
+    /// Whether the node is an editorial comment, marked by `prefix` at the
+    /// start of its title
+    pub fn is_comment(&self, prefix: &str) -> bool {
+        !prefix.is_empty() && self.title.starts_with(prefix)
+    }
 }
 
 #[cfg(test)]
@@ -57,6 +108,14 @@ mod tests {
         assert!(node.is_hidden());
     }
 
+    #[test]
+    fn test_is_comment() {
+        let node = Node::new("// ask Sam".to_string());
+        assert!(node.is_comment("//"));
+        assert!(!node.is_comment(""));
+        assert!(!Node::new("http://x".to_string()).is_comment("//"));
+    }
+
     #[test]
     fn test_node_with_unicode_title() {
         let node = Node::new("✓ Task Complete 🎯".to_string());
@@ -1,50 +1,245 @@
-use crate::model::{Node, NodeId};
-use anyhow::Result;
+use crate::import::{self, TableHierarchy};
+use crate::model::{Node, NodeId, SourceLine, NEW_MAP_TITLE};
+use anyhow::{bail, Context, Result};
 use indextree::Arena;
 use std::fs;
 use std::path::Path;
 
-pub fn load_file(path: &Path) -> Result<(Arena<Node>, NodeId)> {
-    let content = fs::read_to_string(path)?;
-    parse_hmm_content(&content)
+/// File formats a map can be opened from, and for most of them saved back to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MapFormat {
+    /// Tab-indented outline, the native format
+    #[default]
+    Hmm,
+    /// SQLite database, readable with the `sqlite` feature
+    Sqlite,
+    /// Workflowy or Dynalist JSON export
+    Outliner,
+    Csv,
+    Tsv,
+    XMind,
+    MindManager,
 }
 
-pub fn parse_hmm_content(content: &str) -> Result<(Arena<Node>, NodeId)> {
-    let lines: Vec<&str> = content.lines().collect();
+/// Reads the map in a file; the title is for a root the file does not name
+type Reader = fn(&Path, &str) -> Result<(Arena<Node>, NodeId)>;
 
-    if lines.is_empty() {
-        return create_empty_map();
-    }
+/// Writes a map to a file, recording in the nodes where they were stored
+type Writer = fn(&mut Arena<Node>, NodeId, &Path) -> Result<()>;
 
-    // Calculate minimum indentation and clean up lines
-    let mut min_indent = usize::MAX;
-    let mut cleaned_lines = Vec::new();
+/// Registry entry for a format
+struct FormatInfo {
+    name: &'static str,
+    /// Lowercase extensions; the first is used when saving under a new name
+    extensions: &'static [&'static str],
+    read: Reader,
+    /// `None` for formats maps cannot be saved in
+    write: Option<Writer>,
+}
 
-    for line in lines {
-        if line.trim().is_empty() {
-            continue;
+/// Start of every SQLite database file
+const SQLITE_SIGNATURE: &[u8] = b"SQLite format 3\0";
+
+/// Start of a zip archive, which XMind and MindManager files are
+const ZIP_SIGNATURE: &[u8] = b"PK\x03\x04";
+
+impl MapFormat {
+    /// Every format, in the order the save format prompt offers them
+    pub const ALL: [MapFormat; 7] = [
+        MapFormat::Hmm,
+        MapFormat::Sqlite,
+        MapFormat::Outliner,
+        MapFormat::Csv,
+        MapFormat::Tsv,
+        MapFormat::XMind,
+        MapFormat::MindManager,
+    ];
+
+    fn info(self) -> FormatInfo {
+        match self {
+            MapFormat::Hmm => FormatInfo {
+                name: "hmm",
+                extensions: &["hmm"],
+                read: |path, _| parse_hmm_with_source(&fs::read_to_string(path)?),
+                write: Some(|tree, root_id, path| Ok(fs::write(path, map_to_hmm(tree, root_id))?)),
+            },
+            MapFormat::Sqlite => FormatInfo {
+                name: "SQLite",
+                extensions: &["hmmdb"],
+                read: |path, _| load_db(path),
+                write: cfg!(feature = "sqlite").then_some(save_db as Writer),
+            },
+            MapFormat::Outliner => FormatInfo {
+                name: "Workflowy JSON",
+                extensions: &["json"],
+                read: |path, title| {
+                    import::parse_outliner_json(&fs::read_to_string(path)?, title).with_context(
+                        || format!("{} is not a Workflowy or Dynalist export", path.display()),
+                    )
+                },
+                write: Some(|tree, root_id, path| {
+                    Ok(fs::write(path, import::to_outliner_json(tree, root_id)?)?)
+                }),
+            },
+            MapFormat::Csv => FormatInfo {
+                name: "CSV",
+                extensions: &["csv"],
+                read: |path, _| import::load_table(path, &TableHierarchy::Auto),
+                write: Some(|tree, root_id, path| {
+                    Ok(fs::write(path, import::to_table(tree, root_id, b',')?)?)
+                }),
+            },
+            MapFormat::Tsv => FormatInfo {
+                name: "TSV",
+                extensions: &["tsv", "tab"],
+                read: |path, _| import::load_table(path, &TableHierarchy::Auto),
+                write: Some(|tree, root_id, path| {
+                    Ok(fs::write(path, import::to_table(tree, root_id, b'\t')?)?)
+                }),
+            },
+            MapFormat::XMind => FormatInfo {
+                name: "XMind",
+                extensions: &["xmind"],
+                read: |path, title| {
+                    import::parse_xmind(&fs::read(path)?, title)
+                        .with_context(|| format!("cannot import {}", path.display()))
+                },
+                write: None,
+            },
+            MapFormat::MindManager => FormatInfo {
+                name: "MindManager",
+                extensions: &["mmap"],
+                read: |path, title| {
+                    import::parse_mindmanager(&fs::read(path)?, title)
+                        .with_context(|| format!("cannot import {}", path.display()))
+                },
+                write: None,
+            },
         }
+    }
+
+    /// Name shown in the status line and the save format prompt
+    pub fn name(self) -> &'static str {
+        self.info().name
+    }
 
-        let mut clean_line = line.to_string();
+    /// Extension given to files saved in this format
+    pub fn extension(self) -> &'static str {
+        self.info().extensions[0]
+    }
 
-        // Replace bullet points with spaces
-        clean_line = clean_line.replace("•", "*");
-        clean_line = clean_line.replace('\t', "  ");
+    /// Every extension files in this format are recognized by
+    pub fn extensions(self) -> &'static [&'static str] {
+        self.info().extensions
+    }
+
+    /// Whether maps can be saved in this format
+    pub fn is_writable(self) -> bool {
+        self.info().write.is_some()
+    }
 
-        // Calculate indentation
-        let indent = clean_line.len() - clean_line.trim_start().len();
-        let trimmed = clean_line.trim_start();
+    /// Formats maps can be saved in
+    pub fn writable() -> impl Iterator<Item = MapFormat> {
+        Self::ALL.into_iter().filter(|format| format.is_writable())
+    }
 
-        // Handle list markers (* or -)
-        if trimmed.starts_with("* ") || trimmed.starts_with("- ") {
-            clean_line = format!("{}{}", " ".repeat(indent + 2), &trimmed[2..]);
+    /// Field delimiter of the table formats
+    pub fn delimiter(self) -> Option<u8> {
+        match self {
+            MapFormat::Csv => Some(b','),
+            MapFormat::Tsv => Some(b'\t'),
+            _ => None,
         }
+    }
+
+    /// The format registered for the extension of `path`
+    pub fn from_path(path: &Path) -> Option<MapFormat> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|format| format.info().extensions.contains(&extension.as_str()))
+    }
+
+    /// Guess the format from the start of a file, defaulting to `.hmm` text
+    pub fn from_contents(bytes: &[u8]) -> MapFormat {
+        if bytes.starts_with(SQLITE_SIGNATURE) {
+            MapFormat::Sqlite
+        } else if bytes.starts_with(ZIP_SIGNATURE) {
+            if import::is_mindmanager_archive(bytes) {
+                MapFormat::MindManager
+            } else {
+                MapFormat::XMind
+            }
+        } else if matches!(bytes.trim_ascii_start().first(), Some(b'{' | b'[')) {
+            MapFormat::Outliner
+        } else {
+            MapFormat::Hmm
+        }
+    }
+}
+
+/// Format of the file at `path`, by extension or else by its contents
+pub fn detect_format(path: &Path) -> Result<MapFormat> {
+    match MapFormat::from_path(path) {
+        Some(format) => Ok(format),
+        None => Ok(MapFormat::from_contents(&fs::read(path)?)),
+    }
+}
+
+/// Load a map, detecting its format
+pub fn load_file(path: &Path) -> Result<(Arena<Node>, NodeId)> {
+    load_file_as(path, detect_format(path)?)
+}
+
+/// Load a map stored in `format`
+pub fn load_file_as(path: &Path, format: MapFormat) -> Result<(Arena<Node>, NodeId)> {
+    (format.info().read)(path, &import::file_title(path))
+}
+
+#[cfg(feature = "sqlite")]
+fn load_db(path: &Path) -> Result<(Arena<Node>, NodeId)> {
+    crate::sqlite::load_db(path)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn load_db(path: &Path) -> Result<(Arena<Node>, NodeId)> {
+    bail!(
+        "{} is a SQLite map; this build has no SQLite support",
+        path.display()
+    )
+}
+
+/// Title of the root the parser adds above several top-level lines
+const SYNTHETIC_ROOT_TITLE: &str = "root";
+
+pub fn parse_hmm_content(content: &str) -> Result<(Arena<Node>, NodeId)> {
+    parse_hmm(content, false)
+}
 
-        if !clean_line.trim().is_empty() {
-            let actual_indent = clean_line.len() - clean_line.trim_start().len();
-            min_indent = min_indent.min(actual_indent);
-            cleaned_lines.push(clean_line);
+/// Parse `.hmm` text, remembering each node's original line so that
+/// [`map_to_hmm`] can write unchanged parts of the file back byte for byte
+pub fn parse_hmm_with_source(content: &str) -> Result<(Arena<Node>, NodeId)> {
+    parse_hmm(content, true)
+}
+
+fn parse_hmm(content: &str, keep_source: bool) -> Result<(Arena<Node>, NodeId)> {
+    // Calculate minimum indentation and clean up lines, keeping each line as
+    // read along with the blank lines above it
+    let mut min_indent = usize::MAX;
+    let mut cleaned_lines = Vec::new();
+    let mut blank_lines = String::new();
+
+    for raw in content.split_inclusive('\n') {
+        let line = strip_line_ending(raw);
+        let clean_line = clean_line(line);
+
+        if clean_line.trim().is_empty() {
+            blank_lines.push_str(raw);
+            continue;
         }
+
+        min_indent = min_indent.min(indent_width(&clean_line));
+        cleaned_lines.push((clean_line, raw, std::mem::take(&mut blank_lines)));
     }
 
     if cleaned_lines.is_empty() {
@@ -55,28 +250,33 @@ pub fn parse_hmm_content(content: &str) -> Result<(Arena<Node>, NodeId)> {
     let mut tree = Arena::new();
 
     // Create a synthetic root node
-    let root_node = tree.new_node(Node::new("root".to_string()));
+    let root_node = tree.new_node(Node::new(SYNTHETIC_ROOT_TITLE.to_string()));
 
     let mut level_stack: Vec<(NodeId, usize)> = vec![(root_node, 0)];
     let mut first_level_nodes = Vec::new();
 
-    for line in cleaned_lines {
-        let indent = line.len() - line.trim_start().len() - min_indent;
+    for (line, raw, before) in cleaned_lines {
+        let indent = indent_width(&line) - min_indent;
         let title = line.trim().to_string();
 
-        if title.is_empty() {
-            continue;
-        }
-
         // Find the appropriate parent based on indentation
         while level_stack.len() > 1 && level_stack.last().unwrap().1 >= indent {
             level_stack.pop();
         }
 
         let parent_id = level_stack.last().unwrap().0;
-        let new_node = tree.new_node(Node::new(title));
-
-        parent_id.append(new_node, &mut tree);
+        // `append_value` skips the ancestor walk `append` does to rule out
+        // cycles, which would make deeply nested files quadratic to load
+        let new_node = parent_id.append_value(Node::new(title), &mut tree);
+
+        if keep_source {
+            tree[new_node].get_mut().source = Some(Box::new(SourceLine {
+                before,
+                text: raw.to_string(),
+                depth: level_stack.len() - 1,
+                after: String::new(),
+            }));
+        }
 
         // Track first-level nodes
         if parent_id == root_node {
@@ -95,19 +295,115 @@ pub fn parse_hmm_content(content: &str) -> Result<(Arena<Node>, NodeId)> {
         root_node
     };
 
+    if keep_source {
+        let source = tree[final_root].get_mut().source.get_or_insert_default();
+        source.after = blank_lines;
+    }
+
     Ok((tree, final_root))
 }
 
-fn create_empty_map() -> Result<(Arena<Node>, NodeId)> {
+/// A line as the parser sees it: tabs count as two spaces and list markers
+/// as indentation
+pub(crate) fn clean_line(line: &str) -> String {
+    // Replace bullet points with spaces
+    let mut clean_line = line.replace("•", "*");
+    clean_line = clean_line.replace('\t', "  ");
+
+    // Handle list markers (* or -)
+    let indent = indent_width(&clean_line);
+    let trimmed = clean_line.trim_start();
+    if trimmed.starts_with("* ") || trimmed.starts_with("- ") {
+        clean_line = format!("{}{}", " ".repeat(indent + 2), &trimmed[2..]);
+    }
+
+    clean_line
+}
+
+pub(crate) fn indent_width(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+pub(crate) fn strip_line_ending(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Find the node parsed from a 1-based line of `content`, where `tree` and
+/// `root_id` came from parsing that content. Blank lines, bare list markers
+/// included, resolve to the nearest node above them.
+pub fn node_at_line(
+    tree: &Arena<Node>,
+    root_id: NodeId,
+    content: &str,
+    line: usize,
+) -> Option<NodeId> {
+    if line > content.lines().count() {
+        return None;
+    }
+
+    let preceding_nodes = content
+        .lines()
+        .take(line)
+        .filter(|l| !clean_line(l).trim().is_empty())
+        .count();
+
+    // Nodes are created in line order under the synthetic root, which is
+    // either the returned root itself or its parent
+    let synthetic_root = root_id.ancestors(tree).last()?;
+    synthetic_root
+        .descendants(tree)
+        .skip(1)
+        .nth(preceding_nodes.checked_sub(1)?)
+}
+
+/// A map holding only an untitled root node
+pub fn create_empty_map() -> Result<(Arena<Node>, NodeId)> {
     let mut tree = Arena::new();
-    let root = tree.new_node(Node::new("New Mind Map".to_string()));
+    let root = tree.new_node(Node::new(NEW_MAP_TITLE.to_string()));
     Ok((tree, root))
 }
 
-pub fn save_file(tree: &Arena<Node>, root_id: NodeId, path: &Path) -> Result<()> {
-    let content = map_to_list(tree, root_id, false, 0);
-    fs::write(path, content)?;
-    Ok(())
+/// Save a map in the format registered for the extension of `path`, `.hmm` if none
+pub fn save_file(tree: &mut Arena<Node>, root_id: NodeId, path: &Path) -> Result<()> {
+    save_file_as(
+        tree,
+        root_id,
+        path,
+        MapFormat::from_path(path).unwrap_or_default(),
+    )
+}
+
+/// Save a map in `format`, whatever the extension of `path`. SQLite maps
+/// record the rows of new nodes in them, so that the next save updates those
+/// rows instead of writing them again
+pub fn save_file_as(
+    tree: &mut Arena<Node>,
+    root_id: NodeId,
+    path: &Path,
+    format: MapFormat,
+) -> Result<()> {
+    match format.info().write {
+        Some(write) => write(tree, root_id, path),
+        // Never overwrite a foreign file with something it cannot read
+        None => bail!(
+            "{} files cannot be written; use Save As to save the map in another format",
+            format.name()
+        ),
+    }
+}
+
+#[cfg(feature = "sqlite")]
+fn save_db(tree: &mut Arena<Node>, root_id: NodeId, path: &Path) -> Result<()> {
+    crate::sqlite::save_db(tree, root_id, path)
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn save_db(_tree: &mut Arena<Node>, _root_id: NodeId, path: &Path) -> Result<()> {
+    bail!(
+        "{} is a SQLite map; this build has no SQLite support",
+        path.display()
+    )
 }
 
 pub fn map_to_list(
@@ -118,26 +414,208 @@ pub fn map_to_list(
 ) -> String {
     let mut result = String::new();
 
-    if !exclude_parent {
+    let mut stack = if exclude_parent {
+        node_id
+            .children(tree)
+            .rev()
+            .map(|child| (child, base_indent))
+            .collect()
+    } else {
+        vec![(node_id, base_indent)]
+    };
+
+    while let Some((node_id, indent)) = stack.pop() {
         let node = tree.get(node_id).unwrap().get();
-        result.push_str(&"\t".repeat(base_indent));
+        result.push_str(&"\t".repeat(indent));
         result.push_str(&node.title);
         result.push('\n');
-    }
 
-    for child_id in node_id.children(tree) {
-        let child_content = map_to_list(
-            tree,
-            child_id,
-            false,
-            base_indent + 1 - (exclude_parent as usize),
+        stack.extend(
+            node_id
+                .children(tree)
+                .rev()
+                .map(|child| (child, indent + 1)),
         );
-        result.push_str(&child_content);
     }
 
     result
 }
 
+/// The map as `.hmm` text for saving. Nodes still titled and nested as when
+/// the file was read get their original line back, blank lines included;
+/// others get a new line indented like their neighbours.
+pub fn map_to_hmm(tree: &Arena<Node>, root_id: NodeId) -> String {
+    let root = tree[root_id].get();
+    let root_source = root.source.as_deref();
+
+    // A made-up root stays out of the file unless it was renamed
+    let skip_root = root_source.is_some_and(|source| source.text.is_empty())
+        && root.title == SYNTHETIC_ROOT_TITLE;
+    let mut stack: Vec<(NodeId, usize)> = if skip_root {
+        root_id
+            .children(tree)
+            .rev()
+            .map(|child| (child, 0))
+            .collect()
+    } else {
+        vec![(root_id, 0)]
+    };
+
+    let mut writer = HmmWriter::new(tree, root_id);
+    while let Some((node_id, depth)) = stack.pop() {
+        writer.write(tree[node_id].get(), depth);
+        stack.extend(node_id.children(tree).rev().map(|child| (child, depth + 1)));
+    }
+    if let Some(source) = root_source {
+        writer.push(&source.after);
+    }
+
+    writer.output
+}
+
+/// A line written by [`HmmWriter`] that later lines are nested against
+struct WrittenLine {
+    /// Indentation as the parser measures it
+    width: usize,
+    /// The whitespace the line starts with
+    indent: String,
+}
+
+struct HmmWriter {
+    output: String,
+    /// Last line written at each depth down to the current node
+    lines: Vec<WrittenLine>,
+    /// Whether the output ends in a line without its line ending
+    open_line: bool,
+    line_ending: &'static str,
+    indent_unit: String,
+}
+
+impl HmmWriter {
+    /// A writer matching the line endings and indentation of the file the
+    /// map was read from, or using `\n` and tabs
+    fn new(tree: &Arena<Node>, root_id: NodeId) -> Self {
+        let sources = || {
+            root_id
+                .descendants(tree)
+                .filter_map(|id| Some((id, tree[id].get().source.as_deref()?)))
+        };
+
+        let line_ending = sources()
+            .find(|(_, source)| source.text.ends_with('\n'))
+            .map_or("\n", |(_, source)| {
+                if source.text.ends_with("\r\n") {
+                    "\r\n"
+                } else {
+                    "\n"
+                }
+            });
+
+        // The whitespace a child line adds to its parent's
+        let indent_unit = sources()
+            .find_map(|(id, source)| {
+                let parent = tree[tree[id].parent()?].get().source.as_deref()?;
+                leading_whitespace(&source.text)
+                    .strip_prefix(leading_whitespace(&parent.text))
+                    .filter(|unit| !unit.is_empty() && !parent.text.is_empty())
+                    .map(str::to_string)
+            })
+            .unwrap_or_else(|| "\t".to_string());
+
+        Self {
+            output: String::new(),
+            lines: Vec::new(),
+            open_line: false,
+            line_ending,
+            indent_unit,
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if self.open_line {
+            self.output.push_str(self.line_ending);
+        }
+        self.output.push_str(text);
+        self.open_line = !text.ends_with('\n');
+    }
+
+    fn write(&mut self, node: &Node, depth: usize) {
+        let source = node.source.as_deref();
+        if let Some(source) = source {
+            self.push(&source.before);
+        }
+
+        // Reuse the original line only if it still reads back as this node
+        let original = source.filter(|source| {
+            source.depth == depth
+                && clean_line(strip_line_ending(&source.text)).trim() == node.title
+        });
+        let (line, ending) = match original {
+            Some(source) if self.fits(strip_line_ending(&source.text), depth) => {
+                let line = strip_line_ending(&source.text);
+                (line.to_string(), &source.text[line.len()..])
+            }
+            _ => (self.new_line(&node.title, depth), self.line_ending),
+        };
+
+        self.lines.truncate(depth);
+        self.lines.push(WrittenLine {
+            width: indent_width(&clean_line(&line)),
+            indent: leading_whitespace(&line).to_string(),
+        });
+        self.push(&line);
+        self.push_line_ending(ending);
+    }
+
+    fn push_line_ending(&mut self, ending: &str) {
+        self.output.push_str(ending);
+        self.open_line = ending.is_empty();
+    }
+
+    /// Whether `line` would be read back as the next node at `depth`: deeper
+    /// than its parent and no deeper than the line before it at that depth
+    fn fits(&self, line: &str, depth: usize) -> bool {
+        let width = indent_width(&clean_line(line));
+        let parent = depth.checked_sub(1).and_then(|d| self.lines.get(d));
+        parent.is_none_or(|parent| width > parent.width)
+            && self
+                .lines
+                .get(depth)
+                .is_none_or(|sibling| width <= sibling.width)
+    }
+
+    /// A line for `title` at `depth`, indented like its previous sibling,
+    /// else one step in from its parent
+    fn new_line(&self, title: &str, depth: usize) -> String {
+        let parent = depth.checked_sub(1).and_then(|d| self.lines.get(d));
+        let mut candidates = Vec::new();
+        if let Some(sibling) = self.lines.get(depth) {
+            candidates.push(sibling.indent.clone());
+        }
+        match parent {
+            Some(parent) => {
+                candidates.push(format!("{}{}", parent.indent, self.indent_unit));
+                // Always fits: deeper than the parent, and any sibling is deeper still
+                candidates.push(" ".repeat(parent.width + 1));
+            }
+            None => candidates.push(String::new()),
+        }
+
+        candidates
+            .into_iter()
+            .map(|indent| format!("{indent}{title}"))
+            .find(|line| self.fits(line, depth))
+            .unwrap_or_else(|| title.to_string())
+    }
+}
+
+fn leading_whitespace(line: &str) -> &str {
+    &line[..line.len() - line.trim_start().len()]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -272,6 +750,35 @@ mod tests {
         assert_eq!(tree.get(children[1]).unwrap().get().title, "✗ Failed node");
     }
 
+    #[test]
+    fn test_node_at_line() {
+        let content = "Root\n\tChild 1\n\n\tChild 2\n\t\tGrandchild";
+        let (tree, root_id) = parse_hmm_content(content).unwrap();
+        let title_at = |line| {
+            node_at_line(&tree, root_id, content, line)
+                .map(|id| tree.get(id).unwrap().get().title.clone())
+        };
+
+        assert_eq!(title_at(1).as_deref(), Some("Root"));
+        assert_eq!(title_at(2).as_deref(), Some("Child 1"));
+        assert_eq!(title_at(3).as_deref(), Some("Child 1")); // blank line
+        assert_eq!(title_at(5).as_deref(), Some("Grandchild"));
+        assert_eq!(title_at(99).as_deref(), None);
+        assert_eq!(title_at(0), None);
+
+        // Multiple top-level nodes go under a synthetic root
+        let content = "First\nSecond\n\tChild";
+        let (tree, root_id) = parse_hmm_content(content).unwrap();
+        let node = node_at_line(&tree, root_id, content, 2).unwrap();
+        assert_eq!(tree.get(node).unwrap().get().title, "Second");
+
+        // A bare list marker is as blank to the parser as an empty line
+        let content = "Root\n\t- \n\t* Child";
+        let (tree, root_id) = parse_hmm_content(content).unwrap();
+        let node = node_at_line(&tree, root_id, content, 3).unwrap();
+        assert_eq!(tree.get(node).unwrap().get().title, "Child");
+    }
+
     #[test]
     fn test_save_file_creates_correct_format() {
         use tempfile::NamedTempFile;
@@ -285,9 +792,128 @@ mod tests {
         root.append(child2, &mut tree);
 
         let temp_file = NamedTempFile::new().unwrap();
-        save_file(&tree, root, temp_file.path()).unwrap();
+        save_file(&mut tree, root, temp_file.path()).unwrap();
 
         let content = std::fs::read_to_string(temp_file.path()).unwrap();
         assert_eq!(content, "Root\n\tChild 1\n\tChild 2\n");
     }
+
+    #[test]
+    fn test_format_from_extension_and_contents() {
+        let format = |path: &str| MapFormat::from_path(Path::new(path));
+        assert_eq!(format("map.hmm"), Some(MapFormat::Hmm));
+        assert_eq!(format("List.TSV"), Some(MapFormat::Tsv));
+        assert_eq!(format("plan.xmind"), Some(MapFormat::XMind));
+        assert_eq!(format("notes.txt"), None);
+        assert_eq!(format("notes"), None);
+
+        assert_eq!(
+            MapFormat::from_contents(b"SQLite format 3\0..."),
+            MapFormat::Sqlite
+        );
+        assert_eq!(
+            MapFormat::from_contents(b"  \n[{\"nm\": \"x\"}]"),
+            MapFormat::Outliner
+        );
+        assert_eq!(MapFormat::from_contents(b"PK\x03\x04"), MapFormat::XMind);
+        assert_eq!(MapFormat::from_contents(b"Root\n\tChild"), MapFormat::Hmm);
+    }
+
+    #[test]
+    fn test_save_in_each_writable_format_reads_back() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (mut tree, root_id) = parse_hmm_content("Root\n\tA\n\t\tB\n\tC").unwrap();
+
+        for format in MapFormat::writable() {
+            let path = temp_dir.path().join(format!("map.{}", format.extension()));
+            save_file(&mut tree, root_id, &path).unwrap();
+            assert_eq!(detect_format(&path).unwrap(), format);
+
+            let (loaded, loaded_root) = load_file(&path).unwrap();
+            assert_eq!(
+                map_to_list(&loaded, loaded_root, false, 0),
+                "Root\n\tA\n\t\tB\n\tC\n",
+                "{}",
+                format.name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_read_only_formats_are_never_written() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("plan.xmind");
+        let (mut tree, root_id) = create_empty_map().unwrap();
+
+        let err = save_file(&mut tree, root_id, &path).unwrap_err();
+        assert!(err.to_string().contains("Save As"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_unchanged_map_saves_byte_for_byte() {
+        let files = [
+            "Root\n\tA\n\t\tB\n\tC\n",
+            "Root\r\n    A   \r\n        B\r\n\r\n    C\r\n\r\n\r\n",
+            "\n\nFirst\n  * Bullet\n  - Dash\n\nSecond\n\t• Dot",
+            "Top\n\tmixed\n    spaces\n\t\ttabs\n",
+        ];
+        for content in files {
+            let (tree, root_id) = parse_hmm_with_source(content).unwrap();
+            assert_eq!(map_to_hmm(&tree, root_id), content);
+        }
+    }
+
+    #[test]
+    fn test_edited_lines_follow_the_file_style() {
+        let content = "Root\r\n    A\r\n        B\r\n\r\n    C\r\n";
+        let (mut tree, root_id) = parse_hmm_with_source(content).unwrap();
+        let a = root_id.children(&tree).next().unwrap();
+        let c = root_id.children(&tree).nth(1).unwrap();
+
+        tree[a].get_mut().title = "Renamed".to_string();
+        c.append_value(Node::new("New".to_string()), &mut tree);
+        root_id.append_value(Node::new("Last".to_string()), &mut tree);
+
+        assert_eq!(
+            map_to_hmm(&tree, root_id),
+            "Root\r\n    Renamed\r\n        B\r\n\r\n    C\r\n        New\r\n    Last\r\n"
+        );
+    }
+
+    #[test]
+    fn test_moved_lines_still_nest_correctly() {
+        // Siblings indented by different amounts cannot keep their lines everywhere
+        let content = "Root\n      Deep\n  Shallow\n    Child\n";
+        let (mut tree, root_id) = parse_hmm_with_source(content).unwrap();
+        let deep = root_id.children(&tree).next().unwrap();
+        let child = root_id.descendants(&tree).last().unwrap();
+
+        deep.detach(&mut tree);
+        root_id.append(deep, &mut tree);
+        child.detach(&mut tree);
+        deep.append(child, &mut tree);
+
+        let saved = map_to_hmm(&tree, root_id);
+        let (reloaded, reloaded_root) = parse_hmm_content(&saved).unwrap();
+        assert_eq!(
+            map_to_list(&reloaded, reloaded_root, false, 0),
+            "Root\n\tShallow\n\tDeep\n\t\tChild\n"
+        );
+    }
+
+    #[test]
+    fn test_several_top_level_lines_keep_no_root_line() {
+        let content = "One\nTwo\n\tChild";
+        let (mut tree, root_id) = parse_hmm_with_source(content).unwrap();
+        root_id.append_value(Node::new("Three".to_string()), &mut tree);
+        assert_eq!(map_to_hmm(&tree, root_id), "One\nTwo\n\tChild\nThree\n");
+
+        // A renamed root becomes a real line
+        tree[root_id].get_mut().title = "Everything".to_string();
+        assert_eq!(
+            map_to_hmm(&tree, root_id),
+            "Everything\n\tOne\n\tTwo\n\t\tChild\n\tThree\n"
+        );
+    }
 }
@@ -0,0 +1,153 @@
+//! Pinned nodes, remembered per map between sessions.
+//!
+//! Pins are stored by the titles on the path from the root, so they survive
+//! reloading the map as long as the pinned node and its ancestors keep their
+//! titles.
+
+use crate::model::{Node, NodeId};
+use anyhow::Result;
+use indextree::Arena;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Most pins a map can have; one per digit key
+pub const MAX_PINS: usize = 9;
+
+/// Title paths of the pinned nodes of every map, keyed by map path
+#[derive(Debug, Clone, Default)]
+pub struct PinStore {
+    maps: BTreeMap<String, Vec<Vec<String>>>,
+    /// File the pins are persisted to; `None` keeps them in memory only
+    store: Option<PathBuf>,
+}
+
+impl PinStore {
+    /// Load the pins from `store`; a missing or unreadable file has no pins
+    pub fn load(store: PathBuf) -> Self {
+        let maps = fs::read_to_string(&store)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self {
+            maps,
+            store: Some(store),
+        }
+    }
+
+    /// The pinned nodes of `map` that still exist in `tree`, in pin order
+    pub fn pins_for(&self, map: &Path, tree: &Arena<Node>, root_id: NodeId) -> Vec<NodeId> {
+        self.maps
+            .get(&map_key(map))
+            .into_iter()
+            .flatten()
+            .filter_map(|path| find_by_title_path(tree, root_id, path))
+            .collect()
+    }
+
+    /// Remember `pins` as the pinned nodes of `map` and persist them
+    pub fn set(
+        &mut self,
+        map: &Path,
+        tree: &Arena<Node>,
+        root_id: NodeId,
+        pins: &[NodeId],
+    ) -> Result<()> {
+        let paths: Vec<Vec<String>> = pins
+            .iter()
+            .map(|&id| title_path(tree, root_id, id))
+            .collect();
+        if paths.is_empty() {
+            self.maps.remove(&map_key(map));
+        } else {
+            self.maps.insert(map_key(map), paths);
+        }
+        self.save()
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(ref store) = self.store else {
+            return Ok(());
+        };
+        if let Some(dir) = store.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(store, serde_json::to_string_pretty(&self.maps)?)?;
+        Ok(())
+    }
+}
+
+fn map_key(map: &Path) -> String {
+    let map = map.canonicalize().unwrap_or_else(|_| map.to_path_buf());
+    map.to_string_lossy().into_owned()
+}
+
+/// Titles from the root down to `node_id`
+pub fn title_path(tree: &Arena<Node>, root_id: NodeId, node_id: NodeId) -> Vec<String> {
+    let mut path = Vec::new();
+    for id in node_id.ancestors(tree) {
+        path.push(tree[id].get().title.clone());
+        if id == root_id {
+            break;
+        }
+    }
+    path.reverse();
+    path
+}
+
+/// The first node whose path of titles from the root is `path`
+pub fn find_by_title_path(tree: &Arena<Node>, root_id: NodeId, path: &[String]) -> Option<NodeId> {
+    let (root_title, rest) = path.split_first()?;
+    if tree[root_id].get().title != *root_title {
+        return None;
+    }
+    rest.iter().try_fold(root_id, |parent, title| {
+        parent
+            .children(tree)
+            .find(|&child_id| tree[child_id].get().title == *title)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_hmm_content;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pins_survive_reloading() {
+        let dir = TempDir::new().unwrap();
+        let store = dir.path().join("state/pins.json");
+        let map = dir.path().join("plan.hmm");
+        let (tree, root_id) = parse_hmm_content("Plan\n\tWork\n\t\tToday\n\tHome\n").unwrap();
+        let today = root_id.descendants(&tree).nth(2).unwrap();
+        let home = root_id.children(&tree).nth(1).unwrap();
+
+        let mut pins = PinStore::load(store.clone());
+        pins.set(&map, &tree, root_id, &[today, home]).unwrap();
+
+        // A fresh parse of the same map finds the same nodes
+        let (tree, root_id) = parse_hmm_content("Plan\n\tWork\n\t\tToday\n\tHome\n").unwrap();
+        let loaded = PinStore::load(store);
+        let found = loaded.pins_for(&map, &tree, root_id);
+        let titles: Vec<_> = found
+            .iter()
+            .map(|&id| tree[id].get().title.as_str())
+            .collect();
+        assert_eq!(titles, ["Today", "Home"]);
+        assert!(loaded
+            .pins_for(&dir.path().join("other.hmm"), &tree, root_id)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_renamed_nodes_lose_their_pin() {
+        let (tree, root_id) = parse_hmm_content("Plan\n\tWork\n\t\tToday\n").unwrap();
+        let today = root_id.descendants(&tree).nth(2).unwrap();
+        assert_eq!(title_path(&tree, root_id, today), ["Plan", "Work", "Today"]);
+
+        let path = title_path(&tree, root_id, today);
+        let (renamed, root_id) = parse_hmm_content("Plan\n\tJob\n\t\tToday\n").unwrap();
+        assert_eq!(find_by_title_path(&renamed, root_id, &path), None);
+    }
+}
@@ -0,0 +1,196 @@
+//! Most-recently-used list of opened maps, and the fuzzy matching used by the
+//! file pickers.
+
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Number of maps remembered
+pub const MAX_RECENT: usize = 30;
+
+/// Recently opened maps, most recent first
+#[derive(Debug, Clone, Default)]
+pub struct RecentFiles {
+    entries: Vec<PathBuf>,
+    /// File the list is persisted to; `None` keeps it in memory only
+    store: Option<PathBuf>,
+}
+
+impl RecentFiles {
+    /// Load the list from `store` (one path per line); a missing file is an empty list
+    pub fn load(store: PathBuf) -> Self {
+        let entries = fs::read_to_string(&store)
+            .map(|content| {
+                content
+                    .lines()
+                    .filter(|line| !line.is_empty())
+                    .map(PathBuf::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Self {
+            entries,
+            store: Some(store),
+        }
+    }
+
+    pub fn entries(&self) -> &[PathBuf] {
+        &self.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Move `path` to the front of the list and persist it
+    pub fn record(&mut self, path: &Path) -> Result<()> {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.entries.retain(|entry| *entry != path);
+        self.entries.insert(0, path);
+        self.entries.truncate(MAX_RECENT);
+        self.save()
+    }
+
+    /// Forget maps that no longer exist
+    pub fn prune_missing(&mut self) {
+        self.entries.retain(|entry| entry.exists());
+    }
+
+    fn save(&self) -> Result<()> {
+        let Some(ref store) = self.store else {
+            return Ok(());
+        };
+        if let Some(dir) = store.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut content = String::new();
+        for entry in &self.entries {
+            content.push_str(&entry.to_string_lossy());
+            content.push('\n');
+        }
+        fs::write(store, content)?;
+        Ok(())
+    }
+
+    /// Entries matching `query` as a fuzzy subsequence, best match first.
+    /// Equal scores keep most-recent-first order; an empty query matches everything.
+    pub fn filter(&self, query: &str) -> Vec<&PathBuf> {
+        fuzzy_filter(&self.entries, query, |entry| {
+            entry.to_string_lossy().into_owned()
+        })
+    }
+}
+
+/// `items` whose `label` fuzzily matches `query`, best match first.
+/// Equal scores keep the order of `items`.
+pub fn fuzzy_filter<'a, T>(
+    items: &'a [T],
+    query: &str,
+    label: impl Fn(&T) -> String,
+) -> Vec<&'a T> {
+    let mut scored: Vec<(i64, &T)> = items
+        .iter()
+        .filter_map(|item| fuzzy_score(&label(item), query).map(|s| (s, item)))
+        .collect();
+    scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    scored.into_iter().map(|(_, item)| item).collect()
+}
+
+/// Score `candidate` against `query` if every query character appears in order
+/// (case-insensitively). Consecutive matches and matches at the start of a
+/// path component or word score higher, so `note` prefers `notes.hmm` over
+/// `annotated.hmm`, and matches in the file name beat matches in directories.
+pub fn fuzzy_score(candidate: &str, query: &str) -> Option<i64> {
+    let chars: Vec<char> = candidate.chars().collect();
+    let name_start = candidate
+        .rfind(['/', '\\'])
+        .map(|i| candidate[..=i].chars().count())
+        .unwrap_or(0);
+
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let q = q.to_lowercase().next().unwrap_or(q);
+        let found = (pos..chars.len()).find(|&i| chars[i].to_lowercase().next() == Some(q))?;
+
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 3;
+        }
+        if found == 0 || matches!(chars[found - 1], '/' | '\\' | '-' | '_' | ' ' | '.') {
+            score += 2;
+        }
+        if found >= name_start {
+            score += 2;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert!(fuzzy_score("/maps/notes.hmm", "nts").is_some());
+        assert!(fuzzy_score("/maps/notes.hmm", "tsn").is_none());
+        assert_eq!(fuzzy_score("anything", ""), Some(0));
+
+        let notes = fuzzy_score("/maps/notes.hmm", "note").unwrap();
+        let annotated = fuzzy_score("/maps/annotated.hmm", "note").unwrap();
+        assert!(notes > annotated);
+
+        let in_name = fuzzy_score("/work/plan.hmm", "plan").unwrap();
+        let in_dir = fuzzy_score("/plan/work.hmm", "plan").unwrap();
+        assert!(in_name > in_dir);
+    }
+
+    #[test]
+    fn test_record_and_reload() {
+        let dir = TempDir::new().unwrap();
+        let store = dir.path().join("state").join("recent");
+        let a = dir.path().join("a.hmm");
+        let b = dir.path().join("b.hmm");
+        fs::write(&a, "A").unwrap();
+        fs::write(&b, "B").unwrap();
+
+        let mut recent = RecentFiles::load(store.clone());
+        assert!(recent.is_empty());
+        recent.record(&a).unwrap();
+        recent.record(&b).unwrap();
+        recent.record(&a).unwrap();
+
+        let reloaded = RecentFiles::load(store);
+        let names: Vec<_> = reloaded
+            .entries()
+            .iter()
+            .map(|p| p.file_name().unwrap().to_str().unwrap().to_string())
+            .collect();
+        assert_eq!(names, ["a.hmm", "b.hmm"]);
+    }
+
+    #[test]
+    fn test_filter_and_prune() {
+        let mut recent = RecentFiles::default();
+        recent.record(Path::new("/maps/notes.hmm")).unwrap();
+        recent.record(Path::new("/maps/plan.hmm")).unwrap();
+
+        assert_eq!(recent.filter("").len(), 2);
+        let matches = recent.filter("nts");
+        assert_eq!(matches, [Path::new("/maps/notes.hmm")]);
+
+        let dir = TempDir::new().unwrap();
+        let existing = dir.path().join("kept.hmm");
+        fs::write(&existing, "").unwrap();
+        recent.record(&existing).unwrap();
+
+        recent.prune_missing();
+        assert_eq!(recent.entries().len(), 1);
+        assert!(recent.entries()[0].ends_with("kept.hmm"));
+    }
+}
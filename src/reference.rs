@@ -0,0 +1,145 @@
+//! Cross-references between nodes of one map, written as `->1.2.3` (the
+//! target's outline number) or `->#id` (a node tagged `#id` in its title).
+//!
+//! Outline numbers count the root's children from 1 and skip hidden nodes,
+//! so `->2.1` is the first child of the second top-level branch.
+
+use crate::model::{Node, NodeId};
+use indextree::Arena;
+use regex::Regex;
+
+/// Where a reference points
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reference {
+    /// An outline number such as `1.2.3`
+    Number(Vec<usize>),
+    /// A `#id` tag on the target node
+    Id(String),
+}
+
+impl std::fmt::Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Reference::Number(number) => {
+                let parts: Vec<String> = number.iter().map(usize::to_string).collect();
+                write!(f, "->{}", parts.join("."))
+            }
+            Reference::Id(id) => write!(f, "->#{id}"),
+        }
+    }
+}
+
+/// Find the first reference in a node title
+pub fn parse_reference(title: &str) -> Option<Reference> {
+    let pattern = Regex::new(r"->(?:#([\w-]+)|(\d+(?:\.\d+)*))").ok()?;
+    let captures = pattern.captures(title)?;
+
+    if let Some(id) = captures.get(1) {
+        return Some(Reference::Id(id.as_str().to_string()));
+    }
+    let number = captures[2]
+        .split('.')
+        .map(str::parse)
+        .collect::<Result<Vec<usize>, _>>()
+        .ok()?;
+    Some(Reference::Number(number))
+}
+
+/// The node a reference points to, if the map has one
+pub fn resolve_reference(
+    tree: &Arena<Node>,
+    root_id: NodeId,
+    reference: &Reference,
+) -> Option<NodeId> {
+    match reference {
+        Reference::Number(number) => node_by_number(tree, root_id, number),
+        Reference::Id(id) => node_by_id(tree, root_id, id),
+    }
+}
+
+/// Outline number of a node: its position among the shown children of each
+/// ancestor below the root. Empty for the root, `None` under a hidden node.
+pub fn outline_number(tree: &Arena<Node>, root_id: NodeId, node_id: NodeId) -> Option<Vec<usize>> {
+    let mut number = Vec::new();
+    let mut current = node_id;
+    while current != root_id {
+        let parent = tree.get(current)?.parent()?;
+        let position = numbered_children(tree, parent).position(|id| id == current)?;
+        number.push(position + 1);
+        current = parent;
+    }
+    number.reverse();
+    Some(number)
+}
+
+/// The node with the given outline number
+pub fn node_by_number(tree: &Arena<Node>, root_id: NodeId, number: &[usize]) -> Option<NodeId> {
+    number.iter().try_fold(root_id, |parent, &position| {
+        numbered_children(tree, parent).nth(position.checked_sub(1)?)
+    })
+}
+
+/// The first node, in outline order, whose title has the tag `#id`
+pub fn node_by_id(tree: &Arena<Node>, root_id: NodeId, id: &str) -> Option<NodeId> {
+    let tag = format!("#{id}");
+    root_id.descendants(tree).find(|&node_id| {
+        tree[node_id]
+            .get()
+            .title
+            .split_whitespace()
+            .any(|word| word == tag)
+    })
+}
+
+fn numbered_children(tree: &Arena<Node>, parent: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+    parent
+        .children(tree)
+        .filter(|&child_id| !tree[child_id].get().is_hidden())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_hmm_content;
+
+    const SPEC: &str =
+        "Spec\n\tIntro #intro\n\t[HIDDEN] Notes\n\tDesign\n\t\tStorage\n\t\tSync, see ->1\n";
+
+    #[test]
+    fn test_parse_reference() {
+        assert_eq!(
+            parse_reference("see ->1.2.3."),
+            Some(Reference::Number(vec![1, 2, 3]))
+        );
+        assert_eq!(
+            parse_reference("per ->#intro-v2, not ->2"),
+            Some(Reference::Id("intro-v2".to_string()))
+        );
+        assert_eq!(parse_reference("a -> b"), None);
+        assert_eq!(parse_reference("#intro"), None);
+        assert_eq!(Reference::Number(vec![2, 1]).to_string(), "->2.1");
+    }
+
+    #[test]
+    fn test_numbers_skip_hidden_nodes() {
+        let (tree, root_id) = parse_hmm_content(SPEC).unwrap();
+        let sync = node_by_number(&tree, root_id, &[2, 2]).unwrap();
+        assert_eq!(tree[sync].get().title, "Sync, see ->1");
+        assert_eq!(outline_number(&tree, root_id, sync), Some(vec![2, 2]));
+        assert_eq!(outline_number(&tree, root_id, root_id), Some(vec![]));
+        assert_eq!(node_by_number(&tree, root_id, &[3]), None);
+        assert_eq!(node_by_number(&tree, root_id, &[0]), None);
+    }
+
+    #[test]
+    fn test_resolve_reference() {
+        let (tree, root_id) = parse_hmm_content(SPEC).unwrap();
+        let intro = root_id.children(&tree).next().unwrap();
+        for reference in ["->1", "->#intro"] {
+            let reference = parse_reference(reference).unwrap();
+            assert_eq!(resolve_reference(&tree, root_id, &reference), Some(intro));
+        }
+        let missing = Reference::Id("outro".to_string());
+        assert_eq!(resolve_reference(&tree, root_id, &missing), None);
+    }
+}
@@ -0,0 +1,338 @@
+//! In-memory inverted index over node titles.
+//!
+//! The index maps lowercase word tokens to the nodes containing them, so
+//! searches touch only matching nodes instead of scanning every title. It is
+//! kept current with [`SearchIndex::refresh`], which re-tokenizes only nodes
+//! whose title changed since the last refresh and drops removed nodes, or a
+//! slice of the arena at a time with [`SearchIndex::refresh_some`].
+//!
+//! Queries are words separated by spaces (all must match, each as a word
+//! prefix), with alternatives separated by `OR` or `|`:
+//! `rust parser | go lexer` finds nodes matching both "rust" and "parser",
+//! or both "go" and "lexer". Words with punctuation, such as `c++`, `v1.2`
+//! or `#id`, must also appear in the title as typed.
+
+use crate::model::{Node, NodeId};
+use indextree::Arena;
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+#[derive(Debug, Default, Clone)]
+pub struct SearchIndex {
+    /// Token -> nodes whose title contains it
+    postings: BTreeMap<String, HashSet<NodeId>>,
+    /// Node -> the title it was last indexed with
+    indexed_titles: HashMap<NodeId, String>,
+    /// How far a refresh spread over several calls has got, `None` while
+    /// the index is up to date
+    pending: Option<PendingRefresh>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct PendingRefresh {
+    /// Arena slot to look at next
+    next: usize,
+    /// Nodes looked at so far
+    seen: HashSet<NodeId>,
+}
+
+impl SearchIndex {
+    /// An empty index, out of date with any tree until refreshed
+    pub fn new() -> Self {
+        Self {
+            pending: Some(PendingRefresh::default()),
+            ..Self::default()
+        }
+    }
+
+    /// Build an index over every node in the tree
+    pub fn build(tree: &Arena<Node>) -> Self {
+        let mut index = Self::new();
+        index.refresh(tree);
+        index
+    }
+
+    /// Number of indexed nodes
+    pub fn len(&self) -> usize {
+        self.indexed_titles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indexed_titles.is_empty()
+    }
+
+    /// Bring the index up to date with the tree, re-indexing only changed nodes
+    pub fn refresh(&mut self, tree: &Arena<Node>) {
+        self.invalidate();
+        self.refresh_some(tree, usize::MAX);
+    }
+
+    /// Whether the index has caught up with the tree since it last changed
+    pub fn is_current(&self) -> bool {
+        self.pending.is_none()
+    }
+
+    /// Record that the tree changed, so that the next refresh starts over
+    pub fn invalidate(&mut self) {
+        self.pending = Some(PendingRefresh::default());
+    }
+
+    /// Carry on refreshing the index over up to `budget` more arena slots,
+    /// returning whether it is up to date. The tree must not change between
+    /// calls without [`SearchIndex::invalidate`].
+    pub fn refresh_some(&mut self, tree: &Arena<Node>, budget: usize) -> bool {
+        let Some(mut pending) = self.pending.take() else {
+            return true;
+        };
+        for node_ref in tree.iter().skip(pending.next).take(budget) {
+            pending.next += 1;
+            if node_ref.is_removed() {
+                continue;
+            }
+            let node_id = tree.get_node_id(node_ref).unwrap();
+            let title = &node_ref.get().title;
+            pending.seen.insert(node_id);
+
+            if self.indexed_titles.get(&node_id) != Some(title) {
+                self.update_node(node_id, title);
+            }
+        }
+        if pending.next < tree.count() {
+            self.pending = Some(pending);
+            return false;
+        }
+
+        let stale: Vec<NodeId> = self
+            .indexed_titles
+            .keys()
+            .filter(|id| !pending.seen.contains(id))
+            .copied()
+            .collect();
+        for node_id in stale {
+            self.remove_node(node_id);
+        }
+        true
+    }
+
+    /// Re-index a node whose new title is all that changed since the index
+    /// was last up to date, keeping it up to date without a walk
+    pub fn follow_edit(&mut self, node_id: NodeId, title: &str) {
+        self.update_node(node_id, title);
+        self.pending = None;
+    }
+
+    /// Index (or re-index) a single node
+    pub fn update_node(&mut self, node_id: NodeId, title: &str) {
+        self.remove_node(node_id);
+        for token in tokenize(title) {
+            self.postings.entry(token).or_default().insert(node_id);
+        }
+        self.indexed_titles.insert(node_id, title.to_string());
+    }
+
+    /// Drop a node from the index
+    pub fn remove_node(&mut self, node_id: NodeId) {
+        let Some(old_title) = self.indexed_titles.remove(&node_id) else {
+            return;
+        };
+        for token in tokenize(&old_title) {
+            if let Some(nodes) = self.postings.get_mut(&token) {
+                nodes.remove(&node_id);
+                if nodes.is_empty() {
+                    self.postings.remove(&token);
+                }
+            }
+        }
+    }
+
+    /// Run a query, returning matching nodes best-first.
+    ///
+    /// A node scores 2 per query word matching one of its words exactly and 1
+    /// per prefix match; ties keep the order nodes appear in the tree arena.
+    pub fn query(&self, query: &str) -> Vec<NodeId> {
+        let mut scores: HashMap<NodeId, usize> = HashMap::new();
+
+        for alternative in parse_query(query) {
+            let mut matched: Option<HashMap<NodeId, usize>> = None;
+            for word in alternative.iter().flat_map(|term| &term.words) {
+                let word_scores = self.term_scores(word);
+                matched = Some(match matched {
+                    None => word_scores,
+                    Some(previous) => previous
+                        .into_iter()
+                        .filter_map(|(id, score)| word_scores.get(&id).map(|s| (id, score + s)))
+                        .collect(),
+                });
+            }
+            for (node_id, score) in matched.unwrap_or_default() {
+                let title = &self.indexed_titles[&node_id];
+                if !alternative.iter().all(|term| term.matches(title)) {
+                    continue;
+                }
+                let best = scores.entry(node_id).or_default();
+                *best = (*best).max(score);
+            }
+        }
+
+        let mut results: Vec<(NodeId, usize)> = scores.into_iter().collect();
+        results.sort_by(|(id_a, score_a), (id_b, score_b)| {
+            score_b.cmp(score_a).then_with(|| id_a.cmp(id_b))
+        });
+        results.into_iter().map(|(id, _)| id).collect()
+    }
+
+    /// Best score per node for a single query word
+    fn term_scores(&self, term: &str) -> HashMap<NodeId, usize> {
+        let mut scores = HashMap::new();
+        let prefixed = self
+            .postings
+            .range(term.to_string()..)
+            .take_while(|(token, _)| token.starts_with(term));
+        for (token, nodes) in prefixed {
+            let score = if token == term { 2 } else { 1 };
+            for node_id in nodes {
+                let best = scores.entry(*node_id).or_insert(0);
+                *best = (*best).max(score);
+            }
+        }
+        scores
+    }
+}
+
+/// Lowercase alphanumeric words of a title
+fn tokenize(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// A word of a query as typed, lowercased, and the index words it holds
+struct Term {
+    text: String,
+    words: Vec<String>,
+}
+
+impl Term {
+    fn new(text: &str) -> Self {
+        Self {
+            text: text.to_lowercase(),
+            words: tokenize(text).collect(),
+        }
+    }
+
+    /// Whether `title` holds the punctuation of the term besides its words
+    fn matches(&self, title: &str) -> bool {
+        self.text.chars().all(char::is_alphanumeric) || title.to_lowercase().contains(&self.text)
+    }
+}
+
+/// Split a query into OR-alternatives of AND-ed terms
+fn parse_query(query: &str) -> Vec<Vec<Term>> {
+    query
+        .split('|')
+        .flat_map(|part| part.split(" OR "))
+        .map(|alternative| {
+            alternative
+                .split_whitespace()
+                .map(Term::new)
+                .collect::<Vec<_>>()
+        })
+        .filter(|terms| !terms.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+
+    fn titles(tree: &Arena<Node>, ids: &[NodeId]) -> Vec<String> {
+        ids.iter()
+            .map(|id| tree.get(*id).unwrap().get().title.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_and_or_queries() {
+        let (tree, _) = parser::parse_hmm_content(
+            "Languages\n\tRust parser\n\tRust lexer\n\tGo parser\n\tGo lexer",
+        )
+        .unwrap();
+        let index = SearchIndex::build(&tree);
+
+        assert_eq!(
+            titles(&tree, &index.query("rust parser")),
+            vec!["Rust parser"]
+        );
+        assert_eq!(
+            titles(&tree, &index.query("rust parser | go lexer")),
+            vec!["Rust parser", "Go lexer"]
+        );
+        assert_eq!(titles(&tree, &index.query("parser OR lexer")).len(), 4);
+        assert!(index.query("python").is_empty());
+        assert!(index.query("   ").is_empty());
+    }
+
+    #[test]
+    fn test_exact_matches_rank_before_prefixes() {
+        let (tree, _) = parser::parse_hmm_content("Root\n\tParsers overview\n\tParse").unwrap();
+        let index = SearchIndex::build(&tree);
+
+        assert_eq!(
+            titles(&tree, &index.query("parse")),
+            vec!["Parse", "Parsers overview"]
+        );
+    }
+
+    #[test]
+    fn test_refresh_tracks_edits_and_removals() {
+        let (mut tree, root) = parser::parse_hmm_content("Root\n\tAlpha\n\tBeta").unwrap();
+        let mut index = SearchIndex::build(&tree);
+        let alpha = root.children(&tree).next().unwrap();
+        let beta = root.children(&tree).nth(1).unwrap();
+
+        tree.get_mut(alpha).unwrap().get_mut().title = "Gamma".to_string();
+        beta.remove_subtree(&mut tree);
+        index.refresh(&tree);
+
+        assert!(index.query("alpha").is_empty());
+        assert!(index.query("beta").is_empty());
+        assert_eq!(index.query("gamma"), vec![alpha]);
+        assert_eq!(index.len(), tree.iter().filter(|n| !n.is_removed()).count());
+    }
+
+    #[test]
+    fn test_refresh_in_slices() {
+        let (mut tree, root) = parser::parse_hmm_content("Root\n\tAlpha\n\tBeta\n\tGamma").unwrap();
+        let mut index = SearchIndex::build(&tree);
+        let alpha = root.children(&tree).next().unwrap();
+        let gamma = root.children(&tree).nth(2).unwrap();
+
+        tree.get_mut(gamma).unwrap().get_mut().title = "Delta".to_string();
+        alpha.remove_subtree(&mut tree);
+        index.invalidate();
+        assert!(!index.refresh_some(&tree, 2));
+        assert!(!index.is_current());
+        while !index.refresh_some(&tree, 2) {}
+        assert!(index.is_current());
+
+        assert!(index.query("alpha").is_empty());
+        assert!(index.query("gamma").is_empty());
+        assert_eq!(index.query("delta"), vec![gamma]);
+        assert_eq!(index.len(), tree.iter().filter(|n| !n.is_removed()).count());
+    }
+
+    #[test]
+    fn test_punctuation_must_match_as_typed() {
+        let (tree, _) = parser::parse_hmm_content(
+            "Langs\n\tC++ notes\n\tC basics\n\tRelease v1.2\n\tv1 2\n\t#id card",
+        )
+        .unwrap();
+        let index = SearchIndex::build(&tree);
+
+        assert_eq!(titles(&tree, &index.query("c++")), vec!["C++ notes"]);
+        assert_eq!(titles(&tree, &index.query("v1.2")), vec!["Release v1.2"]);
+        assert_eq!(titles(&tree, &index.query("#id")), vec!["#id card"]);
+        // Punctuation alone leaves nothing to look up
+        assert!(index.query("++").is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+//! Settings as listed and edited in the settings overlay.
+//!
+//! The config is round-tripped through TOML, so every [`AppConfig`] field
+//! with a value is listed without naming it here, and edits are checked by
+//! the same deserializer that reads the config file.
+
+use crate::config::AppConfig;
+use anyhow::{anyhow, bail, Result};
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Settings and their values, by name. Optional settings that are unset and
+/// tables such as `[keys]` are left out.
+pub fn settings(config: &AppConfig) -> Vec<(String, Value)> {
+    Table::try_from(config)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(_, value)| !value.is_table())
+        .collect()
+}
+
+/// Text a value is edited as: strings without their quotes, the rest as TOML
+pub fn edit_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// A copy of `config` with the setting `name` read from `text`, as the kind of
+/// value the setting holds
+pub fn with_setting(config: &AppConfig, name: &str, text: &str) -> Result<AppConfig> {
+    let mut table = Table::try_from(config)?;
+    let current = table
+        .get(name)
+        .ok_or_else(|| anyhow!("unknown setting '{}'", name))?;
+
+    let value = match current {
+        Value::String(_) => Value::String(text.to_string()),
+        _ => parse_value(text)
+            .ok_or_else(|| anyhow!("'{}' is not a valid {}", text.trim(), current.type_str()))?,
+    };
+    if value.type_str() != current.type_str() {
+        bail!("'{}' is not a valid {}", text.trim(), current.type_str());
+    }
+
+    table.insert(name.to_string(), value);
+    table.try_into().map_err(|e: toml::de::Error| {
+        let message = e.message().to_string();
+        anyhow!(message)
+    })
+}
+
+fn parse_value(text: &str) -> Option<Value> {
+    let mut table: Table = toml::from_str(&format!("value = {}", text.trim())).ok()?;
+    table.remove("value")
+}
+
+/// Write the settings `names` with their values in `config` to the TOML config
+/// file at `path`, keeping the rest of the file, comments included
+pub fn write_settings(path: &Path, config: &AppConfig, names: &[String]) -> Result<()> {
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        if matches!(ext, "json" | "json5" | "yaml" | "yml" | "ini" | "ron") {
+            bail!("{} is not a TOML config file", path.display());
+        }
+    }
+
+    let table = Table::try_from(config)?;
+    let text = if path.exists() {
+        fs::read_to_string(path)?
+    } else {
+        String::new()
+    };
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    for name in names {
+        if let Some(value) = table.get(name) {
+            set_line(&mut lines, name, format!("{} = {}", name, value));
+        }
+    }
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// Replace the top-level line setting `name`, or else add one below its
+/// commented-out example, or else before the first table
+fn set_line(lines: &mut Vec<String>, name: &str, line: String) {
+    let top_level = lines
+        .iter()
+        .position(|l| l.trim_start().starts_with('['))
+        .unwrap_or(lines.len());
+    let assigns = |l: &str| {
+        l.trim_start()
+            .strip_prefix(name)
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    };
+    let commented = |l: &str| l.trim_start().strip_prefix('#').is_some_and(&assigns);
+
+    if let Some(i) = lines[..top_level].iter().position(|l| assigns(l)) {
+        lines[i] = line;
+    } else if let Some(i) = lines[..top_level].iter().position(|l| commented(l)) {
+        lines.insert(i + 1, line);
+    } else {
+        lines.insert(top_level, line);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HeatmapMode;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_settings_list_config_fields() {
+        let config = AppConfig::default();
+        let listed = settings(&config);
+        let names: Vec<&str> = listed.iter().map(|(name, _)| name.as_str()).collect();
+        assert!(names.contains(&"line_spacing"));
+        assert!(names.contains(&"center_lock"));
+        assert!(!names.contains(&"keys"));
+
+        let (_, symbol) = listed.iter().find(|(name, _)| name == "symbol1").unwrap();
+        assert_eq!(edit_text(symbol), "✓");
+    }
+
+    #[test]
+    fn test_with_setting_keeps_the_value_type() {
+        let config = AppConfig::default();
+
+        let changed = with_setting(&config, "line_spacing", " 3 ").unwrap();
+        assert_eq!(changed.line_spacing, 3);
+        let changed = with_setting(&config, "center_lock", "true").unwrap();
+        assert!(changed.center_lock);
+        let changed = with_setting(&config, "heatmap", "size").unwrap();
+        assert_eq!(changed.heatmap, HeatmapMode::Size);
+        let changed = with_setting(&config, "depth_line_spacing", "[2, 1]").unwrap();
+        assert_eq!(changed.depth_line_spacing, [2, 1]);
+
+        let error = with_setting(&config, "line_spacing", "wide").unwrap_err();
+        assert_eq!(error.to_string(), "'wide' is not a valid integer");
+        assert!(with_setting(&config, "line_spacing", "-1").is_err());
+        assert!(with_setting(&config, "heatmap", "loud").is_err());
+        assert!(with_setting(&config, "no_such_setting", "1").is_err());
+    }
+
+    #[test]
+    fn test_write_settings_keeps_comments() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("h-m-m.conf");
+        fs::write(
+            &path,
+            "# Spacing\n# line_spacing = 1\n\ncenter_lock = false\n\n[keys]\nredo = \"U\"\n",
+        )
+        .unwrap();
+
+        let mut config = with_setting(&AppConfig::default(), "line_spacing", "2").unwrap();
+        config.center_lock = true;
+        config.symbol1 = "done".to_string();
+        let names = ["line_spacing", "center_lock", "symbol1"].map(String::from);
+        write_settings(&path, &config, &names).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# Spacing\n# line_spacing = 1\nline_spacing = 2\n\ncenter_lock = true\n\nsymbol1 = \"done\"\n[keys]\nredo = \"U\"\n"
+        );
+
+        let json = dir.path().join("config.json");
+        assert!(write_settings(&json, &config, &names).is_err());
+    }
+}
@@ -0,0 +1,135 @@
+//! Periodic snapshots of open maps, to look back at or restore the map as it
+//! was at an earlier time.
+//!
+//! Each map has a directory of timestamped `.hmm` files in the state
+//! directory. A snapshot is only written when the map changed since the
+//! newest one, and the oldest are removed beyond the configured number.
+
+use crate::export::fnv1a;
+use anyhow::Result;
+use chrono::NaiveDateTime;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Snapshot file names are their local time in this format
+const FILE_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+/// A stored snapshot of a map
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub time: NaiveDateTime,
+    pub path: PathBuf,
+}
+
+/// Snapshots of every map, one directory per map
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotStore {
+    /// Directory holding the snapshots; `None` keeps none
+    dir: Option<PathBuf>,
+}
+
+impl SnapshotStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir: Some(dir) }
+    }
+
+    /// Directory of the snapshots of `map`, named after the map so it can
+    /// be found by hand
+    fn map_dir(&self, map: &Path) -> Option<PathBuf> {
+        let map = map.canonicalize().unwrap_or_else(|_| map.to_path_buf());
+        let stem = map.file_stem()?.to_string_lossy();
+        let key = fnv1a(&map.to_string_lossy());
+        Some(self.dir.as_ref()?.join(format!("{stem}-{key:016x}")))
+    }
+
+    /// Snapshots of `map`, newest first
+    pub fn list(&self, map: &Path) -> Vec<Snapshot> {
+        let Some(dir) = self.map_dir(map) else {
+            return Vec::new();
+        };
+        let Ok(entries) = fs::read_dir(&dir) else {
+            return Vec::new();
+        };
+
+        let mut snapshots: Vec<Snapshot> = entries
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let stem = path.file_stem()?.to_str()?;
+                let time = NaiveDateTime::parse_from_str(stem, FILE_TIME_FORMAT).ok()?;
+                Some(Snapshot { time, path })
+            })
+            .collect();
+        snapshots.sort_by_key(|snapshot| Reverse(snapshot.time));
+        snapshots
+    }
+
+    /// Store `content` as the snapshot of `map` at `time` unless it matches
+    /// the newest snapshot, then remove all but the newest `keep`. Returns
+    /// whether a snapshot was written.
+    pub fn take(
+        &self,
+        map: &Path,
+        content: &str,
+        time: NaiveDateTime,
+        keep: usize,
+    ) -> Result<bool> {
+        let Some(dir) = self.map_dir(map) else {
+            return Ok(false);
+        };
+        let snapshots = self.list(map);
+        let unchanged = snapshots.first().is_some_and(|newest| {
+            fs::read_to_string(&newest.path).is_ok_and(|last| last == content)
+        });
+        if unchanged {
+            return Ok(false);
+        }
+
+        fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.hmm", time.format(FILE_TIME_FORMAT)));
+        fs::write(path, content)?;
+
+        // The new snapshot is the newest, so one fewer of the old ones stays
+        for old in snapshots.iter().skip(keep.saturating_sub(1)) {
+            fs::remove_file(&old.path)?;
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn time(minute: u32) -> NaiveDateTime {
+        chrono::NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(12, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_snapshots_skip_unchanged_maps_and_keep_the_newest() {
+        let dir = TempDir::new().unwrap();
+        let store = SnapshotStore::new(dir.path().join("snapshots"));
+        let map = dir.path().join("plan.hmm");
+
+        assert!(store.take(&map, "Plan\n", time(0), 2).unwrap());
+        assert!(!store.take(&map, "Plan\n", time(1), 2).unwrap());
+        assert!(store.take(&map, "Plan\n\tA\n", time(2), 2).unwrap());
+        assert!(store.take(&map, "Plan\n\tB\n", time(3), 2).unwrap());
+
+        let snapshots = store.list(&map);
+        let times: Vec<_> = snapshots.iter().map(|s| s.time).collect();
+        assert_eq!(times, vec![time(3), time(2)]);
+        assert_eq!(
+            fs::read_to_string(&snapshots[0].path).unwrap(),
+            "Plan\n\tB\n"
+        );
+
+        // Other maps have their own snapshots
+        assert!(store.list(&dir.path().join("other.hmm")).is_empty());
+        assert!(SnapshotStore::default().list(&map).is_empty());
+    }
+}
@@ -0,0 +1,551 @@
+//! SQLite-backed map storage for `.hmmdb` files.
+//!
+//! Each node is a row with a pointer to its parent and its position among its
+//! siblings. The database runs in WAL mode with a busy timeout so short-lived
+//! writers (e.g. a quick-capture command appending a node) can work on a map
+//! while the editor has it open, and titles are indexed with FTS5 so maps can
+//! be searched without loading them. A single branch can be loaded on its own.
+
+use crate::model::{DbRow, Node, NodeId, NEW_MAP_TITLE};
+use anyhow::{anyhow, Result};
+use indextree::Arena;
+use rusqlite::{params, Connection, OptionalExtension, Params, Statement};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+/// File extension of SQLite-backed maps
+pub const DB_EXTENSION: &str = "hmmdb";
+
+/// How long a writer waits for another connection's lock before failing
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Row ids are never reused, so a row captured after the editor loaded the
+/// map always has a higher id than any it loaded
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS nodes (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        parent_id INTEGER REFERENCES nodes(id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        is_collapsed INTEGER NOT NULL DEFAULT 0,
+        -- Set by writers other than the editor, until a save has seen the row
+        captured INTEGER NOT NULL DEFAULT 0
+    );
+
+    CREATE INDEX IF NOT EXISTS nodes_by_parent ON nodes(parent_id, position);
+
+    CREATE VIRTUAL TABLE IF NOT EXISTS nodes_fts
+        USING fts5(title, content='nodes', content_rowid='id');
+    CREATE TRIGGER IF NOT EXISTS nodes_fts_insert AFTER INSERT ON nodes BEGIN
+        INSERT INTO nodes_fts(rowid, title) VALUES (new.id, new.title);
+    END;
+    CREATE TRIGGER IF NOT EXISTS nodes_fts_delete AFTER DELETE ON nodes BEGIN
+        INSERT INTO nodes_fts(nodes_fts, rowid, title) VALUES ('delete', old.id, old.title);
+    END;
+    CREATE TRIGGER IF NOT EXISTS nodes_fts_update AFTER UPDATE OF title ON nodes BEGIN
+        INSERT INTO nodes_fts(nodes_fts, rowid, title) VALUES ('delete', old.id, old.title);
+        INSERT INTO nodes_fts(rowid, title) VALUES (new.id, new.title);
+    END;
+";
+
+/// Whether a path refers to a SQLite-backed map
+pub fn is_db_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == DB_EXTENSION)
+}
+
+/// Open (creating if needed) a map database, ready for concurrent use
+pub fn open(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "foreign_keys", true)?;
+    conn.execute_batch(SCHEMA)?;
+    Ok(conn)
+}
+
+/// A row as read for building a tree: id, parent, title and collapsed state
+type TreeRow = (i64, Option<i64>, String, bool);
+
+fn read_rows(stmt: &mut Statement, params: impl Params) -> Result<Vec<TreeRow>> {
+    Ok(stmt
+        .query_map(params, |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+/// Build a tree from rows ordered by parent and position; rows whose parent
+/// is not among them are top-level
+fn build_tree(rows: &[TreeRow]) -> (Arena<Node>, NodeId) {
+    let loaded_up_to = rows.iter().map(|(row_id, ..)| *row_id).max().unwrap_or(0);
+    let mut tree = Arena::new();
+    let mut ids = HashMap::new();
+    for (row_id, _, title, is_collapsed) in rows {
+        let mut node = Node::new(title.clone());
+        node.is_collapsed = *is_collapsed;
+        node.row = Some(DbRow {
+            id: *row_id,
+            loaded_up_to,
+        });
+        ids.insert(*row_id, tree.new_node(node));
+    }
+
+    // Rows are ordered by position, so appending keeps sibling order
+    let mut top_level = Vec::new();
+    for (row_id, parent_id, _, _) in rows {
+        let node_id = ids[row_id];
+        match parent_id.and_then(|parent| ids.get(&parent)) {
+            Some(parent_node) => parent_node.append(node_id, &mut tree),
+            None => top_level.push(node_id),
+        }
+    }
+
+    let root_id = match top_level.as_slice() {
+        [] => tree.new_node(Node::new(NEW_MAP_TITLE.to_string())),
+        [single] => *single,
+        // Several top-level rows get a synthetic root, like the text parser
+        _ => {
+            let root = tree.new_node(Node::new("root".to_string()));
+            for node_id in top_level {
+                root.append(node_id, &mut tree);
+            }
+            root
+        }
+    };
+    (tree, root_id)
+}
+
+/// Load the whole map from a database
+pub fn load_db(path: &Path) -> Result<(Arena<Node>, NodeId)> {
+    let conn = open(path)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, parent_id, title, is_collapsed FROM nodes ORDER BY parent_id, position, id",
+    )?;
+    Ok(build_tree(&read_rows(&mut stmt, [])?))
+}
+
+/// Load only the branch under the node reached by following `parent_path`
+/// titles from the root, without reading the rest of the map. The branch is
+/// for reading: saving it back to the same database would drop everything
+/// outside it
+pub fn load_branch(path: &Path, parent_path: &[&str]) -> Result<(Arena<Node>, NodeId)> {
+    let conn = open(path)?;
+    let top = find_row(&conn, parent_path)?;
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE branch(id) AS (
+             SELECT ?1
+             UNION ALL SELECT nodes.id FROM nodes JOIN branch ON nodes.parent_id = branch.id
+         )
+         SELECT id, parent_id, title, is_collapsed FROM nodes
+         WHERE id IN branch ORDER BY parent_id, position, id",
+    )?;
+    Ok(build_tree(&read_rows(&mut stmt, [top])?))
+}
+
+/// What a save compares a node against to tell whether its row changed
+#[derive(PartialEq)]
+struct StoredRow {
+    parent_id: Option<i64>,
+    position: i64,
+    title: String,
+    is_collapsed: bool,
+}
+
+/// Write the map as changes to the rows it was loaded from: changed nodes
+/// update their rows, new nodes get rows and rows of deleted nodes go away.
+/// Rows that another writer captured after the map was loaded are kept, so a
+/// capture made while the editor has the map open survives its next save,
+/// under the root if the editor deleted the node it was captured under.
+/// New nodes are given the rows written for them, so that saving again
+/// updates those rows
+pub fn save_db(tree: &mut Arena<Node>, root_id: NodeId, path: &Path) -> Result<()> {
+    let mut conn = open(path)?;
+    let tx = conn.transaction()?;
+    let mut stored = HashMap::new();
+    let mut captured = HashSet::new();
+    {
+        let mut select =
+            tx.prepare("SELECT id, parent_id, position, title, is_collapsed, captured FROM nodes")?;
+        let mut rows = select.query([])?;
+        while let Some(row) = rows.next()? {
+            let row_id: i64 = row.get(0)?;
+            if row.get::<_, bool>(5)? {
+                captured.insert(row_id);
+            }
+            let stored_row = StoredRow {
+                parent_id: row.get(1)?,
+                position: row.get(2)?,
+                title: row.get(3)?,
+                is_collapsed: row.get(4)?,
+            };
+            stored.insert(row_id, stored_row);
+        }
+    }
+
+    let loaded_up_to = root_id
+        .descendants(tree)
+        .filter_map(|node_id| Some(tree[node_id].get().row?.loaded_up_to))
+        .max()
+        .unwrap_or(0);
+    let mut kept = HashSet::new();
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO nodes (parent_id, position, title, is_collapsed) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        let mut update = tx.prepare(
+            "UPDATE nodes SET parent_id = ?2, position = ?3, title = ?4, is_collapsed = ?5,
+                 captured = 0
+             WHERE id = ?1",
+        )?;
+
+        let mut stack = vec![(root_id, None::<i64>, 0usize)];
+        while let Some((node_id, parent_row, position)) = stack.pop() {
+            let node = tree
+                .get(node_id)
+                .ok_or_else(|| anyhow!("Node missing from tree"))?
+                .get();
+            let current = StoredRow {
+                parent_id: parent_row,
+                position: position as i64,
+                title: node.title.clone(),
+                is_collapsed: node.is_collapsed,
+            };
+            // A row already claimed by another node, or deleted since the
+            // map was loaded, is written anew
+            let row = node.row.map(|row| row.id);
+            let row_id = match row.filter(|row| stored.contains_key(row) && kept.insert(*row)) {
+                Some(row_id) => {
+                    if stored[&row_id] != current || captured.contains(&row_id) {
+                        update.execute(params![
+                            row_id,
+                            current.parent_id,
+                            current.position,
+                            current.title,
+                            current.is_collapsed
+                        ])?;
+                    }
+                    row_id
+                }
+                None => {
+                    insert.execute(params![
+                        current.parent_id,
+                        current.position,
+                        current.title,
+                        current.is_collapsed
+                    ])?;
+                    let row_id = tx.last_insert_rowid();
+                    tree[node_id].get_mut().row = Some(DbRow {
+                        id: row_id,
+                        loaded_up_to,
+                    });
+                    row_id
+                }
+            };
+
+            let children: Vec<NodeId> = node_id.children(tree).collect();
+            for (child_position, child_id) in children.into_iter().enumerate().rev() {
+                stack.push((child_id, Some(row_id), child_position));
+            }
+        }
+
+        let captured_since = |row_id: &i64| captured.contains(row_id) && *row_id > loaded_up_to;
+        let deleted: HashSet<i64> = stored
+            .keys()
+            .filter(|row_id| !kept.contains(row_id) && !captured_since(row_id))
+            .copied()
+            .collect();
+
+        // Captures under a deleted node move to the end of the root rather
+        // than go with it
+        let root_row = tree[root_id].get().row.map(|row| row.id);
+        let mut reparent = tx.prepare(
+            "UPDATE nodes SET parent_id = ?2,
+                 position = (SELECT COALESCE(MAX(position) + 1, 0) FROM nodes WHERE parent_id = ?2)
+             WHERE id = ?1",
+        )?;
+        let mut orphaned: Vec<i64> = stored
+            .iter()
+            .filter(|(row_id, row)| {
+                captured_since(row_id)
+                    && row
+                        .parent_id
+                        .is_some_and(|parent| deleted.contains(&parent))
+            })
+            .map(|(row_id, _)| *row_id)
+            .collect();
+        orphaned.sort_unstable();
+        for row_id in orphaned {
+            reparent.execute(params![row_id, root_row])?;
+        }
+
+        let mut delete = tx.prepare("DELETE FROM nodes WHERE id = ?1")?;
+        for row_id in deleted {
+            delete.execute([row_id])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Row of the node reached by following `parent_path` titles from the root
+fn find_row(conn: &Connection, parent_path: &[&str]) -> Result<i64> {
+    let mut row_id: i64 = conn
+        .query_row(
+            "SELECT id FROM nodes WHERE parent_id IS NULL ORDER BY position, id LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| anyhow!("Map is empty"))?;
+
+    for segment in parent_path {
+        row_id = conn
+            .query_row(
+                "SELECT id FROM nodes WHERE parent_id = ?1 AND title = ?2
+                 ORDER BY position, id LIMIT 1",
+                params![row_id, segment],
+                |row| row.get(0),
+            )
+            .optional()?
+            .ok_or_else(|| anyhow!("No node named '{}'", segment))?;
+    }
+    Ok(row_id)
+}
+
+/// Append a node under the node reached by following `parent_path` titles from
+/// the root (an empty path means the root itself), in a single transaction
+pub fn append_child(path: &Path, parent_path: &[&str], title: &str) -> Result<()> {
+    let mut conn = open(path)?;
+    let tx = conn.transaction()?;
+    let parent_id = find_row(&tx, parent_path)?;
+    tx.execute(
+        "INSERT INTO nodes (parent_id, position, title, captured)
+         VALUES (?1, (SELECT COALESCE(MAX(position) + 1, 0) FROM nodes WHERE parent_id = ?1), ?2, 1)",
+        params![parent_id, title],
+    )?;
+    tx.commit()?;
+    Ok(())
+}
+
+/// Full-text search over titles, returning the ancestor path (root first) of
+/// each match, best matches first
+pub fn search(path: &Path, query: &str) -> Result<Vec<Vec<String>>> {
+    let conn = open(path)?;
+    let mut matches =
+        conn.prepare("SELECT rowid FROM nodes_fts WHERE nodes_fts MATCH ?1 ORDER BY rank")?;
+    let row_ids = matches
+        .query_map([query], |row| row.get::<_, i64>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+
+    let mut lookup = conn.prepare("SELECT parent_id, title FROM nodes WHERE id = ?1")?;
+    let mut paths = Vec::new();
+    for row_id in row_ids {
+        let mut titles = Vec::new();
+        let mut current = Some(row_id);
+        while let Some(id) = current {
+            let (parent_id, title): (Option<i64>, String) =
+                lookup.query_row([id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            titles.push(title);
+            current = parent_id;
+        }
+        titles.reverse();
+        paths.push(titles);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser;
+    use tempfile::TempDir;
+
+    fn sample_tree() -> (Arena<Node>, NodeId) {
+        parser::parse_hmm_content("Root\n\tInbox\n\tProjects\n\t\tRust rewrite\n\t\tGarden")
+            .unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("map.hmmdb");
+        let (mut tree, root) = sample_tree();
+        let projects = root.children(&tree).nth(1).unwrap();
+        tree.get_mut(projects).unwrap().get_mut().is_collapsed = true;
+
+        save_db(&mut tree, root, &path).unwrap();
+        let (mut loaded, loaded_root) = load_db(&path).unwrap();
+
+        assert_eq!(
+            parser::map_to_list(&loaded, loaded_root, false, 0),
+            parser::map_to_list(&tree, root, false, 0)
+        );
+        let loaded_projects = loaded_root.children(&loaded).nth(1).unwrap();
+        assert!(loaded.get(loaded_projects).unwrap().get().is_collapsed);
+
+        // Saving again replaces rather than duplicates
+        save_db(&mut loaded, loaded_root, &path).unwrap();
+        let (reloaded, _) = load_db(&path).unwrap();
+        assert_eq!(reloaded.count(), loaded.count());
+    }
+
+    #[test]
+    fn test_saving_again_keeps_row_ids() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("map.hmmdb");
+        let (mut tree, root) = sample_tree();
+        let rows = |tree: &Arena<Node>| -> HashMap<NodeId, i64> {
+            root.descendants(tree)
+                .filter_map(|id| Some((id, tree[id].get().row?.id)))
+                .collect()
+        };
+
+        save_db(&mut tree, root, &path).unwrap();
+        let first = rows(&tree);
+        assert_eq!(first.len(), 5);
+
+        // Nodes new to the database, then and now, are written once
+        let inbox = root.children(&tree).next().unwrap();
+        inbox.append_value(Node::new("Water plants".to_string()), &mut tree);
+        save_db(&mut tree, root, &path).unwrap();
+        let second = rows(&tree);
+        save_db(&mut tree, root, &path).unwrap();
+        assert_eq!(rows(&tree), second);
+        assert!(first.iter().all(|(id, row)| second[id] == *row));
+
+        let (loaded, loaded_root) = load_db(&path).unwrap();
+        let mut loaded_rows: Vec<i64> = loaded_root
+            .descendants(&loaded)
+            .filter_map(|id| Some(loaded[id].get().row?.id))
+            .collect();
+        let mut saved_rows: Vec<i64> = second.into_values().collect();
+        loaded_rows.sort_unstable();
+        saved_rows.sort_unstable();
+        assert_eq!(loaded_rows, saved_rows);
+    }
+
+    #[test]
+    fn test_parser_dispatches_on_extension() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("map.hmmdb");
+        let (mut tree, root) = sample_tree();
+
+        parser::save_file(&mut tree, root, &path).unwrap();
+        let (loaded, loaded_root) = parser::load_file(&path).unwrap();
+        assert_eq!(loaded.get(loaded_root).unwrap().get().title, "Root");
+    }
+
+    #[test]
+    fn test_append_child_and_search() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("map.hmmdb");
+        let (mut tree, root) = sample_tree();
+        save_db(&mut tree, root, &path).unwrap();
+
+        append_child(&path, &["Inbox"], "Buy rust-proof paint").unwrap();
+        assert!(append_child(&path, &["Missing"], "Nope").is_err());
+
+        let (loaded, loaded_root) = load_db(&path).unwrap();
+        let inbox = loaded_root.children(&loaded).next().unwrap();
+        let captured = inbox.children(&loaded).next().unwrap();
+        assert_eq!(
+            loaded.get(captured).unwrap().get().title,
+            "Buy rust-proof paint"
+        );
+
+        let results = search(&path, "rust").unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.contains(&vec![
+            "Root".to_string(),
+            "Projects".to_string(),
+            "Rust rewrite".to_string()
+        ]));
+    }
+
+    #[test]
+    fn test_save_keeps_rows_captured_since_loading() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("map.hmmdb");
+        let (mut tree, root) = sample_tree();
+        save_db(&mut tree, root, &path).unwrap();
+
+        let (mut tree, root) = load_db(&path).unwrap();
+        append_child(&path, &["Inbox"], "Call the plumber").unwrap();
+
+        // Meanwhile the editor renames one node and deletes another
+        let inbox = root.children(&tree).next().unwrap();
+        tree[inbox].get_mut().title = "Inbox today".to_string();
+        let garden = root.descendants(&tree).last().unwrap();
+        garden.remove_subtree(&mut tree);
+        save_db(&mut tree, root, &path).unwrap();
+
+        let (loaded, loaded_root) = load_db(&path).unwrap();
+        assert_eq!(
+            parser::map_to_list(&loaded, loaded_root, false, 0),
+            "Root\n\tInbox today\n\t\tCall the plumber\n\tProjects\n\t\tRust rewrite\n"
+        );
+        // Rows of the nodes that were loaded keep their ids
+        let row_ids = |tree: &Arena<Node>, root: NodeId| -> Vec<i64> {
+            root.descendants(tree)
+                .filter(|&id| tree[id].get().title != "Call the plumber")
+                .filter_map(|id| Some(tree[id].get().row?.id))
+                .collect()
+        };
+        assert_eq!(row_ids(&tree, root), row_ids(&loaded, loaded_root));
+
+        // Once the editor has loaded the captured row, deleting it sticks
+        let (mut tree, root) = (loaded, loaded_root);
+        let captured = root.descendants(&tree).nth(2).unwrap();
+        captured.remove_subtree(&mut tree);
+        save_db(&mut tree, root, &path).unwrap();
+        let (loaded, loaded_root) = load_db(&path).unwrap();
+        assert_eq!(loaded_root.descendants(&loaded).count(), 4);
+    }
+
+    #[test]
+    fn test_save_moves_captures_under_deleted_nodes_to_the_root() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("map.hmmdb");
+        let (mut tree, root) = sample_tree();
+        save_db(&mut tree, root, &path).unwrap();
+
+        let (mut tree, root) = load_db(&path).unwrap();
+        append_child(&path, &["Projects", "Garden"], "Buy seeds").unwrap();
+
+        // Meanwhile the editor deletes the branch the capture went into
+        let projects = root.children(&tree).nth(1).unwrap();
+        projects.remove_subtree(&mut tree);
+        save_db(&mut tree, root, &path).unwrap();
+
+        let (loaded, loaded_root) = load_db(&path).unwrap();
+        assert_eq!(
+            parser::map_to_list(&loaded, loaded_root, false, 0),
+            "Root\n\tInbox\n\tBuy seeds\n"
+        );
+    }
+
+    #[test]
+    fn test_load_branch_reads_only_the_branch() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("map.hmmdb");
+        let (mut tree, root) = sample_tree();
+        save_db(&mut tree, root, &path).unwrap();
+
+        let (branch, branch_root) = load_branch(&path, &["Projects"]).unwrap();
+        assert_eq!(
+            parser::map_to_list(&branch, branch_root, false, 0),
+            "Projects\n\tRust rewrite\n\tGarden\n"
+        );
+        assert!(load_branch(&path, &["Missing"]).is_err());
+    }
+
+    #[test]
+    fn test_empty_database_loads_default_map() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("new.hmmdb");
+
+        let (tree, root) = load_db(&path).unwrap();
+        assert_eq!(tree.get(root).unwrap().get().title, "New Mind Map");
+    }
+}
@@ -0,0 +1,112 @@
+//! Machine-readable editor status for status bar scripts.
+//!
+//! With `--status-file`, the file is replaced with one line of JSON whenever
+//! the open map, the selected node, the dirty state or the viewport changes,
+//! e.g. `{"file":"notes.hmm","node":["Root","Ideas"],"dirty":true,...}`.
+
+use crate::app::AppState;
+use crate::pins::title_path;
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::PathBuf;
+
+/// What the editor is showing, as written to the status file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Status {
+    /// Open map, if it has a file
+    pub file: Option<PathBuf>,
+    /// Titles from the root down to the selected node
+    pub node: Vec<String>,
+    pub dirty: bool,
+    pub viewport_top: f64,
+    pub viewport_left: f64,
+}
+
+impl Status {
+    pub fn of(app: &AppState) -> Self {
+        let node = match (app.root_id, app.active_node_id) {
+            (Some(root_id), Some(node_id)) if app.is_in_map(node_id) => {
+                title_path(&app.tree, root_id, node_id)
+            }
+            _ => Vec::new(),
+        };
+        Self {
+            file: app.filename.clone(),
+            node,
+            dirty: app.is_dirty,
+            viewport_top: app.viewport_top,
+            viewport_left: app.viewport_left,
+        }
+    }
+}
+
+/// File kept up to date with the editor's [`Status`]
+#[derive(Debug)]
+pub struct StatusFile {
+    path: PathBuf,
+    last: Option<Status>,
+}
+
+impl StatusFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path, last: None }
+    }
+
+    /// Rewrite the file if the status changed since it was last written.
+    /// The new file is renamed into place, so readers never see half of it.
+    pub fn update(&mut self, app: &AppState) -> Result<()> {
+        let status = Status::of(app);
+        if self.last.as_ref() == Some(&status) {
+            return Ok(());
+        }
+
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        fs::write(&temp, serde_json::to_string(&status)? + "\n")?;
+        fs::rename(&temp, &self.path)?;
+        self.last = Some(status);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use crate::model::Node;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_status_file_follows_the_selection() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("status.json");
+
+        let mut app = AppState::new(AppConfig::default());
+        let root = app.tree.new_node(Node::new("Root".to_string()));
+        let child = app.tree.new_node(Node::new("Ideas".to_string()));
+        root.append(child, &mut app.tree);
+        app.root_id = Some(root);
+        app.active_node_id = Some(child);
+        app.filename = Some(PathBuf::from("notes.hmm"));
+
+        let mut status_file = StatusFile::new(path.clone());
+        status_file.update(&app).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written["file"], "notes.hmm");
+        assert_eq!(written["node"], serde_json::json!(["Root", "Ideas"]));
+        assert_eq!(written["dirty"], false);
+
+        // Unchanged status is not written again
+        fs::remove_file(&path).unwrap();
+        status_file.update(&app).unwrap();
+        assert!(!path.exists());
+
+        app.is_dirty = true;
+        status_file.update(&app).unwrap();
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .contains("\"dirty\":true"));
+    }
+}
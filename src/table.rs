@@ -0,0 +1,93 @@
+//! Nodes holding a small table.
+//!
+//! A title starting with `|` is a table: cells are separated by `|` and rows
+//! by `||`, so `|Tool|Speed||hmm|fast||vim|faster|` is shown as
+//!
+//! ```text
+//! Tool │ Speed
+//! hmm  │ fast
+//! vim  │ faster
+//! ```
+//!
+//! Rows too wide for the node are cut short with an ellipsis.
+
+use crate::code::truncate;
+use unicode_width::UnicodeWidthStr;
+
+/// Drawn between the cells of a row
+pub const CELL_SEPARATOR: &str = " │ ";
+
+/// Cells of each row of a table title, or `None` if `title` is no table
+pub fn table_rows(title: &str) -> Option<Vec<Vec<&str>>> {
+    let body = title.trim().strip_prefix('|')?;
+    let body = body.strip_suffix('|').unwrap_or(body);
+    if !body.contains('|') && !title.trim().ends_with('|') {
+        return None;
+    }
+
+    let rows = body
+        .split("||")
+        .map(|row| row.split('|').map(str::trim).collect())
+        .collect();
+    Some(rows)
+}
+
+/// The lines a table is drawn on, with its columns aligned and each line at
+/// most `max_width` columns wide
+pub fn table_lines(rows: &[Vec<&str>], max_width: usize) -> Vec<String> {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.get(column))
+                .map(|cell| cell.width())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    rows.iter()
+        .map(|row| {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, &width)| format!("{}{}", cell, " ".repeat(width - cell.width())))
+                .collect();
+            truncate(cells.join(CELL_SEPARATOR).trim_end(), max_width)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_rows() {
+        assert_eq!(
+            table_rows("|Tool|Speed||hmm|fast|"),
+            Some(vec![vec!["Tool", "Speed"], vec!["hmm", "fast"]])
+        );
+        assert_eq!(table_rows("| a | b"), Some(vec![vec!["a", "b"]]));
+        assert_eq!(table_rows("|single|"), Some(vec![vec!["single"]]));
+        assert_eq!(table_rows("|not a table"), None);
+        assert_eq!(table_rows("a | b"), None);
+    }
+
+    #[test]
+    fn test_table_lines_align_columns() {
+        let rows = table_rows("|Tool|Speed||hmm|fast||vim|faster|").unwrap();
+        assert_eq!(
+            table_lines(&rows, 40),
+            vec!["Tool │ Speed", "hmm  │ fast", "vim  │ faster"]
+        );
+
+        // Short rows end early, wide ones are cut short
+        let rows = table_rows("|a|b|c||long cell|").unwrap();
+        assert_eq!(
+            table_lines(&rows, 40),
+            vec!["a         │ b │ c", "long cell"]
+        );
+        assert_eq!(table_lines(&rows, 8)[0], "a      …");
+    }
+}
@@ -1,3 +1,5 @@
+use crate::math::math_spans;
+
 // Text wrapper utility
 pub struct TextWrapper;
 
@@ -7,7 +9,7 @@ impl TextWrapper {
         let mut current_line = String::new();
         let mut current_width = 0;
 
-        for word in text.split_whitespace() {
+        for word in Self::words(text) {
             let word_width = unicode_width::UnicodeWidthStr::width(word);
 
             if current_width > 0 && current_width + 1 + word_width > max_width {
@@ -34,4 +36,27 @@ impl TextWrapper {
 
         lines
     }
+
+    /// Whitespace-separated words, with each `$...$` math span kept in one
+    /// word so formulas are not split across lines
+    fn words(text: &str) -> Vec<&str> {
+        let spans = math_spans(text);
+        let in_math = |i: usize| spans.iter().any(|span| span.contains(&i));
+
+        let mut words = Vec::new();
+        let mut start = None;
+        for (i, c) in text.char_indices() {
+            if c.is_whitespace() && !in_math(i) {
+                if let Some(start) = start.take() {
+                    words.push(&text[start..i]);
+                }
+            } else if start.is_none() {
+                start = Some(i);
+            }
+        }
+        if let Some(start) = start {
+            words.push(&text[start..]);
+        }
+        words
+    }
 }
@@ -0,0 +1,308 @@
+//! Colors of the map by what they mean rather than what they are.
+//!
+//! The renderer asks for the color of a [`Role`], such as the selected node or
+//! a completed task. A [`Palette`] gives every role a color; the default one
+//! uses the terminal's own colors and two others are tuned for deuteranopia
+//! and protanopia with the Okabe-Ito colors. The `[theme]` table of the config
+//! overrides single roles, and [`clashes`] finds roles shown side by side that
+//! a theme made hard to tell apart, as the chosen palette's viewers see them.
+
+use crate::config::AppConfig;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+/// Built-in colors for every role
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    #[default]
+    Default,
+    /// For red-green color blindness with weak green cones, the most common
+    Deuteranopia,
+    /// For red-green color blindness with weak red cones
+    Protanopia,
+}
+
+/// What a color on the map stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Role {
+    /// Background of the selected node
+    Active,
+    /// Background of nodes ticked while picking
+    Ticked,
+    /// Background of nodes added since the compared version
+    Added,
+    /// Background of nodes removed since the compared version
+    Removed,
+    /// Background of nodes changed since the compared version
+    Changed,
+    /// Text of completed tasks
+    Done,
+    /// Text of tasks still to do
+    Pending,
+    /// Text of hidden nodes and comments
+    Muted,
+}
+
+impl Role {
+    pub const ALL: [Role; 8] = [
+        Role::Active,
+        Role::Ticked,
+        Role::Added,
+        Role::Removed,
+        Role::Changed,
+        Role::Done,
+        Role::Pending,
+        Role::Muted,
+    ];
+
+    /// Name in the `[theme]` table
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::Active => "active",
+            Role::Ticked => "ticked",
+            Role::Added => "added",
+            Role::Removed => "removed",
+            Role::Changed => "changed",
+            Role::Done => "done",
+            Role::Pending => "pending",
+            Role::Muted => "muted",
+        }
+    }
+
+    /// Whether this role colors node backgrounds rather than their text
+    fn is_background(self) -> bool {
+        matches!(
+            self,
+            Role::Active | Role::Ticked | Role::Added | Role::Removed | Role::Changed
+        )
+    }
+}
+
+impl Palette {
+    /// Color of `role`, as a name, a 256-color index or `#rrggbb`
+    pub fn color(self, role: Role) -> &'static str {
+        match (self, role) {
+            (Palette::Default, Role::Active) => "yellow",
+            (Palette::Default, Role::Ticked) => "magenta",
+            (Palette::Default, Role::Added) => "green",
+            (Palette::Default, Role::Removed) => "red",
+            (Palette::Default, Role::Changed) => "cyan",
+            (Palette::Default, Role::Done) => "green",
+            (Palette::Default, Role::Pending) => "red",
+            (Palette::Deuteranopia, Role::Active) => "#f0e442",
+            (Palette::Deuteranopia, Role::Ticked) => "#cc79a7",
+            (Palette::Deuteranopia, Role::Added) => "#56b4e9",
+            (Palette::Deuteranopia, Role::Removed) => "#d55e00",
+            (Palette::Deuteranopia, Role::Changed) => "#0072b2",
+            (Palette::Deuteranopia, Role::Done) => "#56b4e9",
+            (Palette::Deuteranopia, Role::Pending) => "#e69f00",
+            (Palette::Protanopia, Role::Active) => "#f0e442",
+            (Palette::Protanopia, Role::Ticked) => "#cc79a7",
+            (Palette::Protanopia, Role::Added) => "#56b4e9",
+            (Palette::Protanopia, Role::Removed) => "#e69f00",
+            (Palette::Protanopia, Role::Changed) => "#0072b2",
+            (Palette::Protanopia, Role::Done) => "#56b4e9",
+            (Palette::Protanopia, Role::Pending) => "#e69f00",
+            (_, Role::Muted) => "darkgray",
+        }
+    }
+
+    /// How a viewer the palette is made for sees `color`
+    fn perceived(self, (r, g, b): Rgb) -> Rgb {
+        // Machado et al. (2009) at full severity, applied to sRGB values
+        let matrix = match self {
+            Palette::Default => return (r, g, b),
+            Palette::Deuteranopia => [
+                [0.367, 0.861, -0.228],
+                [0.280, 0.673, 0.047],
+                [-0.012, 0.043, 0.969],
+            ],
+            Palette::Protanopia => [
+                [0.152, 1.053, -0.205],
+                [0.115, 0.786, 0.099],
+                [-0.004, -0.048, 1.052],
+            ],
+        };
+        let [r, g, b] = matrix.map(|row| {
+            let value = row[0] * r as f64 + row[1] * g as f64 + row[2] * b as f64;
+            value.round().clamp(0.0, 255.0) as u8
+        });
+        (r, g, b)
+    }
+}
+
+type Rgb = (u8, u8, u8);
+
+/// Colors closer than this, by the redmean distance, are hard to tell apart
+const MIN_DISTANCE: f64 = 80.0;
+
+/// Color of `role`: the `[theme]` table's, else the palette's
+pub fn role_color(config: &AppConfig, role: Role) -> &str {
+    config
+        .theme
+        .get(role.name())
+        .map_or_else(|| config.palette.color(role), String::as_str)
+}
+
+/// Refuse a `[theme]` table naming unknown roles or colors
+pub fn check_theme(config: &AppConfig) -> Result<()> {
+    for (name, color) in &config.theme {
+        if !Role::ALL.iter().any(|role| role.name() == name) {
+            let names: Vec<_> = Role::ALL.iter().map(|role| role.name()).collect();
+            bail!(
+                "unknown theme role '{name}'; the roles are {}",
+                names.join(", ")
+            );
+        }
+        if parse_rgb(color).is_none() {
+            bail!("theme role '{name}' has an unknown color '{color}'");
+        }
+    }
+    Ok(())
+}
+
+/// Pairs of roles shown side by side, both backgrounds or both text, whose
+/// colors the palette's viewers can hardly tell apart
+pub fn clashes(config: &AppConfig) -> Vec<(Role, Role)> {
+    let colors: Vec<(Role, Rgb)> = Role::ALL
+        .into_iter()
+        .filter_map(|role| {
+            let rgb = parse_rgb(role_color(config, role))?;
+            Some((role, config.palette.perceived(rgb)))
+        })
+        .collect();
+    let mut clashes = Vec::new();
+    for (i, &(first, a)) in colors.iter().enumerate() {
+        for &(second, b) in &colors[i + 1..] {
+            if first.is_background() == second.is_background() && distance(a, b) < MIN_DISTANCE {
+                clashes.push((first, second));
+            }
+        }
+    }
+    clashes
+}
+
+/// The "redmean" approximation of perceived distance between two colors
+fn distance(a: Rgb, b: Rgb) -> f64 {
+    let mean_red = (a.0 as f64 + b.0 as f64) / 2.0;
+    let (dr, dg, db) = (
+        a.0 as f64 - b.0 as f64,
+        a.1 as f64 - b.1 as f64,
+        a.2 as f64 - b.2 as f64,
+    );
+    ((2.0 + mean_red / 256.0) * dr * dr
+        + 4.0 * dg * dg
+        + (2.0 + (255.0 - mean_red) / 256.0) * db * db)
+        .sqrt()
+}
+
+/// The 16 terminal colors, as xterm shows them by default
+const ANSI: [(&str, Rgb); 16] = [
+    ("black", (0, 0, 0)),
+    ("red", (205, 0, 0)),
+    ("green", (0, 205, 0)),
+    ("yellow", (205, 205, 0)),
+    ("blue", (0, 0, 238)),
+    ("magenta", (205, 0, 205)),
+    ("cyan", (0, 205, 205)),
+    ("gray", (229, 229, 229)),
+    ("darkgray", (127, 127, 127)),
+    ("lightred", (255, 0, 0)),
+    ("lightgreen", (0, 255, 0)),
+    ("lightyellow", (255, 255, 0)),
+    ("lightblue", (92, 92, 255)),
+    ("lightmagenta", (255, 0, 255)),
+    ("lightcyan", (0, 255, 255)),
+    ("white", (255, 255, 255)),
+];
+
+/// RGB of a color written as the renderer reads it: a name such as
+/// `darkgray` or `light red`, a 256-color index or `#rrggbb`
+pub fn parse_rgb(color: &str) -> Option<Rgb> {
+    if let Some(hex) = color.strip_prefix('#') {
+        let value = u32::from_str_radix(hex, 16)
+            .ok()
+            .filter(|_| hex.len() == 6)?;
+        return Some(((value >> 16) as u8, (value >> 8) as u8, value as u8));
+    }
+    if let Ok(index) = color.parse::<u8>() {
+        return Some(indexed_rgb(index));
+    }
+    let name = color
+        .to_lowercase()
+        .replace([' ', '-', '_'], "")
+        .replace("grey", "gray")
+        .replace("bright", "light");
+    ANSI.iter()
+        .find(|(ansi, _)| *ansi == name)
+        .map(|&(_, rgb)| rgb)
+}
+
+/// RGB of an entry of the xterm 256-color table
+fn indexed_rgb(index: u8) -> Rgb {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    match index {
+        0..=15 => ANSI[index as usize].1,
+        16..=231 => {
+            let cube = index - 16;
+            let level = |i: u8| LEVELS[i as usize];
+            (level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+        }
+        _ => {
+            let gray = 8 + 10 * (index - 232);
+            (gray, gray, gray)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rgb() {
+        assert_eq!(parse_rgb("#0072b2"), Some((0, 114, 178)));
+        assert_eq!(parse_rgb("Dark Grey"), Some((127, 127, 127)));
+        assert_eq!(parse_rgb("light-red"), Some((255, 0, 0)));
+        assert_eq!(parse_rgb("red"), Some((205, 0, 0)));
+        assert_eq!(parse_rgb("196"), Some((255, 0, 0)));
+        assert_eq!(parse_rgb("244"), Some((128, 128, 128)));
+        assert_eq!(parse_rgb("#12345"), None);
+        assert_eq!(parse_rgb("teal"), None);
+    }
+
+    #[test]
+    fn test_palettes_have_no_clashes() {
+        for palette in [Palette::Default, Palette::Deuteranopia, Palette::Protanopia] {
+            let config = AppConfig {
+                palette,
+                ..AppConfig::default()
+            };
+            assert_eq!(clashes(&config), [], "{palette:?}");
+        }
+    }
+
+    #[test]
+    fn test_theme_clashes_and_checks() {
+        let mut config = AppConfig::default();
+        config.theme.insert("ticked".into(), "#d7d700".into());
+        assert_eq!(clashes(&config), [(Role::Active, Role::Ticked)]);
+        assert_eq!(role_color(&config, Role::Ticked), "#d7d700");
+
+        // A green and an olive only deuteranopes mistake for each other
+        config.theme.clear();
+        config.theme.insert("done".into(), "#2e8b00".into());
+        config.theme.insert("pending".into(), "#8a7000".into());
+        assert!(clashes(&config).is_empty());
+        config.palette = Palette::Deuteranopia;
+        assert_eq!(clashes(&config), [(Role::Done, Role::Pending)]);
+
+        assert!(check_theme(&config).is_ok());
+        config.theme.insert("search".into(), "blue".into());
+        assert!(check_theme(&config).is_err());
+        config.theme.clear();
+        config.theme.insert("active".into(), "teal".into());
+        assert!(check_theme(&config).is_err());
+    }
+}
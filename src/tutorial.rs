@@ -0,0 +1,170 @@
+//! The interactive tutorial map shown on first run and with `--tutorial`.
+//!
+//! Steps name the keys from the effective [`Keymap`], so the tutorial stays
+//! correct when keys are remapped, and their text comes from the `tutorial`
+//! section of the [`Catalog`]. Each step can be checked off with the
+//! `toggle_symbol` command.
+
+use crate::i18n::Catalog;
+use crate::keymap::Keymap;
+use crate::model::{Node, NodeId};
+use indextree::Arena;
+use std::fmt;
+
+/// Label for the first key bound to a command, e.g. `j` or `C-r`
+fn key(keymap: &Keymap, command: &str) -> String {
+    keymap
+        .keys_for(command)
+        .first()
+        .map(|keys| keymap.label(keys))
+        .unwrap_or_else(|| format!("<{} unbound>", command))
+}
+
+/// Build the tutorial map for the given keymap, in the catalog's language
+pub fn tutorial_map(keymap: &Keymap, catalog: &Catalog) -> (Arena<Node>, NodeId) {
+    // The text under `tutorial.<name>` with each placeholder standing for
+    // the key of a command
+    let step = |name: &str, commands: &[(&str, &str)]| {
+        let keys: Vec<(&str, String)> = commands
+            .iter()
+            .map(|&(placeholder, command)| (placeholder, key(keymap, command)))
+            .collect();
+        let args: Vec<(&str, &dyn fmt::Display)> = keys
+            .iter()
+            .map(|(placeholder, key)| (*placeholder, key as &dyn fmt::Display))
+            .collect();
+        catalog.format(&format!("tutorial.{name}"), &args)
+    };
+
+    let sections = [
+        (
+            "moving",
+            vec![
+                step("move_down_up", &[("down", "go_down"), ("up", "go_up")]),
+                step("step_in_out", &[("right", "go_right"), ("left", "go_left")]),
+                step(
+                    "jump",
+                    &[
+                        ("top", "go_to_top"),
+                        ("bottom", "go_to_bottom"),
+                        ("root", "go_to_root"),
+                    ],
+                ),
+            ],
+        ),
+        (
+            "editing",
+            vec![
+                step("add_sibling", &[("key", "insert_sibling")]),
+                step("add_child", &[("key", "insert_child")]),
+                step(
+                    "edit",
+                    &[("append", "edit_append"), ("replace", "edit_replace")],
+                ),
+                step(
+                    "delete_undo",
+                    &[
+                        ("delete", "delete_node"),
+                        ("undo", "undo"),
+                        ("redo", "redo"),
+                    ],
+                ),
+                step(
+                    "move_node",
+                    &[("down", "move_node_down"), ("up", "move_node_up")],
+                ),
+            ],
+        ),
+        (
+            "folding",
+            vec![
+                step("toggle_fold", &[("key", "toggle_collapse")]),
+                step(
+                    "fold_all",
+                    &[("collapse", "collapse_all"), ("expand", "expand_all")],
+                ),
+                step(
+                    "fold_levels",
+                    &[
+                        ("one", "collapse_to_level_1"),
+                        ("two", "collapse_to_level_2"),
+                    ],
+                ),
+            ],
+        ),
+        (
+            "saving",
+            vec![step("save_as", &[("key", "save_as")]), step("open", &[])],
+        ),
+    ];
+
+    let mut tree = Arena::new();
+    let root = tree.new_node(Node::new(step("welcome", &[("key", "toggle_symbol")])));
+
+    for (title, steps) in sections {
+        let section = tree.new_node(Node::new(step(title, &[])));
+        root.append(section, &mut tree);
+        for text in steps {
+            let step = tree.new_node(Node::new(text));
+            section.append(step, &mut tree);
+        }
+    }
+
+    let help = tree.new_node(Node::new(step(
+        "help",
+        &[("help", "help"), ("quit", "quit")],
+    )));
+    root.append(help, &mut tree);
+
+    (tree, root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_tutorial_map_structure() {
+        let (tree, root) = tutorial_map(&Keymap::default(), &Catalog::default());
+
+        let sections: Vec<String> = root
+            .children(&tree)
+            .map(|id| tree[id].get().title.clone())
+            .collect();
+        assert_eq!(sections[0], "Moving around");
+        assert_eq!(sections.len(), 5);
+        assert!(tree[root].get().title.contains("Press t"));
+    }
+
+    #[test]
+    fn test_tutorial_follows_remapped_keys() {
+        let overrides = HashMap::from([("go_down".to_string(), "C-n".to_string())]);
+        let (tree, root) = tutorial_map(
+            &Keymap::new(&overrides, crate::keymap::DEFAULT_LEADER).unwrap(),
+            &Catalog::default(),
+        );
+
+        let first_step = root
+            .descendants(&tree)
+            .find(|&id| tree[id].get().title.contains("move down"))
+            .unwrap();
+        assert_eq!(
+            tree[first_step].get().title,
+            "Press C-n and k to move down and up"
+        );
+    }
+
+    #[test]
+    fn test_tutorial_in_the_catalog_language() {
+        let (tree, root) = tutorial_map(&Keymap::default(), &Catalog::new("de"));
+
+        assert_eq!(
+            tree[root.children(&tree).next().unwrap()].get().title,
+            "Bewegen"
+        );
+        assert!(root
+            .descendants(&tree)
+            .any(|id| tree[id].get().title == "Drücke j und k, um nach unten und oben zu gehen"));
+    }
+}
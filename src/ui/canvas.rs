@@ -1,10 +1,14 @@
 use crate::ui::constants::{CharBuffer, StyleBuffer};
 use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
     style::Style,
     text::{Line, Span},
 };
+use unicode_width::UnicodeWidthChar;
 
 // Buffer canvas for drawing characters and styles
+#[derive(Default)]
 pub struct BufferCanvas {
     pub char_buffer: CharBuffer,
     pub style_buffer: StyleBuffer,
@@ -22,9 +26,27 @@ impl BufferCanvas {
         }
     }
 
+    /// Blank the canvas for a new frame, keeping the row allocations
+    pub fn reset(&mut self, width: usize, height: usize) {
+        self.char_buffer.resize_with(height, Vec::new);
+        self.style_buffer.resize_with(height, Vec::new);
+        for row in &mut self.char_buffer {
+            row.clear();
+            row.resize(width, ' ');
+        }
+        for row in &mut self.style_buffer {
+            row.clear();
+            row.resize(width, Style::default());
+        }
+        self.width = width;
+        self.height = height;
+    }
+
+    /// Put an unstyled character, covering the style of anything drawn under it
     pub fn set_char(&mut self, x: usize, y: usize, ch: char) {
         if self.in_bounds(x, y) {
             self.char_buffer[y][x] = ch;
+            self.style_buffer[y][x] = Style::default();
         }
     }
 
@@ -47,6 +69,56 @@ impl BufferCanvas {
         y < self.height && x < self.width
     }
 
+    /// Plain-text contents, one line per row with trailing spaces trimmed
+    pub fn to_plain_string(&self) -> String {
+        let mut output = String::new();
+        for row in &self.char_buffer {
+            let line: String = row.iter().collect();
+            output.push_str(line.trim_end());
+            output.push('\n');
+        }
+        output
+    }
+
+    /// Copy the canvas into `buf` at `area` cell by cell, laying out wide
+    /// characters like a `Paragraph` of [`Self::to_lines`] would
+    pub fn blit(&self, buf: &mut Buffer, area: Rect) {
+        let rows = self.char_buffer.iter().zip(&self.style_buffer);
+        for (y, (chars, styles)) in rows.take(area.height as usize).enumerate() {
+            let y = area.y + y as u16;
+            let mut column = 0u16;
+            for (&ch, &style) in chars.iter().zip(styles) {
+                // Control characters are dropped, combining marks join the previous cell
+                let Some(width) = ch.width().map(|w| w as u16) else {
+                    continue;
+                };
+                if width == 0 {
+                    if let Some(cell) = column
+                        .checked_sub(1)
+                        .and_then(|x| buf.cell_mut((area.x + x, y)))
+                    {
+                        let symbol = format!("{}{}", cell.symbol(), ch);
+                        cell.set_symbol(&symbol);
+                    }
+                    continue;
+                }
+                if column + width > area.width {
+                    break;
+                }
+                if let Some(cell) = buf.cell_mut((area.x + column, y)) {
+                    cell.set_char(ch).set_style(style);
+                }
+                // Cells covered by a wide character are left blank
+                for x in column + 1..column + width {
+                    if let Some(cell) = buf.cell_mut((area.x + x, y)) {
+                        cell.reset();
+                    }
+                }
+                column += width;
+            }
+        }
+    }
+
     pub fn to_lines(&self) -> Vec<Line<'_>> {
         let mut lines = Vec::new();
 
@@ -0,0 +1,61 @@
+use super::canvas::BufferCanvas;
+use super::mindmap::MindMapRenderer;
+use super::snapshots::render_other_map;
+use crate::app::{AppMode, AppState};
+use crate::diff::diff_trees;
+use crate::layout::LayoutEngine;
+use crate::parser::parse_hmm_content;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    widgets::{Block, Borders, Clear},
+    Frame,
+};
+
+// The current map beside another version of it, added and changed nodes
+// highlighted on the left and removed and changed ones on the right
+pub struct CompareRenderer;
+
+impl CompareRenderer {
+    pub fn render(
+        frame: &mut Frame,
+        area: Rect,
+        app: &AppState,
+        layout: &LayoutEngine,
+        canvas: &mut BufferCanvas,
+    ) {
+        let AppMode::Compare {
+            ref label,
+            ref other,
+            ..
+        } = app.mode
+        else {
+            return;
+        };
+        let (Some(root_id), Ok((other_tree, other_root))) = (app.root_id, parse_hmm_content(other))
+        else {
+            return;
+        };
+        let diff = diff_trees(&other_tree, other_root, &app.tree, root_id);
+
+        let halves = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(area);
+
+        let now = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} ", app.catalog.get("ui.snapshots_now")));
+        MindMapRenderer::new(app, layout)
+            .with_changes(&diff.new)
+            .render(frame, now.inner(halves[0]), canvas);
+        frame.render_widget(now, halves[0]);
+
+        let then = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {label} "));
+        let inner = then.inner(halves[1]);
+        frame.render_widget(Clear, halves[1]);
+        frame.render_widget(then, halves[1]);
+        render_other_map(frame, inner, app, other_tree, other_root, Some(&diff.old));
+    }
+}
@@ -7,6 +7,7 @@ use crate::ui::constants::{
     VERTICAL_CONNECTOR_OFFSET,
 };
 use ratatui::layout::Rect;
+use std::ops::Range;
 
 // Connection renderer
 pub struct ConnectionRenderer<'a> {
@@ -31,14 +32,25 @@ impl<'a> ConnectionRenderer<'a> {
         }
     }
 
-    pub fn draw_node_connections(&mut self, node_id: NodeId) {
+    /// Draw the connections below `root_id`, parents before their children.
+    pub fn draw_node_connections(&mut self, root_id: NodeId) {
+        let mut stack = vec![root_id];
+        while let Some(node_id) = stack.pop() {
+            let children = self.draw_connections_from(node_id);
+            stack.extend(children.into_iter().rev());
+        }
+    }
+
+    /// Draw the connections from one node to its children and return the
+    /// children whose own connections still need drawing
+    fn draw_connections_from(&mut self, node_id: NodeId) -> Vec<NodeId> {
         let Some(node_ref) = self.app.tree.get(node_id) else {
-            return;
+            return Vec::new();
         };
         let node = node_ref.get();
 
         let Some(node_layout) = self.layout.nodes.get(&node_id) else {
-            return;
+            return Vec::new();
         };
 
         // Get children information
@@ -54,6 +66,14 @@ impl<'a> ConnectionRenderer<'a> {
             self.draw_collapsed_indicator(node_layout, has_hidden);
         } else if visible_children.is_empty() && !all_children.is_empty() {
             self.draw_hidden_only_indicator(node_layout, node_middle_y);
+        } else if let Some(&rows) = self.layout.columns.get(&node_id) {
+            self.draw_packed_connections(
+                node_layout,
+                node_middle_y,
+                &visible_children,
+                rows,
+                has_hidden,
+            );
         } else if visible_children.len() == 1 {
             self.draw_single_child_connection(
                 node_layout,
@@ -70,12 +90,11 @@ impl<'a> ConnectionRenderer<'a> {
             );
         }
 
-        // Recursively draw connections for visible children
-        // Only recurse if the node is not collapsed
-        if !node.is_collapsed {
-            for child_id in visible_children {
-                self.draw_node_connections(child_id);
-            }
+        // Continue with visible children unless the node is collapsed
+        if node.is_collapsed {
+            Vec::new()
+        } else {
+            visible_children
         }
     }
 
@@ -213,6 +232,31 @@ impl<'a> ConnectionRenderer<'a> {
         self.fix_junction(vert_x, self.viewport_y(middle_y as f64));
     }
 
+    /// Connect the first column of packed children to the parent, and give
+    /// each further column a spine of its own
+    fn draw_packed_connections(
+        &mut self,
+        node_layout: &crate::layout::LayoutNode,
+        middle_y: i32,
+        children: &[NodeId],
+        rows: usize,
+        has_hidden: bool,
+    ) {
+        let mut columns = children.chunks(rows);
+        if let Some(first) = columns.next() {
+            self.draw_multi_child_connections(node_layout, middle_y, first, has_hidden);
+        }
+        for column in columns {
+            let (top_child, top_y, bottom_child, bottom_y) = self.find_extremes(column);
+            let Some(top_layout) = self.layout.nodes.get(&top_child) else {
+                continue;
+            };
+            let vert_x = self.viewport_x(top_layout.x - VERTICAL_CONNECTOR_OFFSET);
+            self.draw_vertical_spine(vert_x, top_y, bottom_y);
+            self.draw_child_connectors(vert_x, column, top_child, bottom_child);
+        }
+    }
+
     fn draw_vertical_connection(
         &mut self,
         child_layout: &crate::layout::LayoutNode,
@@ -222,7 +266,7 @@ impl<'a> ConnectionRenderer<'a> {
         let vert_x = self.viewport_x(child_layout.x - VERTICAL_CONNECTOR_OFFSET);
 
         // Draw vertical line
-        for y in y1.min(y2)..y1.max(y2) {
+        for y in self.visible_rows(y1.min(y2), y1.max(y2)) {
             let py = self.viewport_y(y as f64);
             if self.is_in_bounds(vert_x, py) {
                 self.canvas
@@ -254,7 +298,7 @@ impl<'a> ConnectionRenderer<'a> {
     }
 
     fn draw_vertical_spine(&mut self, x: i32, top_y: i32, bottom_y: i32) {
-        for y in top_y..bottom_y {
+        for y in self.visible_rows(top_y, bottom_y) {
             let py = self.viewport_y(y as f64);
             if self.is_in_bounds(x, py) {
                 self.canvas
@@ -341,6 +385,14 @@ impl<'a> ConnectionRenderer<'a> {
         (top_child, top_y, bottom_child, bottom_y)
     }
 
+    /// The part of the map rows `from..to` that can land inside the viewport,
+    /// so long lines in big maps cost only the rows on screen
+    fn visible_rows(&self, from: i32, to: i32) -> Range<i32> {
+        let top = self.app.viewport_top.floor() as i32 - 1;
+        let bottom = self.app.viewport_top.ceil() as i32 + self.area.height as i32 + 1;
+        from.max(top)..to.min(bottom)
+    }
+
     fn is_in_bounds(&self, x: i32, y: i32) -> bool {
         x >= 0 && y >= 0 && x < self.area.width as i32 && y < self.area.height as i32
     }
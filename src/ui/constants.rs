@@ -11,6 +11,10 @@ pub const VERTICAL_CONNECTOR_OFFSET: f64 = 1.0;
 pub const MIDDLE_CONNECTOR_Y_OFFSET: f64 = 0.2;
 pub const STATUS_EDIT_PREFIX: &str = "Edit: ";
 pub const STATUS_SEARCH_PREFIX: &str = "Search: ";
+pub const STATUS_OPEN_PREFIX: &str = "Open: ";
+pub const STATUS_SAVE_AS_PREFIX: &str = "Save as: ";
+pub const STATUS_RENAME_FILE_PREFIX: &str = "Rename file: ";
+pub const STATUS_CAPTURE_PREFIX: &str = "Capture: ";
 
 // Connection line constants
 pub mod connections {
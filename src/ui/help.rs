@@ -1,3 +1,5 @@
+use crate::i18n::Catalog;
+use crate::keymap::{self, Keymap};
 use ratatui::{
     layout::Rect,
     style::{Modifier, Style},
@@ -6,58 +8,12 @@ use ratatui::{
     Frame,
 };
 
-// Help section structure
-pub struct HelpSection {
-    pub title: &'static str,
-    pub items: &'static [(&'static str, &'static str)],
-}
-
-// Help section definitions
-pub const SECTIONS: &[HelpSection] = &[
-    HelpSection {
-        title: "Navigation:",
-        items: &[
-            ("h/←", "Move left (parent)"),
-            ("j/↓", "Move down"),
-            ("k/↑", "Move up"),
-            ("l/→", "Move right (child)"),
-            ("g  ", "Go to top"),
-            ("G  ", "Go to bottom"),
-            ("m/~", "Go to root"),
-        ],
-    },
-    HelpSection {
-        title: "Editing:",
-        items: &[
-            ("e/i", "Edit node (append)"),
-            ("E/I", "Edit node (replace)"),
-            ("o/⏎", "Insert sibling"),
-            ("O/⇥", "Insert child"),
-            ("d  ", "Delete node"),
-            ("D  ", "Delete children"),
-        ],
-    },
-    HelpSection {
-        title: "View:",
-        items: &[
-            ("␣  ", "Toggle collapse"),
-            ("v  ", "Collapse all"),
-            ("b  ", "Expand all"),
-            ("1-5", "Collapse to level"),
-        ],
-    },
-    HelpSection {
-        title: "File:",
-        items: &[("s  ", "Save"), ("S  ", "Save as"), ("q  ", "Quit")],
-    },
-];
-
 // Help renderer
 pub struct HelpRenderer;
 
 impl HelpRenderer {
-    pub fn render(frame: &mut Frame, area: Rect) {
-        let help_text = Self::build_help_text();
+    pub fn render(frame: &mut Frame, area: Rect, keymap: &Keymap, catalog: &Catalog) {
+        let help_text = Self::build_help_text(keymap, catalog);
         let block = Block::default().borders(Borders::ALL).title(" Help ");
         let paragraph = Paragraph::new(help_text)
             .block(block)
@@ -66,29 +22,34 @@ impl HelpRenderer {
         frame.render_widget(paragraph, area);
     }
 
-    fn build_help_text() -> Vec<Line<'static>> {
+    fn build_help_text(keymap: &Keymap, catalog: &Catalog) -> Vec<Line<'static>> {
         let mut lines = vec![
             Line::from(vec![Span::styled(
-                "h-m-m Help",
+                catalog.get("ui.help_title"),
                 Style::default().add_modifier(Modifier::BOLD),
             )]),
             Line::from(""),
         ];
 
-        for section in SECTIONS {
+        for (section, items) in keymap.sections(catalog) {
             lines.push(Line::from(vec![Span::styled(
-                section.title,
+                format!("{}:", section),
                 Style::default().add_modifier(Modifier::BOLD),
             )]));
 
-            for (key, desc) in section.items {
-                lines.push(Line::from(format!("  {}  {}", key, desc)));
+            let width = keymap::label_width(&items);
+            for (keys, desc) in items {
+                lines.push(Line::from(format!(
+                    "  {}  {}",
+                    keymap::pad(&keys, width),
+                    desc
+                )));
             }
 
             lines.push(Line::from(""));
         }
 
-        lines.push(Line::from("Press ESC or q to close help"));
+        lines.push(Line::from(catalog.get("ui.help_close")));
         lines
     }
 }
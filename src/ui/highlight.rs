@@ -0,0 +1,47 @@
+use ratatui::style::{Color, Style};
+
+/// Color of code when it is not highlighted
+const CODE_COLOR: Color = Color::Cyan;
+
+/// `line` of code split into runs drawn in their own color, in `lang` if
+/// known, else guessed from the line itself
+#[cfg(feature = "highlight")]
+pub fn highlight(line: &str, lang: Option<&str>) -> Vec<(Style, String)> {
+    use std::sync::OnceLock;
+    use syntect::easy::HighlightLines;
+    use syntect::highlighting::ThemeSet;
+    use syntect::parsing::SyntaxSet;
+
+    /// Theme the runs take their colors from; readable on dark and light backgrounds
+    const THEME: &str = "base16-ocean.dark";
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+
+    let syntaxes = SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines);
+    let themes = THEMES.get_or_init(ThemeSet::load_defaults);
+    let syntax = lang
+        .and_then(|lang| syntaxes.find_syntax_by_token(lang))
+        .or_else(|| syntaxes.find_syntax_by_first_line(line))
+        .unwrap_or_else(|| syntaxes.find_syntax_plain_text());
+
+    let mut highlighter = HighlightLines::new(syntax, &themes.themes[THEME]);
+    match highlighter.highlight_line(line, syntaxes) {
+        Ok(runs) => runs
+            .into_iter()
+            .map(|(style, text)| {
+                let color = Color::Rgb(style.foreground.r, style.foreground.g, style.foreground.b);
+                (Style::default().fg(color), text.to_string())
+            })
+            .collect(),
+        Err(_) => plain(line),
+    }
+}
+
+#[cfg(not(feature = "highlight"))]
+pub fn highlight(line: &str, _lang: Option<&str>) -> Vec<(Style, String)> {
+    plain(line)
+}
+
+fn plain(line: &str) -> Vec<(Style, String)> {
+    vec![(Style::default().fg(CODE_COLOR), line.to_string())]
+}
@@ -0,0 +1,42 @@
+use crate::i18n::Catalog;
+use ratatui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    widgets::Paragraph,
+    Frame,
+};
+
+// Skeleton frame drawn at startup while the map is parsed and laid out
+pub struct LoadingRenderer;
+
+impl LoadingRenderer {
+    pub fn render(frame: &mut Frame, catalog: &Catalog, filename: Option<&str>) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .split(frame.area());
+
+        let message = match filename {
+            Some(path) => catalog.format("ui.loading", &[("path", &path)]),
+            None => catalog.get("ui.starting"),
+        };
+        let map_area = chunks[0];
+        let middle = Rect::new(
+            map_area.x,
+            map_area.y + map_area.height / 2,
+            map_area.width,
+            1.min(map_area.height),
+        );
+        frame.render_widget(
+            Paragraph::new(message)
+                .alignment(Alignment::Center)
+                .style(Style::default().fg(Color::DarkGray)),
+            middle,
+        );
+
+        frame.render_widget(
+            Paragraph::new("h-m-m").style(Style::default().fg(Color::Gray).bg(Color::Black)),
+            chunks[1],
+        );
+    }
+}
@@ -1,56 +1,168 @@
 use crate::app::AppState;
+use crate::code::{code_block, truncate};
+use crate::diff::Change;
+use crate::kind::NodeKind;
 use crate::layout::LayoutEngine;
+use crate::lock::is_locked;
 use crate::model::NodeId;
+use crate::table::{table_lines, table_rows};
+use crate::theme::{role_color, Role};
 use crate::ui::canvas::BufferCanvas;
 use crate::ui::connections::ConnectionRenderer;
-use crate::ui::text::TextWrapper;
+use crate::ui::highlight::highlight;
+use crate::text::TextWrapper;
 use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
-    widgets::Paragraph,
     Frame,
 };
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+
+/// Character the depth guides are drawn with
+const DEPTH_GUIDE: &str = "┆";
 
 // Mind map renderer
 pub struct MindMapRenderer<'a> {
     app: &'a AppState,
     layout: &'a LayoutEngine,
+    // Nodes highlighted as differing from another version of the map
+    changes: Option<&'a HashMap<NodeId, Change>>,
+    // Filled on first use while drawing
+    ancestors_of_visible: OnceCell<HashSet<NodeId>>,
 }
 
 impl<'a> MindMapRenderer<'a> {
     pub fn new(app: &'a AppState, layout: &'a LayoutEngine) -> Self {
-        Self { app, layout }
+        Self {
+            app,
+            layout,
+            changes: None,
+            ancestors_of_visible: OnceCell::new(),
+        }
+    }
+
+    /// Highlight the nodes in `changes` as added, removed or changed
+    pub fn with_changes(mut self, changes: &'a HashMap<NodeId, Change>) -> Self {
+        self.changes = Some(changes);
+        self
+    }
+
+    /// Draw the map into `canvas`, reused across frames, and copy it to the frame
+    pub fn render(&self, frame: &mut Frame, area: Rect, canvas: &mut BufferCanvas) {
+        canvas.reset(area.width as usize, area.height as usize);
+        self.draw(canvas, area);
+        canvas.blit(frame.buffer_mut(), area);
     }
 
-    pub fn render(&self, frame: &mut Frame, area: Rect) {
-        let mut canvas = BufferCanvas::new(area.width as usize, area.height as usize);
+    /// Draw the map into a canvas sized to `area`, using the app's viewport
+    pub fn draw(&self, canvas: &mut BufferCanvas, area: Rect) {
+        // Depth guides go under everything else
+        self.draw_depth_guides(canvas);
 
         // Draw connections first (behind nodes)
         if let Some(root_id) = self.app.root_id {
-            let mut conn_renderer =
-                ConnectionRenderer::new(&mut canvas, self.app, self.layout, area);
+            let mut conn_renderer = ConnectionRenderer::new(canvas, self.app, self.layout, area);
             conn_renderer.draw_node_connections(root_id);
         }
 
         // Draw nodes on top
         if let Some(root_id) = self.app.root_id {
-            self.draw_node_content(&mut canvas, root_id, area);
+            self.draw_nodes(canvas, root_id, area);
         }
 
-        // Convert buffer to paragraph and render
-        let lines = canvas.to_lines();
-        let paragraph = Paragraph::new(lines);
-        frame.render_widget(paragraph, area);
+        // Pinned ancestors cover whatever is on the top row
+        if self.app.config.sticky_headers {
+            self.draw_sticky_headers(canvas, area);
+        }
     }
 
-    fn draw_node_content(&self, canvas: &mut BufferCanvas, node_id: NodeId, area: Rect) {
-        let Some(node_ref) = self.app.tree.get(node_id) else {
+    /// Pin the ancestors of the topmost visible node that have scrolled above
+    /// the view to the top row, each in its own column
+    fn draw_sticky_headers(&self, canvas: &mut BufferCanvas, area: Rect) {
+        // The node starting closest to the top; the deepest one on a tie has the most context
+        let top_node = self
+            .layout
+            .nodes
+            .iter()
+            .filter(|(&node_id, _)| self.is_node_in_viewport(node_id, area))
+            .map(|(&node_id, layout)| {
+                let y = (layout.y + layout.yo - self.app.viewport_top) as i32;
+                (node_id, y, layout.x)
+            })
+            .filter(|&(_, y, _)| y >= 0)
+            .min_by(|a, b| a.1.cmp(&b.1).then(b.2.total_cmp(&a.2)));
+        let Some((top_id, _, _)) = top_node else {
             return;
         };
+
+        for ancestor_id in top_id.ancestors(&self.app.tree).skip(1) {
+            let Some(layout) = self.layout.nodes.get(&ancestor_id) else {
+                continue;
+            };
+            let y = (layout.y + layout.yo - self.app.viewport_top) as i32;
+            if y >= 0 {
+                continue;
+            }
+
+            let node = self.app.tree[ancestor_id].get();
+            let lines = title_lines(&self.app.display_title(ancestor_id, node), layout.w as usize);
+            let line = lines.first().map_or("", String::as_str);
+            // A trailing space keeps the header apart from what it covers
+            let header = format!("{:<width$} ", line, width = layout.w as usize);
+            let locked = is_locked(&self.app.tree, ancestor_id);
+            let style = self.get_node_style(ancestor_id, node, locked).add_modifier(Modifier::UNDERLINED);
+
+            let x = (layout.x - self.app.viewport_left) as i32;
+            let visible: String = header.chars().skip((-x).max(0) as usize).collect();
+            canvas.draw_styled_text(x.max(0) as usize, 0, &visible, style);
+        }
+    }
+
+    /// Draw a faint vertical line at the column where each depth starts
+    fn draw_depth_guides(&self, canvas: &mut BufferCanvas) {
+        if !self.app.config.depth_guides {
+            return;
+        }
+        let color = self.app.config.depth_guide_color.parse().unwrap_or(Color::DarkGray);
+        let style = Style::default().fg(color);
+
+        // The root's column has nothing to the left of it to tell apart. Each
+        // guide runs just left of its depth, where the connections turn into it.
+        for &column in self.layout.depth_columns.iter().skip(1) {
+            let x = (column - self.app.viewport_left) as i32 - 1;
+            if x < 0 {
+                continue;
+            }
+            for y in 0..canvas.height {
+                canvas.draw_styled_text(x as usize, y, DEPTH_GUIDE, style);
+            }
+        }
+    }
+
+    /// Draw every node, parents before their children. Whether a node is
+    /// locked is passed down with it, so no node walks its ancestors.
+    fn draw_nodes(&self, canvas: &mut BufferCanvas, root_id: NodeId, area: Rect) {
+        let mut stack = vec![(root_id, is_locked(&self.app.tree, root_id))];
+        while let Some((node_id, locked)) = stack.pop() {
+            let children = self.draw_node_content(canvas, node_id, locked, area);
+            stack.extend(children.into_iter().rev().map(|child| {
+                (child, locked || self.app.tree[child].get().is_locked())
+            }));
+        }
+    }
+
+    /// Draw one node, `locked` when it or an ancestor is, and return the
+    /// children that still need drawing
+    fn draw_node_content(&self, canvas: &mut BufferCanvas, node_id: NodeId, locked: bool, area: Rect) -> Vec<NodeId> {
+        let Some(node_ref) = self.app.tree.get(node_id) else {
+            return Vec::new();
+        };
         let node = node_ref.get();
+        let title = self.app.display_title(node_id, node);
 
         let Some(node_layout) = self.layout.nodes.get(&node_id) else {
-            return;
+            return Vec::new();
         };
 
         // Calculate viewport coordinates as signed integers
@@ -61,10 +173,10 @@ impl<'a> MindMapRenderer<'a> {
         let y = self.get_adjusted_parent_y(node_id, original_y, area);
 
         // Determine node style
-        let style = self.get_node_style(node_id, node);
+        let style = self.get_node_style(node_id, node, locked);
 
         // Skip drawing if the node AND its children are completely off-screen
-        let node_height = TextWrapper::wrap(&node.title, node_layout.w as usize).len() as i32;
+        let node_height = title_lines(&title, node_layout.w as usize).len() as i32;
         let is_node_visible = y + node_height > 0 && y < area.height as i32;
         let has_visible_children = !node.is_collapsed && self.has_visible_children_in_viewport(node_id, area);
 
@@ -72,10 +184,10 @@ impl<'a> MindMapRenderer<'a> {
         // Skip nodes that are completely above or to the left of viewport
         if x >= area.width as i32 || (!is_node_visible && !has_visible_children) {
             // Node and its children are completely off-screen
-            return; // No need to process children if parent and all children are off-screen
+            return Vec::new(); // No need to process children if parent and all children are off-screen
         } else if x >= 0 && y >= 0 && is_node_visible {
             // Node is at least partially visible
-            let lines = TextWrapper::wrap(&node.title, node_layout.w as usize);
+            let lines = title_lines(&title, node_layout.w as usize);
             let num_lines = lines.len() as i32;
 
             // Only draw if at least part of the node is visible
@@ -84,7 +196,8 @@ impl<'a> MindMapRenderer<'a> {
                     let line_y = y + i as i32;
                     // Only draw lines that are within the viewport
                     if line_y >= 0 && line_y < area.height as i32 {
-                        canvas.draw_styled_text(x as usize, line_y as usize, line, style);
+                        let runs = self.line_runs(node_id, &title, line, style);
+                        draw_runs(canvas, x as usize, line_y as usize, 0, &runs);
                     }
                 }
             }
@@ -92,7 +205,7 @@ impl<'a> MindMapRenderer<'a> {
         // If x < 0, the node starts off-screen from the left but might be partially visible
         else if x < 0 && x + node_layout.w as i32 > 0 && y >= 0 && y < area.height as i32 {
             // Node is partially visible from the left
-            let lines = TextWrapper::wrap(&node.title, node_layout.w as usize);
+            let lines = title_lines(&title, node_layout.w as usize);
             for (i, line) in lines.iter().enumerate() {
                 let line_y = y + i as i32;
                 if line_y >= 0 && line_y < area.height as i32 {
@@ -104,39 +217,104 @@ impl<'a> MindMapRenderer<'a> {
                         // The visible width is the total width minus what we skipped
                         let visible_width = (node_layout.w as i32 + x).max(visible_part.len() as i32) as usize;
                         // Pad the visible part to ensure it overwrites any connections
-                        let padded = format!("{:<width$}", visible_part, width = visible_width);
-                        canvas.draw_styled_text(0, line_y as usize, &padded, style);
+                        canvas.draw_styled_text(0, line_y as usize, &" ".repeat(visible_width), style);
+                        let runs = self.line_runs(node_id, &title, line, style);
+                        draw_runs(canvas, 0, line_y as usize, skip_count, &runs);
                     }
                 }
             }
         }
 
         // Draw children if not collapsed
-        if !node.is_collapsed {
-            let children = self.get_visible_children(node_id);
-            for child_id in children {
-                self.draw_node_content(canvas, child_id, area);
-            }
+        if node.is_collapsed {
+            Vec::new()
+        } else {
+            self.get_visible_children(node_id)
         }
     }
 
-    fn get_node_style(&self, node_id: NodeId, node: &crate::model::Node) -> Style {
+    /// Runs of a title line and the style each is drawn in; code is
+    /// highlighted unless it is selected
+    fn line_runs(&self, node_id: NodeId, title: &str, line: &str, style: Style) -> Vec<(Style, String)> {
+        match code_block(title) {
+            Some(code) if Some(node_id) != self.app.active_node_id => highlight(line, code.lang)
+                .into_iter()
+                .map(|(run_style, text)| (style.patch(run_style), text))
+                .collect(),
+            _ => vec![(style, line.to_string())],
+        }
+    }
+
+    fn get_node_style(&self, node_id: NodeId, node: &crate::model::Node, locked: bool) -> Style {
+        let ticked = self.app.pick_selection.contains(&node_id);
         if Some(node_id) == self.app.active_node_id {
-            Style::default()
+            let style = Style::default()
                 .fg(Color::Black)
-                .bg(Color::Yellow)
-                .add_modifier(Modifier::BOLD)
-        } else if node.title.starts_with(&self.app.config.symbol1) {
-            Style::default().fg(Color::Green)
+                .bg(self.role_color(Role::Active))
+                .add_modifier(Modifier::BOLD);
+            return if ticked {
+                style.add_modifier(Modifier::UNDERLINED)
+            } else {
+                style
+            };
+        }
+        if ticked {
+            return Style::default()
+                .fg(Color::Black)
+                .bg(self.role_color(Role::Ticked));
+        }
+        if let Some(&change) = self.changes.and_then(|changes| changes.get(&node_id)) {
+            let role = match change {
+                Change::Added => Role::Added,
+                Change::Removed => Role::Removed,
+                Change::Changed => Role::Changed,
+            };
+            return Style::default().fg(Color::Black).bg(self.role_color(role));
+        }
+        let mut style = self.get_text_style(node);
+        // Locked nodes, and everything below them, are dimmed
+        if locked {
+            style = style.add_modifier(Modifier::DIM);
+        }
+        match self.layout.nodes.get(&node_id).map(|n| n.heat) {
+            Some(heat) if heat > 0.0 => style.bg(heat_color(heat)),
+            _ => style,
+        }
+    }
+
+    fn get_text_style(&self, node: &crate::model::Node) -> Style {
+        let style = self.get_symbol_style(node);
+        match node.kind() {
+            Some(NodeKind::Heading) => style.add_modifier(Modifier::BOLD),
+            Some(NodeKind::Note) => style.add_modifier(Modifier::DIM),
+            Some(NodeKind::Quote) => style.add_modifier(Modifier::ITALIC),
+            Some(NodeKind::Item) | None => style,
+        }
+    }
+
+    fn get_symbol_style(&self, node: &crate::model::Node) -> Style {
+        if node.title.starts_with(&self.app.config.symbol1) {
+            Style::default().fg(self.role_color(Role::Done))
         } else if node.title.starts_with(&self.app.config.symbol2) {
-            Style::default().fg(Color::Red)
+            Style::default().fg(self.role_color(Role::Pending))
         } else if node.is_hidden() {
-            Style::default().fg(Color::DarkGray)
+            Style::default().fg(self.role_color(Role::Muted))
+        } else if node.is_comment(&self.app.config.comment_prefix) {
+            Style::default()
+                .fg(self.role_color(Role::Muted))
+                .add_modifier(Modifier::ITALIC)
         } else {
             Style::default()
         }
     }
 
+    /// Color of `role` in the configured palette and theme
+    fn role_color(&self, role: Role) -> Color {
+        role_color(&self.app.config, role)
+            .parse()
+            .unwrap_or(Color::Reset)
+    }
+
     fn get_visible_children(&self, node_id: NodeId) -> Vec<NodeId> {
         if !self.app.config.show_hidden {
             node_id
@@ -156,26 +334,29 @@ impl<'a> MindMapRenderer<'a> {
 
     /// Check if any children of a node are visible in the viewport
     fn has_visible_children_in_viewport(&self, node_id: NodeId, area: Rect) -> bool {
-        let Some(node_ref) = self.app.tree.get(node_id) else {
-            return false;
-        };
-        let node = node_ref.get();
-
-        if node.is_collapsed {
-            return false;
-        }
+        self.ancestors_of_visible
+            .get_or_init(|| self.find_ancestors_of_visible(area))
+            .contains(&node_id)
+    }
 
-        let children = self.get_visible_children(node_id);
-        for child_id in children {
-            if self.is_node_in_viewport(child_id, area) {
-                return true;
+    /// Nodes with a laid-out descendant in the viewport. The layout only
+    /// holds nodes below expanded, shown parents, so walking up from each
+    /// visible node finds them all in one pass over the map instead of one
+    /// subtree search per drawn node.
+    fn find_ancestors_of_visible(&self, area: Rect) -> HashSet<NodeId> {
+        let mut ancestors = HashSet::new();
+        for &node_id in self.layout.nodes.keys() {
+            if !self.is_node_in_viewport(node_id, area) {
+                continue;
             }
-            // Recursively check children's children
-            if self.has_visible_children_in_viewport(child_id, area) {
-                return true;
+            for ancestor_id in node_id.ancestors(&self.app.tree).skip(1) {
+                // Everything above an already marked node is marked too
+                if !ancestors.insert(ancestor_id) {
+                    break;
+                }
             }
         }
-        false
+        ancestors
     }
 
     /// Check if a node is at least partially visible in the viewport
@@ -219,3 +400,35 @@ impl<'a> MindMapRenderer<'a> {
         original_y
     }
 }
+
+/// Background for a heatmap intensity in 0..=1, from dark to bright red
+fn heat_color(heat: f64) -> Color {
+    const RAMP: [u8; 5] = [52, 88, 124, 160, 196];
+    let step = ((heat * RAMP.len() as f64).ceil() as usize).clamp(1, RAMP.len());
+    Color::Indexed(RAMP[step - 1])
+}
+
+/// Lines a title is drawn on: code cut short to one line, a table a line per
+/// row, anything else wrapped
+fn title_lines(title: &str, width: usize) -> Vec<String> {
+    if let Some(code) = code_block(title) {
+        vec![truncate(code.code, width)]
+    } else if let Some(rows) = table_rows(title) {
+        table_lines(&rows, width)
+    } else {
+        TextWrapper::wrap(title, width)
+    }
+}
+
+/// Draw styled runs of text from column `x`, leaving out the first `skip` characters
+fn draw_runs(canvas: &mut BufferCanvas, x: usize, y: usize, skip: usize, runs: &[(Style, String)]) {
+    let mut column = 0;
+    for (style, text) in runs {
+        for ch in text.chars() {
+            if column >= skip {
+                canvas.draw_styled_text(x + column - skip, y, ch.encode_utf8(&mut [0; 4]), *style);
+            }
+            column += 1;
+        }
+    }
+}
@@ -1,49 +1,132 @@
-mod canvas;
+pub mod canvas;
+mod compare;
 mod connections;
 mod constants;
 mod help;
+mod highlight;
+mod loading;
 mod mindmap;
+mod paste_merge;
+mod picker;
+mod pin_bar;
+mod quit_prompt;
+mod settings;
+mod snapshots;
 mod status_line;
-pub mod text;
+pub use crate::text;
 
 #[cfg(test)]
 mod tests;
 
 use crate::app::{AppMode, AppState};
+use crate::i18n::Catalog;
 use crate::layout::LayoutEngine;
+use canvas::BufferCanvas;
+use compare::CompareRenderer;
 use help::HelpRenderer;
+use loading::LoadingRenderer;
 use mindmap::MindMapRenderer;
+use paste_merge::PasteMergeRenderer;
+use picker::PickerRenderer;
+use pin_bar::PinBarRenderer;
+use quit_prompt::QuitPromptRenderer;
 use ratatui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     Frame,
 };
+use settings::SettingsRenderer;
+use snapshots::SnapshotsRenderer;
 use status_line::StatusLineRenderer;
 
-// Main render function - the only public API
-pub fn render(frame: &mut Frame, app: &mut AppState) {
-    // Update terminal size
-    let size = frame.area();
-    app.terminal_width = size.width;
-    app.terminal_height = size.height;
+/// Frame renderer that keeps its drawing buffers between frames
+#[derive(Default)]
+pub struct Renderer {
+    canvas: BufferCanvas,
+}
 
-    // Calculate layout
-    let layout = LayoutEngine::calculate_layout(app);
+impl Renderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, app: &mut AppState) {
+        // Update terminal size
+        let size = frame.area();
+        app.terminal_width = size.width;
+        app.terminal_height = size.height;
+
+        // Reuse the layout unless the map changed since the last frame
+        let layout = app.layout();
+
+        // Create main layout chunks, with a row for the pin bar when anything is pinned
+        let pin_bar_height = if app.pinned_nodes().is_empty() { 0 } else { 1 };
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(pin_bar_height),
+                Constraint::Min(1),
+                Constraint::Length(1),
+            ])
+            .split(size);
+        if pin_bar_height > 0 && !matches!(app.mode, AppMode::Help) {
+            PinBarRenderer::render(frame, app, rows[0]);
+        }
+        let chunks = [rows[1], rows[2]];
 
-    // Create main layout chunks
-    let chunks = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Min(1), Constraint::Length(1)])
-        .split(size);
-
-    // Render based on mode
-    match &app.mode {
-        AppMode::Help => HelpRenderer::render(frame, chunks[0]),
-        _ => {
-            let renderer = MindMapRenderer::new(app, &layout);
-            renderer.render(frame, chunks[0]);
+        // Render based on mode
+        match &app.mode {
+            AppMode::Help => HelpRenderer::render(frame, chunks[0], &app.keymap, &app.catalog),
+            AppMode::Picker { .. } => {
+                MindMapRenderer::new(app, &layout).render(frame, chunks[0], &mut self.canvas);
+                PickerRenderer::render(frame, chunks[0], app);
+            }
+            AppMode::QuitPrompt { .. } => {
+                MindMapRenderer::new(app, &layout).render(frame, chunks[0], &mut self.canvas);
+                QuitPromptRenderer::render(frame, chunks[0], app);
+            }
+            AppMode::Settings { .. } => {
+                MindMapRenderer::new(app, &layout).render(frame, chunks[0], &mut self.canvas);
+                SettingsRenderer::render(frame, chunks[0], app);
+            }
+            AppMode::PasteMerge { .. } => {
+                MindMapRenderer::new(app, &layout).render(frame, chunks[0], &mut self.canvas);
+                PasteMergeRenderer::render(frame, chunks[0], app);
+            }
+            AppMode::Snapshots { .. } => {
+                SnapshotsRenderer::render(frame, chunks[0], app, &layout, &mut self.canvas);
+            }
+            AppMode::Compare { .. } => {
+                CompareRenderer::render(frame, chunks[0], app, &layout, &mut self.canvas);
+            }
+            _ => {
+                let renderer = MindMapRenderer::new(app, &layout);
+                renderer.render(frame, chunks[0], &mut self.canvas);
+            }
         }
+
+        // Render status line
+        StatusLineRenderer::render(frame, app, chunks[1]);
     }
+}
+
+/// Render a single frame; frontends drawing repeatedly should keep a [`Renderer`]
+pub fn render(frame: &mut Frame, app: &mut AppState) {
+    Renderer::new().render(frame, app);
+}
+
+/// Draw the startup skeleton frame shown while `filename` is loaded
+pub fn render_loading(frame: &mut Frame, catalog: &Catalog, filename: Option<&str>) {
+    LoadingRenderer::render(frame, catalog, filename);
+}
 
-    // Render status line
-    StatusLineRenderer::render(frame, app, chunks[1]);
+/// Render the mind map (without the status line) into a `width` x `height`
+/// plain-text buffer using the app's current viewport.
+///
+/// The output is deterministic and needs no terminal, which makes it suitable
+/// for golden-file tests and for embedding the renderer elsewhere.
+pub fn render_to_string(app: &AppState, width: u16, height: u16) -> String {
+    let layout = LayoutEngine::calculate_layout(app);
+    let mut canvas = BufferCanvas::new(width as usize, height as usize);
+    MindMapRenderer::new(app, &layout).draw(&mut canvas, Rect::new(0, 0, width, height));
+    canvas.to_plain_string()
 }
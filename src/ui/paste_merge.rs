@@ -0,0 +1,56 @@
+use crate::app::{AppMode, AppState};
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+// Question whether to merge a paste into same-titled nodes, above an outline
+// of the merge result
+pub struct PasteMergeRenderer;
+
+impl PasteMergeRenderer {
+    pub fn render(frame: &mut Frame, area: Rect, app: &AppState) {
+        let AppMode::PasteMerge {
+            duplicates,
+            ref preview,
+            ..
+        } = app.mode
+        else {
+            return;
+        };
+
+        let question = Line::from(
+            app.catalog
+                .format("ui.paste_merge_question", &[("count", &duplicates)]),
+        );
+        let mut lines = vec![question, Line::default()];
+        lines.extend(preview.iter().map(|line| {
+            // Nodes the paste adds stand out from the ones it merges into
+            let color = if line.trim_start().starts_with('+') {
+                Color::Green
+            } else {
+                Color::DarkGray
+            };
+            Line::styled(line.as_str(), Style::default().fg(color))
+        }));
+
+        let content_width = lines.iter().map(Line::width).max().unwrap_or(0);
+        let width = (content_width as u16 + 4).min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let popup = Rect::new(
+            area.x + (area.width.saturating_sub(width)) / 2,
+            area.y + (area.height.saturating_sub(height)) / 2,
+            width,
+            height,
+        );
+
+        let title = format!(" {} ", app.catalog.get("ui.paste_merge_title"));
+        let block = Block::default().borders(Borders::ALL).title(title);
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::actions::{picker_label, picker_matches};
+use crate::app::{AppMode, AppState, PickerKind};
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+const MAX_PICKER_WIDTH: u16 = 72;
+
+// File picker overlay for recent and workspace maps
+pub struct PickerRenderer;
+
+impl PickerRenderer {
+    pub fn render(frame: &mut Frame, area: Rect, app: &AppState) {
+        let AppMode::Picker { kind, selected, .. } = app.mode else {
+            return;
+        };
+        let matches = picker_matches(app);
+
+        let width = area.width.saturating_sub(4).min(MAX_PICKER_WIDTH);
+        let height = (matches.len() as u16 + 2).clamp(3, area.height.saturating_sub(2).max(3));
+        let popup = Rect::new(
+            area.x + (area.width.saturating_sub(width)) / 2,
+            area.y + (area.height.saturating_sub(height)) / 2,
+            width,
+            height.min(area.height),
+        );
+
+        // Keep the selected entry visible in long lists
+        let rows = popup.height.saturating_sub(2) as usize;
+        let first = selected.saturating_sub(rows.saturating_sub(1));
+
+        let lines: Vec<Line> = matches
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .map(|(i, path)| {
+                let style = if i == selected {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                Line::styled(picker_label(app, path), style)
+            })
+            .collect();
+
+        let title = match (kind, &app.config.workspace) {
+            (PickerKind::Workspace, Some(dir)) => {
+                let dir = dir.display();
+                app.catalog.format("ui.workspace_title", &[("dir", &dir)])
+            }
+            (PickerKind::ExportTemplate, _) => app.catalog.get("ui.templates_title"),
+            (PickerKind::ExportPipe, _) => app.catalog.get("ui.pipes_title"),
+            _ => app.catalog.get("ui.recent_title"),
+        };
+        let title = format!(" {} ", title);
+        let block = Block::default().borders(Borders::ALL).title(title);
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+}
@@ -0,0 +1,53 @@
+use crate::app::AppState;
+use ratatui::{
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::Paragraph,
+    Frame,
+};
+
+/// Longest title shown for a pin before it is cut off
+const MAX_PIN_TITLE_WIDTH: usize = 20;
+
+// Bar of pinned nodes above the map
+pub struct PinBarRenderer;
+
+impl PinBarRenderer {
+    pub fn render(frame: &mut Frame, app: &AppState, area: Rect) {
+        let bar_style = Style::default().fg(Color::Gray).bg(Color::Black);
+        let mut spans = Vec::new();
+
+        for (index, node_id) in app.pinned_nodes().into_iter().enumerate() {
+            let number_style = if Some(node_id) == app.active_node_id {
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Gray)
+                    .add_modifier(Modifier::BOLD)
+            };
+            spans.push(Span::styled(format!(" {} ", index + 1), number_style));
+            spans.push(Span::raw(format!(
+                " {} ",
+                pin_title(&app.tree[node_id].get().title)
+            )));
+        }
+
+        frame.render_widget(Paragraph::new(Line::from(spans)).style(bar_style), area);
+    }
+}
+
+/// A pinned node's title on one line, shortened to fit the bar
+fn pin_title(title: &str) -> String {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.chars().count() > MAX_PIN_TITLE_WIDTH {
+        let short: String = title.chars().take(MAX_PIN_TITLE_WIDTH - 1).collect();
+        format!("{short}…")
+    } else {
+        title
+    }
+}
@@ -0,0 +1,60 @@
+use crate::app::{AppMode, AppState, QuitChoice};
+use ratatui::{
+    layout::{Alignment, Rect},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+// Save/discard/cancel dialog shown when quitting with unsaved changes
+pub struct QuitPromptRenderer;
+
+impl QuitPromptRenderer {
+    pub fn render(frame: &mut Frame, area: Rect, app: &AppState) {
+        let AppMode::QuitPrompt { selected } = app.mode else {
+            return;
+        };
+
+        let mut buttons = Vec::new();
+        for choice in QuitChoice::ALL {
+            let key = match choice {
+                QuitChoice::Save => "ui.quit_save",
+                QuitChoice::Discard => "ui.quit_discard",
+                QuitChoice::Cancel => "ui.quit_cancel",
+            };
+            let style = if choice == selected {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            if !buttons.is_empty() {
+                buttons.push(Span::raw("  "));
+            }
+            buttons.push(Span::styled(format!("[ {} ]", app.catalog.get(key)), style));
+        }
+        let buttons = Line::from(buttons);
+        let question = Line::from(app.catalog.get("ui.quit_question"));
+
+        let width = (question.width().max(buttons.width()) as u16 + 4).min(area.width);
+        let height = 5.min(area.height);
+        let popup = Rect::new(
+            area.x + (area.width.saturating_sub(width)) / 2,
+            area.y + (area.height.saturating_sub(height)) / 2,
+            width,
+            height,
+        );
+
+        let title = format!(" {} ", app.catalog.get("ui.quit_title"));
+        let block = Block::default().borders(Borders::ALL).title(title);
+        let lines = vec![question, Line::default(), buttons];
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .alignment(Alignment::Center)
+                .block(block),
+            popup,
+        );
+    }
+}
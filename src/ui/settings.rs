@@ -0,0 +1,77 @@
+use crate::app::{AppMode, AppState};
+use crate::settings::{edit_text, settings};
+use crate::ui::constants::CURSOR_INDICATOR;
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+const MAX_SETTINGS_WIDTH: u16 = 72;
+
+// Settings overlay listing every config value
+pub struct SettingsRenderer;
+
+impl SettingsRenderer {
+    pub fn render(frame: &mut Frame, area: Rect, app: &AppState) {
+        let AppMode::Settings {
+            selected,
+            ref buffer,
+            ref changed,
+        } = app.mode
+        else {
+            return;
+        };
+        let entries = settings(&app.config);
+        let name_width = entries
+            .iter()
+            .map(|(name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+
+        let width = area.width.saturating_sub(4).min(MAX_SETTINGS_WIDTH);
+        let height = (entries.len() as u16 + 2).min(area.height.saturating_sub(2).max(3));
+        let popup = Rect::new(
+            area.x + (area.width.saturating_sub(width)) / 2,
+            area.y + (area.height.saturating_sub(height)) / 2,
+            width,
+            height.min(area.height),
+        );
+
+        // Keep the selected setting visible in the scrolled list
+        let rows = popup.height.saturating_sub(2) as usize;
+        let first = selected.saturating_sub(rows.saturating_sub(1));
+
+        let lines: Vec<Line> = entries
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(rows)
+            .map(|(i, (name, value))| {
+                let value = match buffer {
+                    Some(text) if i == selected => format!("{}{}", text, CURSOR_INDICATOR),
+                    _ => edit_text(value),
+                };
+                // Settings changed since the overlay opened are starred
+                let mark = if changed.contains(name) { '*' } else { ' ' };
+                let style = if i == selected {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                Line::styled(
+                    format!("{}{:<width$}  {}", mark, name, value, width = name_width),
+                    style,
+                )
+            })
+            .collect();
+
+        let title = format!(" {} ", app.catalog.get("ui.settings_title"));
+        let block = Block::default().borders(Borders::ALL).title(title);
+
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+}
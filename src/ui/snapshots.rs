@@ -0,0 +1,122 @@
+use super::canvas::BufferCanvas;
+use super::mindmap::MindMapRenderer;
+use crate::app::{AppMode, AppState};
+use crate::diff::Change;
+use crate::layout::LayoutEngine;
+use crate::model::{Node, NodeId};
+use crate::parser::parse_hmm_content;
+use indextree::Arena;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+use std::collections::HashMap;
+
+/// Most snapshots listed above the preview at once
+const LIST_HEIGHT: u16 = 6;
+
+// The selected snapshot, read-only, beside the current map, with the list of
+// snapshots above it
+pub struct SnapshotsRenderer;
+
+impl SnapshotsRenderer {
+    pub fn render(
+        frame: &mut Frame,
+        area: Rect,
+        app: &AppState,
+        layout: &LayoutEngine,
+        canvas: &mut BufferCanvas,
+    ) {
+        let AppMode::Snapshots {
+            ref snapshots,
+            selected,
+            ref preview,
+        } = app.mode
+        else {
+            return;
+        };
+
+        let halves = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(area);
+
+        let now = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} ", app.catalog.get("ui.snapshots_now")));
+        MindMapRenderer::new(app, layout).render(frame, now.inner(halves[0]), canvas);
+        frame.render_widget(now, halves[0]);
+
+        let time = snapshots[selected].time.format("%Y-%m-%d %H:%M");
+        let then = Block::default().borders(Borders::ALL).title(format!(
+            " {} · {} ",
+            app.catalog.get("ui.snapshots_title"),
+            time
+        ));
+        let inner = then.inner(halves[1]);
+        frame.render_widget(Clear, halves[1]);
+        frame.render_widget(then, halves[1]);
+
+        let list_height = (snapshots.len() as u16).min(LIST_HEIGHT).min(inner.height);
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(list_height), Constraint::Min(0)])
+            .split(inner);
+
+        // Keep the selected snapshot in the listed window
+        let first = selected.saturating_sub(list_height.saturating_sub(1) as usize);
+        let lines: Vec<Line> = snapshots
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(list_height as usize)
+            .map(|(i, snapshot)| {
+                let style = if i == selected {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                } else {
+                    Style::default()
+                };
+                Line::styled(snapshot.time.format("%Y-%m-%d %H:%M:%S").to_string(), style)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines), rows[0]);
+
+        if let Ok((tree, root_id)) = parse_hmm_content(preview) {
+            render_other_map(frame, rows[1], app, tree, root_id, None);
+        }
+    }
+}
+
+/// Draw another map read-only into `area`, starting from its root like a
+/// freshly opened map, with the nodes in `changes` highlighted
+pub(super) fn render_other_map(
+    frame: &mut Frame,
+    area: Rect,
+    app: &AppState,
+    tree: Arena<Node>,
+    root_id: NodeId,
+    changes: Option<&HashMap<NodeId, Change>>,
+) {
+    let mut other = AppState::new(app.config.clone());
+    other.tree = tree;
+    other.root_id = Some(root_id);
+    // The root is shown selected unless it may be highlighted as changed
+    other.active_node_id = changes.is_none().then_some(root_id);
+    other.terminal_width = area.width;
+    other.terminal_height = area.height;
+
+    let layout = LayoutEngine::calculate_layout(&other);
+    if let Some(root) = layout.nodes.get(&root_id) {
+        other.viewport_left = root.x - 1.0;
+        other.viewport_top = (root.y + root.yo + root.lh / 2.0 - area.height as f64 / 2.0).round();
+    }
+    let renderer = MindMapRenderer::new(&other, &layout);
+    let renderer = match changes {
+        Some(changes) => renderer.with_changes(changes),
+        None => renderer,
+    };
+    renderer.render(frame, area, &mut BufferCanvas::default());
+}
@@ -1,5 +1,9 @@
 use crate::app::{AppMode, AppState};
-use crate::ui::constants::{CURSOR_INDICATOR, STATUS_EDIT_PREFIX, STATUS_SEARCH_PREFIX};
+use crate::parser::MapFormat;
+use crate::ui::constants::{
+    CURSOR_INDICATOR, STATUS_CAPTURE_PREFIX, STATUS_EDIT_PREFIX, STATUS_OPEN_PREFIX,
+    STATUS_RENAME_FILE_PREFIX, STATUS_SAVE_AS_PREFIX, STATUS_SEARCH_PREFIX,
+};
 use ratatui::{
     layout::Rect,
     style::{Color, Modifier, Style},
@@ -27,16 +31,53 @@ impl StatusLineRenderer {
             AppMode::Editing { buffer, cursor_pos } => {
                 Self::render_edit_mode(buffer, *cursor_pos, area.width)
             }
-            AppMode::Search { query } => Self::render_search_mode(query),
-            AppMode::Help => Self::render_help_mode(),
+            AppMode::Search { query } => Self::render_search_mode(STATUS_SEARCH_PREFIX, query),
+            AppMode::Picker { query, .. } => Self::render_search_mode(STATUS_OPEN_PREFIX, query),
+            AppMode::Help => Self::render_help_mode(app),
+            AppMode::QuitPrompt { .. } => Self::render_quit_prompt_mode(app),
+            AppMode::PasteMerge { .. } => Self::render_paste_merge_mode(app),
+            AppMode::Snapshots { .. } => Self::render_snapshots_mode(app),
+            AppMode::Compare {
+                ref label, counts, ..
+            } => Self::render_compare_mode(app, label, *counts),
+            AppMode::ConfirmRun { command } => Self::render_confirm_run_mode(app, command),
+            AppMode::ConfirmLinks { from, count, .. } => {
+                Self::render_confirm_links_mode(app, from, *count)
+            }
+            AppMode::Rename { buffer, matches } => Self::render_rename_mode(app, buffer, *matches),
+            AppMode::SaveAs { buffer, .. } => {
+                Self::render_search_mode(STATUS_SAVE_AS_PREFIX, buffer)
+            }
+            AppMode::RenameFile { buffer } => {
+                Self::render_search_mode(STATUS_RENAME_FILE_PREFIX, buffer)
+            }
+            AppMode::Capture { buffer } => Self::render_search_mode(STATUS_CAPTURE_PREFIX, buffer),
+            AppMode::FormatPrompt { selected } => Self::render_format_prompt_mode(app, *selected),
+            AppMode::Settings { buffer, .. } => Self::render_settings_mode(app, buffer.is_some()),
         }
     }
 
     fn render_normal_mode(app: &AppState) -> (String, Style) {
+        // Keys of an unfinished sequence, like vim's showcmd
+        if !app.pending_keys.is_empty() {
+            let style = Style::default().fg(Color::Yellow).bg(Color::Black);
+            return (app.keymap.label(&app.pending_keys), style);
+        }
+
         let content = if let Some(ref msg) = app.message {
             msg.clone()
         } else {
-            format!("h-m-m | {} nodes", app.tree.count())
+            let key = if app.read_only.is_some() {
+                "ui.node_count_read_only"
+            } else if app.scratch {
+                "ui.node_count_scratch"
+            } else {
+                "ui.node_count"
+            };
+            app.catalog.format(
+                key,
+                &[("count", &app.tree.count()), ("format", &app.format.name())],
+            )
         };
 
         let style = if app.message.is_some() {
@@ -90,8 +131,8 @@ impl StatusLineRenderer {
         (display, style)
     }
 
-    fn render_search_mode(query: &str) -> (String, Style) {
-        let content = format!("{}{}", STATUS_SEARCH_PREFIX, query);
+    fn render_search_mode(prefix: &str, query: &str) -> (String, Style) {
+        let content = format!("{}{}", prefix, query);
         let style = Style::default()
             .fg(Color::Black)
             .bg(Color::Cyan)
@@ -100,8 +141,8 @@ impl StatusLineRenderer {
         (content, style)
     }
 
-    fn render_help_mode() -> (String, Style) {
-        let content = String::from("Press ESC or q to close help");
+    fn render_help_mode(app: &AppState) -> (String, Style) {
+        let content = app.catalog.get("ui.help_close");
         let style = Style::default()
             .fg(Color::Black)
             .bg(Color::Cyan)
@@ -109,4 +150,141 @@ impl StatusLineRenderer {
 
         (content, style)
     }
+
+    fn render_format_prompt_mode(app: &AppState, selected: MapFormat) -> (String, Style) {
+        let formats: Vec<String> = MapFormat::writable()
+            .map(|format| {
+                if format == selected {
+                    format!("[{}]", format.name())
+                } else {
+                    format.name().to_string()
+                }
+            })
+            .collect();
+        let content = app
+            .catalog
+            .format("ui.format_prompt", &[("formats", &formats.join("  "))]);
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::Cyan)
+            .add_modifier(Modifier::BOLD);
+
+        (content, style)
+    }
+
+    fn render_quit_prompt_mode(app: &AppState) -> (String, Style) {
+        let content = app.catalog.get("ui.quit_keys");
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::Magenta)
+            .add_modifier(Modifier::BOLD);
+
+        (content, style)
+    }
+
+    fn render_paste_merge_mode(app: &AppState) -> (String, Style) {
+        let content = app.catalog.get("ui.paste_merge_keys");
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::Magenta)
+            .add_modifier(Modifier::BOLD);
+
+        (content, style)
+    }
+
+    fn render_snapshots_mode(app: &AppState) -> (String, Style) {
+        let content = app.catalog.get("ui.snapshots_keys");
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::Magenta)
+            .add_modifier(Modifier::BOLD);
+
+        (content, style)
+    }
+
+    fn render_compare_mode(
+        app: &AppState,
+        label: &str,
+        (added, removed, changed): (usize, usize, usize),
+    ) -> (String, Style) {
+        let content = app.catalog.format(
+            "ui.compare_keys",
+            &[
+                ("label", &label),
+                ("added", &added),
+                ("removed", &removed),
+                ("changed", &changed),
+            ],
+        );
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::Magenta)
+            .add_modifier(Modifier::BOLD);
+
+        (content, style)
+    }
+
+    fn render_confirm_run_mode(app: &AppState, command: &str) -> (String, Style) {
+        let content = app
+            .catalog
+            .format("ui.confirm_run", &[("command", &command)]);
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::Magenta)
+            .add_modifier(Modifier::BOLD);
+
+        (content, style)
+    }
+
+    fn render_confirm_links_mode(app: &AppState, title: &str, count: usize) -> (String, Style) {
+        let content = app
+            .catalog
+            .format("ui.confirm_links", &[("count", &count), ("title", &title)]);
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::Magenta)
+            .add_modifier(Modifier::BOLD);
+
+        (content, style)
+    }
+
+    fn render_rename_mode(app: &AppState, buffer: &str, matches: Option<usize>) -> (String, Style) {
+        let matches = match matches {
+            Some(count) => app
+                .catalog
+                .format("ui.rename_matches", &[("count", &count)]),
+            None => app.catalog.get("ui.rename_incomplete"),
+        };
+        let content = app.catalog.format(
+            "ui.rename_prompt",
+            &[("pattern", &buffer), ("matches", &matches)],
+        );
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::Cyan)
+            .add_modifier(Modifier::BOLD);
+
+        (content, style)
+    }
+
+    fn render_settings_mode(app: &AppState, editing: bool) -> (String, Style) {
+        // Errors from applying a setting take the place of the key hints
+        if let Some(ref msg) = app.message {
+            return (
+                msg.clone(),
+                Style::default().fg(Color::Black).bg(Color::Magenta),
+            );
+        }
+        let key = if editing {
+            "ui.setting_edit_keys"
+        } else {
+            "ui.settings_keys"
+        };
+        let style = Style::default()
+            .fg(Color::Black)
+            .bg(Color::Cyan)
+            .add_modifier(Modifier::BOLD);
+
+        (app.catalog.get(key), style)
+    }
 }
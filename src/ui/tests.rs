@@ -108,6 +108,21 @@ fn test_text_wrapper() {
     assert_eq!(single_wrapped[0], long_word);
 }
 
+#[test]
+fn test_highlight_keeps_the_text() {
+    let runs = super::highlight::highlight("let x = 1;", Some("rust"));
+    let text: String = runs.iter().map(|(_, text)| text.as_str()).collect();
+    assert_eq!(text, "let x = 1;");
+    #[cfg(feature = "highlight")]
+    assert!(runs.len() > 1);
+}
+
+#[test]
+fn test_text_wrapper_keeps_math_together() {
+    let wrapped = TextWrapper::wrap("so $a + b = c$ holds", 6);
+    assert_eq!(wrapped, vec!["so", "$a + b = c$", "holds"]);
+}
+
 #[test]
 fn test_connection_total_length() {
     use crate::layout::NODE_CONNECTION_SPACING;
@@ -125,3 +140,149 @@ fn test_connection_total_length() {
         expected_connection_chars
     );
 }
+
+#[test]
+fn test_loading_frame() {
+    use crate::i18n::Catalog;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut terminal = Terminal::new(TestBackend::new(30, 5)).unwrap();
+    terminal
+        .draw(|frame| super::render_loading(frame, &Catalog::default(), Some("big.hmm")))
+        .unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let line = |y: u16| -> String { (0..30).map(|x| buffer[(x, y)].symbol()).collect() };
+    assert_eq!(line(2).trim(), "Loading big.hmm…");
+    assert_eq!(line(4).trim(), "h-m-m");
+}
+
+#[test]
+fn test_buffer_canvas_reset_and_blit() {
+    use ratatui::{buffer::Buffer, layout::Rect};
+
+    let mut canvas = BufferCanvas::new(6, 2);
+    canvas.draw_text(0, 0, "stale!");
+    canvas.reset(6, 2);
+    assert_eq!(canvas.to_plain_string(), "\n\n");
+
+    canvas.reset(8, 1);
+    canvas.draw_text(0, 0, "日本ab");
+    let area = Rect::new(0, 0, 5, 1);
+    let mut buffer = Buffer::empty(area);
+    canvas.blit(&mut buffer, area);
+
+    // Wide characters take two cells and the row is cut at the area's edge
+    let symbols: Vec<&str> = (0..5).map(|x| buffer[(x, 0)].symbol()).collect();
+    assert_eq!(symbols, ["日", " ", "本", " ", "a"]);
+}
+
+#[test]
+fn test_quit_prompt_frame() {
+    use crate::app::{AppMode, AppState, QuitChoice};
+    use crate::config::AppConfig;
+    use crate::model::Node;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut app = AppState::new(AppConfig::default());
+    let root = app.tree.new_node(Node::new("Root".to_string()));
+    app.root_id = Some(root);
+    app.active_node_id = Some(root);
+    app.mode = AppMode::QuitPrompt {
+        selected: QuitChoice::Discard,
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(60, 12)).unwrap();
+    terminal.draw(|frame| super::render(frame, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let screen: String = (0..12)
+        .map(|y| (0..60).map(|x| buffer[(x, y)].symbol()).collect::<String>() + "\n")
+        .collect();
+    assert!(screen.contains("Unsaved changes"));
+    assert!(screen.contains("Save changes before quitting?"));
+    assert!(screen.contains("[ Save ]  [ Discard ]  [ Cancel ]"));
+    assert!(screen.contains("s: save and quit"));
+}
+
+#[test]
+fn test_snapshots_frame() {
+    use crate::app::{AppMode, AppState};
+    use crate::config::AppConfig;
+    use crate::model::Node;
+    use crate::snapshots::Snapshot;
+    use ratatui::{backend::TestBackend, Terminal};
+
+    let mut app = AppState::new(AppConfig::default());
+    let root = app.tree.new_node(Node::new("Plan v2".to_string()));
+    app.root_id = Some(root);
+    app.active_node_id = Some(root);
+    let time = |hour| {
+        chrono::NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    };
+    app.mode = AppMode::Snapshots {
+        snapshots: vec![
+            Snapshot { time: time(12), path: "12.hmm".into() },
+            Snapshot { time: time(9), path: "9.hmm".into() },
+        ],
+        selected: 1,
+        preview: "Plan\n\tOld idea\n".to_string(),
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 14)).unwrap();
+    terminal.draw(|frame| super::render(frame, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let screen: String = (0..14)
+        .map(|y| (0..80).map(|x| buffer[(x, y)].symbol()).collect::<String>() + "\n")
+        .collect();
+    assert!(screen.contains("Now"));
+    assert!(screen.contains("Snapshots · 2024-05-01 09:00"));
+    assert!(screen.contains("2024-05-01 12:00:00"));
+    assert!(screen.contains("Old idea"));
+    assert!(screen.contains("r/Enter: restore"));
+}
+
+#[test]
+fn test_compare_frame() {
+    use crate::app::{AppMode, AppState};
+    use crate::config::AppConfig;
+    use crate::model::Node;
+    use ratatui::{backend::TestBackend, style::Color, Terminal};
+
+    let mut app = AppState::new(AppConfig::default());
+    let root = app.tree.new_node(Node::new("Plan".to_string()));
+    root.append_value(Node::new("New idea".to_string()), &mut app.tree);
+    app.root_id = Some(root);
+    app.active_node_id = Some(root);
+    app.mode = AppMode::Compare {
+        label: "HEAD".to_string(),
+        other: "Plan\n\tOld idea\n".to_string(),
+        counts: (0, 0, 1),
+    };
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 14)).unwrap();
+    terminal.draw(|frame| super::render(frame, &mut app)).unwrap();
+
+    let buffer = terminal.backend().buffer();
+    let screen: String = (0..14)
+        .map(|y| (0..80).map(|x| buffer[(x, y)].symbol()).collect::<String>() + "\n")
+        .collect();
+    assert!(screen.contains("Now"));
+    assert!(screen.contains("HEAD"));
+    assert!(screen.contains("Compared with HEAD: +0 −0 ~1"));
+
+    // The renamed node is highlighted in both panes
+    let highlighted: Vec<u16> = (0..14)
+        .flat_map(|y| (0..80).map(move |x| (x, y)))
+        .filter(|&(x, y)| {
+            buffer[(x, y)].bg == Color::Cyan && matches!(buffer[(x, y)].symbol(), "N" | "O")
+        })
+        .map(|(x, _)| x)
+        .collect();
+    assert!(highlighted.iter().any(|&x| x < 40));
+    assert!(highlighted.iter().any(|&x| x >= 40));
+}
@@ -0,0 +1,141 @@
+//! The terminal window the editor runs in.
+//!
+//! The title names the open map and says whether it has unsaved changes,
+//! e.g. `hmm — notes.hmm (modified)`. With `report_cwd`, the directory of the
+//! map is also announced with OSC 7, which terminals such as WezTerm, kitty,
+//! foot and the VTE family use to open new tabs and splits in it.
+
+use crate::app::AppState;
+use std::path::{Path, PathBuf};
+
+/// Title for the terminal window
+pub fn window_title(app: &AppState) -> String {
+    let mut title = "hmm".to_string();
+    if let Some(name) = app.filename.as_deref().and_then(Path::file_name) {
+        title.push_str(" — ");
+        title.push_str(&name.to_string_lossy());
+    }
+    if app.is_dirty {
+        title.push_str(" (modified)");
+    }
+    // A control character would end the escape sequence carrying the title
+    title.retain(|c| !c.is_control());
+    title
+}
+
+/// OSC 7 sequence reporting `dir` on `host` as the working directory
+pub fn cwd_sequence(host: &str, dir: &Path) -> String {
+    let mut url = format!("\x1b]7;file://{host}");
+    for byte in dir.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    url.push_str("\x1b\\");
+    url
+}
+
+/// Name of this machine for file URLs, or empty, which terminals take to be
+/// the local one
+pub fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Push the terminal's own title onto its title stack (XTWINOPS)
+pub const SAVE_TITLE: &str = "\x1b[22;0t";
+/// Pop the title pushed with [`SAVE_TITLE`] back into place
+pub const RESTORE_TITLE: &str = "\x1b[23;0t";
+
+/// What was last told to the terminal, so that it is only told again when
+/// the map or its dirty state changes
+#[derive(Debug)]
+pub struct WindowState {
+    host: String,
+    title: Option<String>,
+    dir: Option<PathBuf>,
+}
+
+impl Default for WindowState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WindowState {
+    pub fn new() -> Self {
+        Self {
+            host: host_name(),
+            title: None,
+            dir: None,
+        }
+    }
+
+    /// The title, if it changed since the last call and titles are enabled
+    pub fn new_title(&mut self, app: &AppState) -> Option<String> {
+        if !app.config.terminal_title {
+            return None;
+        }
+        let title = window_title(app);
+        (self.title.as_ref() != Some(&title)).then(|| {
+            self.title = Some(title.clone());
+            title
+        })
+    }
+
+    /// OSC 7 report of the directory of the open map, if it changed since
+    /// the last call and reporting it is enabled
+    pub fn cwd_report(&mut self, app: &AppState) -> Option<String> {
+        if !app.config.report_cwd {
+            return None;
+        }
+        let path = app.filename.as_deref()?;
+        let dir = std::path::absolute(path).ok()?.parent()?.to_path_buf();
+        (self.dir.as_ref() != Some(&dir)).then(|| {
+            let report = cwd_sequence(&self.host, &dir);
+            self.dir = Some(dir);
+            report
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    #[test]
+    fn test_title_follows_file_and_dirty_state() {
+        let mut app = AppState::new(AppConfig::default());
+        let mut window = WindowState::new();
+        assert_eq!(window.new_title(&app).as_deref(), Some("hmm"));
+        assert_eq!(window.new_title(&app), None);
+
+        app.filename = Some(PathBuf::from("maps/notes.hmm"));
+        app.is_dirty = true;
+        assert_eq!(
+            window.new_title(&app).as_deref(),
+            Some("hmm — notes.hmm (modified)")
+        );
+        app.is_dirty = false;
+        assert_eq!(window.new_title(&app).as_deref(), Some("hmm — notes.hmm"));
+
+        app.config.terminal_title = false;
+        app.is_dirty = true;
+        assert_eq!(window.new_title(&app), None);
+    }
+
+    #[test]
+    fn test_cwd_sequence_encodes_the_path() {
+        assert_eq!(
+            cwd_sequence("box", Path::new("/home/ann/my maps")),
+            "\x1b]7;file://box/home/ann/my%20maps\x1b\\"
+        );
+    }
+}
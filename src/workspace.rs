@@ -0,0 +1,84 @@
+//! Workspace mode: a directory of maps browsed through the file picker.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Extension of the maps listed in a workspace
+pub const MAP_EXTENSION: &str = "hmm";
+
+/// Resolve a leading `~` to the home directory
+pub fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// All `.hmm` files under `dir`, recursively and sorted by path.
+/// Hidden files and directories are skipped and symlinked directories are not
+/// followed, so a link back up the tree cannot loop.
+pub fn list_maps(dir: &Path) -> Vec<PathBuf> {
+    let mut maps = Vec::new();
+    collect_maps(&expand_home(dir), &mut maps);
+    maps.sort();
+    maps
+}
+
+fn collect_maps(dir: &Path, maps: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => collect_maps(&path, maps),
+            Ok(_) if path.extension().is_some_and(|ext| ext == MAP_EXTENSION) => maps.push(path),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_list_maps_recurses_and_skips_hidden() {
+        let dir = TempDir::new().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("projects/old")).unwrap();
+        fs::create_dir_all(root.join(".trash")).unwrap();
+        for file in [
+            "inbox.hmm",
+            "notes.txt",
+            "projects/plan.hmm",
+            "projects/old/archive.hmm",
+            ".trash/deleted.hmm",
+            ".hidden.hmm",
+        ] {
+            fs::write(root.join(file), "Map").unwrap();
+        }
+
+        let maps: Vec<_> = list_maps(root)
+            .iter()
+            .map(|path| path.strip_prefix(root).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            maps,
+            [
+                PathBuf::from("inbox.hmm"),
+                PathBuf::from("projects/old/archive.hmm"),
+                PathBuf::from("projects/plan.hmm"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_maps_missing_directory() {
+        assert!(list_maps(Path::new("/nonexistent/hmm-workspace")).is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+#![cfg(feature = "tui")]
+
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::TempDir;
+
+/// Run the binary with `args`, using a private config file
+fn run(dir: &Path, args: &[&str]) -> Output {
+    let config = dir.join("config.toml");
+    std::fs::write(&config, "").unwrap();
+    Command::new(env!("CARGO_BIN_EXE_hmm-rs"))
+        .arg("--config")
+        .arg(&config)
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// A failed start reports on stderr without ever switching to the alternate screen
+fn assert_fails_cleanly(output: &Output, message: &str) {
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty(), "wrote to the terminal");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains(message), "unexpected stderr: {stderr}");
+}
+
+#[test]
+fn test_missing_file_fails_without_create() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("missing.hmm");
+
+    let output = run(temp_dir.path(), &[path.to_str().unwrap()]);
+    assert_fails_cleanly(&output, "pass --create");
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_unreadable_file_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("folder.hmm");
+    std::fs::create_dir(&path).unwrap();
+
+    let output = run(temp_dir.path(), &[path.to_str().unwrap()]);
+    assert_fails_cleanly(&output, "cannot open");
+}
+
+#[test]
+fn test_select_line_needs_an_hmm_map() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("map.csv");
+    std::fs::write(&path, "Root,Child\n").unwrap();
+
+    let output = run(
+        temp_dir.path(),
+        &["--select-line", "2", path.to_str().unwrap()],
+    );
+    assert_fails_cleanly(&output, "--select-line needs a .hmm map");
+}
+
+#[test]
+fn test_create_in_missing_directory_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("no/such/dir/map.hmm");
+
+    let output = run(temp_dir.path(), &["--create", path.to_str().unwrap()]);
+    assert_fails_cleanly(&output, "cannot create");
+}
+
+#[test]
+fn test_create_requires_a_file() {
+    let temp_dir = TempDir::new().unwrap();
+
+    let output = run(temp_dir.path(), &["--create"]);
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn test_table_columns_need_a_table() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("map.hmm");
+    std::fs::write(&path, "Root\n").unwrap();
+
+    let output = run(
+        temp_dir.path(),
+        &["--csv-path", "path", path.to_str().unwrap()],
+    );
+    assert_fails_cleanly(&output, "not a .csv or .tsv file");
+}
+
+#[test]
+fn test_export_mermaid() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("map.hmm");
+    std::fs::write(&path, "Root\n\tA\n\t\tB\n\tC (draft)\n").unwrap();
+
+    let output = run(
+        temp_dir.path(),
+        &["export", "--format", "mermaid", path.to_str().unwrap()],
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "mindmap\n  root((\"Root\"))\n    A\n      B\n    n3[\"C (draft)\"]\n"
+    );
+}
+
+#[test]
+fn test_export_branch() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("map.hmm");
+    std::fs::write(&path, "Root\n\tWork\n\t\tToday\n\tHome\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = run(temp_dir.path(), &["export", "--branch", "Work", path]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Work\n\tToday\n");
+
+    let output = run(temp_dir.path(), &["export", "--branch", "Play", path]);
+    assert_fails_cleanly(&output, "No node named 'Play'");
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn test_capture_and_search_db() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("map.hmmdb");
+    let (mut tree, root_id) = hmm_rs::parser::parse_hmm_content("Root\n\tInbox\n\tRust").unwrap();
+    hmm_rs::parser::save_file(&mut tree, root_id, &path).unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = run(
+        temp_dir.path(),
+        &["capture", "--under", "Inbox", path, "Rust-proof paint"],
+    );
+    assert!(output.status.success());
+
+    let output = run(temp_dir.path(), &["search", path, "paint"]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Root > Inbox > Rust-proof paint\n"
+    );
+    let output = run(temp_dir.path(), &["search", path, "garden"]);
+    assert!(!output.status.success());
+
+    let missing = temp_dir.path().join("missing.hmmdb");
+    let output = run(
+        temp_dir.path(),
+        &["capture", missing.to_str().unwrap(), "Note"],
+    );
+    assert_fails_cleanly(&output, "does not exist");
+    assert!(!missing.exists());
+}
+
+#[test]
+fn test_export_and_sync_todo_txt() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("map.hmm");
+    std::fs::write(&path, "Home\n\tGarden\n\t\t✗ Dig\n\t\t✗ Plant\n").unwrap();
+    let path = path.to_str().unwrap();
+
+    let output = run(temp_dir.path(), &["export", "--format", "todo-txt", path]);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Dig +Home.Garden\nPlant +Home.Garden\n"
+    );
+
+    let todo = temp_dir.path().join("todo.txt");
+    std::fs::write(&todo, "x 2024-05-01 Dig +Home.Garden\nPlant +Home.Garden\n").unwrap();
+    let output = run(
+        temp_dir.path(),
+        &["sync-tasks", path, todo.to_str().unwrap()],
+    );
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "1 tasks updated\n");
+    assert!(std::fs::read_to_string(path).unwrap().contains("✓ Dig"));
+}
+
+#[test]
+fn test_export_with_template() {
+    let temp_dir = TempDir::new().unwrap();
+    let path = temp_dir.path().join("map.hmm");
+    std::fs::write(&path, "Paper\n\tIntro\n\tMethod\n\t\tSetup\n").unwrap();
+    std::fs::write(
+        temp_dir.path().join("outline.tex"),
+        "{% for node in nodes if node.depth > 0 %}\\{{ 'sub' * (node.depth - 1) }}section{ {{- node.text -}} }\n{% endfor %}",
+    )
+    .unwrap();
+    let config = temp_dir.path().join("config.toml");
+    std::fs::write(
+        &config,
+        format!(
+            "[export.templates]\nlatex = \"{}\"\n",
+            temp_dir.path().join("outline.tex").display()
+        ),
+    )
+    .unwrap();
+
+    let export = |template: &str| {
+        Command::new(env!("CARGO_BIN_EXE_hmm-rs"))
+            .arg("--config")
+            .arg(&config)
+            .args(["export", "--template", template])
+            .arg(&path)
+            .output()
+            .unwrap()
+    };
+    let output = export("latex");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "\\section{Intro}\n\\section{Method}\n\\subsection{Setup}\n"
+    );
+    assert_fails_cleanly(&export("hugo"), "configured are latex");
+}
+
+#[test]
+fn test_formats_lists_every_format() {
+    let temp_dir = TempDir::new().unwrap();
+    let output = run(temp_dir.path(), &["formats"]);
+    assert!(output.status.success());
+    let list = String::from_utf8_lossy(&output.stdout);
+    for name in ["Workflowy JSON", "MindManager", "mermaid-flowchart", "ical"] {
+        assert!(list.contains(name), "{name} missing from {list}");
+    }
+}
+
+#[test]
+fn test_check_reports_problems_and_fails() {
+    let temp_dir = TempDir::new().unwrap();
+    let good = temp_dir.path().join("good.hmm");
+    std::fs::write(&good, "Root\n\tOne\n\tTwo\n").unwrap();
+    let output = run(temp_dir.path(), &["check", good.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let bad = temp_dir.path().join("bad.hmm");
+    std::fs::write(&bad, "Root\n\tOne\n\tOne\n").unwrap();
+    let output = run(
+        temp_dir.path(),
+        &["check", good.to_str().unwrap(), bad.to_str().unwrap()],
+    );
+    assert!(!output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!("{}:3: duplicate title, first on line 2\n", bad.display())
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("problems found: 1"));
+}
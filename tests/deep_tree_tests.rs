@@ -0,0 +1,153 @@
+#![cfg(feature = "render")]
+
+use hmm_rs::actions;
+use hmm_rs::app::AppState;
+use hmm_rs::config::AppConfig;
+use hmm_rs::export::{to_text, ExportOptions};
+use hmm_rs::model::{Node, NodeId};
+use hmm_rs::parser::map_to_list;
+use hmm_rs::{render_to_string, LayoutEngine};
+use indextree::Arena;
+use std::collections::{HashMap, HashSet};
+use std::thread;
+
+const DEPTH: usize = 100_000;
+
+/// Run `test` on a thread with a small stack, so any traversal that still
+/// recurses once per level fails loudly instead of passing on a big stack
+fn with_small_stack(test: impl FnOnce() + Send + 'static) {
+    thread::Builder::new()
+        .stack_size(256 * 1024)
+        .spawn(test)
+        .unwrap()
+        .join()
+        .unwrap();
+}
+
+/// Minimal deterministic generator so failures are reproducible
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, bound: usize) -> usize {
+        self.0 = self
+            .0
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+        ((self.0 >> 33) as usize) % bound
+    }
+}
+
+/// A chain `depth` levels deep with random short side branches, returning
+/// the app and the deepest node of the chain
+fn create_deep_app(depth: usize, seed: u64) -> (AppState, NodeId) {
+    let mut rng = Lcg(seed);
+    let mut app = AppState::new(AppConfig::default());
+    let root = app.tree.new_node(Node::new("Level 0".to_string()));
+    app.root_id = Some(root);
+
+    let mut parent = root;
+    for level in 1..depth {
+        let node = parent.append_value(Node::new(format!("Level {level}")), &mut app.tree);
+        for branch in 0..rng.next(4).saturating_sub(2) {
+            parent.append_value(Node::new(format!("Side {level}.{branch}")), &mut app.tree);
+        }
+        parent = node;
+    }
+
+    app.active_node_id = Some(parent);
+    (app, parent)
+}
+
+/// Depth of every node below `root`, computed in one pass
+fn depths(tree: &Arena<Node>, root: NodeId) -> HashMap<NodeId, usize> {
+    let mut depths = HashMap::from([(root, 0)]);
+    for node_id in root.descendants(tree).skip(1) {
+        let parent = tree[node_id].parent().unwrap();
+        depths.insert(node_id, depths[&parent] + 1);
+    }
+    depths
+}
+
+#[test]
+fn test_layout_and_render_deep_chain() {
+    with_small_stack(|| {
+        let (mut app, deepest) = create_deep_app(DEPTH, 1);
+
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert_eq!(layout.nodes.len(), app.tree.count());
+        let root_x = layout.nodes[&app.root_id.unwrap()].x;
+        assert!(layout.nodes[&deepest].x > root_x + DEPTH as f64);
+
+        // Render with the viewport at the bottom of the chain
+        let deepest_layout = &layout.nodes[&deepest];
+        app.viewport_left = deepest_layout.x - 40.0;
+        app.viewport_top = deepest_layout.y - 10.0;
+        let screen = render_to_string(&app, 80, 24);
+        assert!(screen.contains(&format!("Level {}", DEPTH - 1)));
+    });
+}
+
+#[test]
+fn test_view_actions_on_deep_chain() {
+    with_small_stack(|| {
+        let (mut app, deepest) = create_deep_app(DEPTH, 2);
+
+        let root = app.root_id.unwrap();
+        let depths = depths(&app.tree, root);
+        actions::collapse_to_level(&mut app, DEPTH / 2);
+        for node_id in root.descendants(&app.tree) {
+            let collapsed = app.tree[node_id].get().is_collapsed;
+            assert_eq!(collapsed, depths[&node_id] >= DEPTH / 2);
+        }
+
+        // Focus collapses every side branch on the way down to the deepest node
+        actions::expand_all(&mut app);
+        actions::focus(&mut app);
+        let path: HashSet<NodeId> = deepest.ancestors(&app.tree).collect();
+        for node_id in root.descendants(&app.tree) {
+            let collapsed = app.tree[node_id].get().is_collapsed;
+            assert_eq!(collapsed, !path.contains(&node_id), "{node_id}");
+        }
+
+        let layout = LayoutEngine::calculate_layout(&app);
+        assert_eq!(layout.nodes.len(), app.tree.count());
+    });
+}
+
+#[test]
+fn test_paste_deep_chain() {
+    with_small_stack(|| {
+        let (source, _) = create_deep_app(DEPTH, 3);
+        let mut target = Arena::new();
+        let target_root = target.new_node(Node::new("Target".to_string()));
+
+        let source_root = source.root_id.unwrap();
+        actions::add_subtree_to_parent(&mut target, &source.tree, source_root, target_root);
+
+        assert_eq!(target.count(), source.tree.count() + 1);
+        let pasted: Vec<&str> = target_root
+            .descendants(&target)
+            .skip(1)
+            .map(|id| target[id].get().title.as_str())
+            .collect();
+        let original: Vec<&str> = source_root
+            .descendants(&source.tree)
+            .map(|id| source.tree[id].get().title.as_str())
+            .collect();
+        assert_eq!(pasted, original);
+    });
+}
+
+#[test]
+fn test_export_deep_chain() {
+    // Text output grows quadratically with depth, so this chain is shallower
+    with_small_stack(|| {
+        let (app, _) = create_deep_app(5_000, 4);
+        let root = app.root_id.unwrap();
+
+        let text = map_to_list(&app.tree, root, false, 0);
+        assert_eq!(text.lines().count(), app.tree.count());
+        assert!(text.contains(&format!("\n{}Level 4999\n", "\t".repeat(4_999))));
+        assert_eq!(to_text(&app.tree, root, &ExportOptions::default()), text);
+    });
+}
@@ -34,10 +34,10 @@ fn test_save_to_readonly_directory() {
         fs::set_permissions(&readonly_dir, perms).unwrap();
     }
 
-    let (tree, root) = create_test_tree();
+    let (mut tree, root) = create_test_tree();
     let file_path = readonly_dir.join("test.hmm");
 
-    let result = parser::save_file(&tree, root, &file_path);
+    let result = parser::save_file(&mut tree, root, &file_path);
     assert!(result.is_err());
 
     // Restore permissions for cleanup
@@ -291,8 +291,8 @@ fn test_concurrent_file_access() {
     let file_path = temp_dir.path().join("concurrent.hmm");
 
     // Create initial file
-    let (tree, root) = create_test_tree();
-    parser::save_file(&tree, root, &file_path).unwrap();
+    let (mut tree, root) = create_test_tree();
+    parser::save_file(&mut tree, root, &file_path).unwrap();
 
     // Try to read from multiple threads simultaneously
     let mut handles = vec![];
@@ -97,12 +97,12 @@ fn test_round_trip_all_fixtures() {
         let path = fixture_path(fixture);
 
         // Load original
-        let (tree1, root1) = parser::load_file(&path).unwrap();
+        let (mut tree1, root1) = parser::load_file(&path).unwrap();
         let original_content = fs::read_to_string(&path).unwrap();
 
         // Save to temp file
         let temp_path = path.with_extension("tmp");
-        parser::save_file(&tree1, root1, &temp_path).unwrap();
+        parser::save_file(&mut tree1, root1, &temp_path).unwrap();
 
         // Load saved version
         let (tree2, root2) = parser::load_file(&temp_path).unwrap();
@@ -198,7 +198,7 @@ fn test_large_fixture_performance() {
     let path = fixture_path("large.hmm");
 
     let start = Instant::now();
-    let (tree, root_id) = parser::load_file(&path).unwrap();
+    let (mut tree, root_id) = parser::load_file(&path).unwrap();
     let load_time = start.elapsed();
 
     // Should load reasonably fast (under 100ms for this size)
@@ -215,7 +215,7 @@ fn test_large_fixture_performance() {
     // Test save performance
     let temp_path = path.with_extension("tmp");
     let start = Instant::now();
-    parser::save_file(&tree, root_id, &temp_path).unwrap();
+    parser::save_file(&mut tree, root_id, &temp_path).unwrap();
     let save_time = start.elapsed();
 
     assert!(
@@ -349,7 +349,7 @@ fn test_modify_and_save() {
 
     // Save to temp file
     let temp_path = path.with_extension("modified");
-    parser::save_file(&tree, root_id, &temp_path).unwrap();
+    parser::save_file(&mut tree, root_id, &temp_path).unwrap();
 
     // Load and verify
     let (tree2, root2) = parser::load_file(&temp_path).unwrap();
@@ -374,7 +374,7 @@ fn test_collapse_state_not_affecting_save() {
 
     // Save and reload
     let temp_path = path.with_extension("collapsed");
-    parser::save_file(&tree, root_id, &temp_path).unwrap();
+    parser::save_file(&mut tree, root_id, &temp_path).unwrap();
     let (tree2, _) = parser::load_file(&temp_path).unwrap();
 
     // All nodes should still be present
@@ -407,6 +407,45 @@ fn test_app_state_with_fixtures() {
     assert!(!app.is_dirty);
 }
 
+#[test]
+fn test_export_html_from_fixture() {
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let path = temp_dir.path().join("hidden.hmm");
+    fs::copy(fixture_path("hidden.hmm"), &path).unwrap();
+
+    let mut app = AppState::new(AppConfig::default());
+    actions::open_file(&mut app, &path).unwrap();
+    let root_id = app.root_id.unwrap();
+    let public_parent = root_id
+        .descendants(&app.tree)
+        .find(|&id| app.tree[id].get().title == "Public Parent")
+        .unwrap();
+    app.tree[public_parent].get_mut().is_collapsed = true;
+
+    actions::execute_action(actions::Action::ExportHtml, &mut app).unwrap();
+
+    // Hidden nodes and the collapsed branch stay out of the page
+    let html = fs::read_to_string(temp_dir.path().join("hidden.html")).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>\n"));
+    assert!(html.contains("<title>Visibility Test</title>"));
+    assert!(html.contains(
+        "<h1>Visibility Test</h1>\n\
+         <ul>\n\
+         \x20 <li>Public Section\n\
+         \x20 <ul>\n\
+         \x20   <li>Visible Node 1</li>\n\
+         \x20   <li>Visible Node 2</li>\n\
+         \x20   <li>Visible Node 3</li>\n\
+         \x20 </ul></li>\n\
+         \x20 <li>Mixed Visibility\n\
+         \x20 <ul>\n\
+         \x20   <li>Public Parent</li>\n\
+         \x20 </ul></li>\n\
+         </ul>\n\
+         </body>\n"
+    ));
+}
+
 #[test]
 fn test_tree_depth_calculation() {
     fn calculate_max_depth(tree: &Arena<Node>, node_id: hmm_rs::NodeId) -> usize {
@@ -442,3 +481,40 @@ fn test_tree_depth_calculation() {
         );
     }
 }
+
+#[test]
+fn test_load_dynalist_export() {
+    let path = fixture_path("dynalist.json");
+    let (mut tree, root_id) = parser::load_file(&path).unwrap();
+
+    assert_eq!(
+        parser::map_to_list(&tree, root_id, false, 0),
+        "Garden plan\n\tVegetables\n\t\t[HIDDEN] Raised beds only\n\t\tTomatoes\n\t\tBeans\n\t✓ Order seeds\n"
+    );
+
+    // Saved back as JSON, the map reads the same
+    let temp_dir = tempfile::TempDir::new().unwrap();
+    let copy = temp_dir.path().join("garden");
+    parser::save_file_as(&mut tree, root_id, &copy, parser::MapFormat::Outliner).unwrap();
+    assert_eq!(
+        parser::detect_format(&copy).unwrap(),
+        parser::MapFormat::Outliner
+    );
+    let (loaded, loaded_root) = parser::load_file(&copy).unwrap();
+    assert_eq!(
+        parser::map_to_list(&loaded, loaded_root, false, 0),
+        parser::map_to_list(&tree, root_id, false, 0)
+    );
+}
+
+#[test]
+fn test_load_tsv_table() {
+    let path = fixture_path("inventory.tsv");
+    let (tree, root_id) = parser::load_file(&path).unwrap();
+
+    // Two top-level categories hang under a root named after the file
+    assert_eq!(
+        parser::map_to_list(&tree, root_id, false, 0),
+        "inventory\n\tGarden\n\t\tTools\n\t\t\tSpade\n\t\t\tRake\n\t\tSeeds\n\tKitchen\n\t\tKnives\n"
+    );
+}
@@ -1,3 +1,4 @@
+use hmm_rs::actions::{execute_action, Action};
 use hmm_rs::{parser, AppConfig, AppState, Node, NodeId};
 use std::fs;
 use std::path::{Path, PathBuf};
@@ -20,14 +21,14 @@ fn test_load_and_save_file() {
     fs::write(&file_path, content).unwrap();
 
     // Load the file
-    let (tree, root_id) = parser::load_file(&file_path).unwrap();
+    let (mut tree, root_id) = parser::load_file(&file_path).unwrap();
 
     // Verify structure
     assert!(tree.count() > 1);
     assert_eq!(tree.get(root_id).unwrap().get().title, "Root");
 
     // Save the file
-    parser::save_file(&tree, root_id, &file_path).unwrap();
+    parser::save_file(&mut tree, root_id, &file_path).unwrap();
 
     // Read it back
     let saved_content = fs::read_to_string(&file_path).unwrap();
@@ -98,6 +99,133 @@ fn test_history_management() {
     assert!(app.history_index < initial_index);
 }
 
+#[test]
+fn test_undo_never_leaves_selection_dangling() {
+    let mut app = AppState::new(AppConfig::default());
+    let root = app.tree.new_node(Node::new("Root".to_string()));
+    let parent = app.tree.new_node(Node::new("Parent".to_string()));
+    root.append(parent, &mut app.tree);
+    app.root_id = Some(root);
+    app.active_node_id = Some(parent);
+    app.push_history();
+
+    // The inserted child does not exist in the restored tree
+    execute_action(Action::InsertChild, &mut app).unwrap();
+    execute_action(Action::CancelEdit, &mut app).unwrap();
+    let child = app.active_node_id.unwrap();
+    assert_ne!(child, parent);
+
+    execute_action(Action::Undo, &mut app).unwrap();
+    assert_eq!(app.active_node_id, Some(parent));
+    assert!(app.is_in_map(parent));
+}
+
+#[test]
+fn test_rootless_map_gets_a_new_root() {
+    let mut app = AppState::new(AppConfig::default());
+    let root = app.tree.new_node(Node::new("Root".to_string()));
+    app.root_id = Some(root);
+    app.active_node_id = Some(root);
+
+    // Simulate a buggy operation removing the root
+    root.remove(&mut app.tree);
+    execute_action(Action::GoDown, &mut app).unwrap();
+
+    let new_root = app.root_id.unwrap();
+    assert_ne!(new_root, root);
+    assert_eq!(app.tree[new_root].get().title, "New Mind Map");
+    assert_eq!(app.active_node_id, Some(new_root));
+    assert!(app.message.is_some());
+}
+
+#[test]
+fn test_edits_are_timed_for_the_heatmap() {
+    let mut app = AppState::new(AppConfig::default());
+    let root = app.tree.new_node(Node::new("Root".to_string()));
+    let child = root.append_value(Node::new("Child".to_string()), &mut app.tree);
+    app.root_id = Some(root);
+    app.active_node_id = Some(root);
+
+    execute_action(Action::GoRight, &mut app).unwrap();
+    assert!(app.edit_times.is_empty());
+
+    execute_action(Action::InsertChild, &mut app).unwrap();
+    let inserted = app.active_node_id.unwrap();
+    assert_ne!(inserted, child);
+    assert!(app.edit_times.contains_key(&inserted));
+    assert!(!app.edit_times.contains_key(&child));
+}
+
+#[test]
+fn test_undo_history_memory_limit() {
+    let config = AppConfig {
+        max_undo_steps: 1000,
+        max_undo_memory: 2,
+        ..Default::default()
+    };
+    let mut app = AppState::new(config);
+
+    let root = app.tree.new_node(Node::new("Root".to_string()));
+    app.root_id = Some(root);
+    app.push_history();
+
+    // Each step adds a node with a poorly compressible title; the 2 MiB
+    // budget caps the history well before the step limit does
+    let mut seed: u32 = 1;
+    for _ in 0..200 {
+        app.push_history();
+        let title: String = (0..512)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                char::from(b'a' + (seed >> 16) as u8 % 26)
+            })
+            .collect();
+        let child = app.tree.new_node(Node::new(title));
+        root.append(child, &mut app.tree);
+    }
+
+    assert!(app.history.len() < 200);
+    assert!(app.history.memory_usage() <= 2 * 1024 * 1024);
+    assert!(app.history.compressed_count() > 0);
+
+    // Undo still walks back through compressed snapshots
+    while app.undo() {}
+    assert!(root.children(&app.tree).count() < 200);
+}
+
+#[test]
+fn test_damage_tracking_and_layout_cache() {
+    use hmm_rs::actions::{execute_action, Action};
+    use hmm_rs::app::Damage;
+    use std::rc::Rc;
+
+    let (tree, root_id) = parser::parse_hmm_content("Root\n\tChild 1\n\tChild 2").unwrap();
+    let mut app = AppState::new(AppConfig::default());
+    app.tree = tree;
+    app.root_id = Some(root_id);
+    app.active_node_id = Some(root_id);
+
+    // A new app needs a full first frame
+    assert_eq!(app.take_damage(), Damage::Layout);
+    assert_eq!(app.take_damage(), Damage::None);
+
+    // Moving the selection reuses the cached layout
+    execute_action(Action::GoRight, &mut app).unwrap();
+    app.take_damage();
+    let layout = app.layout();
+    execute_action(Action::GoDown, &mut app).unwrap();
+    assert_eq!(app.take_damage(), Damage::View);
+    assert!(Rc::ptr_eq(&layout, &app.layout()));
+
+    // Folding changes node positions, so the layout is recomputed
+    execute_action(Action::GoToRoot, &mut app).unwrap();
+    execute_action(Action::ToggleCollapse, &mut app).unwrap();
+    assert_eq!(app.take_damage(), Damage::Layout);
+    let folded = app.layout();
+    assert!(!Rc::ptr_eq(&layout, &folded));
+    assert_eq!(folded.nodes.len(), 1);
+}
+
 #[test]
 fn test_message_system() {
     let config = AppConfig::default();
@@ -135,8 +263,8 @@ fn test_round_trip_with_special_characters() {
     fs::write(&file_path, content).unwrap();
 
     // Load and save
-    let (tree, root_id) = parser::load_file(&file_path).unwrap();
-    parser::save_file(&tree, root_id, &file_path).unwrap();
+    let (mut tree, root_id) = parser::load_file(&file_path).unwrap();
+    parser::save_file(&mut tree, root_id, &file_path).unwrap();
 
     // Verify content is preserved
     let saved_content = fs::read_to_string(&file_path).unwrap();
@@ -341,10 +469,10 @@ fn test_fixture_round_trips() {
         let temp_path = temp_dir.path().join(fixture);
 
         // Load original
-        let (tree, root) = parser::load_file(&original_path).unwrap();
+        let (mut tree, root) = parser::load_file(&original_path).unwrap();
 
         // Save to temp
-        parser::save_file(&tree, root, &temp_path).unwrap();
+        parser::save_file(&mut tree, root, &temp_path).unwrap();
 
         // Load from temp
         let (tree2, root2) = parser::load_file(&temp_path).unwrap();
@@ -357,3 +485,33 @@ fn test_fixture_round_trips() {
         );
     }
 }
+
+#[test]
+fn test_large_map_switches_to_lighter_settings() {
+    let config = AppConfig {
+        large_map_nodes: 10,
+        sticky_headers: true,
+        max_undo_steps: 50,
+        ..AppConfig::default()
+    };
+    let mut app = AppState::new(config);
+    let root = app.tree.new_node(Node::new("Root".to_string()));
+    for i in 0..10 {
+        root.append_value(Node::new(format!("Item {i}")), &mut app.tree);
+    }
+    app.root_id = Some(root);
+    app.active_node_id = Some(root);
+
+    execute_action(Action::GoDown, &mut app).unwrap();
+    assert!(app.large_map);
+    assert!(!app.config.sticky_headers);
+    assert_eq!(app.config.max_undo_steps, 8);
+    assert!(app.message.as_deref().unwrap().contains("11 nodes"));
+
+    // The warning is shown once per session
+    app.message = None;
+    app.config.sticky_headers = true;
+    execute_action(Action::GoDown, &mut app).unwrap();
+    assert!(app.config.sticky_headers);
+    assert_eq!(app.message, None);
+}
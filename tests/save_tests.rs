@@ -1,5 +1,5 @@
 use hmm_rs::{
-    actions::{save, save_as},
+    actions::{open_file, save, save_as},
     app::AppState,
     config::AppConfig,
     model::Node,
@@ -176,10 +176,10 @@ fn test_round_trip_preservation() {
     fs::write(&file_path, original_content).unwrap();
 
     // Load
-    let (tree, root_id) = parser::load_file(&file_path).unwrap();
+    let (mut tree, root_id) = parser::load_file(&file_path).unwrap();
 
     // Save back
-    parser::save_file(&tree, root_id, &file_path).unwrap();
+    parser::save_file(&mut tree, root_id, &file_path).unwrap();
 
     // Read saved content
     let saved_content = fs::read_to_string(&file_path).unwrap();
@@ -271,3 +271,43 @@ fn test_save_deeply_nested_tree() {
         assert!(content.contains(&expected_line));
     }
 }
+
+#[test]
+fn test_open_and_save_leaves_file_untouched() {
+    let temp_dir = TempDir::new().unwrap();
+    let file_path = temp_dir.path().join("styled.hmm");
+    let content = "\nProject  \r\n  - Design\r\n  - Build\r\n\r\n      Tests\r\nNotes";
+    fs::write(&file_path, content).unwrap();
+
+    let mut app = AppState::new(AppConfig::default());
+    open_file(&mut app, &file_path).unwrap();
+    save(&mut app).unwrap();
+
+    assert_eq!(fs::read_to_string(&file_path).unwrap(), content);
+}
+
+#[test]
+fn test_auto_save_skips_scratch_and_excluded_maps() {
+    let temp_dir = TempDir::new().unwrap();
+    let shared = temp_dir.path().join("shared.hmm");
+    let own = temp_dir.path().join("own.hmm");
+    fs::write(&shared, "Shared\n").unwrap();
+    fs::write(&own, "Own\n").unwrap();
+
+    let config = AppConfig {
+        auto_save: true,
+        no_auto_save: vec![shared.clone()],
+        ..AppConfig::default()
+    };
+    let mut app = AppState::new(config);
+    assert!(!app.auto_saves(), "a map without a file has nowhere to go");
+
+    app.filename = Some(own.clone());
+    assert!(app.auto_saves());
+    app.filename = Some(shared);
+    assert!(!app.auto_saves());
+
+    app.filename = Some(own);
+    app.scratch = true;
+    assert!(!app.auto_saves());
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "render")]
+
 use hmm_rs::app::{AppMode, AppState};
 use hmm_rs::config::AppConfig;
 use hmm_rs::model::Node;
@@ -443,3 +445,129 @@ fn test_parent_remains_visible_with_children() {
     assert!(output.contains("Features"));
     assert!(output.contains("Completed Task") || output.contains("Failed Task"));
 }
+
+#[test]
+fn test_render_to_string() {
+    let app = create_test_app_with_tree();
+
+    let output = ui::render_to_string(&app, 60, 10);
+
+    assert_eq!(output.lines().count(), 10);
+    assert_snapshot!(output);
+}
+
+#[test]
+fn test_render_to_string_matches_terminal_render() {
+    let mut app = create_test_app_with_tree();
+
+    let backend = TestBackend::new(80, 20);
+    let mut terminal = Terminal::new(backend).unwrap();
+    terminal.draw(|frame| ui::render(frame, &mut app)).unwrap();
+    let terminal_output = terminal.backend().to_string();
+
+    // The plain renderer covers the map area only (status line excluded)
+    let plain_output = ui::render_to_string(&app, 80, 19);
+    for (plain, rendered) in plain_output.lines().zip(terminal_output.lines()) {
+        assert_eq!(plain, rendered.trim_matches('"').trim_end());
+    }
+}
+
+#[test]
+fn test_render_depth_guides() {
+    let mut app = create_test_app_with_tree();
+    app.config.depth_guides = true;
+
+    let output = ui::render_to_string(&app, 60, 10);
+
+    assert_snapshot!(output);
+}
+
+#[test]
+fn test_render_sticky_headers() {
+    let mut app = AppState::new(AppConfig::default());
+    let root = app.tree.new_node(Node::new("Root".to_string()));
+    let branch = root.append_value(Node::new("Long branch".to_string()), &mut app.tree);
+    let section = branch.append_value(Node::new("Section".to_string()), &mut app.tree);
+    for i in 1..=20 {
+        section.append_value(Node::new(format!("Item {i}")), &mut app.tree);
+    }
+    app.root_id = Some(root);
+    app.active_node_id = Some(root);
+    app.viewport_top = 24.0;
+
+    let plain = ui::render_to_string(&app, 60, 6);
+    assert!(!plain.contains("Root"));
+
+    app.config.sticky_headers = true;
+    let output = ui::render_to_string(&app, 60, 6);
+    assert!(output.lines().next().unwrap().contains("Root"));
+    assert_snapshot!(output);
+}
+
+#[test]
+fn test_render_pin_bar() {
+    let mut app = create_test_app_with_tree();
+    let root = app.root_id.unwrap();
+    let features = root.children(&app.tree).next().unwrap();
+    let task = features.children(&app.tree).next().unwrap();
+    app.pins = vec![features, task];
+    app.active_node_id = Some(task);
+
+    let backend = TestBackend::new(60, 10);
+    let mut terminal = Terminal::new(backend).unwrap();
+
+    terminal.draw(|frame| ui::render(frame, &mut app)).unwrap();
+
+    assert_snapshot!(terminal.backend());
+}
+
+#[test]
+fn test_render_code_and_math_nodes() {
+    let mut app = create_test_app_with_tree();
+    let root = app.root_id.unwrap();
+    let features_id = root.children(&app.tree).next().unwrap();
+
+    // Code is cut short instead of wrapped, math is shown as unicode
+    let code = app.tree.new_node(Node::new(
+        "```rust fn layout(app: &AppState) -> LayoutEngine { LayoutEngine::calculate_layout(app) }```".to_string(),
+    ));
+    let math = app.tree.new_node(Node::new("Area $\\pi r^2$".to_string()));
+    features_id.append(code, &mut app.tree);
+    features_id.append(math, &mut app.tree);
+
+    let output = ui::render_to_string(&app, 100, 12);
+
+    assert_snapshot!(output);
+}
+
+#[test]
+fn test_render_table_node() {
+    let mut app = create_test_app_with_tree();
+    let root = app.root_id.unwrap();
+    let features_id = root.children(&app.tree).next().unwrap();
+
+    // Each row of the table takes a line, with its columns aligned
+    let table = app.tree.new_node(Node::new("|Tool|Speed||hmm|fast||vim|faster|".to_string()));
+    features_id.append(table, &mut app.tree);
+
+    let output = ui::render_to_string(&app, 60, 12);
+
+    assert_snapshot!(output);
+}
+
+#[test]
+fn test_render_packed_leaf_columns() {
+    let mut app = create_test_app_with_tree();
+    let root = app.root_id.unwrap();
+    let architecture_id = root.children(&app.tree).nth(1).unwrap();
+    for name in ["app.rs", "layout.rs", "parser.rs", "export.rs", "config.rs"] {
+        let module = app.tree.new_node(Node::new(name.to_string()));
+        architecture_id.append(module, &mut app.tree);
+    }
+
+    // Seven leaves with at most three rows are packed into three columns
+    app.config.pack_leaves_after = 3;
+    let output = ui::render_to_string(&app, 80, 12);
+
+    assert_snapshot!(output);
+}